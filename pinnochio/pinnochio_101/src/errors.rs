@@ -0,0 +1,17 @@
+use pinocchio::program_error::ProgramError;
+
+#[derive(Clone, PartialEq)]
+pub enum PinocchioError {
+    /// The vault was passed read-only, so no lamports could be moved into it.
+    VaultNotWritable,
+    /// Lamports can't leave the vault until its hold period since the last deposit is over.
+    CooldownActive,
+    /// The vault is locked until its unlock time.
+    VaultLocked,
+}
+
+impl From<PinocchioError> for ProgramError {
+    fn from(e: PinocchioError) -> Self {
+        Self::Custom(e as u32)
+    }
+}
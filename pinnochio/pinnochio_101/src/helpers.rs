@@ -0,0 +1,121 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+};
+
+// minimum lamports the account must keep to stay rent exempt at its current size
+#[inline(always)]
+pub fn rent_floor(account: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(rent_floor_at(&Rent::get()?, account))
+}
+
+// `rent_floor` under a given rent, which host tests can build without the sysvar
+#[inline(always)]
+pub fn rent_floor_at(rent: &Rent, account: &AccountInfo) -> u64 {
+    rent.minimum_balance(account.data_len())
+}
+
+// what Withdraw pays out of `vault`: everything above the rent floor, 0 at or below it
+#[inline(always)]
+pub fn withdrawable(vault: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(withdrawable_at(&Rent::get()?, vault))
+}
+
+#[inline(always)]
+pub fn withdrawable_at(rent: &Rent, vault: &AccountInfo) -> u64 {
+    vault.lamports().saturating_sub(rent_floor_at(rent, vault))
+}
+
+#[cfg(test)]
+mod tests {
+    use pinocchio::pubkey::Pubkey;
+
+    use super::*;
+    use crate::{AccountInfoExt, RawAccount};
+
+    const OWNER: Pubkey = [7u8; 32];
+
+    // mainnet's rent: 3480 lamports per byte-year, exempt at two years, half burned. u64
+    // words keep it aligned for `Rent::from_bytes`
+    const RENT: [u64; 3] = [3_480, 2f64.to_bits(), 50];
+
+    fn rent() -> &'static Rent {
+        let bytes = unsafe { core::slice::from_raw_parts(RENT.as_ptr() as *const u8, Rent::LEN) };
+        Rent::from_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn rent_floor_of_empty_and_sized_accounts() {
+        // the 128 bytes of account overhead are paid for even with no data
+        let mut empty = RawAccount::new(false, &OWNER, &[]);
+        assert_eq!(rent_floor_at(rent(), &empty.info()), 890_880);
+
+        let mut sized = RawAccount::new(false, &OWNER, &[0u8; 24]);
+        assert_eq!(rent_floor_at(rent(), &sized.info()), (128 + 24) * 3_480 * 2);
+    }
+
+    #[test]
+    fn withdrawable_stops_at_the_rent_floor() {
+        let floor = (128 + 16) * 3_480 * 2;
+        for (lamports, withdrawable) in [(floor + 1_000, 1_000), (floor, 0), (floor - 1, 0), (0, 0)]
+        {
+            let mut vault = RawAccount::new(false, &OWNER, &[0u8; 16]).with_lamports(lamports);
+            assert_eq!(withdrawable_at(rent(), &vault.info()), withdrawable);
+        }
+    }
+
+    #[test]
+    fn require_signer() {
+        assert_eq!(
+            RawAccount::new(true, &OWNER, &[]).info().require_signer(),
+            Ok(())
+        );
+        assert_eq!(
+            RawAccount::new(false, &OWNER, &[]).info().require_signer(),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn require_owner() {
+        let mut raw = RawAccount::new(false, &OWNER, &[]);
+        assert_eq!(raw.info().require_owner(&OWNER), Ok(()));
+        assert_eq!(
+            raw.info().require_owner(&[8u8; 32]),
+            Err(ProgramError::InvalidAccountOwner)
+        );
+    }
+
+    #[test]
+    fn require_len() {
+        let mut raw = RawAccount::new(false, &OWNER, &[0u8; 16]);
+        assert_eq!(raw.info().require_len(16), Ok(()));
+        assert_eq!(
+            raw.info().require_len(15),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            raw.info().require_len(17),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn read_u64_at() {
+        let mut data = [0u8; 16];
+        data[0..8].copy_from_slice(&1u64.to_le_bytes());
+        data[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        let mut raw = RawAccount::new(false, &OWNER, &data);
+        let info = raw.info();
+
+        assert_eq!(info.read_u64_at(0), Ok(1));
+        assert_eq!(info.read_u64_at(8), Ok(u64::MAX));
+        assert_eq!(info.read_u64_at(9), Err(ProgramError::InvalidAccountData));
+        assert_eq!(info.read_u64_at(16), Err(ProgramError::InvalidAccountData));
+        assert_eq!(
+            info.read_u64_at(usize::MAX - 3),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
@@ -3,16 +3,203 @@ use core::mem::size_of;
 use pinocchio::{
     account_info::AccountInfo,
     instruction::{Seed, Signer},
+    program::set_return_data,
     program_error::ProgramError,
     pubkey::find_program_address,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
     ProgramResult,
 };
-use pinocchio_system::instructions::Transfer;
+use pinocchio_system::instructions::{CreateAccount, Transfer};
+
+use crate::{withdrawable, AccountInfoExt, PinocchioError, SignerAccount};
+
+//state
+// deposit cap of one vault, stored at ["cap", owner]; 0 or no cap account means unlimited
+#[repr(C)]
+pub struct VaultCap {
+    pub max_balance: [u8; 8],
+}
+
+impl VaultCap {
+    pub const LEN: usize = size_of::<VaultCap>();
+
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*(data.as_ptr() as *const Self) })
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+    }
+
+    pub fn max_balance(&self) -> u64 {
+        u64::from_le_bytes(self.max_balance)
+    }
+
+    // whether the vault may hold `balance` lamports
+    pub fn allows(&self, balance: u64) -> bool {
+        self.max_balance().eq(&0) || balance.le(&self.max_balance())
+    }
+}
+
+// withdraw restrictions of one vault, stored at ["hold", owner]: a cooldown after every
+// deposit and a fixed unlock time, 0 or no hold account means neither
+#[repr(C)]
+pub struct VaultHold {
+    pub min_hold_seconds: [u8; 8],
+    // unix timestamp of the last deposit, only recorded while a hold account exists
+    pub last_deposit_at: [u8; 8],
+    // unix timestamp before which nothing leaves the vault
+    pub unlock_at: [u8; 8],
+}
+
+impl VaultHold {
+    pub const LEN: usize = size_of::<VaultHold>();
+
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*(data.as_ptr() as *const Self) })
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+    }
+
+    pub fn min_hold_seconds(&self) -> u64 {
+        u64::from_le_bytes(self.min_hold_seconds)
+    }
+
+    pub fn last_deposit_at(&self) -> i64 {
+        i64::from_le_bytes(self.last_deposit_at)
+    }
+
+    pub fn unlock_at(&self) -> i64 {
+        i64::from_le_bytes(self.unlock_at)
+    }
+
+    // why lamports can't leave the vault at `now`, if anything stops them
+    pub fn blocked(&self, now: i64) -> Option<PinocchioError> {
+        if now < self.unlock_at() {
+            return Some(PinocchioError::VaultLocked);
+        }
+        let min_hold_seconds = self.min_hold_seconds().min(i64::MAX as u64) as i64;
+        if now < self.last_deposit_at().saturating_add(min_hold_seconds) {
+            return Some(PinocchioError::CooldownActive);
+        }
+        None
+    }
+
+    // a longer hold applies at once; a shorter one only once the running hold is over, or
+    // the owner could lift a cooldown the moment it started
+    pub fn set_min_hold(&mut self, min_hold_seconds: u64, now: i64) -> ProgramResult {
+        if min_hold_seconds.lt(&self.min_hold_seconds())
+            && matches!(self.blocked(now), Some(PinocchioError::CooldownActive))
+        {
+            return Err(PinocchioError::CooldownActive.into());
+        }
+        self.min_hold_seconds = min_hold_seconds.to_le_bytes();
+        Ok(())
+    }
+}
+
+// the cap account is always passed wherever lamports arrive, so leaving it out can't skip
+// the limit
+fn check_cap(owner: &AccountInfo, cap: &AccountInfo) -> ProgramResult {
+    let (cap_key, _) = find_program_address(&[b"cap", owner.key()], &crate::ID);
+    if cap.key().ne(&cap_key) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if cap.data_len().ne(&0) && !cap.is_owned_by(&crate::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    Ok(())
+}
+
+// fails unless a vault under `cap` may hold `balance` lamports
+fn check_cap_allows(cap: &AccountInfo, balance: u64) -> ProgramResult {
+    if cap.data_len().ne(&0) && !VaultCap::load(&cap.try_borrow_data()?)?.allows(balance) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// fails unless `vault` may take `amount` more lamports under `cap`
+fn check_deposit(vault: &AccountInfo, cap: &AccountInfo, amount: u64) -> ProgramResult {
+    let balance = vault
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    check_cap_allows(cap, balance)
+}
+
+// every deposit restarts the hold period
+fn record_deposit(hold: &AccountInfo) -> ProgramResult {
+    if hold.data_len().ne(&0) {
+        VaultHold::load_mut(&mut hold.try_borrow_mut_data()?)?.last_deposit_at =
+            Clock::get()?.unix_timestamp.to_le_bytes();
+    }
+    Ok(())
+}
+
+// the hold account is always passed wherever lamports move, so leaving it out can't skip
+// the cooldown; returns its bump for the instruction that creates it
+fn check_hold(owner: &AccountInfo, hold: &AccountInfo) -> Result<u8, ProgramError> {
+    let (hold_key, bump) = find_program_address(&[b"hold", owner.key()], &crate::ID);
+    if hold.key().ne(&hold_key) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if hold.data_len().ne(&0) && !hold.is_owned_by(&crate::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    Ok(bump)
+}
+
+// why lamports can't leave the vault right now, if anything stops them
+fn withdraw_blocked(hold: &AccountInfo) -> Result<Option<PinocchioError>, ProgramError> {
+    if hold.data_len().eq(&0) {
+        return Ok(None);
+    }
+    let data = hold.try_borrow_data()?;
+    Ok(VaultHold::load(&data)?.blocked(Clock::get()?.unix_timestamp))
+}
+
+// creates the owner's hold account at `bump` if it doesn't exist yet
+fn init_hold(owner: &AccountInfo, hold: &AccountInfo, bump: &[u8; 1]) -> ProgramResult {
+    if hold.data_len().ne(&0) {
+        return Ok(());
+    }
+    let seeds = [
+        Seed::from(b"hold"),
+        Seed::from(owner.key().as_ref()),
+        Seed::from(bump),
+    ];
+
+    CreateAccount {
+        from: owner,
+        to: hold,
+        lamports: Rent::get()?.minimum_balance(VaultHold::LEN),
+        space: VaultHold::LEN as u64,
+        owner: &crate::ID,
+    }
+    .invoke_signed(&[Signer::from(&seeds)])
+}
 
 //account structs
 pub struct DepositAccounts<'a> {
     pub owner: &'a AccountInfo,
     pub vault: &'a AccountInfo,
+    pub cap: &'a AccountInfo,
+    pub hold: &'a AccountInfo,
 }
 
 pub struct DepositInstructionData {
@@ -24,14 +211,82 @@ pub struct Deposit<'a> {
     pub instruction_data: DepositInstructionData,
 }
 
+pub struct SetDepositCapAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub cap: &'a AccountInfo,
+    pub bumps: [u8; 1],
+}
+
+pub struct SetDepositCap<'a> {
+    pub accounts: SetDepositCapAccounts<'a>,
+    pub max_balance: u64,
+}
+
 pub struct WithdrawAccounts<'a> {
     pub owner: &'a AccountInfo,
     pub vault: &'a AccountInfo,
+    pub hold: &'a AccountInfo,
     pub bumps: [u8; 1],
 }
 
+pub struct WithdrawInstructionData {
+    // None takes everything above rent
+    pub amount: Option<u64>,
+}
+
 pub struct Withdraw<'a> {
     pub accounts: WithdrawAccounts<'a>,
+    pub instruction_data: WithdrawInstructionData,
+}
+
+// most vaults one GetVaultBalances call reports on, bounds the return data buffer
+pub const MAX_BALANCE_VAULTS: usize = 16;
+
+pub struct GetVaultBalancesAccounts<'a> {
+    pub vaults: &'a [AccountInfo],
+}
+
+pub struct GetVaultBalances<'a> {
+    pub accounts: GetVaultBalancesAccounts<'a>,
+}
+
+pub struct TransferOwnerAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub new_vault: &'a AccountInfo,
+    pub hold: &'a AccountInfo,
+    pub new_cap: &'a AccountInfo,
+    pub new_hold: &'a AccountInfo,
+    pub bumps: [u8; 1],
+}
+
+pub struct TransferOwner<'a> {
+    pub accounts: TransferOwnerAccounts<'a>,
+}
+
+pub struct SimulateWithdrawAccounts<'a> {
+    pub vault: &'a AccountInfo,
+    pub hold: &'a AccountInfo,
+}
+
+pub struct SimulateWithdraw<'a> {
+    pub accounts: SimulateWithdrawAccounts<'a>,
+}
+
+pub struct SetMinHoldAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub hold: &'a AccountInfo,
+    pub bumps: [u8; 1],
+}
+
+pub struct SetMinHold<'a> {
+    pub accounts: SetMinHoldAccounts<'a>,
+    pub min_hold_seconds: u64,
+}
+
+pub struct LockVault<'a> {
+    pub accounts: SetMinHoldAccounts<'a>,
+    pub unlock_at: i64,
 }
 
 //validating the accounts struct
@@ -39,28 +294,33 @@ impl<'a> TryFrom<&'a [AccountInfo]> for DepositAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [owner, vault, _] = accounts else {
+        let [owner, vault, _, cap, hold] = accounts else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
 
-        if !owner.is_signer() {
-            return Err(ProgramError::InvalidAccountOwner);
-        }
+        SignerAccount::check(owner)?;
 
-        if !vault.is_owned_by(&pinocchio_system::ID) {
-            return Err(ProgramError::InvalidAccountOwner);
+        // caught here rather than as an opaque failure inside the system transfer
+        if !vault.is_writable() {
+            return Err(PinocchioError::VaultNotWritable.into());
         }
 
-        if vault.lamports().ne(&0) {
-            return Err(ProgramError::InvalidAccountData);
-        }
+        vault.require_owner(&pinocchio_system::ID)?;
 
         let (vault_key, _) = find_program_address(&[b"vault", owner.key()], &crate::ID);
         if vault.key().ne(&vault_key) {
             return Err(ProgramError::InvalidAccountOwner);
         }
 
-        Ok(Self { owner, vault })
+        check_cap(owner, cap)?;
+        check_hold(owner, hold)?;
+
+        Ok(Self {
+            owner,
+            vault,
+            cap,
+            hold,
+        })
     }
 }
 
@@ -102,6 +362,12 @@ impl<'a> Deposit<'a> {
     pub const DISCRIMINATOR: &'a u8 = &0;
 
     pub fn process(&mut self) -> ProgramResult {
+        check_deposit(
+            self.accounts.vault,
+            self.accounts.cap,
+            self.instruction_data.amount,
+        )?;
+
         Transfer {
             from: self.accounts.owner,
             to: self.accounts.vault,
@@ -109,7 +375,7 @@ impl<'a> Deposit<'a> {
         }
         .invoke()?;
 
-        Ok(())
+        record_deposit(self.accounts.hold)
     }
 }
 
@@ -118,18 +384,14 @@ impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [owner, vault, _] = accounts else {
+        let [owner, vault, _, hold] = accounts else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
 
         // Basic Accounts Checks
-        if !owner.is_signer() {
-            return Err(ProgramError::InvalidAccountOwner);
-        }
+        SignerAccount::check(owner)?;
 
-        if !vault.is_owned_by(&pinocchio_system::ID) {
-            return Err(ProgramError::InvalidAccountOwner);
-        }
+        vault.require_owner(&pinocchio_system::ID)?;
 
         if vault.lamports().eq(&0) {
             return Err(ProgramError::InvalidAccountData);
@@ -140,21 +402,46 @@ impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawAccounts<'a> {
             return Err(ProgramError::InvalidAccountOwner);
         }
 
+        check_hold(owner, hold)?;
+
         Ok(Self {
             owner,
             vault,
+            hold,
             bumps: [bump],
         })
     }
 }
 
-impl<'a> TryFrom<&'a [AccountInfo]> for Withdraw<'a> {
+impl<'a> TryFrom<&'a [u8]> for WithdrawInstructionData {
     type Error = ProgramError;
 
-    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let amount = match data.len() {
+            0 => None,
+            8 => Some(u64::from_le_bytes(data.try_into().unwrap())),
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+
+        if amount.eq(&Some(0)) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self { amount })
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Withdraw<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
         let accounts = WithdrawAccounts::try_from(accounts)?;
+        let instruction_data = WithdrawInstructionData::try_from(data)?;
 
-        Ok(Self { accounts })
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
     }
 }
 
@@ -163,6 +450,10 @@ impl<'a> Withdraw<'a> {
     pub const DISCRIMINATOR: &'a u8 = &1;
 
     pub fn process(&mut self) -> ProgramResult {
+        if let Some(error) = withdraw_blocked(self.accounts.hold)? {
+            return Err(error.into());
+        }
+
         let seeds = [
             Seed::from(b"vault"),
             Seed::from(self.accounts.owner.key().as_ref()),
@@ -170,13 +461,655 @@ impl<'a> Withdraw<'a> {
         ];
         let signers = [Signer::from(&seeds)];
 
+        // leave the vault rent exempt so it can keep receiving deposits
+        let lamports = withdraw_amount(
+            withdrawable(self.accounts.vault)?,
+            self.instruction_data.amount,
+        )?;
+
         Transfer {
             from: self.accounts.vault,
             to: self.accounts.owner,
+            lamports,
+        }
+        .invoke_signed(&signers)?;
+
+        Ok(())
+    }
+}
+
+// lamports a Withdraw of `requested` pays out of a vault with `available` above its rent floor:
+// all of it when no amount was asked for, never more than that
+fn withdraw_amount(available: u64, requested: Option<u64>) -> Result<u64, ProgramError> {
+    let lamports = requested.unwrap_or(available);
+    if available.eq(&0) || lamports.gt(&available) {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    Ok(lamports)
+}
+
+// one little-endian u64 of lamports per vault, in account order, packed into `buf`
+pub fn pack_balances<'b>(
+    vaults: &[AccountInfo],
+    buf: &'b mut [u8; MAX_BALANCE_VAULTS * size_of::<u64>()],
+) -> Result<&'b [u8], ProgramError> {
+    if vaults.is_empty() || vaults.len().gt(&MAX_BALANCE_VAULTS) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    for (chunk, vault) in buf.chunks_exact_mut(size_of::<u64>()).zip(vaults) {
+        chunk.copy_from_slice(&vault.lamports().to_le_bytes());
+    }
+    Ok(&buf[..vaults.len() * size_of::<u64>()])
+}
+
+//validating the balance query accounts
+impl<'a> TryFrom<&'a [AccountInfo]> for GetVaultBalancesAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, vaults @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        if vaults.is_empty() || vaults.len().gt(&MAX_BALANCE_VAULTS) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // the owner only names whose vaults these are, nothing moves so no signature;
+        // every vault is derived from it the same way Deposit and Withdraw do
+        let (vault_key, _) = find_program_address(&[b"vault", owner.key()], &crate::ID);
+        for vault in vaults {
+            vault.require_owner(&pinocchio_system::ID)?;
+
+            if vault.key().ne(&vault_key) {
+                return Err(ProgramError::InvalidAccountOwner);
+            }
+        }
+
+        Ok(Self { vaults })
+    }
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetVaultBalances<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = GetVaultBalancesAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+//balance query, read only
+impl<'a> GetVaultBalances<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &2;
+
+    // return data is one little-endian u64 of lamports per vault, in account order
+    pub fn process(&mut self) -> ProgramResult {
+        let mut buf = [0u8; MAX_BALANCE_VAULTS * size_of::<u64>()];
+        set_return_data(pack_balances(self.accounts.vaults, &mut buf)?);
+
+        Ok(())
+    }
+}
+
+//validating the deposit cap accounts
+impl<'a> TryFrom<&'a [AccountInfo]> for SetDepositCapAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, cap, _] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(owner)?;
+
+        let (cap_key, bump) = find_program_address(&[b"cap", owner.key()], &crate::ID);
+        if cap.key().ne(&cap_key) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if cap.data_len().ne(&0) && !cap.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Self {
+            owner,
+            cap,
+            bumps: [bump],
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for SetDepositCap<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = SetDepositCapAccounts::try_from(accounts)?;
+
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let max_balance = u64::from_le_bytes(data.try_into().unwrap());
+
+        Ok(Self {
+            accounts,
+            max_balance,
+        })
+    }
+}
+
+//deposit cap instruction
+impl<'a> SetDepositCap<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &3;
+
+    // creates the cap on first use; lowering it below the current balance only stops
+    // further deposits, nothing already in the vault is touched
+    pub fn process(&mut self) -> ProgramResult {
+        if self.accounts.cap.data_len().eq(&0) {
+            let seeds = [
+                Seed::from(b"cap"),
+                Seed::from(self.accounts.owner.key().as_ref()),
+                Seed::from(&self.accounts.bumps),
+            ];
+
+            CreateAccount {
+                from: self.accounts.owner,
+                to: self.accounts.cap,
+                lamports: Rent::get()?.minimum_balance(VaultCap::LEN),
+                space: VaultCap::LEN as u64,
+                owner: &crate::ID,
+            }
+            .invoke_signed(&[Signer::from(&seeds)])?;
+        }
+
+        VaultCap::load_mut(&mut self.accounts.cap.try_borrow_mut_data()?)?.max_balance =
+            self.max_balance.to_le_bytes();
+
+        Ok(())
+    }
+}
+
+//validating the transfer owner accounts
+impl<'a> TryFrom<&'a [AccountInfo]> for TransferOwnerAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, vault, new_owner, new_vault, _, hold, new_cap, new_hold] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(owner)?;
+
+        if owner.key().eq(new_owner.key()) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        vault.require_owner(&pinocchio_system::ID)?;
+
+        if vault.lamports().eq(&0) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (vault_key, bump) = find_program_address(&[b"vault", owner.key()], &crate::ID);
+        if vault.key().ne(&vault_key) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        // the new owner only names where the savings go, it doesn't have to sign
+        let (new_vault_key, _) = find_program_address(&[b"vault", new_owner.key()], &crate::ID);
+        if new_vault.key().ne(&new_vault_key) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        new_vault.require_owner(&pinocchio_system::ID)?;
+
+        if !new_vault.is_writable() {
+            return Err(PinocchioError::VaultNotWritable.into());
+        }
+
+        check_hold(owner, hold)?;
+        // the savings arrive in the new owner's vault like a deposit, under their limits
+        check_cap(new_owner, new_cap)?;
+        check_hold(new_owner, new_hold)?;
+
+        Ok(Self {
+            owner,
+            vault,
+            new_vault,
+            hold,
+            new_cap,
+            new_hold,
+            bumps: [bump],
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for TransferOwner<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = TransferOwnerAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+//transfer owner instruction
+impl<'a> TransferOwner<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &4;
+
+    // the vault address is derived from its owner, so a new owner means a new vault: every
+    // lamport moves to the new owner's vault, which the transfer creates if needed, and the
+    // emptied old vault is reaped. The old owner's cap and hold stay behind with the old key;
+    // the new owner's cap must fit the merged balance, and their hold restarts as on a deposit.
+    pub fn process(&mut self) -> ProgramResult {
+        // moving the savings elsewhere is still taking them out of this vault
+        if let Some(error) = withdraw_blocked(self.accounts.hold)? {
+            return Err(error.into());
+        }
+
+        let balance = self
+            .accounts
+            .new_vault
+            .lamports()
+            .checked_add(self.accounts.vault.lamports())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        check_cap_allows(self.accounts.new_cap, balance)?;
+
+        let seeds = [
+            Seed::from(b"vault"),
+            Seed::from(self.accounts.owner.key().as_ref()),
+            Seed::from(&self.accounts.bumps),
+        ];
+        let signers = [Signer::from(&seeds)];
+
+        Transfer {
+            from: self.accounts.vault,
+            to: self.accounts.new_vault,
             lamports: self.accounts.vault.lamports(),
         }
         .invoke_signed(&signers)?;
 
+        record_deposit(self.accounts.new_hold)
+    }
+}
+
+//validating the simulate withdraw accounts
+impl<'a> TryFrom<&'a [AccountInfo]> for SimulateWithdrawAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, vault, hold] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        // read only like GetVaultBalances, the owner just names the vault
+        vault.require_owner(&pinocchio_system::ID)?;
+
+        let (vault_key, _) = find_program_address(&[b"vault", owner.key()], &crate::ID);
+        if vault.key().ne(&vault_key) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        check_hold(owner, hold)?;
+
+        Ok(Self { vault, hold })
+    }
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SimulateWithdraw<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = SimulateWithdrawAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+//simulate withdraw, read only
+impl<'a> SimulateWithdraw<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &5;
+
+    // return data is the little-endian u64 of lamports a Withdraw without an amount would pay
+    // out right now, the most any Withdraw may ask for, computed by the same helper; 0 where
+    // Withdraw would fail for lack of funds or because the vault is still locked or in its
+    // hold period
+    pub fn process(&mut self) -> ProgramResult {
+        let lamports = match withdraw_blocked(self.accounts.hold)? {
+            Some(_) => 0,
+            None => withdrawable(self.accounts.vault)?,
+        };
+        set_return_data(&lamports.to_le_bytes());
+
         Ok(())
     }
 }
+
+//validating the min hold accounts
+impl<'a> TryFrom<&'a [AccountInfo]> for SetMinHoldAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, hold, _] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(owner)?;
+
+        let bump = check_hold(owner, hold)?;
+
+        Ok(Self {
+            owner,
+            hold,
+            bumps: [bump],
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for SetMinHold<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = SetMinHoldAccounts::try_from(accounts)?;
+
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let min_hold_seconds = u64::from_le_bytes(data.try_into().unwrap());
+
+        Ok(Self {
+            accounts,
+            min_hold_seconds,
+        })
+    }
+}
+
+//min hold instruction
+impl<'a> SetMinHold<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &6;
+
+    // creates the hold account on first use; the cooldown counts from the next deposit, as
+    // none before it was recorded. Raising it applies to the current hold period too,
+    // lowering it is refused until that period is over.
+    pub fn process(&mut self) -> ProgramResult {
+        init_hold(self.accounts.owner, self.accounts.hold, &self.accounts.bumps)?;
+
+        VaultHold::load_mut(&mut self.accounts.hold.try_borrow_mut_data()?)?
+            .set_min_hold(self.min_hold_seconds, Clock::get()?.unix_timestamp)
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for LockVault<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        // same accounts as SetMinHold, both only write the owner's hold account
+        let accounts = SetMinHoldAccounts::try_from(accounts)?;
+
+        if data.len() != size_of::<i64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let unlock_at = i64::from_le_bytes(data.try_into().unwrap());
+
+        Ok(Self {
+            accounts,
+            unlock_at,
+        })
+    }
+}
+
+//lock vault instruction
+impl<'a> LockVault<'a> {
+    // 2 is GetVaultBalances, so the lock takes the next free one after SetMinHold
+    pub const DISCRIMINATOR: &'a u8 = &7;
+
+    // commits everything in the vault until `unlock_at`. A lock can only be pushed further
+    // out, never shortened or lifted, or it would commit nothing.
+    pub fn process(&mut self) -> ProgramResult {
+        if self.unlock_at <= Clock::get()?.unix_timestamp {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        init_hold(self.accounts.owner, self.accounts.hold, &self.accounts.bumps)?;
+
+        let mut data = self.accounts.hold.try_borrow_mut_data()?;
+        let hold = VaultHold::load_mut(&mut data)?;
+        if self.unlock_at < hold.unlock_at() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        hold.unlock_at = self.unlock_at.to_le_bytes();
+
+        Ok(())
+    }
+}
+
+// one per instruction, each dispatched on its own in lib.rs
+pub const DISCRIMINATORS: &[u8] = &[
+    *Deposit::DISCRIMINATOR,
+    *Withdraw::DISCRIMINATOR,
+    *GetVaultBalances::DISCRIMINATOR,
+    *SetDepositCap::DISCRIMINATOR,
+    *TransferOwner::DISCRIMINATOR,
+    *SimulateWithdraw::DISCRIMINATOR,
+    *SetMinHold::DISCRIMINATOR,
+    *LockVault::DISCRIMINATOR,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RawAccount;
+
+    fn hold(min_hold_seconds: u64, last_deposit_at: i64, unlock_at: i64) -> [u8; VaultHold::LEN] {
+        let mut data = [0u8; VaultHold::LEN];
+        let hold = VaultHold::load_mut(&mut data).unwrap();
+        hold.min_hold_seconds = min_hold_seconds.to_le_bytes();
+        hold.last_deposit_at = last_deposit_at.to_le_bytes();
+        hold.unlock_at = unlock_at.to_le_bytes();
+        data
+    }
+
+    fn blocked(hold: &VaultHold, now: i64) -> Option<ProgramError> {
+        hold.blocked(now).map(ProgramError::from)
+    }
+
+    #[test]
+    fn withdraw_within_and_after_the_cooldown() {
+        let data = hold(3_600, 1_000, 0);
+        let hold = VaultHold::load(&data).unwrap();
+
+        assert_eq!(
+            blocked(hold, 1_000),
+            Some(PinocchioError::CooldownActive.into())
+        );
+        assert_eq!(
+            blocked(hold, 4_599),
+            Some(PinocchioError::CooldownActive.into())
+        );
+        assert_eq!(blocked(hold, 4_600), None);
+
+        // a huge hold saturates instead of wrapping into the past
+        let data = self::hold(u64::MAX, 1_000, 0);
+        assert_eq!(
+            blocked(VaultHold::load(&data).unwrap(), i64::MAX - 1),
+            Some(PinocchioError::CooldownActive.into())
+        );
+    }
+
+    #[test]
+    fn the_lock_comes_before_the_cooldown() {
+        let data = hold(60, 1_000, 2_000);
+        let hold = VaultHold::load(&data).unwrap();
+
+        assert_eq!(
+            blocked(hold, 1_030),
+            Some(PinocchioError::VaultLocked.into())
+        );
+        assert_eq!(
+            blocked(hold, 1_999),
+            Some(PinocchioError::VaultLocked.into())
+        );
+        assert_eq!(blocked(hold, 2_000), None);
+    }
+
+    #[test]
+    fn min_hold_decreases_wait_for_the_running_hold() {
+        let mut data = hold(3_600, 1_000, 0);
+        let hold = VaultHold::load_mut(&mut data).unwrap();
+
+        // raising always applies, and stretches the running hold
+        assert_eq!(hold.set_min_hold(7_200, 2_000), Ok(()));
+        assert_eq!(
+            blocked(hold, 5_000),
+            Some(PinocchioError::CooldownActive.into())
+        );
+
+        // lowering mid-hold would cut it short
+        assert_eq!(
+            hold.set_min_hold(0, 5_000),
+            Err(PinocchioError::CooldownActive.into())
+        );
+        assert_eq!(hold.min_hold_seconds(), 7_200);
+
+        // once the hold is over it can go down again
+        assert_eq!(hold.set_min_hold(0, 8_200), Ok(()));
+        assert_eq!(hold.min_hold_seconds(), 0);
+    }
+
+    #[test]
+    fn cap_limits_the_merged_balance() {
+        let mut data = [0u8; VaultCap::LEN];
+        VaultCap::load_mut(&mut data).unwrap().max_balance = 1_000u64.to_le_bytes();
+        let cap = VaultCap::load(&data).unwrap();
+        assert!(cap.allows(1_000));
+        assert!(!cap.allows(1_001));
+
+        let mut cap_account = RawAccount::new(false, &crate::ID, &data);
+        assert_eq!(check_cap_allows(&cap_account.info(), 1_000), Ok(()));
+        assert_eq!(
+            check_cap_allows(&cap_account.info(), 1_001),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // a zero cap, or none at all, is unlimited
+        let unlimited = [0u8; VaultCap::LEN];
+        assert!(VaultCap::load(&unlimited).unwrap().allows(u64::MAX));
+        let mut no_cap = RawAccount::new(false, &pinocchio_system::ID, &[]);
+        assert_eq!(check_cap_allows(&no_cap.info(), u64::MAX), Ok(()));
+    }
+
+    #[test]
+    fn deposits_fill_the_vault_up_to_its_cap() {
+        let mut data = [0u8; VaultCap::LEN];
+        VaultCap::load_mut(&mut data).unwrap().max_balance = 1_000u64.to_le_bytes();
+        let mut cap = RawAccount::new(false, &crate::ID, &data);
+        let mut vault = RawAccount::new(false, &pinocchio_system::ID, &[]).with_lamports(600);
+        let (vault, cap) = (vault.info(), cap.info());
+
+        assert_eq!(check_deposit(&vault, &cap, 400), Ok(()));
+        assert_eq!(
+            check_deposit(&vault, &cap, 401),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            check_deposit(&vault, &cap, u64::MAX),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+    }
+
+    #[test]
+    fn unlimited_vaults_take_any_deposit() {
+        let mut zero_cap = RawAccount::new(false, &crate::ID, &[0u8; VaultCap::LEN]);
+        let mut no_cap = RawAccount::new(false, &pinocchio_system::ID, &[]);
+        let mut vault = RawAccount::new(false, &pinocchio_system::ID, &[]).with_lamports(600);
+        let vault = vault.info();
+
+        for cap in [zero_cap.info(), no_cap.info()] {
+            assert_eq!(check_deposit(&vault, &cap, u64::MAX - 600), Ok(()));
+            // only the lamport count itself can overflow
+            assert_eq!(
+                check_deposit(&vault, &cap, u64::MAX - 599),
+                Err(ProgramError::ArithmeticOverflow)
+            );
+        }
+    }
+
+    #[test]
+    fn withdraw_takes_part_or_all_of_the_balance() {
+        assert_eq!(withdraw_amount(5_000, None), Ok(5_000));
+        assert_eq!(withdraw_amount(5_000, Some(1)), Ok(1));
+        assert_eq!(withdraw_amount(5_000, Some(5_000)), Ok(5_000));
+
+        // anything past the rent floor, or a vault already at it, is refused
+        assert_eq!(
+            withdraw_amount(5_000, Some(5_001)),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(
+            withdraw_amount(0, None),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(
+            withdraw_amount(0, Some(1)),
+            Err(ProgramError::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn withdraw_amount_is_optional_but_never_zero() {
+        let amount = |data: &[u8]| WithdrawInstructionData::try_from(data).map(|data| data.amount);
+
+        assert_eq!(amount(&[]), Ok(None));
+        assert_eq!(amount(&7u64.to_le_bytes()), Ok(Some(7)));
+        assert_eq!(
+            amount(&0u64.to_le_bytes()),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(amount(&[1u8; 7]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(amount(&[1u8; 9]), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn balances_of_three_vaults_in_one_call() {
+        let mut raw = [
+            RawAccount::new(false, &pinocchio_system::ID, &[]).with_lamports(1_000_000),
+            RawAccount::new(false, &pinocchio_system::ID, &[]).with_lamports(0),
+            RawAccount::new(false, &pinocchio_system::ID, &[]).with_lamports(u64::MAX),
+        ];
+        let vaults = raw.each_mut().map(|vault| vault.info());
+
+        let mut buf = [0u8; MAX_BALANCE_VAULTS * size_of::<u64>()];
+        let balances = pack_balances(&vaults, &mut buf).unwrap();
+
+        assert_eq!(balances.len(), 3 * size_of::<u64>());
+        assert_eq!(balances[0..8], 1_000_000u64.to_le_bytes());
+        assert_eq!(balances[8..16], 0u64.to_le_bytes());
+        assert_eq!(balances[16..24], u64::MAX.to_le_bytes());
+    }
+
+    #[test]
+    fn balances_need_one_to_max_vaults() {
+        let mut buf = [0u8; MAX_BALANCE_VAULTS * size_of::<u64>()];
+        assert_eq!(
+            pack_balances(&[], &mut buf),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut raw: [RawAccount; MAX_BALANCE_VAULTS + 1] =
+            core::array::from_fn(|_| RawAccount::new(false, &pinocchio_system::ID, &[]));
+        let vaults = raw.each_mut().map(|vault| vault.info());
+        assert!(pack_balances(&vaults[..MAX_BALANCE_VAULTS], &mut buf).is_ok());
+        assert_eq!(
+            pack_balances(&vaults, &mut buf),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn discriminators_are_unique() {
+        for (i, discriminator) in DISCRIMINATORS.iter().enumerate() {
+            assert!(!DISCRIMINATORS[i + 1..].contains(discriminator));
+        }
+    }
+}
@@ -1,6 +1,7 @@
 #![no_std]
 
 use pinocchio::{account_info::AccountInfo, entrypoint, nostd_panic_handler, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+use pinocchio_log::log;
 
 entrypoint!(process_instruction);
 nostd_panic_handler!();
@@ -8,6 +9,22 @@ nostd_panic_handler!();
 pub mod instructions;
 pub use instructions::*;
 
+pub mod helpers;
+pub use helpers::*;
+
+#[path = "../../shared/accounts.rs"]
+pub mod accounts;
+pub use accounts::*;
+
+#[cfg(test)]
+#[path = "../../shared/raw_account.rs"]
+mod raw_account;
+#[cfg(test)]
+pub(crate) use raw_account::RawAccount;
+
+pub mod errors;
+pub use errors::*;
+
 pub const ID: Pubkey = [
     0x0f, 0x1e, 0x6b, 0x14, 0x21, 0xc0, 0x4a, 0x07,
     0x04, 0x31, 0x26, 0x5c, 0x19, 0xc5, 0xbb, 0xee,
@@ -15,6 +32,8 @@ pub const ID: Pubkey = [
     0x8e, 0xf8, 0xaf, 0x70, 0x47, 0xdc, 0x11, 0xf7,
 ];
 
+// discriminators: 0 Deposit, 1 Withdraw, 2 GetVaultBalances, 3 SetDepositCap,
+// 4 TransferOwner, 5 SimulateWithdraw, 6 SetMinHold, 7 LockVault
 fn process_instruction(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -22,7 +41,25 @@ fn process_instruction(
 ) -> ProgramResult {
     match instruction_data.split_first() {
         Some((Deposit::DISCRIMINATOR, data)) => Deposit::try_from((data, accounts))?.process(),
-        Some((Withdraw::DISCRIMINATOR, _)) => Withdraw::try_from(accounts)?.process(),
-        _ => Err(ProgramError::InvalidInstructionData),
+        Some((Withdraw::DISCRIMINATOR, data)) => Withdraw::try_from((data, accounts))?.process(),
+        Some((GetVaultBalances::DISCRIMINATOR, _)) => {
+            GetVaultBalances::try_from(accounts)?.process()
+        }
+        Some((SetDepositCap::DISCRIMINATOR, data)) => {
+            SetDepositCap::try_from((data, accounts))?.process()
+        }
+        Some((TransferOwner::DISCRIMINATOR, _)) => TransferOwner::try_from(accounts)?.process(),
+        Some((SimulateWithdraw::DISCRIMINATOR, _)) => {
+            SimulateWithdraw::try_from(accounts)?.process()
+        }
+        Some((SetMinHold::DISCRIMINATOR, data)) => {
+            SetMinHold::try_from((data, accounts))?.process()
+        }
+        Some((LockVault::DISCRIMINATOR, data)) => LockVault::try_from((data, accounts))?.process(),
+        Some((discriminator, _)) => {
+            log!("Unknown instruction discriminator: {}", *discriminator);
+            Err(ProgramError::InvalidInstructionData)
+        }
+        None => Err(ProgramError::InvalidInstructionData),
     }
 }
\ No newline at end of file
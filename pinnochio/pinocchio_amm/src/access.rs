@@ -0,0 +1,271 @@
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+
+use crate::Config;
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum AccessMode {
+    Open = 0u8,
+    Whitelist = 1u8,
+    Blacklist = 2u8,
+}
+
+impl TryFrom<u8> for AccessMode {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AccessMode::Open),
+            1 => Ok(AccessMode::Whitelist),
+            2 => Ok(AccessMode::Blacklist),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+const INDEX_LEN: usize = size_of::<u32>();
+const NODE_LEN: usize = size_of::<[u8; 32]>();
+
+/// Checks `user` against the pool's access list.
+///
+/// The list is a merkle tree over the lexicographically sorted user keys, with leaves
+/// `sha256(0x00 | key)` and nodes `sha256(0x01 | left | right)`. Proof layouts:
+/// - whitelist: `index: u32 | siblings: [[u8; 32]; depth]`
+/// - blacklist: `low_key | high_key | low_index: u32 | low_siblings | high_siblings`,
+///   proving two adjacent leaves that bracket `user`. List builders should include the
+///   all-zero and all-`0xff` keys as sentinels so every user can be bracketed.
+pub fn verify_access(config: &Config, user: &Pubkey, proof: &[u8]) -> ProgramResult {
+    match config.access_mode()? {
+        AccessMode::Open => Ok(()),
+        AccessMode::Whitelist => {
+            let (index, siblings) = proof
+                .split_at_checked(INDEX_LEN)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            let index = u32::from_le_bytes(index.try_into().unwrap());
+
+            if !verify_leaf(config.access_list_root(), user, index, siblings)? {
+                return Err(ProgramError::InvalidArgument);
+            }
+            Ok(())
+        }
+        AccessMode::Blacklist => {
+            let (low_key, proof) = proof
+                .split_at_checked(NODE_LEN)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            let (high_key, proof) = proof
+                .split_at_checked(NODE_LEN)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            let (low_index, siblings) = proof
+                .split_at_checked(INDEX_LEN)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            if (siblings.len() % (NODE_LEN * 2)).ne(&0) {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            let (low_siblings, high_siblings) = siblings.split_at(siblings.len() / 2);
+
+            let low_key: Pubkey = low_key.try_into().unwrap();
+            let high_key: Pubkey = high_key.try_into().unwrap();
+            let low_index = u32::from_le_bytes(low_index.try_into().unwrap());
+            let high_index = low_index
+                .checked_add(1)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+
+            // a listed user can never sit strictly between two adjacent leaves
+            if !(low_key.lt(user) && user.lt(&high_key)) {
+                return Err(ProgramError::InvalidArgument);
+            }
+            if !verify_leaf(config.access_list_root(), &low_key, low_index, low_siblings)?
+                || !verify_leaf(config.access_list_root(), &high_key, high_index, high_siblings)?
+            {
+                return Err(ProgramError::InvalidArgument);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn verify_leaf(
+    root: &[u8; 32],
+    key: &Pubkey,
+    index: u32,
+    siblings: &[u8],
+) -> Result<bool, ProgramError> {
+    if (siblings.len() % NODE_LEN).ne(&0) || siblings.len() / NODE_LEN > u32::BITS as usize {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut node = hashv(&[LEAF_PREFIX, key]);
+    let mut position = index;
+    for sibling in siblings.chunks_exact(NODE_LEN) {
+        node = match position & 1 {
+            0 => hashv(&[NODE_PREFIX, &node, sibling]),
+            _ => hashv(&[NODE_PREFIX, sibling, &node]),
+        };
+        position >>= 1;
+    }
+
+    // leftover bits mean the index points past the end of a tree of this depth
+    Ok(position.eq(&0) && node.eq(root))
+}
+
+#[cfg(target_os = "solana")]
+fn hashv(vals: &[&[u8]]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    unsafe {
+        pinocchio::syscalls::sol_sha256(
+            vals as *const _ as *const u8,
+            vals.len() as u64,
+            hash.as_mut_ptr(),
+        );
+    }
+    hash
+}
+
+// there is no sha256 syscall off-chain, so host builds hash the same bytes in software
+#[cfg(not(target_os = "solana"))]
+fn hashv(vals: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = sha2_const_stable::Sha256::new();
+    for val in vals {
+        hasher = hasher.update(val);
+    }
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sorted, with the all-zero and all-0xff sentinels at either end
+    const LIST: [Pubkey; 4] = [[0x00; 32], [0x10; 32], [0x20; 32], [0xff; 32]];
+
+    fn leaf(key: &Pubkey) -> [u8; 32] {
+        hashv(&[LEAF_PREFIX, key])
+    }
+
+    fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        hashv(&[NODE_PREFIX, left, right])
+    }
+
+    fn root() -> [u8; 32] {
+        let leaves = LIST.map(|key| leaf(&key));
+        node(&node(&leaves[0], &leaves[1]), &node(&leaves[2], &leaves[3]))
+    }
+
+    // siblings of leaf `index`, bottom up
+    fn siblings(index: usize) -> [u8; 64] {
+        let leaves = LIST.map(|key| leaf(&key));
+        let pair = index & !1;
+        let other = 2 - pair;
+        let mut siblings = [0u8; 64];
+        siblings[..32].copy_from_slice(&leaves[index ^ 1]);
+        siblings[32..].copy_from_slice(&node(&leaves[other], &leaves[other + 1]));
+        siblings
+    }
+
+    fn whitelist_proof(index: u32) -> [u8; 68] {
+        let mut proof = [0u8; 68];
+        proof[..4].copy_from_slice(&index.to_le_bytes());
+        proof[4..].copy_from_slice(&siblings(index as usize));
+        proof
+    }
+
+    fn blacklist_proof(low_index: u32) -> [u8; 196] {
+        let low = low_index as usize;
+        let mut proof = [0u8; 196];
+        proof[..32].copy_from_slice(&LIST[low]);
+        proof[32..64].copy_from_slice(&LIST[low + 1]);
+        proof[64..68].copy_from_slice(&low_index.to_le_bytes());
+        proof[68..132].copy_from_slice(&siblings(low));
+        proof[132..].copy_from_slice(&siblings(low + 1));
+        proof
+    }
+
+    fn with_config(mode: AccessMode, check: impl FnOnce(&Config)) {
+        let mut data = [0u8; Config::LEN];
+        let config = unsafe { Config::from_bytes_unchecked_mut(&mut data) };
+        config.set_access_control(mode, root()).unwrap();
+        check(config);
+    }
+
+    #[test]
+    fn hashv_is_sha256_of_the_joined_slices() {
+        let abc = [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad,
+        ];
+        assert_eq!(hashv(&[b"abc"]), abc);
+        assert_eq!(hashv(&[b"a", b"", b"bc"]), abc);
+    }
+
+    #[test]
+    fn whitelist_admits_listed_users_only() {
+        with_config(AccessMode::Whitelist, |config| {
+            for (index, user) in LIST.iter().enumerate() {
+                assert_eq!(
+                    verify_access(config, user, &whitelist_proof(index as u32)),
+                    Ok(())
+                );
+            }
+
+            // a valid proof for one user says nothing about another
+            assert_eq!(
+                verify_access(config, &[0x11; 32], &whitelist_proof(1)),
+                Err(ProgramError::InvalidArgument)
+            );
+            // nor does the right path at the wrong index, or an index past the tree
+            assert_eq!(
+                verify_access(config, &LIST[1], &whitelist_proof(0)),
+                Err(ProgramError::InvalidArgument)
+            );
+            let mut proof = whitelist_proof(1);
+            proof[..4].copy_from_slice(&5u32.to_le_bytes());
+            assert_eq!(
+                verify_access(config, &LIST[1], &proof),
+                Err(ProgramError::InvalidArgument)
+            );
+            assert_eq!(
+                verify_access(config, &LIST[1], &[]),
+                Err(ProgramError::InvalidInstructionData)
+            );
+        });
+    }
+
+    #[test]
+    fn blacklist_admits_users_bracketed_by_adjacent_leaves() {
+        with_config(AccessMode::Blacklist, |config| {
+            assert_eq!(
+                verify_access(config, &[0x11; 32], &blacklist_proof(1)),
+                Ok(())
+            );
+            assert_eq!(
+                verify_access(config, &[0x05; 32], &blacklist_proof(0)),
+                Ok(())
+            );
+
+            // a listed user sits on a leaf, never strictly between two
+            for low_index in 0..3 {
+                assert_eq!(
+                    verify_access(config, &LIST[1], &blacklist_proof(low_index)),
+                    Err(ProgramError::InvalidArgument)
+                );
+            }
+            // the bracket must be the one the tree proves
+            let mut proof = blacklist_proof(1);
+            proof[32..64].copy_from_slice(&[0x30; 32]);
+            assert_eq!(
+                verify_access(config, &[0x21; 32], &proof),
+                Err(ProgramError::InvalidArgument)
+            );
+        });
+    }
+
+    #[test]
+    fn open_pools_need_no_proof() {
+        with_config(AccessMode::Open, |config| {
+            assert_eq!(verify_access(config, &[0x11; 32], &[]), Ok(()));
+        });
+    }
+}
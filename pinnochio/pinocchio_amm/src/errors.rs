@@ -0,0 +1,39 @@
+use pinocchio::program_error::ProgramError;
+
+/// Program errors, surfaced as `ProgramError::Custom(code)`. The codes are part of the
+/// program's interface: new variants only ever go at the end, none is renumbered or reused.
+#[derive(Clone, PartialEq)]
+pub enum PinocchioError {
+    VaultNotInitialized = 0,
+    PoolNotSeeded = 1,
+    PoolAlreadySeeded = 2,
+    SwapExceedsCap = 3,
+    AccountAliased = 4,
+    MaxAmountInExceeded = 5,
+    NotDust = 6,
+    ZeroLpMinted = 7,
+    RebalanceThresholdNotMet = 8,
+    POLTargetReached = 9,
+    TimelockActive = 10,
+    NonCanonicalBump = 11,
+    InsufficientUserFunds = 12,
+    AmountTooLarge = 13,
+    /// The curve asks for more than the caller's maximum, or pays less than its minimum.
+    SlippageExceeded = 14,
+    /// The transaction's expiration has passed.
+    Expired = 15,
+    /// The config was never initialized.
+    PoolNotInitialized = 16,
+    /// The pool is initialized but its state doesn't allow this instruction.
+    PoolNotActive = 17,
+    /// A vault isn't the config's ATA for its mint, or doesn't hold what the config expects.
+    InvalidVault = 18,
+    /// A TWAP window reaches back past the oldest price observation the pool keeps.
+    TwapWindowTooLong = 19,
+}
+
+impl From<PinocchioError> for ProgramError {
+    fn from(e: PinocchioError) -> Self {
+        Self::Custom(e as u32)
+    }
+}
@@ -0,0 +1,160 @@
+//! Events emitted with `sol_log_data`, one data entry per event: a one-byte tag, then the
+//! fields in declaration order, keys as raw bytes and integers little-endian. Indexers can
+//! decode them with `decode` or the same fixed layout instead of parsing log text.
+
+use pinocchio::{log::sol_log_data, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{read_pubkey, read_u64};
+
+pub struct DepositEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub lp_amount: u64,
+    pub x: u64,
+    pub y: u64,
+    // the user's LP balance once this deposit's LP is minted
+    pub lp_balance: u64,
+}
+
+impl DepositEvent {
+    pub const TAG: u8 = 0;
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 + 8;
+
+    pub fn encode(&self) -> [u8; Self::LEN] {
+        let mut data = [0u8; Self::LEN];
+        data[0] = Self::TAG;
+        data[1..33].copy_from_slice(&self.pool);
+        data[33..65].copy_from_slice(&self.user);
+        data[65..73].copy_from_slice(&self.lp_amount.to_le_bytes());
+        data[73..81].copy_from_slice(&self.x.to_le_bytes());
+        data[81..89].copy_from_slice(&self.y.to_le_bytes());
+        data[89..97].copy_from_slice(&self.lp_balance.to_le_bytes());
+        data
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len().ne(&Self::LEN) || data[0].ne(&Self::TAG) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            pool: read_pubkey(data, 1)?,
+            user: read_pubkey(data, 33)?,
+            lp_amount: read_u64(data, 65)?,
+            x: read_u64(data, 73)?,
+            y: read_u64(data, 81)?,
+            lp_balance: read_u64(data, 89)?,
+        })
+    }
+
+    pub fn emit(&self) {
+        sol_log_data(&[&self.encode()]);
+    }
+}
+
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    // true when x went in and y came out
+    pub is_x: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+impl SwapEvent {
+    pub const TAG: u8 = 1;
+    pub const LEN: usize = 1 + 32 + 32 + 1 + 8 + 8;
+
+    pub fn encode(&self) -> [u8; Self::LEN] {
+        let mut data = [0u8; Self::LEN];
+        data[0] = Self::TAG;
+        data[1..33].copy_from_slice(&self.pool);
+        data[33..65].copy_from_slice(&self.user);
+        data[65] = self.is_x as u8;
+        data[66..74].copy_from_slice(&self.amount_in.to_le_bytes());
+        data[74..82].copy_from_slice(&self.amount_out.to_le_bytes());
+        data
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len().ne(&Self::LEN) || data[0].ne(&Self::TAG) || data[65] > 1 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            pool: read_pubkey(data, 1)?,
+            user: read_pubkey(data, 33)?,
+            is_x: data[65].eq(&1),
+            amount_in: read_u64(data, 66)?,
+            amount_out: read_u64(data, 74)?,
+        })
+    }
+
+    pub fn emit(&self) {
+        sol_log_data(&[&self.encode()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposit_event_round_trips() {
+        let event = DepositEvent {
+            pool: [1u8; 32],
+            user: [2u8; 32],
+            lp_amount: 3,
+            x: 4,
+            y: u64::MAX,
+            lp_balance: 6,
+        };
+        let data = event.encode();
+        assert_eq!(data[0], DepositEvent::TAG);
+
+        let decoded = DepositEvent::decode(&data).unwrap();
+        assert_eq!((decoded.pool, decoded.user), (event.pool, event.user));
+        assert_eq!(
+            (decoded.lp_amount, decoded.x, decoded.y, decoded.lp_balance),
+            (3, 4, u64::MAX, 6)
+        );
+    }
+
+    #[test]
+    fn swap_event_round_trips() {
+        for is_x in [true, false] {
+            let event = SwapEvent {
+                pool: [1u8; 32],
+                user: [2u8; 32],
+                is_x,
+                amount_in: 1_000,
+                amount_out: 997,
+            };
+            let decoded = SwapEvent::decode(&event.encode()).unwrap();
+            assert_eq!((decoded.pool, decoded.user), (event.pool, event.user));
+            assert_eq!(decoded.is_x, is_x);
+            assert_eq!((decoded.amount_in, decoded.amount_out), (1_000, 997));
+        }
+    }
+
+    #[test]
+    fn decode_refuses_other_events_and_lengths() {
+        let swap = SwapEvent {
+            pool: [1u8; 32],
+            user: [2u8; 32],
+            is_x: true,
+            amount_in: 1,
+            amount_out: 1,
+        }
+        .encode();
+        assert!(DepositEvent::decode(&swap).is_err());
+        assert!(SwapEvent::decode(&swap[..SwapEvent::LEN - 1]).is_err());
+
+        let mut retagged = swap;
+        retagged[0] = DepositEvent::TAG;
+        assert!(SwapEvent::decode(&retagged).is_err());
+
+        // is_x is a single 0 or 1 byte
+        let mut bad_direction = swap;
+        bad_direction[65] = 2;
+        assert!(SwapEvent::decode(&bad_direction).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{log_pool_stats, SECONDS_PER_DAY};
+
+pub const MAX_BATCH_POOLS: usize = 10;
+
+// config, vault_x, vault_y, mint_lp
+const ACCOUNTS_PER_POOL: usize = 4;
+
+pub struct BatchGetPoolStatsAccounts<'a> {
+    pub token_program: &'a AccountInfo,
+    pub pool_accounts: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for BatchGetPoolStatsAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [token_program, pool_accounts @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        if pool_accounts.is_empty()
+            || (pool_accounts.len() % ACCOUNTS_PER_POOL).ne(&0)
+            || pool_accounts.len() > ACCOUNTS_PER_POOL * MAX_BATCH_POOLS
+        {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        Ok(Self {
+            token_program,
+            pool_accounts,
+        })
+    }
+}
+
+pub struct BatchGetPoolStats<'a> {
+    pub accounts: BatchGetPoolStatsAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for BatchGetPoolStats<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = BatchGetPoolStatsAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> BatchGetPoolStats<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &11;
+
+    // one log_data entry per pool, in account order
+    pub fn process(&mut self) -> ProgramResult {
+        let day = Clock::get()?.unix_timestamp as u64 / SECONDS_PER_DAY;
+
+        for accounts in self.accounts.pool_accounts.chunks_exact(ACCOUNTS_PER_POOL) {
+            let [config, vault_x, vault_y, mint_lp] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            log_pool_stats(
+                config,
+                vault_x,
+                vault_y,
+                mint_lp,
+                self.accounts.token_program,
+                day,
+            )?;
+        }
+
+        Ok(())
+    }
+}
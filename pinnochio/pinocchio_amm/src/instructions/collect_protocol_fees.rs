@@ -0,0 +1,122 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    ProgramResult,
+};
+use pinocchio_token::instructions::Transfer;
+
+use crate::{assert_same_mint, check_vault_address, Config, SignerAccount};
+
+pub struct CollectProtocolFeesAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub treasury_x: &'a AccountInfo,
+    pub treasury_y: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CollectProtocolFeesAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, vault_x, vault_y, treasury_x, treasury_y, token_program] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self {
+            authority,
+            config,
+            vault_x,
+            vault_y,
+            treasury_x,
+            treasury_y,
+            token_program,
+        })
+    }
+}
+
+pub struct CollectProtocolFees<'a> {
+    pub accounts: CollectProtocolFeesAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CollectProtocolFees<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = CollectProtocolFeesAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> CollectProtocolFees<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &15;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        for (vault, mint, bump) in [
+            (
+                self.accounts.vault_x,
+                config.mint_x(),
+                config.vault_x_bump(),
+            ),
+            (
+                self.accounts.vault_y,
+                config.mint_y(),
+                config.vault_y_bump(),
+            ),
+        ] {
+            check_vault_address(
+                vault,
+                self.accounts.config,
+                self.accounts.token_program,
+                mint,
+                bump,
+            )?;
+        }
+        assert_same_mint(self.accounts.vault_x, self.accounts.treasury_x)?;
+        assert_same_mint(self.accounts.vault_y, self.accounts.treasury_y)?;
+
+        let seed_binding = config.seed().to_le_bytes();
+        let config_bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_binding),
+            Seed::from(config.mint_x()),
+            Seed::from(config.mint_y()),
+            Seed::from(&config_bump),
+        ];
+        let signer = [Signer::from(&config_seeds)];
+
+        let (fees_x, fees_y) = (config.fees_collected_x(), config.fees_collected_y());
+        for (vault, treasury, amount) in [
+            (self.accounts.vault_x, self.accounts.treasury_x, fees_x),
+            (self.accounts.vault_y, self.accounts.treasury_y, fees_y),
+        ] {
+            if amount.eq(&0) {
+                continue;
+            }
+            Transfer {
+                from: vault,
+                to: treasury,
+                authority: self.accounts.config,
+                amount,
+            }
+            .invoke_signed(&signer)?;
+        }
+        drop(config);
+
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.reset_fees_collected();
+        config.debit_expected_balances(fees_x, fees_y);
+
+        Ok(())
+    }
+}
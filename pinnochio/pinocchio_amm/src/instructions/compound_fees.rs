@@ -0,0 +1,111 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_log::log;
+use pinocchio_token::state::TokenAccount;
+
+use crate::{check_vault_address, Config, SignerAccount};
+
+pub struct CompoundFeesAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CompoundFeesAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, vault_x, vault_y, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self {
+            authority,
+            config,
+            vault_x,
+            vault_y,
+            token_program,
+        })
+    }
+}
+
+pub struct CompoundFees<'a> {
+    pub accounts: CompoundFeesAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CompoundFees<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = CompoundFeesAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> CompoundFees<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &16;
+
+    /// Hands accrued protocol fees back to LPs. The fees already sit in the vaults, so
+    /// compounding only stops excluding them from the reserves: every LP token's share grows
+    /// without minting. Only the part matching the current x:y ratio is released so the
+    /// price does not move; the one-sided remainder stays accrued for a later call.
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        for (vault, mint, bump) in [
+            (
+                self.accounts.vault_x,
+                config.mint_x(),
+                config.vault_x_bump(),
+            ),
+            (
+                self.accounts.vault_y,
+                config.mint_y(),
+                config.vault_y_bump(),
+            ),
+        ] {
+            check_vault_address(
+                vault,
+                self.accounts.config,
+                self.accounts.token_program,
+                mint,
+                bump,
+            )?;
+        }
+
+        let (reserve_x, reserve_y) = config.pool_reserves(
+            TokenAccount::from_account_info(self.accounts.vault_x)?.amount(),
+            TokenAccount::from_account_info(self.accounts.vault_y)?.amount(),
+        );
+        if reserve_x.eq(&0) || reserve_y.eq(&0) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (fees_x, fees_y) = (config.fees_collected_x() as u128, config.fees_collected_y() as u128);
+        let (reserve_x, reserve_y) = (reserve_x as u128, reserve_y as u128);
+        // whichever side runs out first at the pool ratio bounds the release
+        let (release_x, release_y) = match fees_x * reserve_y <= fees_y * reserve_x {
+            true => (fees_x, fees_x * reserve_y / reserve_x),
+            false => (fees_y * reserve_x / reserve_y, fees_y),
+        };
+
+        config.release_fees_collected(release_x as u64, release_y as u64);
+        config.update_price_cumulatives(
+            (reserve_x + release_x) as u64,
+            (reserve_y + release_y) as u64,
+            Clock::get()?.unix_timestamp,
+        );
+        log!("CompoundFees: released x {} y {}", release_x as u64, release_y as u64);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use pinocchio::{
+    program_error::ProgramError,
+    pubkey::{self, find_program_address, Pubkey},
+    ProgramResult,
+};
+use pinocchio_log::log;
+
+use crate::{read_pubkey, read_u64};
+
+pub struct ComputePoolAddressInstructionData {
+    pub seed: u64,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+}
+
+impl<'a> TryFrom<&'a [u8]> for ComputePoolAddressInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() + size_of::<Pubkey>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let seed = read_u64(data, 0)?;
+        let mint_x = read_pubkey(data, 8)?;
+        let mint_y = read_pubkey(data, 40)?;
+        Ok(Self {
+            seed,
+            mint_x,
+            mint_y,
+        })
+    }
+}
+
+pub struct ComputePoolAddress {
+    pub instruction_data: ComputePoolAddressInstructionData,
+}
+
+impl<'a> TryFrom<&'a [u8]> for ComputePoolAddress {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let instruction_data = ComputePoolAddressInstructionData::try_from(data)?;
+        Ok(Self { instruction_data })
+    }
+}
+
+impl ComputePoolAddress {
+    pub const DISCRIMINATOR: &'static u8 = &8;
+
+    // meant for simulation: logs the config PDA and LP mint PDA exactly as Initialize expects them
+    pub fn process(&mut self) -> ProgramResult {
+        let (config, config_bump) = find_program_address(
+            &[
+                b"config",
+                &self.instruction_data.seed.to_le_bytes(),
+                &self.instruction_data.mint_x,
+                &self.instruction_data.mint_y,
+            ],
+            &crate::ID,
+        );
+        let (mint_lp, lp_bump) = find_program_address(&[b"mint_lp", &config], &crate::ID);
+
+        log!("config bump: {}", config_bump);
+        pubkey::log(&config);
+        log!("mint_lp bump: {}", lp_bump);
+        pubkey::log(&mint_lp);
+
+        Ok(())
+    }
+}
@@ -1,8 +1,17 @@
-use constant_product_curve::ConstantProduct;
-use pinocchio::{account_info::AccountInfo, instruction::{Seed, Signer}, msg, program_error::ProgramError, pubkey::find_program_address, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{clock::Clock, Sysvar}, ProgramResult};
 use pinocchio_token::{instructions::{MintTo, Transfer}, state::{Mint, TokenAccount}};
 
-use crate::{AmmState, Config};
+use crate::{
+    assert_distinct, assert_same_mint, check_not_frozen, check_vault_address,
+    check_vault_initialized, lp_to_deposit_amounts, read_i64, read_u64, signer_seeds,
+    underlying_to_lp, validate_expiration, verify_access, AmmState, Config, DepositEvent,
+    PinocchioError, SignerAccount,
+};
+
+// largest `amount`, `max_x` or `max_y` a single deposit accepts. The curve multiplies two
+// amounts and its 10^6 precision factor in u128, which stays in range while both are below
+// ~1.8 * 10^16; 10^16 leaves headroom and still covers 10M tokens at 9 decimals per deposit.
+pub const MAX_DEPOSIT_AMOUNT: u64 = 10_000_000_000_000_000;
 
 pub struct DepositAccounts<'a> {
     pub user: &'a AccountInfo,
@@ -22,39 +31,42 @@ impl<'a> TryFrom<&'a [AccountInfo]> for DepositAccounts<'a> {
         let [user, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program] = accounts else {
             return Err(ProgramError::NotEnoughAccountKeys); 
         };
+        SignerAccount::check(user)?;
+        assert_distinct(&[config.key(), mint_lp.key(), vault_x.key(), vault_y.key()])?;
         Ok(Self { user, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program })
     }
 }   
 
-pub struct DepositInstructionData {
+pub struct DepositInstructionData<'a> {
     pub amount: u64,
     pub max_x: u64,
     pub max_y: u64,
     pub expiration: i64, 
+    pub access_proof: &'a [u8],
 }
 
-impl<'a> TryFrom<&'a [u8]> for DepositInstructionData {
+impl<'a> TryFrom<&'a [u8]> for DepositInstructionData<'a> {
     type Error = ProgramError;
     fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
-        msg!(&format!("{}", data.len()));
-        if data.len() != size_of::<u64>() * 3 + size_of::<i64>() {
-            return Err(ProgramError::InvalidInstructionData); 
-        }
-        let amount = u64::from_le_bytes(data[0..8].try_into().unwrap());
-        let max_x = u64::from_le_bytes(data[8..16].try_into().unwrap());
-        let max_y = u64::from_le_bytes(data[16..24].try_into().unwrap());
-        let expiration = i64::from_le_bytes(data[24..32].try_into().unwrap());
-        let now = Clock::get()?.unix_timestamp;
-        if amount.eq(&0) || max_x.eq(&0) || max_y.eq(&0) || now > expiration{
+        #[cfg(feature = "verbose-logs")]
+        pinocchio_log::log!("Deposit: instruction data len {}", data.len());
+        let (data, access_proof) = data
+            .split_at_checked(size_of::<u64>() * 3 + size_of::<i64>())
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let amount = read_u64(data, 0)?;
+        let max_x = read_u64(data, 8)?;
+        let max_y = read_u64(data, 16)?;
+        let expiration = read_i64(data, 24)?;
+        if amount.eq(&0) || max_x.eq(&0) || max_y.eq(&0) {
             return Err(ProgramError::InvalidInstructionData); 
         }
-        Ok(Self { amount, max_x, max_y, expiration })
+        Ok(Self { amount, max_x, max_y, expiration, access_proof })
     }
 }
 
 pub struct Deposit<'a> {
     pub accounts: DepositAccounts<'a>,
-    pub instruction_data: DepositInstructionData
+    pub instruction_data: DepositInstructionData<'a>
 }
 impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Deposit<'a> {
     type Error = ProgramError;
@@ -68,35 +80,51 @@ impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Deposit<'a> {
 impl<'a> Deposit<'a> {
     pub const DISCRIMINATOR: &'a u8 = &1;
     pub fn process(&mut self) -> ProgramResult {
-        let config = Config::load(self.accounts.config)?;
+        self.execute(false, Clock::get()?.unix_timestamp)
+    }
 
-        if config.state() != AmmState::Initialized as u8 {
-            return Err(ProgramError::InvalidAccountData);
+    // `seeding` is set by SeedDeposit, the only way to set the price of an empty pool. `now`
+    // is the one clock read of the calling instruction, every time check in it uses that value
+    pub(crate) fn execute(&mut self, seeding: bool, now: i64) -> ProgramResult {
+        // kept out of the parser so DepositInstructionData::try_from stays a pure byte parse
+        validate_expiration(now, self.instruction_data.expiration)?;
+        // checked here rather than in the parser so seeding through InitializeWithLiquidity,
+        // which builds its instruction data directly, is bounded too
+        if self.instruction_data.amount > MAX_DEPOSIT_AMOUNT
+            || self.instruction_data.max_x > MAX_DEPOSIT_AMOUNT
+            || self.instruction_data.max_y > MAX_DEPOSIT_AMOUNT
+        {
+            return Err(PinocchioError::AmountTooLarge.into());
         }
-        let (vault_x, _) = find_program_address(
-            &[
-                self.accounts.config.key(),
-                self.accounts.token_program.key(),
-                config.mint_x() 
-            ], 
-            &pinocchio_associated_token_account::ID
-        );
 
-        if vault_x.ne(self.accounts.vault_x.key()) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let (vault_y, _) = find_program_address(
-            &[
-                self.accounts.config.key(),
-                self.accounts.token_program.key(),
-                config.mint_y() 
-            ], 
-            &pinocchio_associated_token_account::ID
-        );
+        let config = Config::load(self.accounts.config)?;
 
-        if vault_y.ne(self.accounts.vault_y.key()) {
-            return Err(ProgramError::InvalidAccountData);
+        match config.state_enum()? {
+            AmmState::Initialized => {}
+            AmmState::Uninitialized => return Err(PinocchioError::PoolNotInitialized.into()),
+            _ => return Err(PinocchioError::PoolNotActive.into()),
         }
+        verify_access(&config, self.accounts.user.key(), self.instruction_data.access_proof)?;
+        check_vault_address(
+            self.accounts.vault_x,
+            self.accounts.config,
+            self.accounts.token_program,
+            config.mint_x(),
+            config.vault_x_bump(),
+        )?;
+        check_vault_address(
+            self.accounts.vault_y,
+            self.accounts.config,
+            self.accounts.token_program,
+            config.mint_y(),
+            config.vault_y_bump(),
+        )?;
+        check_vault_initialized(self.accounts.vault_x)?;
+        check_vault_initialized(self.accounts.vault_y)?;
+        check_not_frozen(self.accounts.user_x_ata)?;
+        check_not_frozen(self.accounts.user_y_ata)?;
+        assert_same_mint(self.accounts.user_x_ata, self.accounts.vault_x)?;
+        assert_same_mint(self.accounts.user_y_ata, self.accounts.vault_y)?;
         let mint_lp = unsafe {
             Mint::from_account_info_unchecked(self.accounts.mint_lp)?
         };
@@ -106,24 +134,59 @@ impl<'a> Deposit<'a> {
         let vault_y = unsafe {
             TokenAccount::from_account_info_unchecked(self.accounts.vault_y)?
         };
-        let (x, y) = match mint_lp.supply() == 0 && vault_x.amount() == 0 && vault_y.amount() == 0 {
+        // the ATA address only pins the mint for the token program we were handed, check
+        // what the vaults actually hold
+        if vault_x.mint().ne(config.mint_x()) || vault_y.mint().ne(config.mint_y()) {
+            return Err(PinocchioError::InvalidVault.into());
+        }
+        // and that the config PDA is the token authority they pay out under
+        if vault_x.owner().ne(self.accounts.config.key())
+            || vault_y.owner().ne(self.accounts.config.key())
+        {
+            return Err(PinocchioError::InvalidVault.into());
+        }
+        let (reserve_x, reserve_y) = config.pool_reserves(vault_x.amount(), vault_y.amount());
+        let is_empty = mint_lp.supply() == 0 && reserve_x == 0 && reserve_y == 0;
+        match (is_empty, seeding) {
+            (true, false) => return Err(PinocchioError::PoolNotSeeded.into()),
+            (false, true) => return Err(PinocchioError::PoolAlreadySeeded.into()),
+            _ => {}
+        }
+        let (x, y) = match is_empty {
             true => (self.instruction_data.max_x, self.instruction_data.max_y),
             false => {
-                let amounts = ConstantProduct::xy_deposit_amounts_from_l(
-                    vault_x.amount(),
-                    vault_y.amount(),
-                    mint_lp.supply(),
+                // rounded up: the depositor pays at least its share, never the pool
+                let (x, y) = lp_to_deposit_amounts(
                     self.instruction_data.amount,
-                    6,
-                )
-                .map_err(|_| ProgramError::InvalidArgument)?;
+                    mint_lp.supply(),
+                    reserve_x,
+                    reserve_y,
+                )?;
+
+                // never mint more LP than the tokens actually deposited are worth at these reserves
+                let implied_lp = underlying_to_lp(x, y, mint_lp.supply(), reserve_x, reserve_y);
+                // a dust deposit into a deep pool is worth less than one LP unit, refuse it
+                // before any tokens move
+                if implied_lp.eq(&0) {
+                    return Err(PinocchioError::ZeroLpMinted.into());
+                }
+                if self.instruction_data.amount > implied_lp {
+                    return Err(PinocchioError::SlippageExceeded.into());
+                }
 
-                (amounts.x, amounts.y)
+                (x, y)
             }
         };
 
         if !(x <= self.instruction_data.max_x && y <= self.instruction_data.max_y) {
-            return Err(ProgramError::InvalidArgument); 
+            return Err(PinocchioError::SlippageExceeded.into());
+        }
+        // the token program refuses an overdraft as well, but with an error that doesn't say
+        // which side was short
+        if TokenAccount::from_account_info(self.accounts.user_x_ata)?.amount() < x
+            || TokenAccount::from_account_info(self.accounts.user_y_ata)?.amount() < y
+        {
+            return Err(PinocchioError::InsufficientUserFunds.into());
         }
         Transfer {
             from: self.accounts.user_x_ata,
@@ -140,15 +203,13 @@ impl<'a> Deposit<'a> {
         }
         .invoke()?;
 
-        let seed_binding = config.seed().to_le_bytes();
-        let config_bump = config.config_bump();
-        let config_seeds = [
-            Seed::from(b"config"),
-            Seed::from(&seed_binding),
-            Seed::from(config.mint_x()),
-            Seed::from(config.mint_y()),
-            Seed::from(&config_bump), 
-        ];
+        signer_seeds!(config_seeds = [
+            b"config",
+            config.seed().to_le_bytes(),
+            config.mint_x(),
+            config.mint_y(),
+            config.config_bump(),
+        ]);
         let signer = [Signer::from(&config_seeds)];
 
         MintTo {
@@ -158,6 +219,28 @@ impl<'a> Deposit<'a> {
             mint_authority: self.accounts.config 
         }
         .invoke_signed(&signer)?;
+        drop(config);
+        // read back rather than added up, so it is exactly what the LP account now holds
+        let lp_balance = TokenAccount::from_account_info(self.accounts.user_lp_ata)?.amount();
+
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.credit_expected_balances(x, y);
+        config.update_price_cumulatives(
+            reserve_x.saturating_add(x),
+            reserve_y.saturating_add(y),
+            now,
+        );
+        drop(config);
+
+        DepositEvent {
+            pool: *self.accounts.config.key(),
+            user: *self.accounts.user.key(),
+            lp_amount: self.instruction_data.amount,
+            x,
+            y,
+            lp_balance,
+        }
+        .emit();
 
         Ok(())
     }
@@ -0,0 +1,149 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::find_program_address,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_associated_token_account::instructions::CreateIdempotent;
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::state::TokenAccount;
+
+use crate::{
+    check_vault_address, read_u64, Config, Deposit, DepositAccounts, DepositInstructionData,
+    LPPosition,
+};
+
+pub struct DepositPositionAccounts<'a> {
+    pub deposit: DepositAccounts<'a>,
+    pub position: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for DepositPositionAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [deposit @ .., position, system_program, _associated_token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        // the LP ATA slot of Deposit takes the pool's own LP account
+        let deposit = DepositAccounts::try_from(deposit)?;
+        Ok(Self {
+            deposit,
+            position,
+            system_program,
+        })
+    }
+}
+
+pub struct DepositPosition<'a> {
+    pub deposit: Deposit<'a>,
+    pub position: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub deposit_index: u64,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for DepositPosition<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let (deposit_index, data) = data
+            .split_at_checked(size_of::<u64>())
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let accounts = DepositPositionAccounts::try_from(accounts)?;
+        let instruction_data = DepositInstructionData::try_from(data)?;
+        Ok(Self {
+            deposit: Deposit {
+                accounts: accounts.deposit,
+                instruction_data,
+            },
+            position: accounts.position,
+            system_program: accounts.system_program,
+            deposit_index: read_u64(deposit_index, 0)?,
+        })
+    }
+}
+
+impl<'a> DepositPosition<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &24;
+
+    /// Deposits like `Deposit`, but instead of handing LP tokens to the user it mints them
+    /// into the pool's own LP account and records them in a new `LPPosition`. The position
+    /// is owned by the program, so it can't be moved unless the pool allows transfers.
+    pub fn process(&mut self) -> ProgramResult {
+        // read once: the expiry check and the position's deposit_ts must agree
+        let now = Clock::get()?.unix_timestamp;
+        let accounts = &self.deposit.accounts;
+
+        // the deposit's LP goes to the config's own LP ATA, where the position holds it
+        check_vault_address(
+            accounts.user_lp_ata,
+            accounts.config,
+            accounts.token_program,
+            accounts.mint_lp.key(),
+            Config::load(accounts.config)?.lp_vault_bump(),
+        )?;
+
+        CreateIdempotent {
+            funding_account: accounts.user,
+            account: accounts.user_lp_ata,
+            wallet: accounts.config,
+            mint: accounts.mint_lp,
+            system_program: self.system_program,
+            token_program: accounts.token_program,
+        }
+        .invoke()?;
+
+        let deposit_index = self.deposit_index.to_le_bytes();
+        let (position, bump) = find_program_address(
+            &[
+                b"position",
+                accounts.config.key(),
+                accounts.user.key(),
+                &deposit_index,
+            ],
+            &crate::ID,
+        );
+        if position.ne(self.position.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let bump = [bump];
+        let position_seeds = [
+            Seed::from(b"position"),
+            Seed::from(accounts.config.key()),
+            Seed::from(accounts.user.key()),
+            Seed::from(&deposit_index),
+            Seed::from(&bump),
+        ];
+        CreateAccount {
+            from: accounts.user,
+            to: self.position,
+            lamports: Rent::get()?.minimum_balance(LPPosition::LEN),
+            space: LPPosition::LEN as u64,
+            owner: &crate::ID,
+        }
+        .invoke_signed(&[Signer::from(&position_seeds)])?;
+
+        self.deposit.execute(false, now)?;
+
+        let accounts = &self.deposit.accounts;
+        let (reserve_x, reserve_y) = {
+            let config = Config::load(accounts.config)?;
+            let vault_x = unsafe { TokenAccount::from_account_info_unchecked(accounts.vault_x)? };
+            let vault_y = unsafe { TokenAccount::from_account_info_unchecked(accounts.vault_y)? };
+            config.pool_reserves(vault_x.amount(), vault_y.amount())
+        };
+
+        LPPosition::load_mut(self.position)?.set_inner(
+            *accounts.user.key(),
+            *accounts.config.key(),
+            self.deposit.instruction_data.amount,
+            reserve_x,
+            reserve_y,
+            now,
+        );
+
+        Ok(())
+    }
+}
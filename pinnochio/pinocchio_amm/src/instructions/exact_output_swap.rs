@@ -0,0 +1,243 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::state::TokenAccount;
+
+use crate::{
+    check_vault_initialized, read_i64, read_u64, swap_in_pool, validate_expiration, Config,
+    PinocchioError, SwapAccounts,
+};
+
+pub struct ExactOutputSwapInstructionData<'a> {
+    pub is_x: bool,
+    pub amount_out: u64,
+    pub max_amount_in: u64,
+    pub expiration: i64,
+    pub access_proof: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for ExactOutputSwapInstructionData<'a> {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let (data, access_proof) = data
+            .split_at_checked(size_of::<u8>() + size_of::<u64>() * 2 + size_of::<i64>())
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let is_x = match data[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        let amount_out = read_u64(data, 1)?;
+        let max_amount_in = read_u64(data, 9)?;
+        let expiration = read_i64(data, 17)?;
+        let now = Clock::get()?.unix_timestamp;
+        if amount_out.eq(&0) || max_amount_in.eq(&0) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        validate_expiration(now, expiration)?;
+        Ok(Self {
+            is_x,
+            amount_out,
+            max_amount_in,
+            expiration,
+            access_proof,
+        })
+    }
+}
+
+pub struct ExactOutputSwap<'a> {
+    pub accounts: SwapAccounts<'a>,
+    pub instruction_data: ExactOutputSwapInstructionData<'a>,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for ExactOutputSwap<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = SwapAccounts::try_from(accounts)?;
+        let instruction_data = ExactOutputSwapInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> ExactOutputSwap<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &21;
+
+    /// Same accounts as `Swap`, but the user names the `amount_out` they want and the input
+    /// is solved from the curve. The swap itself goes through `swap_in_pool` with `amount_out`
+    /// as its minimum, so curve rounding can only ever pay out slightly more, never less.
+    pub fn process(&mut self) -> ProgramResult {
+        let (user_from, user_to, vault_in, vault_out) = match self.instruction_data.is_x {
+            true => (
+                self.accounts.user_x_ata,
+                self.accounts.user_y_ata,
+                self.accounts.vault_x,
+                self.accounts.vault_y,
+            ),
+            false => (
+                self.accounts.user_y_ata,
+                self.accounts.user_x_ata,
+                self.accounts.vault_y,
+                self.accounts.vault_x,
+            ),
+        };
+
+        // the vault addresses themselves are verified again inside swap_in_pool
+        check_vault_initialized(vault_in)?;
+        check_vault_initialized(vault_out)?;
+        let amount_in = {
+            let config = Config::load(self.accounts.config)?;
+            let vault_in = unsafe { TokenAccount::from_account_info_unchecked(vault_in)? };
+            let vault_out = unsafe { TokenAccount::from_account_info_unchecked(vault_out)? };
+            let (reserve_in, reserve_out) = match self.instruction_data.is_x {
+                true => config.pool_reserves(vault_in.amount(), vault_out.amount()),
+                false => {
+                    let (reserve_x, reserve_y) =
+                        config.pool_reserves(vault_out.amount(), vault_in.amount());
+                    (reserve_y, reserve_x)
+                }
+            };
+            amount_in_for_exact_output(
+                reserve_in,
+                reserve_out,
+                self.instruction_data.amount_out,
+                config.effective_fee(),
+            )
+            .ok_or(ProgramError::InvalidArgument)?
+        };
+
+        if amount_in > self.instruction_data.max_amount_in {
+            return Err(PinocchioError::MaxAmountInExceeded.into());
+        }
+
+        swap_in_pool(
+            self.accounts.user,
+            user_from,
+            user_to,
+            self.accounts.vault_x,
+            self.accounts.vault_y,
+            self.accounts.config,
+            self.accounts.token_program,
+            self.accounts.referrer,
+            self.instruction_data.is_x,
+            amount_in,
+            self.instruction_data.amount_out,
+            self.instruction_data.access_proof,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Constant product solved for the input: the fee-adjusted input has to move the pool from
+/// `reserve_in * reserve_out` to `(reserve_in + in) * (reserve_out - amount_out)`, i.e.
+/// `in = reserve_in * amount_out / (reserve_out - amount_out)`, grossed up by the fee the
+/// curve takes from the input. Both divisions round up so the curve never pays out short.
+/// Returns `None` when the pool cannot pay `amount_out` at all.
+pub fn amount_in_for_exact_output(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    fee_bps: u16,
+) -> Option<u64> {
+    if amount_out >= reserve_out || fee_bps >= 10_000 {
+        return None;
+    }
+    let net_in = (reserve_in as u128 * amount_out as u128)
+        .div_ceil((reserve_out - amount_out) as u128);
+    // near the whole out reserve the net input alone can come close to u128::MAX
+    let amount_in = net_in.checked_mul(10_000)?.div_ceil(10_000 - fee_bps as u128);
+    u64::try_from(amount_in).ok()
+}
+
+/// The forward quote `amount_in_for_exact_output` inverts: the fee comes off the input first,
+/// then the output is the constant product's, rounded down.
+pub fn amount_out_for_exact_input(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee_bps: u16,
+) -> Option<u64> {
+    if fee_bps >= 10_000 {
+        return None;
+    }
+    let net_in = amount_in as u128 - amount_in as u128 * fee_bps as u128 / 10_000;
+    let amount_out = reserve_out as u128 * net_in / (reserve_in as u128 + net_in).max(1);
+    u64::try_from(amount_out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEES: [u16; 5] = [0, 1, 30, 100, 1_000];
+
+    // the solved input always buys at least `amount_out`, and rounding the fee up costs at
+    // most two units more than the least input that would
+    fn assert_round_trip(reserve_in: u64, reserve_out: u64, amount_out: u64, fee_bps: u16) {
+        let Some(amount_in) =
+            amount_in_for_exact_output(reserve_in, reserve_out, amount_out, fee_bps)
+        else {
+            return;
+        };
+        let quoted = amount_out_for_exact_input(reserve_in, reserve_out, amount_in, fee_bps);
+        assert!(quoted >= Some(amount_out));
+        let short = amount_out_for_exact_input(
+            reserve_in,
+            reserve_out,
+            amount_in.saturating_sub(3),
+            fee_bps,
+        );
+        assert!(short < Some(amount_out));
+    }
+
+    #[test]
+    fn round_trips_against_the_forward_quote() {
+        for fee_bps in FEES {
+            for (reserve_in, reserve_out) in [(1_000, 1_000), (1_000, 7_919), (123_457, 99)] {
+                for amount_out in 1..reserve_out {
+                    assert_round_trip(reserve_in, reserve_out, amount_out, fee_bps);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_with_large_reserves() {
+        let reserves = [
+            (u64::MAX / 2, u64::MAX / 3),
+            (10u64.pow(18), 10u64.pow(12)),
+            (10u64.pow(9), 10u64.pow(18)),
+        ];
+        for fee_bps in FEES {
+            for (reserve_in, reserve_out) in reserves {
+                for amount_out in [
+                    1,
+                    1_000,
+                    reserve_out / 1_000,
+                    reserve_out / 2,
+                    reserve_out - 1,
+                ] {
+                    assert_round_trip(reserve_in, reserve_out, amount_out, fee_bps);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_what_the_pool_cannot_pay() {
+        assert_eq!(amount_in_for_exact_output(1_000, 1_000, 1_000, 30), None);
+        assert_eq!(amount_in_for_exact_output(1_000, 1_000, 1_001, 30), None);
+        assert_eq!(amount_in_for_exact_output(1_000, 1_000, 10, 10_000), None);
+        // solvable, but the input would not fit a u64
+        assert_eq!(
+            amount_in_for_exact_output(u64::MAX, u64::MAX, u64::MAX - 1, 30),
+            None
+        );
+    }
+}
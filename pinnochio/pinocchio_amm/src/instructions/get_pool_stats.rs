@@ -0,0 +1,121 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    log::sol_log_data,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::state::TokenAccount;
+
+use crate::{
+    check_mint_lp_address, check_vault_address, load_mint_checked, spot_price, Config,
+    SECONDS_PER_DAY,
+};
+
+// spot_x_to_y u128 | spot_y_to_x u128 | tvl_x u64 | tvl_y u64 | fee_bps u16 | daily_volume u64 | lp_supply u64
+pub const POOL_STATS_LEN: usize = 16 + 16 + 8 + 8 + 2 + 8 + 8;
+
+pub struct GetPoolStatsAccounts<'a> {
+    pub config: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetPoolStatsAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [config, vault_x, vault_y, mint_lp, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        Ok(Self {
+            config,
+            vault_x,
+            vault_y,
+            mint_lp,
+            token_program,
+        })
+    }
+}
+
+pub struct GetPoolStats<'a> {
+    pub accounts: GetPoolStatsAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetPoolStats<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = GetPoolStatsAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> GetPoolStats<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &10;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let day = Clock::get()?.unix_timestamp as u64 / SECONDS_PER_DAY;
+        log_pool_stats(
+            self.accounts.config,
+            self.accounts.vault_x,
+            self.accounts.vault_y,
+            self.accounts.mint_lp,
+            self.accounts.token_program,
+            day,
+        )
+    }
+}
+
+/// Emits `config key` and the pool's stats as one `sol_log_data` entry. Spot prices are
+/// Q64.64 fixed point, which needs the full u128; a one-sided empty pool reports zero.
+pub fn log_pool_stats(
+    config_account: &AccountInfo,
+    vault_x: &AccountInfo,
+    vault_y: &AccountInfo,
+    mint_lp: &AccountInfo,
+    token_program: &AccountInfo,
+    day: u64,
+) -> ProgramResult {
+    let config = Config::load(config_account)?;
+
+    check_vault_address(
+        vault_x,
+        config_account,
+        token_program,
+        config.mint_x(),
+        config.vault_x_bump(),
+    )?;
+    check_vault_address(
+        vault_y,
+        config_account,
+        token_program,
+        config.mint_y(),
+        config.vault_y_bump(),
+    )?;
+    check_mint_lp_address(mint_lp, config_account, config.mint_lp_bump())?;
+
+    let (tvl_x, tvl_y) = config.pool_reserves(
+        TokenAccount::from_account_info(vault_x)?.amount(),
+        TokenAccount::from_account_info(vault_y)?.amount(),
+    );
+    let lp_supply = load_mint_checked(mint_lp)?.supply();
+
+    // whole token prices, scaled by the mint decimals cached at Initialize
+    let (decimals_x, decimals_y) = (config.decimals_x(), config.decimals_y());
+    let spot_x_to_y = spot_price(tvl_x, tvl_y, decimals_x, decimals_y).unwrap_or(0);
+    let spot_y_to_x = spot_price(tvl_y, tvl_x, decimals_y, decimals_x).unwrap_or(0);
+
+    let mut stats = [0u8; POOL_STATS_LEN];
+    stats[0..16].copy_from_slice(&spot_x_to_y.to_le_bytes());
+    stats[16..32].copy_from_slice(&spot_y_to_x.to_le_bytes());
+    stats[32..40].copy_from_slice(&tvl_x.to_le_bytes());
+    stats[40..48].copy_from_slice(&tvl_y.to_le_bytes());
+    stats[48..50].copy_from_slice(&config.effective_fee().to_le_bytes());
+    stats[50..58].copy_from_slice(&config.daily_volume(day).to_le_bytes());
+    stats[58..66].copy_from_slice(&lp_supply.to_le_bytes());
+
+    sol_log_data(&[config_account.key(), &stats]);
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use pinocchio::{
+    account_info::AccountInfo, program::set_return_data, program_error::ProgramError, ProgramResult,
+};
+use pinocchio_token::state::TokenAccount;
+
+use crate::{
+    check_mint_lp_address, check_vault_address, load_mint_checked, lp_to_underlying, read_u64,
+    Config,
+};
+
+pub struct GetPositionValueAccounts<'a> {
+    pub mint_lp: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetPositionValueAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [mint_lp, vault_x, vault_y, config, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        Ok(Self {
+            mint_lp,
+            vault_x,
+            vault_y,
+            config,
+            token_program,
+        })
+    }
+}
+
+pub struct GetPositionValueInstructionData {
+    pub lp_amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for GetPositionValueInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let lp_amount = read_u64(data, 0)?;
+        Ok(Self { lp_amount })
+    }
+}
+
+pub struct GetPositionValue<'a> {
+    pub accounts: GetPositionValueAccounts<'a>,
+    pub instruction_data: GetPositionValueInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for GetPositionValue<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = GetPositionValueAccounts::try_from(accounts)?;
+        let instruction_data = GetPositionValueInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> GetPositionValue<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &7;
+
+    // read-only: returns `x: u64 | y: u64` (little endian) through return data
+    pub fn process(&mut self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+
+        check_mint_lp_address(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            config.mint_lp_bump(),
+        )?;
+        for (vault, mint, bump) in [
+            (
+                self.accounts.vault_x,
+                config.mint_x(),
+                config.vault_x_bump(),
+            ),
+            (
+                self.accounts.vault_y,
+                config.mint_y(),
+                config.vault_y_bump(),
+            ),
+        ] {
+            check_vault_address(
+                vault,
+                self.accounts.config,
+                self.accounts.token_program,
+                mint,
+                bump,
+            )?;
+        }
+
+        let lp_supply = load_mint_checked(self.accounts.mint_lp)?.supply();
+        let (reserve_x, reserve_y) = config.pool_reserves(
+            TokenAccount::from_account_info(self.accounts.vault_x)?.amount(),
+            TokenAccount::from_account_info(self.accounts.vault_y)?.amount(),
+        );
+
+        let (x, y) = lp_to_underlying(
+            self.instruction_data.lp_amount,
+            lp_supply,
+            reserve_x,
+            reserve_y,
+        )?;
+
+        let mut return_data = [0u8; 16];
+        return_data[..8].copy_from_slice(&x.to_le_bytes());
+        return_data[8..].copy_from_slice(&y.to_le_bytes());
+        set_return_data(&return_data);
+
+        Ok(())
+    }
+}
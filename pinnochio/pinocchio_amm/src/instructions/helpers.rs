@@ -0,0 +1,451 @@
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+#[cfg(target_os = "solana")]
+use pinocchio::pubkey::create_program_address;
+use pinocchio_token::state::{Mint, TokenAccount};
+
+use crate::PinocchioError;
+
+// furthest ahead a transaction deadline may be set; anything later is a client bug (a
+// millisecond timestamp, a "never" sentinel) rather than a deadline anyone meant
+pub const MAX_EXPIRATION_HORIZON_SECONDS: i64 = 24 * 60 * 60;
+
+// `expiration` must not have passed yet and must be within the horizon of `now`
+pub fn validate_expiration(now: i64, expiration: i64) -> ProgramResult {
+    if now > expiration {
+        return Err(PinocchioError::Expired.into());
+    }
+    if expiration > now.saturating_add(MAX_EXPIRATION_HORIZON_SECONDS) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+// compares every byte whatever the first mismatch, so the time taken says nothing about how
+// much of `a` a caller got right
+pub fn keys_eq_constant_time(a: &Pubkey, b: &Pubkey) -> bool {
+    a.iter().zip(b.iter()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// the config's ATA for `mint`, from the bump Initialize stored: a single hash instead of
+// find_program_address walking down from 255
+pub fn vault_address(
+    config: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    bump: [u8; 1],
+) -> Result<Pubkey, ProgramError> {
+    create_program_address(
+        &[config, token_program, mint, &bump],
+        &pinocchio_associated_token_account::ID,
+    )
+    .map_err(|_| PinocchioError::InvalidVault.into())
+}
+
+// `vault` must be the config's ATA for `mint`
+pub fn check_vault_address(
+    vault: &AccountInfo,
+    config: &AccountInfo,
+    token_program: &AccountInfo,
+    mint: &Pubkey,
+    bump: [u8; 1],
+) -> ProgramResult {
+    if vault_address(config.key(), token_program.key(), mint, bump)?.ne(vault.key()) {
+        return Err(PinocchioError::InvalidVault.into());
+    }
+    Ok(())
+}
+
+// `mint_lp` must be the pool's LP mint, checked with the bump Initialize stored
+pub fn check_mint_lp_address(
+    mint_lp: &AccountInfo,
+    config: &AccountInfo,
+    bump: [u8; 1],
+) -> ProgramResult {
+    let expected = create_program_address(&[b"mint_lp", config.key(), &bump], &crate::ID)?;
+    if expected.ne(mint_lp.key()) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// host builds have no PDA syscall. Every bump checked here is a canonical one Initialize
+// stored, so hashing the seeds the way the runtime does gives the same address
+#[cfg(not(target_os = "solana"))]
+fn create_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+    let mut hasher = sha2_const_stable::Sha256::new();
+    for seed in seeds {
+        hasher = hasher.update(seed);
+    }
+    Ok(hasher
+        .update(program_id)
+        .update(pinocchio::pubkey::PDA_MARKER)
+        .finalize())
+}
+
+// vaults are read with `from_account_info_unchecked`, so make sure one actually exists first
+pub fn check_vault_initialized(vault: &AccountInfo) -> ProgramResult {
+    if !vault.is_owned_by(&pinocchio_token::ID) || vault.data_len().ne(&TokenAccount::LEN) {
+        return Err(PinocchioError::VaultNotInitialized.into());
+    }
+    Ok(())
+}
+
+// user accounts frozen by the mint's freeze authority (e.g. a compliance freeze) are refused
+// before any tokens move
+pub fn check_not_frozen(token_account: &AccountInfo) -> ProgramResult {
+    if TokenAccount::from_account_info(token_account)?.is_frozen() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+
+// both sides of a transfer must hold the same mint, or pool accounting drifts from reality
+pub fn assert_same_mint(a: &AccountInfo, b: &AccountInfo) -> ProgramResult {
+    let mint_a = *TokenAccount::from_account_info(a)?.mint();
+    if mint_a.ne(TokenAccount::from_account_info(b)?.mint()) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// `token_account` must hold `mint`, for accounts whose mint a flag or the config decides
+pub fn assert_mint(token_account: &AccountInfo, mint: &Pubkey) -> ProgramResult {
+    if TokenAccount::from_account_info(token_account)?.mint().ne(mint) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// the config PDA is authority over both the LP mint and the vaults, none of them may alias
+pub fn assert_distinct(keys: &[&Pubkey]) -> ProgramResult {
+    for (i, a) in keys.iter().enumerate() {
+        if keys[i + 1..].iter().any(|b| a.eq(b)) {
+            return Err(PinocchioError::AccountAliased.into());
+        }
+    }
+    Ok(())
+}
+
+/// The fields of an SPL mint, copied out so no borrow of the account outlives the read.
+#[derive(Clone, Copy)]
+pub struct MintView {
+    supply: u64,
+    decimals: u8,
+    mint_authority: Option<Pubkey>,
+}
+
+impl MintView {
+    #[inline(always)]
+    pub fn supply(&self) -> u64 {
+        self.supply
+    }
+    #[inline(always)]
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+    #[inline(always)]
+    pub fn mint_authority(&self) -> Option<&Pubkey> {
+        self.mint_authority.as_ref()
+    }
+}
+
+// checks owner, length and initialization before reading; hot paths that already trust the
+// mint through a PDA derivation can keep using `Mint::from_account_info_unchecked`
+pub fn load_mint_checked(account: &AccountInfo) -> Result<MintView, ProgramError> {
+    if !account.is_owned_by(&pinocchio_token::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    if account.data_len().ne(&Mint::LEN) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mint = Mint::from_account_info(account)?;
+    if !mint.is_initialized() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(MintView {
+        supply: mint.supply(),
+        decimals: mint.decimals(),
+        mint_authority: mint.mint_authority().copied(),
+    })
+}
+
+/// Price of one whole x token in whole y tokens, as Q64.64 fixed point: `reserve_y /
+/// reserve_x`, scaled by the difference in decimals. Passing equal decimals gives the raw
+/// price in base units. `None` for an empty side or a price too large for a u128.
+pub fn spot_price(
+    reserve_x: u64,
+    reserve_y: u64,
+    decimals_x: u8,
+    decimals_y: u8,
+) -> Option<u128> {
+    if reserve_x.eq(&0) || reserve_y.eq(&0) {
+        return None;
+    }
+    let num = (reserve_y as u128) << 64;
+    let den = reserve_x as u128;
+    // only the difference in decimals matters, so only one side is ever scaled up
+    if decimals_x >= decimals_y {
+        let scale = 10u128.checked_pow((decimals_x - decimals_y) as u32)?;
+        Some(num.checked_mul(scale)? / den)
+    } else {
+        // a denominator past u128 means a price below the Q64.64 resolution, not an error
+        let scale = 10u128.checked_pow((decimals_y - decimals_x) as u32);
+        Some(scale.and_then(|scale| den.checked_mul(scale)).map_or(0, |den| num / den))
+    }
+}
+
+/// Refuses a swap that leaves `reserve_in * reserve_out` smaller than it found it, counting only
+/// the input after fees. The curve rounds the output down, so this holds for every swap it
+/// prices; checking it keeps any rounding in the pool's favour whatever the curve does.
+pub fn check_swap_invariant(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in_after_fee: u64,
+    amount_out: u64,
+) -> ProgramResult {
+    let new_in = reserve_in as u128 + amount_in_after_fee as u128;
+    let new_out = (reserve_out as u128)
+        .checked_sub(amount_out as u128)
+        .ok_or(ProgramError::InvalidArgument)?;
+    // the old product is below 2^128, so a new one too large for a u128 is larger still
+    let k = reserve_in as u128 * reserve_out as u128;
+    if new_in.checked_mul(new_out).is_some_and(|new_k| new_k < k) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Splits `data` into its `required_len` prefix and an optional `N`-byte suffix.
+/// Anything other than exactly `required_len` or `required_len + N` bytes is rejected.
+pub fn parse_optional_suffix<const N: usize>(
+    data: &[u8],
+    required_len: usize,
+) -> Result<(&[u8], Option<[u8; N]>), ProgramError> {
+    let (required, suffix) = data
+        .split_at_checked(required_len)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match suffix.len() {
+        0 => Ok((required, None)),
+        len if len.eq(&N) => Ok((required, Some(suffix.try_into().unwrap()))),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Declares `$name` as the `[Seed; N]` array for a PDA signer.
+///
+/// Each seed expression is first bound to its own local, so owned temporaries such as
+/// `seed.to_le_bytes()` live as long as the array instead of being dropped at the end of the
+/// `let`. Any seed that is `AsRef<[u8]>` works: byte strings, keys, bump arrays.
+///
+/// ```
+/// use pinocchio::instruction::Signer;
+/// use pinocchio_amm::signer_seeds;
+///
+/// let (seed, config_bump) = (42u64, [255u8]);
+/// signer_seeds!(config_seeds = [b"config", seed.to_le_bytes(), config_bump]);
+/// let signer = [Signer::from(&config_seeds)];
+/// # assert_eq!(config_seeds.len(), 3);
+/// # assert_eq!(signer.len(), 1);
+/// ```
+#[macro_export]
+macro_rules! signer_seeds {
+    ($name:ident = [$($seed:expr),+ $(,)?]) => {
+        $crate::signer_seeds!(@bind $name [] $($seed,)+);
+    };
+    // every level introduces a fresh hygienic `seed`, so the bindings never shadow each other
+    (@bind $name:ident [$($bound:ident)*] $seed:expr, $($rest:expr,)*) => {
+        let seed = $seed;
+        $crate::signer_seeds!(@bind $name [$($bound)* seed] $($rest,)*);
+    };
+    (@bind $name:ident [$($bound:ident)*]) => {
+        let $name = [$(::pinocchio::instruction::Seed::from(AsRef::<[u8]>::as_ref(&$bound))),*];
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assert_distinct_refuses_aliased_pool_accounts() {
+        let (config, mint_lp, vault_x, vault_y) = ([1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]);
+        assert_eq!(
+            assert_distinct(&[&config, &mint_lp, &vault_x, &vault_y]),
+            Ok(())
+        );
+
+        // config or LP mint passed again in another slot, and one vault passed for both sides
+        for keys in [
+            [&config, &config, &vault_x, &vault_y],
+            [&config, &mint_lp, &config, &vault_y],
+            [&config, &mint_lp, &vault_x, &mint_lp],
+            [&config, &mint_lp, &vault_y, &vault_y],
+        ] {
+            assert_eq!(
+                assert_distinct(&keys),
+                Err(PinocchioError::AccountAliased.into())
+            );
+        }
+    }
+
+    #[test]
+    fn vault_address_from_the_stored_bump() {
+        let config = [1u8; 32];
+        let mint = [5u8; 32];
+        // the ATA of `config` for `mint` as find_program_address and the associated token
+        // program derive it: 255 to 252 all land on the curve, so the canonical bump is 251
+        let ata = [
+            166, 131, 239, 165, 163, 217, 188, 81, 24, 39, 18, 25, 181, 61, 122, 39, 131, 143, 79,
+            211, 48, 228, 95, 51, 135, 71, 166, 161, 170, 159, 222, 12,
+        ];
+
+        assert_eq!(
+            vault_address(&config, &pinocchio_token::ID, &mint, [251]),
+            Ok(ata)
+        );
+        assert_ne!(
+            vault_address(&config, &pinocchio_token::ID, &mint, [252]),
+            Ok(ata)
+        );
+        assert_ne!(vault_address(&config, &config, &mint, [251]), Ok(ata));
+    }
+
+    #[test]
+    fn parse_optional_suffix_absent() {
+        let data = [1, 2, 3];
+        assert_eq!(parse_optional_suffix::<2>(&data, 3), Ok((&data[..], None)));
+    }
+
+    #[test]
+    fn parse_optional_suffix_present() {
+        let data = [1, 2, 3, 4, 5];
+        assert_eq!(
+            parse_optional_suffix::<2>(&data, 3),
+            Ok((&data[..3], Some([4, 5])))
+        );
+    }
+
+    #[test]
+    fn parse_optional_suffix_rejects_other_lengths() {
+        // too short for the required prefix
+        assert_eq!(
+            parse_optional_suffix::<2>(&[1, 2], 3),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        // a partial suffix
+        assert_eq!(
+            parse_optional_suffix::<2>(&[1, 2, 3, 4], 3),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        // a suffix with trailing bytes
+        assert_eq!(
+            parse_optional_suffix::<2>(&[1, 2, 3, 4, 5, 6], 3),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    const ONE: u128 = 1 << 64;
+
+    #[test]
+    fn spot_price_raw_ratio() {
+        assert_eq!(spot_price(1_000, 1_000, 6, 6), Some(ONE));
+        assert_eq!(spot_price(2_000, 1_000, 6, 6), Some(ONE / 2));
+        assert_eq!(spot_price(1_000, 3_000, 0, 0), Some(3 * ONE));
+        // rounded down to the Q64.64 resolution
+        assert_eq!(spot_price(3, 1, 0, 0), Some(ONE / 3));
+    }
+
+    #[test]
+    fn spot_price_empty_side_is_none() {
+        assert_eq!(spot_price(0, 1_000, 6, 6), None);
+        assert_eq!(spot_price(1_000, 0, 6, 6), None);
+        assert_eq!(spot_price(0, 0, 6, 6), None);
+    }
+
+    #[test]
+    fn spot_price_scales_by_the_decimal_difference() {
+        // 1 whole x (9 decimals) against 100 whole y (6 decimals)
+        assert_eq!(
+            spot_price(1_000_000_000, 100_000_000, 9, 6),
+            Some(100 * ONE)
+        );
+        // 1 whole x (6 decimals) against 100 whole y (9 decimals)
+        assert_eq!(
+            spot_price(1_000_000, 100_000_000_000, 6, 9),
+            Some(100 * ONE)
+        );
+        // only the difference counts
+        assert_eq!(
+            spot_price(1_000_000, 100_000_000_000, 12, 15),
+            spot_price(1_000_000, 100_000_000_000, 6, 9)
+        );
+    }
+
+    #[test]
+    fn spot_price_too_large_is_none() {
+        // u64::MAX in Q64.64 fits, ten times it doesn't
+        assert_eq!(
+            spot_price(1, u64::MAX, 0, 0),
+            Some((u64::MAX as u128) << 64)
+        );
+        assert_eq!(spot_price(1, u64::MAX, 1, 0), None);
+        // 10^255 doesn't fit a u128 at all
+        assert_eq!(spot_price(1, 1, 255, 0), None);
+    }
+
+    #[test]
+    fn spot_price_below_resolution_is_zero() {
+        assert_eq!(spot_price(u64::MAX, 1, 0, 0), Some(1));
+        assert_eq!(spot_price(u64::MAX, 1, 0, 1), Some(0));
+        assert_eq!(spot_price(1, 1, 0, 255), Some(0));
+    }
+
+    #[test]
+    fn check_swap_invariant_accepts_the_floored_output() {
+        // 1_000 * 10 / 1_010 = 9.9: 9 keeps k, the rounded-up 10 would shrink it
+        assert_eq!(check_swap_invariant(1_000, 1_000, 10, 9), Ok(()));
+        assert_eq!(
+            check_swap_invariant(1_000, 1_000, 10, 10),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn check_swap_invariant_accepts_an_unchanged_product() {
+        // 200 * 50 is exactly 100 * 100
+        assert_eq!(check_swap_invariant(100, 100, 100, 50), Ok(()));
+        assert_eq!(
+            check_swap_invariant(100, 100, 100, 51),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(check_swap_invariant(100, 100, 0, 0), Ok(()));
+    }
+
+    #[test]
+    fn check_swap_invariant_rejects_draining_the_reserve() {
+        assert_eq!(
+            check_swap_invariant(100, 100, 1_000_000, 100),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            check_swap_invariant(100, 100, 1_000_000, 101),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn check_swap_invariant_at_full_reserves() {
+        // the new product passes 2^128 here, which still means k grew
+        assert_eq!(
+            check_swap_invariant(u64::MAX, u64::MAX, u64::MAX, 1),
+            Ok(())
+        );
+        assert_eq!(
+            check_swap_invariant(u64::MAX, u64::MAX, 1, 1),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+}
@@ -1,38 +1,56 @@
 use pinocchio::{
     account_info::{AccountInfo, RefMut},
-    instruction::{Seed, Signer},
+    instruction::Signer,
     program_error::ProgramError,
+    pubkey::find_program_address,
     sysvars::{rent::Rent, Sysvar},
     ProgramResult,
 };
 use pinocchio_system::instructions::CreateAccount;
 use pinocchio_token::{instructions::InitializeMint2, state::Mint};
-use std::mem::MaybeUninit;
+use crate::{
+    assert_distinct, load_mint_checked, parse_optional_suffix, read_pubkey, read_u16, read_u64,
+    signer_seeds, Config, PinocchioError, SignerAccount,
+};
 
-use crate::Config;
+// LP mint decimals when Initialize doesn't specify any
+pub const DEFAULT_LP_DECIMALS: u8 = 6;
+// past this a single whole LP token no longer leaves room in a u64 amount
+pub const MAX_LP_DECIMALS: u8 = 18;
 
 pub struct InitializeAccounts<'a> {
     pub initializer: &'a AccountInfo,
     pub mint_lp: &'a AccountInfo,
     pub config: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for InitializeAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [initializer, mint_lp, config, _system_program, _token_program] = accounts else {
+        let [initializer, mint_lp, config, _system_program, token_program, mint_x, mint_y] =
+            accounts
+        else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
+        SignerAccount::check(initializer)?;
+        assert_distinct(&[config.key(), mint_lp.key()])?;
         Ok(Self {
             initializer,
             mint_lp,
             config,
+            token_program,
+            mint_x,
+            mint_y,
         })
     }
 }
 
 #[repr(C, packed)]
+#[derive(Clone, Copy)]
 pub struct InitializeInstructionData {
     pub seed: u64,
     pub fee: u16,
@@ -41,30 +59,41 @@ pub struct InitializeInstructionData {
     pub config_bump: [u8; 1],
     pub lp_bump: [u8; 1],
     pub authority: [u8; 32],
+    pub lp_decimals: u8,
 }
 
+// the parser below slices fixed offsets, keep it in step with the packed layout
+const _: () = assert!(size_of::<InitializeInstructionData>() == 8 + 2 + 32 + 32 + 1 + 1 + 32 + 1);
+
 impl TryFrom<&[u8]> for InitializeInstructionData {
     type Error = ProgramError;
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        const INITIALIZE_DATA_LEN_WITH_AUTHORITY: usize = size_of::<InitializeInstructionData>();
         const INITIALIZE_DATA_LEN: usize =
-            INITIALIZE_DATA_LEN_WITH_AUTHORITY - size_of::<[u8; 32]>();
-        match data.len() {
-            INITIALIZE_DATA_LEN_WITH_AUTHORITY => {
-                Ok(unsafe { (data.as_ptr() as *const Self).read_unaligned() })
-            }
-            INITIALIZE_DATA_LEN => {
-                let mut raw: MaybeUninit<[u8; INITIALIZE_DATA_LEN_WITH_AUTHORITY]> =
-                    MaybeUninit::uninit();
-                let raw_ptr = raw.as_mut_ptr() as *mut u8;
-                unsafe {
-                    core::ptr::copy_nonoverlapping(data.as_ptr(), raw_ptr, INITIALIZE_DATA_LEN);
-                    core::ptr::write_bytes(raw_ptr.add(INITIALIZE_DATA_LEN), 0, 32);
-                    Ok((raw.as_ptr() as *const Self).read_unaligned())
-                }
+            size_of::<InitializeInstructionData>() - size_of::<[u8; 32]>() - size_of::<u8>();
+        // lp_decimals can only follow an authority; pass the zero key with it for an
+        // immutable pool
+        let (data, lp_decimals) = match data.len() {
+            len if len.eq(&(INITIALIZE_DATA_LEN + size_of::<[u8; 32]>() + size_of::<u8>())) => {
+                let (data, lp_decimals) = data.split_at(len - size_of::<u8>());
+                (data, lp_decimals[0])
             }
-            _ => Err(ProgramError::InvalidInstructionData),
+            _ => (data, DEFAULT_LP_DECIMALS),
+        };
+        if lp_decimals.gt(&MAX_LP_DECIMALS) {
+            return Err(ProgramError::InvalidInstructionData);
         }
+        // a missing authority is stored as the zero key, i.e. an immutable pool
+        let (data, authority) = parse_optional_suffix::<32>(data, INITIALIZE_DATA_LEN)?;
+        Ok(Self {
+            seed: read_u64(data, 0)?,
+            fee: read_u16(data, 8)?,
+            mint_x: read_pubkey(data, 10)?,
+            mint_y: read_pubkey(data, 42)?,
+            config_bump: [data[74]],
+            lp_bump: [data[75]],
+            authority: authority.unwrap_or([0u8; 32]),
+            lp_decimals,
+        })
     }
 }
 
@@ -89,14 +118,23 @@ impl<'a> Initialize<'a> {
     pub const DISCRIMINATOR: &'a u8 = &0;
 
     pub fn process(&mut self) -> ProgramResult {
-        let seed_bindings = self.instruction_data.seed.to_le_bytes();
-        let config_seeds = [
-            Seed::from(b"config"),
-            Seed::from(&seed_bindings),
-            Seed::from(&self.instruction_data.mint_x),
-            Seed::from(&self.instruction_data.mint_y),
-            Seed::from(&self.instruction_data.config_bump),
-        ];
+        self.check_bumps()?;
+
+        if self.accounts.mint_x.key().ne(&self.instruction_data.mint_x)
+            || self.accounts.mint_y.key().ne(&self.instruction_data.mint_y)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let decimals_x = load_mint_checked(self.accounts.mint_x)?.decimals();
+        let decimals_y = load_mint_checked(self.accounts.mint_y)?.decimals();
+
+        signer_seeds!(config_seeds = [
+            b"config",
+            self.instruction_data.seed.to_le_bytes(),
+            self.instruction_data.mint_x,
+            self.instruction_data.mint_y,
+            self.instruction_data.config_bump,
+        ]);
         let signer = [Signer::from(&config_seeds)];
 
         CreateAccount {
@@ -119,12 +157,36 @@ impl<'a> Initialize<'a> {
             self.instruction_data.fee,
             self.instruction_data.config_bump,
         )?;
-
-        let mint_lp_seeds = [
-            Seed::from(b"mint_lp"),
-            Seed::from(self.accounts.config.key()),
-            Seed::from(&self.instruction_data.lp_bump),
-        ];
+        config.set_lp_decimals(self.instruction_data.lp_decimals);
+        // mint decimals can't change, so later instructions read them here instead of
+        // passing the mints along
+        config.set_mint_decimals(decimals_x, decimals_y);
+        // the vaults and the LP ATA are the config's ATAs, deterministic from here on; finding
+        // their bumps once spares every later instruction the search
+        let [vault_x_bump, vault_y_bump, lp_vault_bump] = [
+            &self.instruction_data.mint_x,
+            &self.instruction_data.mint_y,
+            self.accounts.mint_lp.key(),
+        ]
+        .map(|mint| {
+            let (_, bump) = find_program_address(
+                &[
+                    config_account.key(),
+                    self.accounts.token_program.key(),
+                    mint,
+                ],
+                &pinocchio_associated_token_account::ID,
+            );
+            [bump]
+        });
+        config.set_vault_bumps(vault_x_bump, vault_y_bump);
+        config.set_lp_bumps(self.instruction_data.lp_bump, lp_vault_bump);
+
+        signer_seeds!(mint_lp_seeds = [
+            b"mint_lp",
+            self.accounts.config.key(),
+            self.instruction_data.lp_bump,
+        ]);
         let signer = [Signer::from(&mint_lp_seeds)];
 
         CreateAccount {
@@ -138,7 +200,7 @@ impl<'a> Initialize<'a> {
 
         InitializeMint2 {
             mint: self.accounts.mint_lp,
-            decimals: 6,
+            decimals: self.instruction_data.lp_decimals,
             mint_authority: self.accounts.config.key(),
             freeze_authority: None,
         }
@@ -146,4 +208,34 @@ impl<'a> Initialize<'a> {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    // both bumps must be the canonical ones find_program_address returns: a wrong one would
+    // only fail deep inside invoke_signed, and another valid one would create the pool at an
+    // address clients never derive
+    fn check_bumps(&self) -> ProgramResult {
+        let (config, config_bump) = find_program_address(
+            &[
+                b"config",
+                &self.instruction_data.seed.to_le_bytes(),
+                &self.instruction_data.mint_x,
+                &self.instruction_data.mint_y,
+            ],
+            &crate::ID,
+        );
+        if config_bump.ne(&self.instruction_data.config_bump[0]) {
+            return Err(PinocchioError::NonCanonicalBump.into());
+        }
+        if config.ne(self.accounts.config.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let (mint_lp, lp_bump) = find_program_address(&[b"mint_lp", &config], &crate::ID);
+        if lp_bump.ne(&self.instruction_data.lp_bump[0]) {
+            return Err(PinocchioError::NonCanonicalBump.into());
+        }
+        if mint_lp.ne(self.accounts.mint_lp.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_associated_token_account::instructions::CreateIdempotent;
+
+use crate::{
+    read_u64, Deposit, DepositAccounts, DepositInstructionData, Initialize, InitializeAccounts,
+    InitializeInstructionData, SignerAccount,
+};
+
+pub struct InitializeWithLiquidityAccounts<'a> {
+    pub initializer: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub user_x_ata: &'a AccountInfo,
+    pub user_y_ata: &'a AccountInfo,
+    pub user_lp_ata: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for InitializeWithLiquidityAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [initializer, mint_lp, config, mint_x, mint_y, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, system_program, token_program, _associated_token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(initializer)?;
+        Ok(Self {
+            initializer,
+            mint_lp,
+            config,
+            mint_x,
+            mint_y,
+            vault_x,
+            vault_y,
+            user_x_ata,
+            user_y_ata,
+            user_lp_ata,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+// amount | max_x | max_y | InitializeInstructionData
+pub struct InitializeWithLiquidityInstructionData {
+    pub amount: u64,
+    pub max_x: u64,
+    pub max_y: u64,
+    pub initialize: InitializeInstructionData,
+}
+
+impl<'a> TryFrom<&'a [u8]> for InitializeWithLiquidityInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let (data, initialize) = data
+            .split_at_checked(size_of::<u64>() * 3)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let amount = read_u64(data, 0)?;
+        let max_x = read_u64(data, 8)?;
+        let max_y = read_u64(data, 16)?;
+        if amount.eq(&0) || max_x.eq(&0) || max_y.eq(&0) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let initialize = InitializeInstructionData::try_from(initialize)?;
+        Ok(Self {
+            amount,
+            max_x,
+            max_y,
+            initialize,
+        })
+    }
+}
+
+pub struct InitializeWithLiquidity<'a> {
+    pub accounts: InitializeWithLiquidityAccounts<'a>,
+    pub instruction_data: InitializeWithLiquidityInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for InitializeWithLiquidity<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = InitializeWithLiquidityAccounts::try_from(accounts)?;
+        let instruction_data = InitializeWithLiquidityInstructionData::try_from(data)?;
+        if accounts.mint_x.key().ne(&instruction_data.initialize.mint_x)
+            || accounts.mint_y.key().ne(&instruction_data.initialize.mint_y)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> InitializeWithLiquidity<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &13;
+
+    /// Creates the pool, its vaults and the initializer's LP ATA, then seeds it, so the pool
+    /// never exists without liquidity.
+    pub fn process(&mut self) -> ProgramResult {
+        let data = &self.instruction_data;
+
+        Initialize {
+            accounts: InitializeAccounts {
+                initializer: self.accounts.initializer,
+                mint_lp: self.accounts.mint_lp,
+                config: self.accounts.config,
+                token_program: self.accounts.token_program,
+                mint_x: self.accounts.mint_x,
+                mint_y: self.accounts.mint_y,
+            },
+            instruction_data: data.initialize,
+        }
+        .process()?;
+
+        for (account, wallet, mint) in [
+            (self.accounts.vault_x, self.accounts.config, self.accounts.mint_x),
+            (self.accounts.vault_y, self.accounts.config, self.accounts.mint_y),
+            (self.accounts.user_lp_ata, self.accounts.initializer, self.accounts.mint_lp),
+        ] {
+            CreateIdempotent {
+                funding_account: self.accounts.initializer,
+                account,
+                wallet,
+                mint,
+                system_program: self.accounts.system_program,
+                token_program: self.accounts.token_program,
+            }
+            .invoke()?;
+        }
+
+        // created in this same instruction, so the deadline is simply now. The seeding deposit
+        // still goes through verify_access, which passes with no proof since Initialize leaves
+        // the access mode Open until UpdateAccessControl sets a list
+        let now = Clock::get()?.unix_timestamp;
+        Deposit {
+            accounts: DepositAccounts {
+                user: self.accounts.initializer,
+                mint_lp: self.accounts.mint_lp,
+                vault_x: self.accounts.vault_x,
+                vault_y: self.accounts.vault_y,
+                user_x_ata: self.accounts.user_x_ata,
+                user_y_ata: self.accounts.user_y_ata,
+                user_lp_ata: self.accounts.user_lp_ata,
+                config: self.accounts.config,
+                token_program: self.accounts.token_program,
+            },
+            instruction_data: DepositInstructionData {
+                amount: data.amount,
+                max_x: data.max_x,
+                max_y: data.max_y,
+                expiration: now,
+                access_proof: &[],
+            },
+        }
+        // the seeding deposit books the first price, which starts the pool's TWAP oracle
+        .execute(true, now)
+    }
+}
@@ -0,0 +1,234 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref},
+    cpi::invoke_signed,
+    instruction::{AccountMeta, Instruction, Seed, Signer},
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    ProgramResult,
+};
+
+use crate::{Config, SignerAccount};
+
+// metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = [
+    0x0b, 0x70, 0x65, 0xb1, 0xe3, 0xd1, 0x7c, 0x45, 0x38, 0x9d, 0x52, 0x7f, 0x6b, 0x04, 0xc3, 0xcd,
+    0x58, 0xb8, 0x6c, 0x73, 0x1a, 0xa0, 0xfd, 0xb5, 0x49, 0xb6, 0xd1, 0xbc, 0x03, 0xf8, 0x29, 0x46,
+];
+
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_SYMBOL_LENGTH: usize = 10;
+const MAX_URI_LENGTH: usize = 200;
+// key | update_authority | mint, then the borsh strings start
+const METADATA_NAME_OFFSET: usize = 1 + 32 + 32;
+const LP_NAME_PREFIX: &[u8] = b"LP: ";
+const LP_SYMBOL: &[u8] = b"LP";
+// discriminator, three strings and the 8 bytes of trailing options and flags
+const CREATE_METADATA_DATA_LEN: usize =
+    1 + 4 + MAX_NAME_LENGTH + 4 + MAX_SYMBOL_LENGTH + 4 + MAX_URI_LENGTH + 8;
+
+pub struct MintLpWithMetadataAccounts<'a> {
+    pub payer: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub metadata_lp: &'a AccountInfo,
+    pub metadata_x: &'a AccountInfo,
+    pub metadata_y: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MintLpWithMetadataAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, config, mint_lp, metadata_lp, metadata_x, metadata_y, system_program, metadata_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(payer)?;
+        if metadata_program.key().ne(&TOKEN_METADATA_PROGRAM_ID) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(Self {
+            payer,
+            config,
+            mint_lp,
+            metadata_lp,
+            metadata_x,
+            metadata_y,
+            system_program,
+        })
+    }
+}
+
+pub struct MintLpWithMetadataInstructionData<'a> {
+    pub uri: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for MintLpWithMetadataInstructionData<'a> {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() > MAX_URI_LENGTH {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Self { uri: data })
+    }
+}
+
+pub struct MintLpWithMetadata<'a> {
+    pub accounts: MintLpWithMetadataAccounts<'a>,
+    pub instruction_data: MintLpWithMetadataInstructionData<'a>,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for MintLpWithMetadata<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = MintLpWithMetadataAccounts::try_from(accounts)?;
+        let instruction_data = MintLpWithMetadataInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> MintLpWithMetadata<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &18;
+
+    /// Creates the Metaplex metadata account of the LP mint, named `LP: {symbol_x}/{symbol_y}`
+    /// after the pool's token metadata. The config PDA is both mint and update authority, and
+    /// while the pool has an authority only it may attach metadata, since it picks the uri.
+    pub fn process(&mut self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+
+        if let Some(authority) = config.has_authority() {
+            if authority.ne(self.accounts.payer.key()) {
+                return Err(ProgramError::IncorrectAuthority);
+            }
+        }
+
+        let (mint_lp, _) = find_program_address(
+            &[b"mint_lp", self.accounts.config.key()],
+            &crate::ID,
+        );
+        if mint_lp.ne(self.accounts.mint_lp.key()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if metadata_address(&mint_lp).ne(self.accounts.metadata_lp.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let mut name = [0u8; MAX_NAME_LENGTH];
+        let name_len = {
+            let data_x = read_metadata(self.accounts.metadata_x, config.mint_x())?;
+            let data_y = read_metadata(self.accounts.metadata_y, config.mint_y())?;
+            let symbol_x = metadata_symbol(&data_x)?;
+            let symbol_y = metadata_symbol(&data_y)?;
+
+            let len = LP_NAME_PREFIX.len() + symbol_x.len() + 1 + symbol_y.len();
+            if len > MAX_NAME_LENGTH {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            let (prefix, rest) = name.split_at_mut(LP_NAME_PREFIX.len());
+            prefix.copy_from_slice(LP_NAME_PREFIX);
+            let (x, rest) = rest.split_at_mut(symbol_x.len());
+            x.copy_from_slice(symbol_x);
+            rest[0] = b'/';
+            rest[1..1 + symbol_y.len()].copy_from_slice(symbol_y);
+            len
+        };
+
+        // CreateMetadataAccountV3: name | symbol | uri | seller_fee_basis_points | creators |
+        // collection | uses | is_mutable | collection_details, strings as borsh u32-prefixed
+        let mut data = [0u8; CREATE_METADATA_DATA_LEN];
+        data[0] = CREATE_METADATA_ACCOUNT_V3;
+        let mut offset = 1;
+        for field in [&name[..name_len], LP_SYMBOL, self.instruction_data.uri] {
+            data[offset..offset + 4].copy_from_slice(&(field.len() as u32).to_le_bytes());
+            offset += 4;
+            data[offset..offset + field.len()].copy_from_slice(field);
+            offset += field.len();
+        }
+        // no royalties, creators, collection or uses; mutable; no collection details
+        data[offset..offset + 7].copy_from_slice(&[0, 0, 0, 0, 0, 1, 0]);
+        offset += 7;
+
+        let account_metas = [
+            AccountMeta::writable(self.accounts.metadata_lp.key()),
+            AccountMeta::readonly(self.accounts.mint_lp.key()),
+            AccountMeta::readonly_signer(self.accounts.config.key()),
+            AccountMeta::writable_signer(self.accounts.payer.key()),
+            AccountMeta::readonly_signer(self.accounts.config.key()),
+            AccountMeta::readonly(self.accounts.system_program.key()),
+        ];
+        let instruction = Instruction {
+            program_id: &TOKEN_METADATA_PROGRAM_ID,
+            data: &data[..offset],
+            accounts: &account_metas,
+        };
+
+        let seed_binding = config.seed().to_le_bytes();
+        let config_bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_binding),
+            Seed::from(config.mint_x()),
+            Seed::from(config.mint_y()),
+            Seed::from(&config_bump),
+        ];
+        let signer = [Signer::from(&config_seeds)];
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.accounts.metadata_lp,
+                self.accounts.mint_lp,
+                self.accounts.config,
+                self.accounts.payer,
+                self.accounts.config,
+                self.accounts.system_program,
+            ],
+            &signer,
+        )
+    }
+}
+
+fn metadata_address(mint: &Pubkey) -> Pubkey {
+    find_program_address(
+        &[b"metadata", &TOKEN_METADATA_PROGRAM_ID, mint],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+fn read_metadata<'a>(
+    metadata: &'a AccountInfo,
+    mint: &Pubkey,
+) -> Result<Ref<'a, [u8]>, ProgramError> {
+    if !metadata.is_owned_by(&TOKEN_METADATA_PROGRAM_ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    if metadata_address(mint).ne(metadata.key()) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    metadata.try_borrow_data()
+}
+
+// metaplex pads names and symbols with NULs to their maximum length
+fn metadata_symbol(data: &[u8]) -> Result<&[u8], ProgramError> {
+    let (_, symbol_offset) = read_string(data, METADATA_NAME_OFFSET)?;
+    let (symbol, _) = read_string(data, symbol_offset)?;
+    let len = symbol.iter().rposition(|b| b.ne(&0)).map_or(0, |i| i + 1);
+    Ok(&symbol[..len])
+}
+
+// returns the string at `offset` and the offset just past it
+fn read_string(data: &[u8], offset: usize) -> Result<(&[u8], usize), ProgramError> {
+    let len = data
+        .get(offset..offset + 4)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let start = offset + 4;
+    let end = start + u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    let string = data.get(start..end).ok_or(ProgramError::InvalidAccountData)?;
+    Ok((string, end))
+}
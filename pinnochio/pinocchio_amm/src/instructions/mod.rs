@@ -2,8 +2,135 @@ pub mod initialize;
 pub mod deposit;
 pub mod swap;
 pub mod withdraw;
+pub mod multi_hop_swap;
+pub mod update_access_control;
+pub mod update_volatility_fee;
+pub mod get_position_value;
+pub mod helpers;
+pub mod compute_pool_address;
+pub mod rescue_misdirected_lp;
+pub mod get_pool_stats;
+pub mod batch_get_pool_stats;
+pub mod seed_deposit;
+pub mod initialize_with_liquidity;
+pub mod update_protocol_fee_share;
+pub mod collect_protocol_fees;
+pub mod compound_fees;
+pub mod update_max_swap;
+pub mod mint_lp_with_metadata;
+pub mod verify_pool_integrity;
+pub mod recalibrate_expected_balances;
+pub mod exact_output_swap;
+pub mod update_dust_threshold;
+pub mod sweep_dust;
+pub mod deposit_position;
+pub mod withdraw_position;
+pub mod transfer_position;
+pub mod update_transferable_positions;
+pub mod update_pol_config;
+pub mod rebalance_pol;
+pub mod withdraw_pol;
+pub mod wrap_lp;
+pub mod unwrap_lp;
+pub mod withdraw_bps;
+pub mod update_referral_fee;
+pub mod pause;
+pub mod resume;
+pub mod update_state;
+pub mod recover_stray_tokens;
 
 pub use initialize::*;
 pub use deposit::*;
 pub use swap::*;
-pub use withdraw::*;
\ No newline at end of file
+pub use withdraw::*;
+pub use multi_hop_swap::*;
+pub use update_access_control::*;
+pub use update_volatility_fee::*;
+pub use get_position_value::*;
+pub use helpers::*;
+pub use compute_pool_address::*;
+pub use rescue_misdirected_lp::*;
+pub use get_pool_stats::*;
+pub use batch_get_pool_stats::*;
+pub use seed_deposit::*;
+pub use initialize_with_liquidity::*;
+pub use update_protocol_fee_share::*;
+pub use collect_protocol_fees::*;
+pub use compound_fees::*;
+pub use update_max_swap::*;
+pub use mint_lp_with_metadata::*;
+pub use verify_pool_integrity::*;
+pub use recalibrate_expected_balances::*;
+pub use exact_output_swap::*;
+pub use update_dust_threshold::*;
+pub use sweep_dust::*;
+pub use deposit_position::*;
+pub use withdraw_position::*;
+pub use transfer_position::*;
+pub use update_transferable_positions::*;
+pub use update_pol_config::*;
+pub use rebalance_pol::*;
+pub use withdraw_pol::*;
+pub use wrap_lp::*;
+pub use unwrap_lp::*;
+pub use withdraw_bps::*;
+pub use update_referral_fee::*;
+pub use pause::*;
+pub use resume::*;
+pub use update_state::*;
+pub use recover_stray_tokens::*;
+
+// one per instruction, each dispatched on its own in lib.rs
+pub const DISCRIMINATORS: &[u8] = &[
+    *Initialize::DISCRIMINATOR,
+    *Deposit::DISCRIMINATOR,
+    *Withdraw::DISCRIMINATOR,
+    *Swap::DISCRIMINATOR,
+    *MultiHopSwap::DISCRIMINATOR,
+    *UpdateAccessControl::DISCRIMINATOR,
+    *UpdateVolatilityFee::DISCRIMINATOR,
+    *GetPositionValue::DISCRIMINATOR,
+    *ComputePoolAddress::DISCRIMINATOR,
+    *RescueMisdirectedLp::DISCRIMINATOR,
+    *GetPoolStats::DISCRIMINATOR,
+    *BatchGetPoolStats::DISCRIMINATOR,
+    *SeedDeposit::DISCRIMINATOR,
+    *InitializeWithLiquidity::DISCRIMINATOR,
+    *UpdateProtocolFeeShare::DISCRIMINATOR,
+    *CollectProtocolFees::DISCRIMINATOR,
+    *CompoundFees::DISCRIMINATOR,
+    *UpdateMaxSwap::DISCRIMINATOR,
+    *MintLpWithMetadata::DISCRIMINATOR,
+    *VerifyPoolIntegrity::DISCRIMINATOR,
+    *RecalibrateExpectedBalances::DISCRIMINATOR,
+    *ExactOutputSwap::DISCRIMINATOR,
+    *UpdateDustThreshold::DISCRIMINATOR,
+    *SweepDust::DISCRIMINATOR,
+    *DepositPosition::DISCRIMINATOR,
+    *WithdrawPosition::DISCRIMINATOR,
+    *TransferPosition::DISCRIMINATOR,
+    *UpdateTransferablePositions::DISCRIMINATOR,
+    *UpdatePOLConfig::DISCRIMINATOR,
+    *RebalancePOL::DISCRIMINATOR,
+    *WithdrawPOL::DISCRIMINATOR,
+    *WrapLp::DISCRIMINATOR,
+    *UnwrapLp::DISCRIMINATOR,
+    *WithdrawBps::DISCRIMINATOR,
+    *UpdateReferralFee::DISCRIMINATOR,
+    *Pause::DISCRIMINATOR,
+    *Resume::DISCRIMINATOR,
+    *UpdateState::DISCRIMINATOR,
+    *RecoverStrayTokens::DISCRIMINATOR,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discriminators_are_unique() {
+        for (i, discriminator) in DISCRIMINATORS.iter().enumerate() {
+            assert!(!DISCRIMINATORS[i + 1..].contains(discriminator));
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_associated_token_account::instructions::CreateIdempotent;
+use pinocchio_token::instructions::CloseAccount;
+
+use crate::{
+    read_i64, read_pubkey, read_u64, swap_in_pool, validate_expiration, PinocchioError,
+    SignerAccount,
+};
+
+pub const MAX_HOPS: usize = 4;
+
+// config, vault_x, vault_y, user_in_ata, user_out_ata, mint_out
+const ACCOUNTS_PER_HOP: usize = 6;
+
+pub struct MultiHopSwapAccounts<'a> {
+    pub user: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub hop_accounts: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MultiHopSwapAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [user, token_program, system_program, _associated_token_program, hop_accounts @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(user)?;
+        if hop_accounts.is_empty()
+            || (hop_accounts.len() % ACCOUNTS_PER_HOP).ne(&0)
+            || hop_accounts.len() > ACCOUNTS_PER_HOP * MAX_HOPS
+        {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        Ok(Self {
+            user,
+            token_program,
+            system_program,
+            hop_accounts,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Hop {
+    pub config: Pubkey,
+    pub x_to_y: bool,
+}
+
+pub struct MultiHopSwapInstructionData {
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    pub expiration: i64,
+    pub hop_count: usize,
+    pub hops: [Hop; MAX_HOPS],
+}
+
+impl<'a> TryFrom<&'a [u8]> for MultiHopSwapInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        const HEADER_LEN: usize = size_of::<u64>() * 2 + size_of::<i64>();
+        const HOP_LEN: usize = size_of::<Pubkey>() + size_of::<u8>();
+
+        let (header, hop_data) = data
+            .split_at_checked(HEADER_LEN)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        if hop_data.is_empty()
+            || (hop_data.len() % HOP_LEN).ne(&0)
+            || hop_data.len() > HOP_LEN * MAX_HOPS
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount_in = read_u64(header, 0)?;
+        let min_amount_out = read_u64(header, 8)?;
+        let expiration = read_i64(header, 16)?;
+        let now = Clock::get()?.unix_timestamp;
+        if amount_in.eq(&0) || min_amount_out.eq(&0) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        validate_expiration(now, expiration)?;
+
+        let mut hops = [Hop {
+            config: [0u8; 32],
+            x_to_y: false,
+        }; MAX_HOPS];
+        for (hop, chunk) in hops.iter_mut().zip(hop_data.chunks_exact(HOP_LEN)) {
+            hop.config = read_pubkey(chunk, 0)?;
+            hop.x_to_y = match chunk[32] {
+                0 => false,
+                1 => true,
+                _ => return Err(ProgramError::InvalidInstructionData),
+            };
+        }
+
+        Ok(Self {
+            amount_in,
+            min_amount_out,
+            expiration,
+            hop_count: hop_data.len() / HOP_LEN,
+            hops,
+        })
+    }
+}
+
+pub struct MultiHopSwap<'a> {
+    pub accounts: MultiHopSwapAccounts<'a>,
+    pub instruction_data: MultiHopSwapInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for MultiHopSwap<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = MultiHopSwapAccounts::try_from(accounts)?;
+        let instruction_data = MultiHopSwapInstructionData::try_from(data)?;
+        if instruction_data.hop_count != accounts.hop_accounts.len() / ACCOUNTS_PER_HOP {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> MultiHopSwap<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &4;
+
+    /// Intermediate output ATAs the user doesn't hold yet are created for the route and closed
+    /// again at the end, rent back to the user; ATAs that already existed are left as they were.
+    pub fn process(&mut self) -> ProgramResult {
+        let hops = &self.instruction_data.hops[..self.instruction_data.hop_count];
+        let mut amount = self.instruction_data.amount_in;
+        let mut previous_out: Option<&Pubkey> = None;
+        let mut ephemeral: [Option<&AccountInfo>; MAX_HOPS] = [None; MAX_HOPS];
+
+        for (index, (hop, accounts)) in hops
+            .iter()
+            .zip(self.accounts.hop_accounts.chunks_exact(ACCOUNTS_PER_HOP))
+            .enumerate()
+        {
+            let [config, vault_x, vault_y, user_in_ata, user_out_ata, mint_out] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            if config.key().ne(&hop.config) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            // each hop must spend exactly what the previous hop delivered
+            if previous_out.is_some_and(|key| key.ne(user_in_ata.key())) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            // the ATA program derives the address from user and mint, so a wrong account fails
+            // here, and swap_in_pool still checks the mint against the pool
+            let is_last = index + 1 == hops.len();
+            if !is_last && user_out_ata.data_len().eq(&0) {
+                CreateIdempotent {
+                    funding_account: self.accounts.user,
+                    account: user_out_ata,
+                    wallet: self.accounts.user,
+                    mint: mint_out,
+                    system_program: self.accounts.system_program,
+                    token_program: self.accounts.token_program,
+                }
+                .invoke()?;
+                ephemeral[index] = Some(user_out_ata);
+            }
+
+            // intermediate hops accept any non-zero output, the route minimum is enforced below;
+            // routes carry no access proofs so only open pools can be hopped through, and no
+            // referrer, so the protocol keeps its full share on every hop
+            amount = swap_in_pool(
+                self.accounts.user,
+                user_in_ata,
+                user_out_ata,
+                vault_x,
+                vault_y,
+                config,
+                self.accounts.token_program,
+                None,
+                hop.x_to_y,
+                amount,
+                1,
+                &[],
+            )?;
+            previous_out = Some(user_out_ata.key());
+        }
+
+        if amount < self.instruction_data.min_amount_out {
+            return Err(PinocchioError::SlippageExceeded.into());
+        }
+
+        // the next hop spent everything each of these received, so they are empty again
+        for account in ephemeral.into_iter().flatten() {
+            CloseAccount {
+                account,
+                destination: self.accounts.user,
+                authority: self.accounts.user,
+            }
+            .invoke()?;
+        }
+
+        Ok(())
+    }
+}
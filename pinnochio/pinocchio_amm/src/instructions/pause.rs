@@ -0,0 +1,60 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{keys_eq_constant_time, AmmState, Config, SignerAccount};
+
+pub struct PauseAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for PauseAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+impl PauseAccounts<'_> {
+    // moves the pool from `from` to `to`, for its authority only; a pool without one can't
+    // be paused or resumed by anyone
+    pub fn switch_state(&self, from: AmmState, to: AmmState) -> ProgramResult {
+        let mut config = Config::load_mut(self.config)?;
+
+        match config.has_authority() {
+            Some(authority) if keys_eq_constant_time(&authority, self.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        if config.state_enum()?.ne(&from) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        config.set_state(to.into())
+    }
+}
+
+pub struct Pause<'a> {
+    pub accounts: PauseAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for Pause<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = PauseAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> Pause<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &35;
+
+    // halts deposits, swaps and withdrawals until Resume; only a live pool can be paused, so
+    // a pool Disabled by a failed integrity check can't be brought back through Resume
+    pub fn process(&mut self) -> ProgramResult {
+        self.accounts.switch_state(AmmState::Initialized, AmmState::Paused)
+    }
+}
@@ -0,0 +1,175 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    ProgramResult,
+};
+use pinocchio_log::log;
+use pinocchio_token::{instructions::MintTo, state::TokenAccount};
+
+use crate::{
+    check_mint_lp_address, check_vault_address, load_mint_checked, underlying_to_lp, Config,
+    POLConfig, PinocchioError, SignerAccount,
+};
+
+pub struct RebalancePOLAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub pol: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub pool_lp: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RebalancePOLAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, pol, mint_lp, vault_x, vault_y, pool_lp, token_program] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self {
+            authority,
+            config,
+            pol,
+            mint_lp,
+            vault_x,
+            vault_y,
+            pool_lp,
+            token_program,
+        })
+    }
+}
+
+pub struct RebalancePOL<'a> {
+    pub accounts: RebalancePOLAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RebalancePOL<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = RebalancePOLAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> RebalancePOL<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &29;
+
+    /// Turns accrued protocol fees into treasury-owned LP. Like `CompoundFees` only the part
+    /// matching the pool ratio is released into the reserves, but here LP is minted for it
+    /// into the pool's LP account and credited to the `POLConfig`, so the value stays the
+    /// protocol's instead of going to existing LPs.
+    pub fn process(&mut self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        let (threshold_bps, target_ratio_bps, treasury_lp) = {
+            let pol = POLConfig::load(self.accounts.pol)?;
+            if pol.pool().ne(self.accounts.config.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            (pol.rebalance_threshold_bps(), pol.target_ratio_bps(), pol.lp_amount())
+        };
+
+        check_mint_lp_address(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            config.mint_lp_bump(),
+        )?;
+        let mint_lp = *self.accounts.mint_lp.key();
+        for (vault, mint, bump) in [
+            (
+                self.accounts.vault_x,
+                config.mint_x(),
+                config.vault_x_bump(),
+            ),
+            (
+                self.accounts.vault_y,
+                config.mint_y(),
+                config.vault_y_bump(),
+            ),
+            (self.accounts.pool_lp, &mint_lp, config.lp_vault_bump()),
+        ] {
+            check_vault_address(
+                vault,
+                self.accounts.config,
+                self.accounts.token_program,
+                mint,
+                bump,
+            )?;
+        }
+
+        let lp_supply = load_mint_checked(self.accounts.mint_lp)?.supply();
+        let (reserve_x, reserve_y) = config.pool_reserves(
+            TokenAccount::from_account_info(self.accounts.vault_x)?.amount(),
+            TokenAccount::from_account_info(self.accounts.vault_y)?.amount(),
+        );
+        if reserve_x.eq(&0) || reserve_y.eq(&0) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if treasury_lp as u128 * 10_000 >= lp_supply as u128 * target_ratio_bps as u128 {
+            return Err(PinocchioError::POLTargetReached.into());
+        }
+
+        let (fees_x, fees_y) = (config.fees_collected_x() as u128, config.fees_collected_y() as u128);
+        let (reserve_x, reserve_y) = (reserve_x as u128, reserve_y as u128);
+        if fees_x * 10_000 < reserve_x * threshold_bps as u128
+            && fees_y * 10_000 < reserve_y * threshold_bps as u128
+        {
+            return Err(PinocchioError::RebalanceThresholdNotMet.into());
+        }
+
+        // whichever side runs out first at the pool ratio bounds the release
+        let (release_x, release_y) = match fees_x * reserve_y <= fees_y * reserve_x {
+            true => (fees_x, fees_x * reserve_y / reserve_x),
+            false => (fees_y * reserve_x / reserve_y, fees_y),
+        };
+        let (release_x, release_y) = (release_x as u64, release_y as u64);
+
+        let lp = underlying_to_lp(
+            release_x,
+            release_y,
+            lp_supply,
+            reserve_x as u64,
+            reserve_y as u64,
+        );
+        if lp.eq(&0) {
+            return Err(PinocchioError::ZeroLpMinted.into());
+        }
+
+        let seed_binding = config.seed().to_le_bytes();
+        let config_bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_binding),
+            Seed::from(config.mint_x()),
+            Seed::from(config.mint_y()),
+            Seed::from(&config_bump),
+        ];
+
+        MintTo {
+            mint: self.accounts.mint_lp,
+            account: self.accounts.pool_lp,
+            mint_authority: self.accounts.config,
+            amount: lp,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+        drop(config);
+
+        // the fees never left the vaults, releasing them is all the deposit there is
+        Config::load_mut(self.accounts.config)?.release_fees_collected(release_x, release_y);
+        POLConfig::load_mut(self.accounts.pol)?.credit_lp(lp);
+        log!("RebalancePOL: released x {} y {} for lp {}", release_x, release_y, lp);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+use pinocchio_log::log;
+
+use crate::{vault_balances, Config, SignerAccount};
+
+pub struct RecalibrateExpectedBalancesAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RecalibrateExpectedBalancesAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, vault_x, vault_y, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self {
+            authority,
+            config,
+            vault_x,
+            vault_y,
+            token_program,
+        })
+    }
+}
+
+pub struct RecalibrateExpectedBalances<'a> {
+    pub accounts: RecalibrateExpectedBalancesAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RecalibrateExpectedBalances<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = RecalibrateExpectedBalancesAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> RecalibrateExpectedBalances<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &20;
+
+    // resets the expected balances to what the vaults hold now, the pool state is left alone
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        let (balance_x, balance_y) = vault_balances(
+            &config,
+            self.accounts.config,
+            self.accounts.vault_x,
+            self.accounts.vault_y,
+            self.accounts.token_program,
+        )?;
+        log!(
+            "RecalibrateExpectedBalances: x {} -> {}, y {} -> {}",
+            config.expected_vault_x_balance(),
+            balance_x,
+            config.expected_vault_y_balance(),
+            balance_y
+        );
+        config.set_expected_balances(balance_x, balance_y);
+
+        Ok(())
+    }
+}
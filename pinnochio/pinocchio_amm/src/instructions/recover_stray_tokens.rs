@@ -0,0 +1,138 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Signer,
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    ProgramResult,
+};
+use pinocchio_log::log;
+use pinocchio_token::{instructions::Transfer, state::TokenAccount};
+
+use crate::{keys_eq_constant_time, signer_seeds, Config, SignerAccount};
+
+// tokens of mint_x, mint_y or the LP mint are the pool's own vaults and POL, whatever account
+// they sit in, so they are never stray
+pub fn check_stray_mint(
+    mint: &Pubkey,
+    mint_x: &Pubkey,
+    mint_y: &Pubkey,
+    mint_lp: &Pubkey,
+) -> ProgramResult {
+    if mint.eq(mint_x) || mint.eq(mint_y) || mint.eq(mint_lp) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+pub struct RecoverStrayTokensAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub stray: &'a AccountInfo,
+    pub destination: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RecoverStrayTokensAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, stray, destination, _token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self {
+            authority,
+            config,
+            stray,
+            destination,
+        })
+    }
+}
+
+pub struct RecoverStrayTokens<'a> {
+    pub accounts: RecoverStrayTokensAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RecoverStrayTokens<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = RecoverStrayTokensAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> RecoverStrayTokens<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &38;
+
+    /// Admin recovery for tokens sent to the pool by mistake: moves the whole balance of a
+    /// token account the config owns to `destination`, any token account of the same mint.
+    /// Accounts of mint_x, mint_y or the LP mint are the pool's own vaults and POL, whatever
+    /// their address, and are always refused.
+    pub fn process(&mut self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+
+        let signer = self.accounts.authority.key();
+        match config.has_authority() {
+            Some(authority) if keys_eq_constant_time(&authority, signer) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        let (mint_lp, _) =
+            find_program_address(&[b"mint_lp", self.accounts.config.key()], &crate::ID);
+        let amount = {
+            let stray = TokenAccount::from_account_info(self.accounts.stray)?;
+            if stray.owner().ne(self.accounts.config.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            check_stray_mint(stray.mint(), config.mint_x(), config.mint_y(), &mint_lp)?;
+            stray.amount()
+        };
+        if amount.eq(&0) {
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        signer_seeds!(config_seeds = [
+            b"config",
+            config.seed().to_le_bytes(),
+            config.mint_x(),
+            config.mint_y(),
+            config.config_bump(),
+        ]);
+
+        Transfer {
+            from: self.accounts.stray,
+            to: self.accounts.destination,
+            authority: self.accounts.config,
+            amount,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        log!("RecoverStrayTokens: moved {} tokens", amount);
+        pinocchio::pubkey::log(self.accounts.stray.key());
+        pinocchio::pubkey::log(self.accounts.destination.key());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT_X: Pubkey = [1u8; 32];
+    const MINT_Y: Pubkey = [2u8; 32];
+    const MINT_LP: Pubkey = [3u8; 32];
+
+    #[test]
+    fn refuses_the_pool_mints() {
+        for mint in [MINT_X, MINT_Y, MINT_LP] {
+            assert_eq!(
+                check_stray_mint(&mint, &MINT_X, &MINT_Y, &MINT_LP),
+                Err(ProgramError::InvalidArgument)
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_any_other_mint() {
+        assert_eq!(check_stray_mint(&[4u8; 32], &MINT_X, &MINT_Y, &MINT_LP), Ok(()));
+    }
+}
@@ -0,0 +1,240 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    ProgramResult,
+};
+use pinocchio_log::log;
+use pinocchio_token::instructions::{Burn, MintTo};
+
+use crate::{check_mint_lp_address, keys_eq_constant_time, read_u64, Config, SignerAccount};
+
+pub struct RescueMisdirectedLpAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub holder: &'a AccountInfo,
+    pub from_lp_ata: &'a AccountInfo,
+    pub recipient: &'a AccountInfo,
+    pub recipient_lp_ata: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RescueMisdirectedLpAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, holder, from_lp_ata, recipient, recipient_lp_ata, mint_lp, config, token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        // the token program only lets the holder burn, so both sides have to sign off
+        SignerAccount::check(authority)?;
+        SignerAccount::check(holder)?;
+        Ok(Self {
+            authority,
+            holder,
+            from_lp_ata,
+            recipient,
+            recipient_lp_ata,
+            mint_lp,
+            config,
+            token_program,
+        })
+    }
+}
+
+pub struct RescueMisdirectedLpInstructionData {
+    pub amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for RescueMisdirectedLpInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let amount = read_u64(data, 0)?;
+        if amount.eq(&0) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Self { amount })
+    }
+}
+
+pub struct RescueMisdirectedLp<'a> {
+    pub accounts: RescueMisdirectedLpAccounts<'a>,
+    pub instruction_data: RescueMisdirectedLpInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for RescueMisdirectedLp<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = RescueMisdirectedLpAccounts::try_from(accounts)?;
+        let instruction_data = RescueMisdirectedLpInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> RescueMisdirectedLp<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &9;
+
+    /// Admin recovery for LP minted to the wrong account: burns `amount` from `from_lp_ata`
+    /// and mints the same amount to the recipient's canonical LP ATA.
+    pub fn process(&mut self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+
+        check_mint_lp_address(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            config.mint_lp_bump(),
+        )?;
+        let mint_lp = *self.accounts.mint_lp.key();
+        let (recipient_lp_ata, _) = find_program_address(
+            &[
+                self.accounts.recipient.key(),
+                self.accounts.token_program.key(),
+                &mint_lp,
+            ],
+            &pinocchio_associated_token_account::ID,
+        );
+        check_rescue(
+            config.has_authority(),
+            self.accounts.authority.key(),
+            &recipient_lp_ata,
+            self.accounts.recipient_lp_ata.key(),
+            self.accounts.from_lp_ata.key(),
+        )?;
+
+        Burn {
+            account: self.accounts.from_lp_ata,
+            mint: self.accounts.mint_lp,
+            authority: self.accounts.holder,
+            amount: self.instruction_data.amount,
+        }
+        .invoke()?;
+
+        let seed_binding = config.seed().to_le_bytes();
+        let config_bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_binding),
+            Seed::from(config.mint_x()),
+            Seed::from(config.mint_y()),
+            Seed::from(&config_bump),
+        ];
+        let signer = [Signer::from(&config_seeds)];
+
+        MintTo {
+            mint: self.accounts.mint_lp,
+            account: self.accounts.recipient_lp_ata,
+            mint_authority: self.accounts.config,
+            amount: self.instruction_data.amount,
+        }
+        .invoke_signed(&signer)?;
+
+        log!("RescueMisdirectedLp: moved {} LP", self.instruction_data.amount);
+        pinocchio::pubkey::log(self.accounts.from_lp_ata.key());
+        pinocchio::pubkey::log(self.accounts.recipient_lp_ata.key());
+
+        Ok(())
+    }
+}
+
+// only the config authority may move LP, and only into the recipient's canonical LP ATA, which
+// must not be the account the LP is burned from
+fn check_rescue(
+    config_authority: Option<Pubkey>,
+    authority: &Pubkey,
+    canonical_recipient_lp_ata: &Pubkey,
+    recipient_lp_ata: &Pubkey,
+    from_lp_ata: &Pubkey,
+) -> ProgramResult {
+    match config_authority {
+        Some(config_authority) if keys_eq_constant_time(&config_authority, authority) => {}
+        _ => return Err(ProgramError::IncorrectAuthority),
+    }
+    if canonical_recipient_lp_ata.ne(recipient_lp_ata) || canonical_recipient_lp_ata.eq(from_lp_ata)
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTHORITY: Pubkey = [1u8; 32];
+    const RECIPIENT_LP: Pubkey = [2u8; 32];
+    const FROM_LP: Pubkey = [3u8; 32];
+
+    #[test]
+    fn authority_rescues_to_the_recipients_ata() {
+        assert_eq!(
+            check_rescue(
+                Some(AUTHORITY),
+                &AUTHORITY,
+                &RECIPIENT_LP,
+                &RECIPIENT_LP,
+                &FROM_LP
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn only_the_config_authority_can_rescue() {
+        let mut other = AUTHORITY;
+        other[31] ^= 1;
+        assert_eq!(
+            check_rescue(
+                Some(AUTHORITY),
+                &other,
+                &RECIPIENT_LP,
+                &RECIPIENT_LP,
+                &FROM_LP
+            ),
+            Err(ProgramError::IncorrectAuthority)
+        );
+        // a pool without an authority has nobody to rescue for it
+        assert_eq!(
+            check_rescue(None, &AUTHORITY, &RECIPIENT_LP, &RECIPIENT_LP, &FROM_LP),
+            Err(ProgramError::IncorrectAuthority)
+        );
+    }
+
+    #[test]
+    fn mints_only_to_another_canonical_ata() {
+        assert_eq!(
+            check_rescue(
+                Some(AUTHORITY),
+                &AUTHORITY,
+                &RECIPIENT_LP,
+                &FROM_LP,
+                &FROM_LP
+            ),
+            Err(ProgramError::InvalidAccountData)
+        );
+        // burning from and minting back to the same account would only log a fake rescue
+        assert_eq!(
+            check_rescue(Some(AUTHORITY), &AUTHORITY, &FROM_LP, &FROM_LP, &FROM_LP),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn instruction_data_needs_a_nonzero_amount() {
+        let data = 5u64.to_le_bytes();
+        assert_eq!(
+            RescueMisdirectedLpInstructionData::try_from(&data[..]).map(|data| data.amount),
+            Ok(5)
+        );
+        assert!(RescueMisdirectedLpInstructionData::try_from(&0u64.to_le_bytes()[..]).is_err());
+        assert!(RescueMisdirectedLpInstructionData::try_from(&data[..7]).is_err());
+    }
+}
@@ -0,0 +1,25 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{AmmState, PauseAccounts};
+
+pub struct Resume<'a> {
+    pub accounts: PauseAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for Resume<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        // same accounts as Pause: the authority and the config
+        let accounts = PauseAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> Resume<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &36;
+
+    // reopens a paused pool; any other state is left to whatever put the pool there
+    pub fn process(&mut self) -> ProgramResult {
+        self.accounts.switch_state(AmmState::Paused, AmmState::Initialized)
+    }
+}
@@ -0,0 +1,31 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::Deposit;
+
+/// First deposit into an empty pool. Same accounts and data as `Deposit`, but `max_x` and
+/// `max_y` are deposited exactly and so define the pool's initial price; `amount` is the LP
+/// minted for them. Plain `Deposit` refuses empty pools so the price is never set by accident.
+pub struct SeedDeposit<'a> {
+    pub deposit: Deposit<'a>,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for SeedDeposit<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let deposit = Deposit::try_from((data, accounts))?;
+        Ok(Self { deposit })
+    }
+}
+
+impl<'a> SeedDeposit<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &12;
+
+    pub fn process(&mut self) -> ProgramResult {
+        self.deposit.execute(true, Clock::get()?.unix_timestamp)
+    }
+}
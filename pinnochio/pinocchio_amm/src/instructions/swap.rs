@@ -0,0 +1,327 @@
+use constant_product_curve::{ConstantProduct, LiquidityPair};
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::{instructions::Transfer, state::TokenAccount};
+
+use crate::{
+    assert_distinct, assert_mint, check_not_frozen, check_swap_invariant, check_vault_address,
+    check_vault_initialized, read_i64, read_u64, validate_expiration, verify_access, AmmState,
+    Config, SignerAccount, SwapEvent,
+};
+
+pub struct SwapAccounts<'a> {
+    pub user: &'a AccountInfo,
+    pub user_x_ata: &'a AccountInfo,
+    pub user_y_ata: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    // token account of the input mint that collects the referral fee, when a front end
+    // routed the swap
+    pub referrer: Option<&'a AccountInfo>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SwapAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [user, user_x_ata, user_y_ata, vault_x, vault_y, config, token_program, rest @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let referrer = match rest {
+            [] => None,
+            [referrer] => Some(referrer),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        SignerAccount::check(user)?;
+        Ok(Self {
+            user,
+            user_x_ata,
+            user_y_ata,
+            vault_x,
+            vault_y,
+            config,
+            token_program,
+            referrer,
+        })
+    }
+}
+
+pub struct SwapInstructionData<'a> {
+    pub is_x: bool,
+    pub amount: u64,
+    pub min: u64,
+    pub expiration: i64,
+    pub access_proof: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for SwapInstructionData<'a> {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let (data, access_proof) = data
+            .split_at_checked(size_of::<u8>() + size_of::<u64>() * 2 + size_of::<i64>())
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let is_x = match data[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        let amount = read_u64(data, 1)?;
+        let min = read_u64(data, 9)?;
+        let expiration = read_i64(data, 17)?;
+        let now = Clock::get()?.unix_timestamp;
+        if amount.eq(&0) || min.eq(&0) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        validate_expiration(now, expiration)?;
+        Ok(Self {
+            is_x,
+            amount,
+            min,
+            expiration,
+            access_proof,
+        })
+    }
+}
+
+pub struct Swap<'a> {
+    pub accounts: SwapAccounts<'a>,
+    pub instruction_data: SwapInstructionData<'a>,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Swap<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = SwapAccounts::try_from(accounts)?;
+        let instruction_data = SwapInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> Swap<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &2;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let (user_from, user_to) = match self.instruction_data.is_x {
+            true => (self.accounts.user_x_ata, self.accounts.user_y_ata),
+            false => (self.accounts.user_y_ata, self.accounts.user_x_ata),
+        };
+
+        swap_in_pool(
+            self.accounts.user,
+            user_from,
+            user_to,
+            self.accounts.vault_x,
+            self.accounts.vault_y,
+            self.accounts.config,
+            self.accounts.token_program,
+            self.accounts.referrer,
+            self.instruction_data.is_x,
+            self.instruction_data.amount,
+            self.instruction_data.min,
+            self.instruction_data.access_proof,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Swaps `amount` of the input side of a single pool and returns the amount withdrawn to
+/// `user_to`. Shared by `Swap` and `MultiHopSwap` so every hop runs the same checks,
+/// including the pool's access list (an empty `access_proof` only passes open pools).
+/// A `referrer` is paid its cut of the protocol fee out of the input vault.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_in_pool(
+    user: &AccountInfo,
+    user_from: &AccountInfo,
+    user_to: &AccountInfo,
+    vault_x: &AccountInfo,
+    vault_y: &AccountInfo,
+    config_account: &AccountInfo,
+    token_program: &AccountInfo,
+    referrer: Option<&AccountInfo>,
+    is_x: bool,
+    amount: u64,
+    min: u64,
+    access_proof: &[u8],
+) -> Result<u64, ProgramError> {
+    let config = Config::load(config_account)?;
+
+    if config.state_enum()?.ne(&AmmState::Initialized) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    verify_access(&config, user.key(), access_proof)?;
+    for (vault, mint, bump) in [
+        (vault_x, config.mint_x(), config.vault_x_bump()),
+        (vault_y, config.mint_y(), config.vault_y_bump()),
+    ] {
+        check_vault_address(vault, config_account, token_program, mint, bump)?;
+    }
+
+    check_vault_initialized(vault_x)?;
+    check_vault_initialized(vault_y)?;
+    check_not_frozen(user_from)?;
+    check_not_frozen(user_to)?;
+    // `is_x` picks the side, the accounts have to agree with it rather than be trusted
+    let (mint_in, mint_out) = match is_x {
+        true => (config.mint_x(), config.mint_y()),
+        false => (config.mint_y(), config.mint_x()),
+    };
+    assert_mint(vault_x, config.mint_x())?;
+    assert_mint(vault_y, config.mint_y())?;
+    assert_mint(user_from, mint_in)?;
+    assert_mint(user_to, mint_out)?;
+    if let Some(referrer) = referrer {
+        // the fee is taken on the input side, so that's the mint the referrer is paid in
+        assert_mint(referrer, mint_in)?;
+        assert_distinct(&[referrer.key(), vault_x.key(), vault_y.key()])?;
+    }
+    let (reserve_x, reserve_y) = {
+        let vault_x = unsafe { TokenAccount::from_account_info_unchecked(vault_x)? };
+        let vault_y = unsafe { TokenAccount::from_account_info_unchecked(vault_y)? };
+        config.pool_reserves(vault_x.amount(), vault_y.amount())
+    };
+
+    let reserve_in = match is_x {
+        true => reserve_x,
+        false => reserve_y,
+    };
+    config.check_swap_cap(amount, reserve_in)?;
+
+    let mut curve = ConstantProduct::init(reserve_x, reserve_y, reserve_x, config.effective_fee(), None)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    let pair = match is_x {
+        true => LiquidityPair::X,
+        false => LiquidityPair::Y,
+    };
+    let swap_result = curve
+        .swap(pair, amount, min)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+
+    if swap_result.deposit.eq(&0) || swap_result.withdraw.eq(&0) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let reserve_out = match is_x {
+        true => reserve_y,
+        false => reserve_x,
+    };
+    check_swap_invariant(
+        reserve_in,
+        reserve_out,
+        swap_result.deposit.saturating_sub(swap_result.fee),
+        swap_result.withdraw,
+    )?;
+
+    let (vault_from, vault_to) = match is_x {
+        true => (vault_x, vault_y),
+        false => (vault_y, vault_x),
+    };
+
+    Transfer {
+        from: user_from,
+        to: vault_from,
+        authority: user,
+        amount: swap_result.deposit,
+    }
+    .invoke()?;
+
+    let seed_binding = config.seed().to_le_bytes();
+    let config_bump = config.config_bump();
+    let config_seeds = [
+        Seed::from(b"config"),
+        Seed::from(&seed_binding),
+        Seed::from(config.mint_x()),
+        Seed::from(config.mint_y()),
+        Seed::from(&config_bump),
+    ];
+    let signer = [Signer::from(&config_seeds)];
+
+    Transfer {
+        from: vault_to,
+        to: user_to,
+        authority: config_account,
+        amount: swap_result.withdraw,
+    }
+    .invoke_signed(&signer)?;
+
+    let referral_fee = match referrer {
+        Some(referrer) => {
+            let referral_fee = config.referral_fee(swap_result.fee);
+            if referral_fee.ne(&0) {
+                Transfer {
+                    from: vault_from,
+                    to: referrer,
+                    authority: config_account,
+                    amount: referral_fee,
+                }
+                .invoke_signed(&signer)?;
+            }
+            referral_fee
+        }
+        None => 0,
+    };
+    drop(config);
+
+    let (new_x, new_y) = match is_x {
+        true => (reserve_x + swap_result.deposit, reserve_y - swap_result.withdraw),
+        false => (reserve_x - swap_result.withdraw, reserve_y + swap_result.deposit),
+    };
+    let clock = Clock::get()?;
+    let mut config = Config::load_mut(config_account)?;
+    config.record_price_change(
+        price_change_bps(reserve_x, reserve_y, new_x, new_y),
+        clock.slot,
+    );
+    let volume_x = match is_x {
+        true => swap_result.deposit,
+        false => swap_result.withdraw,
+    };
+    config.record_volume(volume_x, clock.unix_timestamp as u64 / SECONDS_PER_DAY);
+    config.accrue_protocol_fee(is_x, swap_result.fee, referral_fee);
+    config.update_price_cumulatives(new_x, new_y, clock.unix_timestamp);
+    // the referral fee already left the input vault, it was never pool liquidity
+    let kept = swap_result.deposit - referral_fee;
+    match is_x {
+        true => {
+            config.credit_expected_balances(kept, 0);
+            config.debit_expected_balances(0, swap_result.withdraw);
+        }
+        false => {
+            config.credit_expected_balances(0, kept);
+            config.debit_expected_balances(swap_result.withdraw, 0);
+        }
+    }
+
+    SwapEvent {
+        pool: *config_account.key(),
+        user: *user.key(),
+        is_x,
+        amount_in: swap_result.deposit,
+        amount_out: swap_result.withdraw,
+    }
+    .emit();
+
+    Ok(swap_result.withdraw)
+}
+
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+// |p1 - p0| / p0 in basis points with p = y / x, i.e. |y1 * x0 - y0 * x1| * 10_000 / (y0 * x1)
+fn price_change_bps(x0: u64, y0: u64, x1: u64, y1: u64) -> u64 {
+    let before = y1 as u128 * x0 as u128;
+    let after = y0 as u128 * x1 as u128;
+    if after.eq(&0) {
+        return 0;
+    }
+    (before.abs_diff(after) * 10_000 / after).min(u64::MAX as u128) as u64
+}
@@ -0,0 +1,124 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::{
+    instructions::{CloseAccount, Transfer},
+    state::TokenAccount,
+};
+
+use crate::{check_vault_address, check_vault_initialized, Config, PinocchioError, SignerAccount};
+
+pub struct SweepDustAccounts<'a> {
+    pub user: &'a AccountInfo,
+    pub user_ata: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SweepDustAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [user, user_ata, config, vault, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(user)?;
+        if token_program.key().ne(&pinocchio_token::ID) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(Self {
+            user,
+            user_ata,
+            config,
+            vault,
+            token_program,
+        })
+    }
+}
+
+pub struct SweepDust<'a> {
+    pub accounts: SweepDustAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SweepDust<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = SweepDustAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> SweepDust<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &23;
+
+    /// Donates a token account holding less than the pool's `dust_threshold` of mint x or y to
+    /// the matching vault and closes it, returning the rent to the user. Only the account
+    /// owner can sweep, so nobody can close someone else's account out from under them.
+    pub fn process(&mut self) -> ProgramResult {
+        let (is_x, amount) = {
+            let config = Config::load(self.accounts.config)?;
+            let user_ata = TokenAccount::from_account_info(self.accounts.user_ata)?;
+
+            if user_ata.owner().ne(self.accounts.user.key()) {
+                return Err(ProgramError::IllegalOwner);
+            }
+            let is_x = match user_ata.mint() {
+                mint if mint.eq(config.mint_x()) => true,
+                mint if mint.eq(config.mint_y()) => false,
+                _ => return Err(ProgramError::InvalidAccountData),
+            };
+
+            let amount = user_ata.amount();
+            if amount >= config.dust_threshold() {
+                return Err(PinocchioError::NotDust.into());
+            }
+
+            check_vault_address(
+                self.accounts.vault,
+                self.accounts.config,
+                self.accounts.token_program,
+                user_ata.mint(),
+                match is_x {
+                    true => config.vault_x_bump(),
+                    false => config.vault_y_bump(),
+                },
+            )?;
+            (is_x, amount)
+        };
+        check_vault_initialized(self.accounts.vault)?;
+
+        if amount.ne(&0) {
+            Transfer {
+                from: self.accounts.user_ata,
+                to: self.accounts.vault,
+                authority: self.accounts.user,
+                amount,
+            }
+            .invoke()?;
+
+            // a donation, not a pool transfer the integrity check should flag
+            let mut config = Config::load_mut(self.accounts.config)?;
+            match is_x {
+                true => config.credit_expected_balances(amount, 0),
+                false => config.credit_expected_balances(0, amount),
+            }
+            // only the swept vault is passed, the other side's reserve is what the pool's own
+            // transfers left in it
+            let (reserve_x, reserve_y) = config.pool_reserves(
+                config.expected_vault_x_balance(),
+                config.expected_vault_y_balance(),
+            );
+            config.update_price_cumulatives(reserve_x, reserve_y, Clock::get()?.unix_timestamp);
+        }
+
+        CloseAccount {
+            account: self.accounts.user_ata,
+            destination: self.accounts.user,
+            authority: self.accounts.user,
+        }
+        .invoke()
+    }
+}
@@ -0,0 +1,59 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{Config, LPPosition, SignerAccount};
+
+pub struct TransferPositionAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub position: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub new_owner: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for TransferPositionAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, position, config, new_owner] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        Ok(Self {
+            owner,
+            position,
+            config,
+            new_owner,
+        })
+    }
+}
+
+pub struct TransferPosition<'a> {
+    pub accounts: TransferPositionAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for TransferPosition<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = TransferPositionAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> TransferPosition<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &26;
+
+    // positions are soulbound unless the pool authority turned on transferable_positions
+    pub fn process(&mut self) -> ProgramResult {
+        if !Config::load(self.accounts.config)?.transferable_positions() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut position = LPPosition::load_mut(self.accounts.position)?;
+        if position.owner().ne(self.accounts.owner.key())
+            || position.pool().ne(self.accounts.config.key())
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        position.set_owner(*self.accounts.new_owner.key());
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    ProgramResult,
+};
+use pinocchio_token::instructions::Transfer;
+
+use crate::{
+    assert_mint, check_mint_lp_address, check_vault_address, Config, LPPosition, SignerAccount,
+};
+
+pub struct UnwrapLpAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub owner_lp_ata: &'a AccountInfo,
+    pub position_lp: &'a AccountInfo,
+    pub position: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UnwrapLpAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, config, mint_lp, owner_lp_ata, position_lp, position, token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        Ok(Self {
+            owner,
+            config,
+            mint_lp,
+            owner_lp_ata,
+            position_lp,
+            position,
+            token_program,
+        })
+    }
+}
+
+pub struct UnwrapLp<'a> {
+    pub accounts: UnwrapLpAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UnwrapLp<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = UnwrapLpAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> UnwrapLp<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &32;
+
+    /// Pays a whole `LPPosition` out as fungible LP to the owner and closes it, rent going
+    /// to the owner. Works for positions from `DepositPosition` as well as `WrapLp`.
+    pub fn process(&mut self) -> ProgramResult {
+        let liquidity = {
+            let position = LPPosition::load(self.accounts.position)?;
+            if position.owner().ne(self.accounts.owner.key())
+                || position.pool().ne(self.accounts.config.key())
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            position.liquidity()
+        };
+
+        let config = Config::load(self.accounts.config)?;
+
+        check_mint_lp_address(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            config.mint_lp_bump(),
+        )?;
+        let mint_lp = *self.accounts.mint_lp.key();
+        check_vault_address(
+            self.accounts.position_lp,
+            self.accounts.config,
+            self.accounts.token_program,
+            &mint_lp,
+            config.lp_vault_bump(),
+        )?;
+        assert_mint(self.accounts.owner_lp_ata, &mint_lp)?;
+
+        let seed_binding = config.seed().to_le_bytes();
+        let config_bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_binding),
+            Seed::from(config.mint_x()),
+            Seed::from(config.mint_y()),
+            Seed::from(&config_bump),
+        ];
+
+        Transfer {
+            from: self.accounts.position_lp,
+            to: self.accounts.owner_lp_ata,
+            authority: self.accounts.config,
+            amount: liquidity,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+        drop(config);
+
+        let lamports = self.accounts.position.lamports();
+        let owner_lamports = self
+            .accounts
+            .owner
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        *self.accounts.owner.try_borrow_mut_lamports()? = owner_lamports;
+        self.accounts.position.close()
+    }
+}
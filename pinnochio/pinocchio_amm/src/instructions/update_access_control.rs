@@ -0,0 +1,75 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{read_pubkey, AccessMode, Config, SignerAccount};
+
+pub struct UpdateAccessControlAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateAccessControlAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateAccessControlInstructionData {
+    pub access_mode: AccessMode,
+    pub access_list_root: [u8; 32],
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateAccessControlInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u8>() + size_of::<[u8; 32]>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let access_mode =
+            AccessMode::try_from(data[0]).map_err(|_| ProgramError::InvalidInstructionData)?;
+        let access_list_root = read_pubkey(data, 1)?;
+        Ok(Self {
+            access_mode,
+            access_list_root,
+        })
+    }
+}
+
+pub struct UpdateAccessControl<'a> {
+    pub accounts: UpdateAccessControlAccounts<'a>,
+    pub instruction_data: UpdateAccessControlInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateAccessControl<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateAccessControlAccounts::try_from(accounts)?;
+        let instruction_data = UpdateAccessControlInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateAccessControl<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &5;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        config.set_access_control(
+            self.instruction_data.access_mode,
+            self.instruction_data.access_list_root,
+        )
+    }
+}
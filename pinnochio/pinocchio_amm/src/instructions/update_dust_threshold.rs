@@ -0,0 +1,68 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{read_u64, Config, SignerAccount};
+
+pub struct UpdateDustThresholdAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateDustThresholdAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateDustThresholdInstructionData {
+    pub dust_threshold: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateDustThresholdInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let dust_threshold = read_u64(data, 0)?;
+        Ok(Self { dust_threshold })
+    }
+}
+
+pub struct UpdateDustThreshold<'a> {
+    pub accounts: UpdateDustThresholdAccounts<'a>,
+    pub instruction_data: UpdateDustThresholdInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateDustThreshold<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateDustThresholdAccounts::try_from(accounts)?;
+        let instruction_data = UpdateDustThresholdInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateDustThreshold<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &22;
+
+    // token balances below `dust_threshold` may be swept into the vaults, 0 turns sweeping off
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        config.set_dust_threshold(self.instruction_data.dust_threshold);
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{read_u16, Config, SignerAccount};
+
+pub struct UpdateMaxSwapAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateMaxSwapAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateMaxSwapInstructionData {
+    pub max_swap_bps: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateMaxSwapInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u16>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let max_swap_bps = read_u16(data, 0)?;
+        Ok(Self { max_swap_bps })
+    }
+}
+
+pub struct UpdateMaxSwap<'a> {
+    pub accounts: UpdateMaxSwapAccounts<'a>,
+    pub instruction_data: UpdateMaxSwapInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateMaxSwap<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateMaxSwapAccounts::try_from(accounts)?;
+        let instruction_data = UpdateMaxSwapInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateMaxSwap<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &17;
+
+    // caps a single swap's input at `max_swap_bps` of the input reserve, 0 lifts the cap
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        config.set_max_swap_bps(self.instruction_data.max_swap_bps)
+    }
+}
@@ -0,0 +1,114 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::{read_pubkey, read_u16, Config, POLConfig, SignerAccount};
+
+pub struct UpdatePOLConfigAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub pol: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdatePOLConfigAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, pol, _system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self {
+            authority,
+            config,
+            pol,
+        })
+    }
+}
+
+pub struct UpdatePOLConfigInstructionData {
+    pub treasury: Pubkey,
+    pub rebalance_threshold_bps: u16,
+    pub target_ratio_bps: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdatePOLConfigInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<Pubkey>() + size_of::<u16>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let treasury = read_pubkey(data, 0)?;
+        let rebalance_threshold_bps = read_u16(data, 32)?;
+        let target_ratio_bps = read_u16(data, 34)?;
+        Ok(Self {
+            treasury,
+            rebalance_threshold_bps,
+            target_ratio_bps,
+        })
+    }
+}
+
+pub struct UpdatePOLConfig<'a> {
+    pub accounts: UpdatePOLConfigAccounts<'a>,
+    pub instruction_data: UpdatePOLConfigInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdatePOLConfig<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdatePOLConfigAccounts::try_from(accounts)?;
+        let instruction_data = UpdatePOLConfigInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdatePOLConfig<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &28;
+
+    // creates the pool's POLConfig on first use; the treasury LP already held is unaffected
+    pub fn process(&mut self) -> ProgramResult {
+        match Config::load(self.accounts.config)?.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        let (pol, bump) = find_program_address(&[b"pol", self.accounts.config.key()], &crate::ID);
+        if pol.ne(self.accounts.pol.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if self.accounts.pol.data_len().eq(&0) {
+            let bump = [bump];
+            let pol_seeds = [
+                Seed::from(b"pol"),
+                Seed::from(self.accounts.config.key()),
+                Seed::from(&bump),
+            ];
+            CreateAccount {
+                from: self.accounts.authority,
+                to: self.accounts.pol,
+                lamports: Rent::get()?.minimum_balance(POLConfig::LEN),
+                space: POLConfig::LEN as u64,
+                owner: &crate::ID,
+            }
+            .invoke_signed(&[Signer::from(&pol_seeds)])?;
+
+            POLConfig::load_mut(self.accounts.pol)?.set_inner(*self.accounts.config.key(), bump);
+        }
+
+        POLConfig::load_mut(self.accounts.pol)?.set_params(
+            self.instruction_data.treasury,
+            self.instruction_data.rebalance_threshold_bps,
+            self.instruction_data.target_ratio_bps,
+        )
+    }
+}
@@ -0,0 +1,67 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{read_u16, Config, SignerAccount};
+
+pub struct UpdateProtocolFeeShareAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateProtocolFeeShareAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateProtocolFeeShareInstructionData {
+    pub share_bps: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateProtocolFeeShareInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u16>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let share_bps = read_u16(data, 0)?;
+        Ok(Self { share_bps })
+    }
+}
+
+pub struct UpdateProtocolFeeShare<'a> {
+    pub accounts: UpdateProtocolFeeShareAccounts<'a>,
+    pub instruction_data: UpdateProtocolFeeShareInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateProtocolFeeShare<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateProtocolFeeShareAccounts::try_from(accounts)?;
+        let instruction_data = UpdateProtocolFeeShareInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateProtocolFeeShare<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &14;
+
+    // share of each swap fee, in bps of the fee, set aside for the treasury instead of LPs
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        config.set_protocol_fee_share(self.instruction_data.share_bps)
+    }
+}
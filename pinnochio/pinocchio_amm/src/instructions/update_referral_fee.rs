@@ -0,0 +1,68 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{read_u16, Config, SignerAccount};
+
+pub struct UpdateReferralFeeAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateReferralFeeAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateReferralFeeInstructionData {
+    pub referral_fee_bps: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateReferralFeeInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u16>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let referral_fee_bps = read_u16(data, 0)?;
+        Ok(Self { referral_fee_bps })
+    }
+}
+
+pub struct UpdateReferralFee<'a> {
+    pub accounts: UpdateReferralFeeAccounts<'a>,
+    pub instruction_data: UpdateReferralFeeInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateReferralFee<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateReferralFeeAccounts::try_from(accounts)?;
+        let instruction_data = UpdateReferralFeeInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateReferralFee<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &34;
+
+    // share of the protocol fee, in bps, paid to the referrer of a swap; at most
+    // `MAX_REFERRAL_FEE_BPS`, 0 turns referrals off
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        config.set_referral_fee_bps(self.instruction_data.referral_fee_bps)
+    }
+}
@@ -0,0 +1,79 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{keys_eq_constant_time, AmmState, Config, SignerAccount};
+
+pub struct UpdateStateAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateStateAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateStateInstructionData {
+    pub state: AmmState,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateStateInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let [state] = data else {
+            return Err(ProgramError::InvalidInstructionData);
+        };
+        // a pool can't be put back to before Initialize ran
+        let state = match AmmState::try_from(*state) {
+            Ok(AmmState::Uninitialized) | Err(_) => {
+                return Err(ProgramError::InvalidInstructionData)
+            }
+            Ok(state) => state,
+        };
+        Ok(Self { state })
+    }
+}
+
+pub struct UpdateState<'a> {
+    pub accounts: UpdateStateAccounts<'a>,
+    pub instruction_data: UpdateStateInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateState<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateStateAccounts::try_from(accounts)?;
+        let instruction_data = UpdateStateInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateState<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &37;
+
+    // moves the pool to any state but Uninitialized, e.g. WithdrawOnly to let LPs out during
+    // an incident while deposits and swaps stay shut; only the pool's authority may
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        let signer = self.accounts.authority.key();
+        match config.has_authority() {
+            Some(authority) if keys_eq_constant_time(&authority, signer) => {}
+            _ => return Err(ProgramError::InvalidArgument),
+        }
+
+        if config.state_enum()?.eq(&AmmState::Uninitialized) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        config.set_state(self.instruction_data.state.into())
+    }
+}
@@ -0,0 +1,71 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{Config, SignerAccount};
+
+pub struct UpdateTransferablePositionsAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateTransferablePositionsAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateTransferablePositionsInstructionData {
+    pub transferable_positions: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateTransferablePositionsInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let transferable_positions = match data {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        Ok(Self {
+            transferable_positions,
+        })
+    }
+}
+
+pub struct UpdateTransferablePositions<'a> {
+    pub accounts: UpdateTransferablePositionsAccounts<'a>,
+    pub instruction_data: UpdateTransferablePositionsInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateTransferablePositions<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateTransferablePositionsAccounts::try_from(accounts)?;
+        let instruction_data = UpdateTransferablePositionsInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateTransferablePositions<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &27;
+
+    // lets LPPosition owners hand positions on with TransferPosition, or makes them soulbound
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        config.set_transferable_positions(self.instruction_data.transferable_positions);
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{read_u16, Config, SignerAccount};
+
+pub struct UpdateVolatilityFeeAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateVolatilityFeeAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateVolatilityFeeInstructionData {
+    pub min_fee_bps: u16,
+    pub max_fee_bps: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateVolatilityFeeInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u16>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let min_fee_bps = read_u16(data, 0)?;
+        let max_fee_bps = read_u16(data, 2)?;
+        Ok(Self {
+            min_fee_bps,
+            max_fee_bps,
+        })
+    }
+}
+
+pub struct UpdateVolatilityFee<'a> {
+    pub accounts: UpdateVolatilityFeeAccounts<'a>,
+    pub instruction_data: UpdateVolatilityFeeInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateVolatilityFee<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateVolatilityFeeAccounts::try_from(accounts)?;
+        let instruction_data = UpdateVolatilityFeeInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateVolatilityFee<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &6;
+
+    // max_fee_bps of zero turns the adapter off and restores the static fee
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        config.set_volatility_fee_bounds(
+            self.instruction_data.min_fee_bps,
+            self.instruction_data.max_fee_bps,
+        )
+    }
+}
@@ -0,0 +1,124 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+use pinocchio_log::log;
+use pinocchio_token::state::TokenAccount;
+
+use crate::{check_vault_address, check_vault_initialized, AmmState, Config};
+
+pub struct VerifyPoolIntegrityAccounts<'a> {
+    pub config: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for VerifyPoolIntegrityAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [config, vault_x, vault_y, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        Ok(Self {
+            config,
+            vault_x,
+            vault_y,
+            token_program,
+        })
+    }
+}
+
+pub struct VerifyPoolIntegrityInstructionData {
+    pub pause_on_deficit: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for VerifyPoolIntegrityInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let pause_on_deficit = match data {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        Ok(Self { pause_on_deficit })
+    }
+}
+
+pub struct VerifyPoolIntegrity<'a> {
+    pub accounts: VerifyPoolIntegrityAccounts<'a>,
+    pub instruction_data: VerifyPoolIntegrityInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for VerifyPoolIntegrity<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = VerifyPoolIntegrityAccounts::try_from(accounts)?;
+        let instruction_data = VerifyPoolIntegrityInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> VerifyPoolIntegrity<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &19;
+
+    /// Compares the vault balances with what the config expects them to be. Anyone can donate
+    /// to a vault, so a surplus is only logged; a deficit means tokens left the pool outside
+    /// of its own transfers and, when asked to, disables the pool until the authority steps in.
+    pub fn process(&mut self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        let (balance_x, balance_y) = vault_balances(
+            &config,
+            self.accounts.config,
+            self.accounts.vault_x,
+            self.accounts.vault_y,
+            self.accounts.token_program,
+        )?;
+        let (expected_x, expected_y) =
+            (config.expected_vault_x_balance(), config.expected_vault_y_balance());
+
+        if balance_x.eq(&expected_x) && balance_y.eq(&expected_y) {
+            return Ok(());
+        }
+
+        log!(
+            "PoolIntegrityAlert: vault_x {} expected {}, vault_y {} expected {}",
+            balance_x,
+            expected_x,
+            balance_y,
+            expected_y
+        );
+
+        let deficit = balance_x < expected_x || balance_y < expected_y;
+        if deficit
+            && self.instruction_data.pause_on_deficit
+            && config.state_enum()?.eq(&AmmState::Initialized)
+        {
+            config.set_state(AmmState::Disabled.into())?;
+            log!("PoolIntegrityAlert: pool disabled");
+        }
+
+        Ok(())
+    }
+}
+
+// checks the vaults are the pool's own and returns their token balances
+pub(crate) fn vault_balances(
+    config: &Config,
+    config_account: &AccountInfo,
+    vault_x: &AccountInfo,
+    vault_y: &AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<(u64, u64), ProgramError> {
+    for (vault, mint, bump) in [
+        (vault_x, config.mint_x(), config.vault_x_bump()),
+        (vault_y, config.mint_y(), config.vault_y_bump()),
+    ] {
+        check_vault_address(vault, config_account, token_program, mint, bump)?;
+        check_vault_initialized(vault)?;
+    }
+
+    let vault_x = unsafe { TokenAccount::from_account_info_unchecked(vault_x)? };
+    let vault_y = unsafe { TokenAccount::from_account_info_unchecked(vault_y)? };
+    Ok((vault_x.amount(), vault_y.amount()))
+}
@@ -0,0 +1,372 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Signer,
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::{
+    instructions::{Burn, Transfer},
+    state::{Mint, TokenAccount},
+};
+
+use crate::{
+    assert_distinct, assert_same_mint, check_mint_lp_address, check_not_frozen,
+    check_vault_address, check_vault_initialized, lp_to_underlying, read_i64, read_u64,
+    signer_seeds, validate_expiration, AmmState, Config, PinocchioError, SignerAccount,
+};
+
+pub struct WithdrawAccounts<'a> {
+    pub user: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub user_x_ata: &'a AccountInfo,
+    pub user_y_ata: &'a AccountInfo,
+    pub user_lp_ata: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [user, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(user)?;
+        assert_distinct(&[config.key(), mint_lp.key(), vault_x.key(), vault_y.key()])?;
+        Ok(Self {
+            user,
+            mint_lp,
+            vault_x,
+            vault_y,
+            user_x_ata,
+            user_y_ata,
+            user_lp_ata,
+            config,
+            token_program,
+        })
+    }
+}
+
+pub struct WithdrawInstructionData {
+    pub lp_amount: u64,
+    pub min_x: u64,
+    pub min_y: u64,
+    pub expiration: i64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for WithdrawInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 3 + size_of::<i64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let lp_amount = read_u64(data, 0)?;
+        let min_x = read_u64(data, 8)?;
+        let min_y = read_u64(data, 16)?;
+        let expiration = read_i64(data, 24)?;
+        if lp_amount.eq(&0) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Self {
+            lp_amount,
+            min_x,
+            min_y,
+            expiration,
+        })
+    }
+}
+
+pub struct Withdraw<'a> {
+    pub accounts: WithdrawAccounts<'a>,
+    pub instruction_data: WithdrawInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Withdraw<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = WithdrawAccounts::try_from(accounts)?;
+        let instruction_data = WithdrawInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> Withdraw<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &3;
+
+    /// Burns `lp_amount` of the caller's LP and pays out its share of both reserves, failing
+    /// if either side comes to less than the caller's minimum.
+    pub fn process(&mut self) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        validate_expiration(now, self.instruction_data.expiration)?;
+
+        let config = Config::load(self.accounts.config)?;
+        // winding a pool down to WithdrawOnly must still let LPs out
+        match config.state_enum()? {
+            AmmState::Initialized | AmmState::WithdrawOnly => {}
+            _ => return Err(ProgramError::InvalidAccountData),
+        }
+
+        check_mint_lp_address(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            config.mint_lp_bump(),
+        )?;
+        let mint_lp = *self.accounts.mint_lp.key();
+        for (vault, mint, bump) in [
+            (
+                self.accounts.vault_x,
+                config.mint_x(),
+                config.vault_x_bump(),
+            ),
+            (
+                self.accounts.vault_y,
+                config.mint_y(),
+                config.vault_y_bump(),
+            ),
+        ] {
+            check_vault_address(
+                vault,
+                self.accounts.config,
+                self.accounts.token_program,
+                mint,
+                bump,
+            )?;
+        }
+        check_vault_initialized(self.accounts.vault_x)?;
+        check_vault_initialized(self.accounts.vault_y)?;
+        check_not_frozen(self.accounts.user_x_ata)?;
+        check_not_frozen(self.accounts.user_y_ata)?;
+        assert_same_mint(self.accounts.user_x_ata, self.accounts.vault_x)?;
+        assert_same_mint(self.accounts.user_y_ata, self.accounts.vault_y)?;
+        // only the caller's own LP account can be burned from, not merely any account of the
+        // LP mint the caller happens to be delegated on
+        let (user_lp_ata, _) = find_program_address(
+            &[
+                self.accounts.user.key(),
+                self.accounts.token_program.key(),
+                &mint_lp,
+            ],
+            &pinocchio_associated_token_account::ID,
+        );
+        if user_lp_ata.ne(self.accounts.user_lp_ata.key()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_lp_account(
+            &*TokenAccount::from_account_info(self.accounts.user_lp_ata)?,
+            self.accounts.user.key(),
+            &mint_lp,
+            self.instruction_data.lp_amount,
+        )?;
+
+        let (x, y, reserve_x, reserve_y) = {
+            let mint_lp = unsafe { Mint::from_account_info_unchecked(self.accounts.mint_lp)? };
+            let vault_x =
+                unsafe { TokenAccount::from_account_info_unchecked(self.accounts.vault_x)? };
+            let vault_y =
+                unsafe { TokenAccount::from_account_info_unchecked(self.accounts.vault_y)? };
+            let (reserve_x, reserve_y) = config.pool_reserves(vault_x.amount(), vault_y.amount());
+            let (x, y) = withdraw_amounts(
+                self.instruction_data.lp_amount,
+                mint_lp.supply(),
+                reserve_x,
+                reserve_y,
+                self.instruction_data.min_x,
+                self.instruction_data.min_y,
+            )?;
+            (x, y, reserve_x, reserve_y)
+        };
+
+        // the LP is gone before anything is paid out against it
+        Burn {
+            account: self.accounts.user_lp_ata,
+            mint: self.accounts.mint_lp,
+            authority: self.accounts.user,
+            amount: self.instruction_data.lp_amount,
+        }
+        .invoke()?;
+
+        signer_seeds!(config_seeds = [
+            b"config",
+            config.seed().to_le_bytes(),
+            config.mint_x(),
+            config.mint_y(),
+            config.config_bump(),
+        ]);
+        let signer = [Signer::from(&config_seeds)];
+
+        for (from, to, amount) in [
+            (self.accounts.vault_x, self.accounts.user_x_ata, x),
+            (self.accounts.vault_y, self.accounts.user_y_ata, y),
+        ] {
+            if amount.eq(&0) {
+                continue;
+            }
+            Transfer {
+                from,
+                to,
+                authority: self.accounts.config,
+                amount,
+            }
+            .invoke_signed(&signer)?;
+        }
+        drop(config);
+
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.debit_expected_balances(x, y);
+        config.update_price_cumulatives(
+            reserve_x.saturating_sub(x),
+            reserve_y.saturating_sub(y),
+            now,
+        );
+
+        Ok(())
+    }
+}
+
+// the LP account has to be the caller's own and hold what is burned: as a delegate the caller
+// could otherwise burn from somebody else's LP
+fn check_lp_account(
+    lp_account: &TokenAccount,
+    user: &Pubkey,
+    mint_lp: &Pubkey,
+    lp_amount: u64,
+) -> ProgramResult {
+    if lp_account.owner().ne(user) {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if lp_account.mint().ne(mint_lp) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // the burn would refuse it too, but with an error that doesn't say why
+    if lp_account.amount() < lp_amount {
+        return Err(PinocchioError::InsufficientUserFunds.into());
+    }
+    Ok(())
+}
+
+// what burning `lp_amount` of `lp_supply` pays out, held to the caller's minimums
+fn withdraw_amounts(
+    lp_amount: u64,
+    lp_supply: u64,
+    reserve_x: u64,
+    reserve_y: u64,
+    min_x: u64,
+    min_y: u64,
+) -> Result<(u64, u64), ProgramError> {
+    // the curve would happily price more LP than exists, burning it could not
+    if lp_amount > lp_supply {
+        return Err(ProgramError::InvalidArgument);
+    }
+    // the last LP out takes everything, so no rounding dust is left behind in a pool with no
+    // supply, which Deposit could neither price nor seed again
+    let (x, y) = if lp_amount == lp_supply {
+        (reserve_x, reserve_y)
+    } else {
+        // rounded down: whatever the division leaves over stays with the remaining LPs
+        lp_to_underlying(lp_amount, lp_supply, reserve_x, reserve_y)?
+    };
+    if x < min_x || y < min_y {
+        return Err(PinocchioError::SlippageExceeded.into());
+    }
+    Ok((x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: Pubkey = [1u8; 32];
+    const MINT_LP: Pubkey = [2u8; 32];
+
+    // a token account as the token program lays it out: mint, owner, amount, ...
+    fn lp_account(owner: &Pubkey, mint: &Pubkey, amount: u64) -> [u8; TokenAccount::LEN] {
+        let mut data = [0u8; TokenAccount::LEN];
+        data[..32].copy_from_slice(mint);
+        data[32..64].copy_from_slice(owner);
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    fn check(data: &[u8], lp_amount: u64) -> ProgramResult {
+        let account = unsafe { TokenAccount::from_bytes_unchecked(data) };
+        check_lp_account(account, &USER, &MINT_LP, lp_amount)
+    }
+
+    #[test]
+    fn burns_from_the_callers_own_lp_account() {
+        let data = lp_account(&USER, &MINT_LP, 500);
+        assert_eq!(check(&data, 500), Ok(()));
+        assert_eq!(check(&data, 1), Ok(()));
+    }
+
+    #[test]
+    fn refuses_an_lp_account_the_caller_does_not_own() {
+        // the caller may well be a delegate on it, the burn would go through
+        let data = lp_account(&[3u8; 32], &MINT_LP, 500);
+        assert_eq!(check(&data, 500), Err(ProgramError::IllegalOwner));
+    }
+
+    #[test]
+    fn refuses_another_mint_or_a_short_balance() {
+        let data = lp_account(&USER, &[3u8; 32], 500);
+        assert_eq!(check(&data, 500), Err(ProgramError::InvalidAccountData));
+
+        let data = lp_account(&USER, &MINT_LP, 499);
+        assert_eq!(
+            check(&data, 500),
+            Err(PinocchioError::InsufficientUserFunds.into())
+        );
+    }
+
+    #[test]
+    fn minimums_are_inclusive() {
+        // a quarter of the supply is a quarter of each reserve
+        assert_eq!(
+            withdraw_amounts(250, 1_000, 4_000, 8_000, 1_000, 2_000),
+            Ok((1_000, 2_000))
+        );
+        assert_eq!(
+            withdraw_amounts(250, 1_000, 4_000, 8_000, 1_001, 0),
+            Err(PinocchioError::SlippageExceeded.into())
+        );
+        assert_eq!(
+            withdraw_amounts(250, 1_000, 4_000, 8_000, 0, 2_001),
+            Err(PinocchioError::SlippageExceeded.into())
+        );
+    }
+
+    #[test]
+    fn rounding_counts_against_the_minimums() {
+        // 1/3 of 100 and 200 floors to 33 and 66
+        assert_eq!(withdraw_amounts(1, 3, 100, 200, 33, 66), Ok((33, 66)));
+        assert_eq!(
+            withdraw_amounts(1, 3, 100, 200, 34, 66),
+            Err(PinocchioError::SlippageExceeded.into())
+        );
+    }
+
+    #[test]
+    fn last_lp_out_drains_the_pool() {
+        assert_eq!(
+            withdraw_amounts(1_000, 1_000, 4_001, 7_999, 4_001, 7_999),
+            Ok((4_001, 7_999))
+        );
+    }
+
+    #[test]
+    fn refuses_more_than_the_supply() {
+        assert_eq!(
+            withdraw_amounts(1_001, 1_000, 4_000, 8_000, 0, 0),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Signer,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::{
+    instructions::{Burn, Transfer},
+    state::{Mint, TokenAccount},
+};
+
+use crate::{
+    assert_mint, assert_same_mint, check_mint_lp_address, check_not_frozen, check_vault_address,
+    check_vault_initialized, lp_to_underlying, read_u16, read_u64, signer_seeds, AmmState, Config,
+    SignerAccount,
+};
+
+pub struct WithdrawBpsAccounts<'a> {
+    pub user: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub user_x_ata: &'a AccountInfo,
+    pub user_y_ata: &'a AccountInfo,
+    pub user_lp_ata: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawBpsAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [user, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(user)?;
+        Ok(Self {
+            user,
+            mint_lp,
+            vault_x,
+            vault_y,
+            user_x_ata,
+            user_y_ata,
+            user_lp_ata,
+            config,
+            token_program,
+        })
+    }
+}
+
+pub struct WithdrawBpsInstructionData {
+    // share of the caller's LP balance to redeem, 1..=10_000
+    pub bps: u16,
+    pub min_x: u64,
+    pub min_y: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for WithdrawBpsInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u16>() + size_of::<u64>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let bps = read_u16(data, 0)?;
+        let min_x = read_u64(data, 2)?;
+        let min_y = read_u64(data, 10)?;
+        if bps.eq(&0) || bps.gt(&10_000) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Self { bps, min_x, min_y })
+    }
+}
+
+pub struct WithdrawBps<'a> {
+    pub accounts: WithdrawBpsAccounts<'a>,
+    pub instruction_data: WithdrawBpsInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for WithdrawBps<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = WithdrawBpsAccounts::try_from(accounts)?;
+        let instruction_data = WithdrawBpsInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> WithdrawBps<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &33;
+
+    /// Burns `bps` of the caller's LP balance, rounded down, and pays out its share of the
+    /// reserves. 10_000 redeems the whole balance, leaving no LP dust behind.
+    pub fn process(&mut self) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        let config = Config::load(self.accounts.config)?;
+        match config.state_enum()? {
+            AmmState::Initialized | AmmState::WithdrawOnly => {}
+            _ => return Err(ProgramError::InvalidAccountData),
+        }
+
+        check_mint_lp_address(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            config.mint_lp_bump(),
+        )?;
+        let mint_lp = *self.accounts.mint_lp.key();
+        for (vault, mint, bump) in [
+            (
+                self.accounts.vault_x,
+                config.mint_x(),
+                config.vault_x_bump(),
+            ),
+            (
+                self.accounts.vault_y,
+                config.mint_y(),
+                config.vault_y_bump(),
+            ),
+        ] {
+            check_vault_address(
+                vault,
+                self.accounts.config,
+                self.accounts.token_program,
+                mint,
+                bump,
+            )?;
+        }
+        check_vault_initialized(self.accounts.vault_x)?;
+        check_vault_initialized(self.accounts.vault_y)?;
+        check_not_frozen(self.accounts.user_x_ata)?;
+        check_not_frozen(self.accounts.user_y_ata)?;
+        assert_same_mint(self.accounts.user_x_ata, self.accounts.vault_x)?;
+        assert_same_mint(self.accounts.user_y_ata, self.accounts.vault_y)?;
+        assert_mint(self.accounts.user_lp_ata, &mint_lp)?;
+
+        let balance = TokenAccount::from_account_info(self.accounts.user_lp_ata)?.amount();
+        let lp_amount = (balance as u128 * self.instruction_data.bps as u128 / 10_000) as u64;
+        if lp_amount.eq(&0) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (x, y, reserve_x, reserve_y) = {
+            let mint_lp = unsafe { Mint::from_account_info_unchecked(self.accounts.mint_lp)? };
+            let vault_x =
+                unsafe { TokenAccount::from_account_info_unchecked(self.accounts.vault_x)? };
+            let vault_y =
+                unsafe { TokenAccount::from_account_info_unchecked(self.accounts.vault_y)? };
+            let (reserve_x, reserve_y) = config.pool_reserves(vault_x.amount(), vault_y.amount());
+            let (x, y) = lp_to_underlying(lp_amount, mint_lp.supply(), reserve_x, reserve_y)?;
+            (x, y, reserve_x, reserve_y)
+        };
+        if x < self.instruction_data.min_x || y < self.instruction_data.min_y {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Burn {
+            account: self.accounts.user_lp_ata,
+            mint: self.accounts.mint_lp,
+            authority: self.accounts.user,
+            amount: lp_amount,
+        }
+        .invoke()?;
+
+        signer_seeds!(config_seeds = [
+            b"config",
+            config.seed().to_le_bytes(),
+            config.mint_x(),
+            config.mint_y(),
+            config.config_bump(),
+        ]);
+        let signer = [Signer::from(&config_seeds)];
+
+        for (from, to, amount) in [
+            (self.accounts.vault_x, self.accounts.user_x_ata, x),
+            (self.accounts.vault_y, self.accounts.user_y_ata, y),
+        ] {
+            if amount.eq(&0) {
+                continue;
+            }
+            Transfer {
+                from,
+                to,
+                authority: self.accounts.config,
+                amount,
+            }
+            .invoke_signed(&signer)?;
+        }
+        drop(config);
+
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.debit_expected_balances(x, y);
+        config.update_price_cumulatives(
+            reserve_x.saturating_sub(x),
+            reserve_y.saturating_sub(y),
+            now,
+        );
+
+        Ok(())
+    }
+}
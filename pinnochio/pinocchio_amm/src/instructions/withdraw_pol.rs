@@ -0,0 +1,161 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::{instructions::Transfer, state::TokenAccount};
+
+use crate::{
+    assert_same_mint, check_mint_lp_address, check_vault_address, read_u64, Config, POLConfig,
+    PinocchioError, SignerAccount,
+};
+
+// how long a scheduled treasury LP withdrawal waits before it can be paid out
+pub const POL_WITHDRAW_DELAY: i64 = 14 * 24 * 60 * 60;
+
+pub struct WithdrawPOLAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub pol: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub pool_lp: &'a AccountInfo,
+    pub treasury_lp: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawPOLAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, pol, mint_lp, pool_lp, treasury_lp, token_program] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        Ok(Self {
+            authority,
+            config,
+            pol,
+            mint_lp,
+            pool_lp,
+            treasury_lp,
+            token_program,
+        })
+    }
+}
+
+pub struct WithdrawPOLInstructionData {
+    pub amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for WithdrawPOLInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let amount = read_u64(data, 0)?;
+        Ok(Self { amount })
+    }
+}
+
+pub struct WithdrawPOL<'a> {
+    pub accounts: WithdrawPOLAccounts<'a>,
+    pub instruction_data: WithdrawPOLInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for WithdrawPOL<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = WithdrawPOLAccounts::try_from(accounts)?;
+        let instruction_data = WithdrawPOLInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> WithdrawPOL<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &30;
+
+    /// Two steps: a non-zero `amount` schedules a withdrawal of that much treasury LP,
+    /// restarting the `POL_WITHDRAW_DELAY` timelock; `amount == 0` pays the scheduled
+    /// withdrawal out to the treasury's LP account once the timelock has passed.
+    pub fn process(&mut self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+
+        match config.has_authority() {
+            Some(authority) if authority.eq(self.accounts.authority.key()) => {}
+            _ => return Err(ProgramError::IncorrectAuthority),
+        }
+
+        let mut pol = POLConfig::load_mut(self.accounts.pol)?;
+        if pol.pool().ne(self.accounts.config.key()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if self.instruction_data.amount.ne(&0) {
+            if self.instruction_data.amount.gt(&pol.lp_amount()) {
+                return Err(ProgramError::InsufficientFunds);
+            }
+            pol.schedule_withdrawal(
+                self.instruction_data.amount,
+                now.saturating_add(POL_WITHDRAW_DELAY),
+            );
+            return Ok(());
+        }
+
+        let amount = pol.withdraw_amount();
+        if amount.eq(&0) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if now < pol.withdraw_unlocks_at() {
+            return Err(PinocchioError::TimelockActive.into());
+        }
+
+        check_mint_lp_address(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            config.mint_lp_bump(),
+        )?;
+        let mint_lp = *self.accounts.mint_lp.key();
+        check_vault_address(
+            self.accounts.pool_lp,
+            self.accounts.config,
+            self.accounts.token_program,
+            &mint_lp,
+            config.lp_vault_bump(),
+        )?;
+        assert_same_mint(self.accounts.pool_lp, self.accounts.treasury_lp)?;
+        if TokenAccount::from_account_info(self.accounts.treasury_lp)?
+            .owner()
+            .ne(pol.treasury())
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let seed_binding = config.seed().to_le_bytes();
+        let config_bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_binding),
+            Seed::from(config.mint_x()),
+            Seed::from(config.mint_y()),
+            Seed::from(&config_bump),
+        ];
+
+        Transfer {
+            from: self.accounts.pool_lp,
+            to: self.accounts.treasury_lp,
+            authority: self.accounts.config,
+            amount,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        pol.settle_withdrawal();
+        Ok(())
+    }
+}
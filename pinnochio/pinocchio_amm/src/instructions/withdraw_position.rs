@@ -0,0 +1,214 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::{
+    instructions::{Burn, Transfer},
+    state::{Mint, TokenAccount},
+};
+
+use crate::{
+    assert_same_mint, check_mint_lp_address, check_not_frozen, check_vault_address,
+    check_vault_initialized, lp_to_underlying, read_u64, AmmState, Config, LPPosition,
+    SignerAccount,
+};
+
+pub struct WithdrawPositionAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub owner_x_ata: &'a AccountInfo,
+    pub owner_y_ata: &'a AccountInfo,
+    pub position_lp: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub position: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawPositionAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, mint_lp, vault_x, vault_y, owner_x_ata, owner_y_ata, position_lp, config, position, token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        Ok(Self {
+            owner,
+            mint_lp,
+            vault_x,
+            vault_y,
+            owner_x_ata,
+            owner_y_ata,
+            position_lp,
+            config,
+            position,
+            token_program,
+        })
+    }
+}
+
+pub struct WithdrawPositionInstructionData {
+    pub min_x: u64,
+    pub min_y: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for WithdrawPositionInstructionData {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let min_x = read_u64(data, 0)?;
+        let min_y = read_u64(data, 8)?;
+        Ok(Self { min_x, min_y })
+    }
+}
+
+pub struct WithdrawPosition<'a> {
+    pub accounts: WithdrawPositionAccounts<'a>,
+    pub instruction_data: WithdrawPositionInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for WithdrawPosition<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = WithdrawPositionAccounts::try_from(accounts)?;
+        let instruction_data = WithdrawPositionInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> WithdrawPosition<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &25;
+
+    /// Redeems a whole `LPPosition`: burns its LP out of the pool's LP account, pays the
+    /// owner their share of the reserves and closes the position, rent going to the owner.
+    pub fn process(&mut self) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        let liquidity = {
+            let position = LPPosition::load(self.accounts.position)?;
+            if position.owner().ne(self.accounts.owner.key())
+                || position.pool().ne(self.accounts.config.key())
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            position.liquidity()
+        };
+
+        let config = Config::load(self.accounts.config)?;
+        match config.state_enum()? {
+            AmmState::Initialized | AmmState::WithdrawOnly => {}
+            _ => return Err(ProgramError::InvalidAccountData),
+        }
+
+        check_mint_lp_address(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            config.mint_lp_bump(),
+        )?;
+        let mint_lp = *self.accounts.mint_lp.key();
+        for (vault, mint, bump) in [
+            (
+                self.accounts.vault_x,
+                config.mint_x(),
+                config.vault_x_bump(),
+            ),
+            (
+                self.accounts.vault_y,
+                config.mint_y(),
+                config.vault_y_bump(),
+            ),
+            (self.accounts.position_lp, &mint_lp, config.lp_vault_bump()),
+        ] {
+            check_vault_address(
+                vault,
+                self.accounts.config,
+                self.accounts.token_program,
+                mint,
+                bump,
+            )?;
+        }
+        check_vault_initialized(self.accounts.vault_x)?;
+        check_vault_initialized(self.accounts.vault_y)?;
+        check_not_frozen(self.accounts.owner_x_ata)?;
+        check_not_frozen(self.accounts.owner_y_ata)?;
+        assert_same_mint(self.accounts.owner_x_ata, self.accounts.vault_x)?;
+        assert_same_mint(self.accounts.owner_y_ata, self.accounts.vault_y)?;
+
+        let (x, y, reserve_x, reserve_y) = {
+            let mint_lp = unsafe { Mint::from_account_info_unchecked(self.accounts.mint_lp)? };
+            let vault_x =
+                unsafe { TokenAccount::from_account_info_unchecked(self.accounts.vault_x)? };
+            let vault_y =
+                unsafe { TokenAccount::from_account_info_unchecked(self.accounts.vault_y)? };
+            let (reserve_x, reserve_y) = config.pool_reserves(vault_x.amount(), vault_y.amount());
+            let (x, y) = lp_to_underlying(liquidity, mint_lp.supply(), reserve_x, reserve_y)?;
+            (x, y, reserve_x, reserve_y)
+        };
+        if x < self.instruction_data.min_x || y < self.instruction_data.min_y {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let seed_binding = config.seed().to_le_bytes();
+        let config_bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_binding),
+            Seed::from(config.mint_x()),
+            Seed::from(config.mint_y()),
+            Seed::from(&config_bump),
+        ];
+        let signer = [Signer::from(&config_seeds)];
+
+        Burn {
+            account: self.accounts.position_lp,
+            mint: self.accounts.mint_lp,
+            authority: self.accounts.config,
+            amount: liquidity,
+        }
+        .invoke_signed(&signer)?;
+        for (from, to, amount) in [
+            (self.accounts.vault_x, self.accounts.owner_x_ata, x),
+            (self.accounts.vault_y, self.accounts.owner_y_ata, y),
+        ] {
+            if amount.eq(&0) {
+                continue;
+            }
+            Transfer {
+                from,
+                to,
+                authority: self.accounts.config,
+                amount,
+            }
+            .invoke_signed(&signer)?;
+        }
+        drop(config);
+
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.debit_expected_balances(x, y);
+        config.update_price_cumulatives(
+            reserve_x.saturating_sub(x),
+            reserve_y.saturating_sub(y),
+            now,
+        );
+
+        let lamports = self.accounts.position.lamports();
+        let owner_lamports = self
+            .accounts
+            .owner
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        *self.accounts.owner.try_borrow_mut_lamports()? = owner_lamports;
+        self.accounts.position.close()
+    }
+}
@@ -0,0 +1,211 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::find_program_address,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_associated_token_account::instructions::CreateIdempotent;
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::{instructions::Transfer, state::TokenAccount};
+
+use crate::{
+    assert_mint, check_mint_lp_address, check_vault_address, read_u64, verify_access, Config,
+    LPPosition, SignerAccount,
+};
+
+pub struct WrapLpAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub mint_lp: &'a AccountInfo,
+    pub owner_lp_ata: &'a AccountInfo,
+    pub position_lp: &'a AccountInfo,
+    pub position: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WrapLpAccounts<'a> {
+    type Error = ProgramError;
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, config, mint_lp, owner_lp_ata, position_lp, position, vault_x, vault_y, token_program, system_program, _associated_token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        Ok(Self {
+            owner,
+            config,
+            mint_lp,
+            owner_lp_ata,
+            position_lp,
+            position,
+            vault_x,
+            vault_y,
+            token_program,
+            system_program,
+        })
+    }
+}
+
+// deposit_index | amount | access_proof
+pub struct WrapLpInstructionData<'a> {
+    pub deposit_index: u64,
+    pub amount: u64,
+    pub access_proof: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for WrapLpInstructionData<'a> {
+    type Error = ProgramError;
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let (data, access_proof) = data
+            .split_at_checked(size_of::<u64>() * 2)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let deposit_index = read_u64(data, 0)?;
+        let amount = read_u64(data, 8)?;
+        if amount.eq(&0) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Self {
+            deposit_index,
+            amount,
+            access_proof,
+        })
+    }
+}
+
+pub struct WrapLp<'a> {
+    pub accounts: WrapLpAccounts<'a>,
+    pub instruction_data: WrapLpInstructionData<'a>,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for WrapLp<'a> {
+    type Error = ProgramError;
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = WrapLpAccounts::try_from(accounts)?;
+        let instruction_data = WrapLpInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> WrapLp<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &31;
+
+    /// Moves `amount` fungible LP from the owner into the pool's own LP account and records
+    /// it in a new `LPPosition`, the same receipt `DepositPosition` hands out. `UnwrapLp`
+    /// turns it back into fungible LP; the pool's LP supply never changes either way. Pools
+    /// with an access list take the owner's proof, as `Deposit` does.
+    pub fn process(&mut self) -> ProgramResult {
+        let (reserve_x, reserve_y) = {
+            let config = Config::load(self.accounts.config)?;
+
+            // a position is as much a stake in the pool as a deposit, so it takes the same proof
+            verify_access(
+                &config,
+                self.accounts.owner.key(),
+                self.instruction_data.access_proof,
+            )?;
+            check_mint_lp_address(
+                self.accounts.mint_lp,
+                self.accounts.config,
+                config.mint_lp_bump(),
+            )?;
+            let mint_lp = *self.accounts.mint_lp.key();
+            for (vault, mint, bump) in [
+                (
+                    self.accounts.vault_x,
+                    config.mint_x(),
+                    config.vault_x_bump(),
+                ),
+                (
+                    self.accounts.vault_y,
+                    config.mint_y(),
+                    config.vault_y_bump(),
+                ),
+                (self.accounts.position_lp, &mint_lp, config.lp_vault_bump()),
+            ] {
+                check_vault_address(
+                    vault,
+                    self.accounts.config,
+                    self.accounts.token_program,
+                    mint,
+                    bump,
+                )?;
+            }
+            assert_mint(self.accounts.owner_lp_ata, &mint_lp)?;
+
+            // entry prices are recorded against the reserves at the time of wrapping
+            config.pool_reserves(
+                TokenAccount::from_account_info(self.accounts.vault_x)?.amount(),
+                TokenAccount::from_account_info(self.accounts.vault_y)?.amount(),
+            )
+        };
+
+        CreateIdempotent {
+            funding_account: self.accounts.owner,
+            account: self.accounts.position_lp,
+            wallet: self.accounts.config,
+            mint: self.accounts.mint_lp,
+            system_program: self.accounts.system_program,
+            token_program: self.accounts.token_program,
+        }
+        .invoke()?;
+
+        let deposit_index = self.instruction_data.deposit_index.to_le_bytes();
+        let (position, bump) = find_program_address(
+            &[
+                b"position",
+                self.accounts.config.key(),
+                self.accounts.owner.key(),
+                &deposit_index,
+            ],
+            &crate::ID,
+        );
+        if position.ne(self.accounts.position.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let bump = [bump];
+        let position_seeds = [
+            Seed::from(b"position"),
+            Seed::from(self.accounts.config.key()),
+            Seed::from(self.accounts.owner.key()),
+            Seed::from(&deposit_index),
+            Seed::from(&bump),
+        ];
+        CreateAccount {
+            from: self.accounts.owner,
+            to: self.accounts.position,
+            lamports: Rent::get()?.minimum_balance(LPPosition::LEN),
+            space: LPPosition::LEN as u64,
+            owner: &crate::ID,
+        }
+        .invoke_signed(&[Signer::from(&position_seeds)])?;
+
+        Transfer {
+            from: self.accounts.owner_lp_ata,
+            to: self.accounts.position_lp,
+            authority: self.accounts.owner,
+            amount: self.instruction_data.amount,
+        }
+        .invoke()?;
+
+        LPPosition::load_mut(self.accounts.position)?.set_inner(
+            *self.accounts.owner.key(),
+            *self.accounts.config.key(),
+            self.instruction_data.amount,
+            reserve_x,
+            reserve_y,
+            Clock::get()?.unix_timestamp,
+        );
+
+        Ok(())
+    }
+}
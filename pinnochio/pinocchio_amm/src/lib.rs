@@ -2,15 +2,41 @@ use pinocchio::{
     account_info::AccountInfo, entrypoint, program_error::ProgramError, pubkey::Pubkey,
     ProgramResult,
 };
+use pinocchio_log::log;
 
 entrypoint!(process_instruction);
 
 pub mod instructions;
 pub use instructions::*;
 
+#[path = "../../shared/accounts.rs"]
+pub mod accounts;
+pub use accounts::*;
+
 pub mod state;
 pub use state::*;
 
+pub mod access;
+pub use access::*;
+
+pub mod position;
+pub use position::*;
+
+pub mod errors;
+pub use errors::*;
+
+pub mod events;
+pub use events::*;
+
+// shared with the secp256r1 vault
+#[path = "../../shared/bytes.rs"]
+pub mod bytes;
+pub use bytes::*;
+
+// also compiled for tests, which check every vector against its parser
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod test_vectors;
+
 // 22222222222222222222222222222222222222222222
 pub const ID: Pubkey = [
     0x0f, 0x1e, 0x6b, 0x14, 0x21, 0xc0, 0x4a, 0x07, 0x04, 0x31, 0x26, 0x5c, 0x19, 0xc5, 0xbb, 0xee,
@@ -29,6 +55,99 @@ fn process_instruction(
         Some((Deposit::DISCRIMINATOR, data)) => Deposit::try_from((data, accounts))?.process(),
         Some((Withdraw::DISCRIMINATOR, data)) => Withdraw::try_from((data, accounts))?.process(),
         Some((Swap::DISCRIMINATOR, data)) => Swap::try_from((data, accounts))?.process(),
-        _ => Err(ProgramError::InvalidInstructionData),
+        Some((MultiHopSwap::DISCRIMINATOR, data)) => {
+            MultiHopSwap::try_from((data, accounts))?.process()
+        }
+        Some((UpdateAccessControl::DISCRIMINATOR, data)) => {
+            UpdateAccessControl::try_from((data, accounts))?.process()
+        }
+        Some((UpdateVolatilityFee::DISCRIMINATOR, data)) => {
+            UpdateVolatilityFee::try_from((data, accounts))?.process()
+        }
+        Some((GetPositionValue::DISCRIMINATOR, data)) => {
+            GetPositionValue::try_from((data, accounts))?.process()
+        }
+        Some((ComputePoolAddress::DISCRIMINATOR, data)) => {
+            ComputePoolAddress::try_from(data)?.process()
+        }
+        Some((RescueMisdirectedLp::DISCRIMINATOR, data)) => {
+            RescueMisdirectedLp::try_from((data, accounts))?.process()
+        }
+        Some((GetPoolStats::DISCRIMINATOR, _)) => GetPoolStats::try_from(accounts)?.process(),
+        Some((BatchGetPoolStats::DISCRIMINATOR, _)) => {
+            BatchGetPoolStats::try_from(accounts)?.process()
+        }
+        Some((SeedDeposit::DISCRIMINATOR, data)) => {
+            SeedDeposit::try_from((data, accounts))?.process()
+        }
+        Some((InitializeWithLiquidity::DISCRIMINATOR, data)) => {
+            InitializeWithLiquidity::try_from((data, accounts))?.process()
+        }
+        Some((UpdateProtocolFeeShare::DISCRIMINATOR, data)) => {
+            UpdateProtocolFeeShare::try_from((data, accounts))?.process()
+        }
+        Some((CollectProtocolFees::DISCRIMINATOR, _)) => {
+            CollectProtocolFees::try_from(accounts)?.process()
+        }
+        Some((CompoundFees::DISCRIMINATOR, _)) => CompoundFees::try_from(accounts)?.process(),
+        Some((UpdateMaxSwap::DISCRIMINATOR, data)) => {
+            UpdateMaxSwap::try_from((data, accounts))?.process()
+        }
+        Some((MintLpWithMetadata::DISCRIMINATOR, data)) => {
+            MintLpWithMetadata::try_from((data, accounts))?.process()
+        }
+        Some((VerifyPoolIntegrity::DISCRIMINATOR, data)) => {
+            VerifyPoolIntegrity::try_from((data, accounts))?.process()
+        }
+        Some((RecalibrateExpectedBalances::DISCRIMINATOR, _)) => {
+            RecalibrateExpectedBalances::try_from(accounts)?.process()
+        }
+        Some((ExactOutputSwap::DISCRIMINATOR, data)) => {
+            ExactOutputSwap::try_from((data, accounts))?.process()
+        }
+        Some((UpdateDustThreshold::DISCRIMINATOR, data)) => {
+            UpdateDustThreshold::try_from((data, accounts))?.process()
+        }
+        Some((SweepDust::DISCRIMINATOR, _)) => SweepDust::try_from(accounts)?.process(),
+        Some((DepositPosition::DISCRIMINATOR, data)) => {
+            DepositPosition::try_from((data, accounts))?.process()
+        }
+        Some((WithdrawPosition::DISCRIMINATOR, data)) => {
+            WithdrawPosition::try_from((data, accounts))?.process()
+        }
+        Some((TransferPosition::DISCRIMINATOR, _)) => {
+            TransferPosition::try_from(accounts)?.process()
+        }
+        Some((UpdateTransferablePositions::DISCRIMINATOR, data)) => {
+            UpdateTransferablePositions::try_from((data, accounts))?.process()
+        }
+        Some((UpdatePOLConfig::DISCRIMINATOR, data)) => {
+            UpdatePOLConfig::try_from((data, accounts))?.process()
+        }
+        Some((RebalancePOL::DISCRIMINATOR, _)) => RebalancePOL::try_from(accounts)?.process(),
+        Some((WithdrawPOL::DISCRIMINATOR, data)) => {
+            WithdrawPOL::try_from((data, accounts))?.process()
+        }
+        Some((WrapLp::DISCRIMINATOR, data)) => WrapLp::try_from((data, accounts))?.process(),
+        Some((UnwrapLp::DISCRIMINATOR, _)) => UnwrapLp::try_from(accounts)?.process(),
+        Some((WithdrawBps::DISCRIMINATOR, data)) => {
+            WithdrawBps::try_from((data, accounts))?.process()
+        }
+        Some((UpdateReferralFee::DISCRIMINATOR, data)) => {
+            UpdateReferralFee::try_from((data, accounts))?.process()
+        }
+        Some((Pause::DISCRIMINATOR, _)) => Pause::try_from(accounts)?.process(),
+        Some((Resume::DISCRIMINATOR, _)) => Resume::try_from(accounts)?.process(),
+        Some((UpdateState::DISCRIMINATOR, data)) => {
+            UpdateState::try_from((data, accounts))?.process()
+        }
+        Some((RecoverStrayTokens::DISCRIMINATOR, _)) => {
+            RecoverStrayTokens::try_from(accounts)?.process()
+        }
+        Some((discriminator, _)) => {
+            log!("Unknown instruction discriminator: {}", *discriminator);
+            Err(ProgramError::InvalidInstructionData)
+        }
+        None => Err(ProgramError::InvalidInstructionData),
     }
 }
\ No newline at end of file
@@ -0,0 +1,137 @@
+use pinocchio::program_error::ProgramError;
+
+/// Underlying `(x, y)` redeemable for `lp_amount` out of `lp_supply`, each rounded down so
+/// any remainder stays in the pool.
+#[inline(always)]
+pub fn lp_to_underlying(
+    lp_amount: u64,
+    lp_supply: u64,
+    reserve_x: u64,
+    reserve_y: u64,
+) -> Result<(u64, u64), ProgramError> {
+    if lp_amount.gt(&lp_supply) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if lp_supply.eq(&0) {
+        return Ok((0, 0));
+    }
+    let x = reserve_x as u128 * lp_amount as u128 / lp_supply as u128;
+    let y = reserve_y as u128 * lp_amount as u128 / lp_supply as u128;
+    Ok((x as u64, y as u64))
+}
+
+/// Underlying `(x, y)` a deposit must pay for `lp_amount` new LP on top of `lp_supply`, each
+/// rounded up so the depositor never gets LP for less than its share of the reserves.
+#[inline(always)]
+pub fn lp_to_deposit_amounts(
+    lp_amount: u64,
+    lp_supply: u64,
+    reserve_x: u64,
+    reserve_y: u64,
+) -> Result<(u64, u64), ProgramError> {
+    if lp_supply.eq(&0) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let ceil = |reserve: u64| {
+        let amount = (reserve as u128 * lp_amount as u128).div_ceil(lp_supply as u128);
+        u64::try_from(amount).map_err(|_| ProgramError::ArithmeticOverflow)
+    };
+    Ok((ceil(reserve_x)?, ceil(reserve_y)?))
+}
+
+/// LP that depositing `(x, y)` at the current reserves justifies, i.e. the smaller of the two
+/// proportional shares, rounded down. Zero for an empty pool.
+#[inline(always)]
+pub fn underlying_to_lp(x: u64, y: u64, lp_supply: u64, reserve_x: u64, reserve_y: u64) -> u64 {
+    if reserve_x.eq(&0) || reserve_y.eq(&0) {
+        return 0;
+    }
+    let lp_from_x = x as u128 * lp_supply as u128 / reserve_x as u128;
+    let lp_from_y = y as u128 * lp_supply as u128 / reserve_y as u128;
+    lp_from_x.min(lp_from_y).min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lp_to_underlying_rounds_down() {
+        // 1/3 of 100 and 200 is 33.3 and 66.6, both floored
+        assert_eq!(lp_to_underlying(1, 3, 100, 200), Ok((33, 66)));
+        // a share worth less than one unit of either side redeems nothing
+        assert_eq!(lp_to_underlying(1, 1_000, 999, 999), Ok((0, 0)));
+    }
+
+    #[test]
+    fn lp_to_underlying_full_supply_redeems_all_reserves() {
+        assert_eq!(lp_to_underlying(3, 3, 100, 200), Ok((100, 200)));
+        assert_eq!(
+            lp_to_underlying(u64::MAX, u64::MAX, u64::MAX, 1),
+            Ok((u64::MAX, 1))
+        );
+    }
+
+    #[test]
+    fn lp_to_underlying_rejects_more_than_supply() {
+        assert_eq!(
+            lp_to_underlying(4, 3, 100, 200),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn lp_to_underlying_empty_supply_is_zero() {
+        assert_eq!(lp_to_underlying(0, 0, 100, 200), Ok((0, 0)));
+    }
+
+    #[test]
+    fn underlying_to_lp_takes_the_smaller_share_rounded_down() {
+        // x alone would justify 10 LP, y only 5.5
+        assert_eq!(underlying_to_lp(100, 55, 100, 1_000, 1_000), 5);
+        assert_eq!(underlying_to_lp(1, 1, 100, 1_000, 1_000), 0);
+    }
+
+    #[test]
+    fn underlying_to_lp_empty_reserves_is_zero() {
+        assert_eq!(underlying_to_lp(100, 100, 100, 0, 1_000), 0);
+        assert_eq!(underlying_to_lp(100, 100, 100, 1_000, 0), 0);
+    }
+
+    #[test]
+    fn lp_to_deposit_amounts_rounds_up() {
+        // 1/3 of 100 and 200 is 33.3 and 66.6, where lp_to_underlying pays out 33 and 66
+        assert_eq!(lp_to_deposit_amounts(1, 3, 100, 200), Ok((34, 67)));
+        // even a share worth less than one unit costs one unit of each side
+        assert_eq!(lp_to_deposit_amounts(1, 1_000, 999, 999), Ok((1, 1)));
+    }
+
+    #[test]
+    fn lp_to_deposit_amounts_exact_shares_are_not_rounded() {
+        assert_eq!(lp_to_deposit_amounts(1, 4, 100, 200), Ok((25, 50)));
+        assert_eq!(lp_to_deposit_amounts(3, 3, 100, 200), Ok((100, 200)));
+        assert_eq!(lp_to_deposit_amounts(0, 3, 100, 200), Ok((0, 0)));
+    }
+
+    #[test]
+    fn lp_to_deposit_amounts_covers_what_the_lp_redeems() {
+        for (lp, supply, x, y) in [(1, 3, 100, 200), (7, 10, 1, 999), (5, 9, 1_000_003, 17)] {
+            let (deposit_x, deposit_y) = lp_to_deposit_amounts(lp, supply, x, y).unwrap();
+            let (redeem_x, redeem_y) =
+                lp_to_underlying(lp, supply + lp, x + deposit_x, y + deposit_y).unwrap();
+            assert!(redeem_x <= deposit_x && redeem_y <= deposit_y);
+        }
+    }
+
+    #[test]
+    fn lp_to_deposit_amounts_rejects_empty_supply_and_overflow() {
+        assert_eq!(
+            lp_to_deposit_amounts(1, 0, 100, 200),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            lp_to_deposit_amounts(2, 1, u64::MAX, 1),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+    }
+}
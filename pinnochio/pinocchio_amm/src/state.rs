@@ -3,8 +3,11 @@ use pinocchio::{
     account_info::{AccountInfo, Ref, RefMut},
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
 };
 
+use crate::{spot_price, AccessMode, PinocchioError};
+
 #[repr(C)]
 pub struct Config {
     state: u8,
@@ -14,14 +17,89 @@ pub struct Config {
     mint_y: Pubkey,
     fee: [u8; 2],
     config_bump: [u8; 1],
+    access_mode: u8,
+    access_list_root: [u8; 32],
+    short_volatility: [u8; 8],
+    long_volatility: [u8; 8],
+    min_fee_bps: [u8; 2],
+    max_fee_bps: [u8; 2],
+    last_update_slot: [u8; 8],
+    daily_volume: [u8; 8],
+    volume_day: [u8; 8],
+    protocol_fee_share_bps: [u8; 2],
+    fees_collected_x: [u8; 8],
+    fees_collected_y: [u8; 8],
+    max_swap_bps: [u8; 2],
+    expected_vault_x_balance: [u8; 8],
+    expected_vault_y_balance: [u8; 8],
+    dust_threshold: [u8; 8],
+    transferable_positions: u8,
+    lp_decimals: u8,
+    referral_fee_bps: [u8; 2],
+    // canonical ATA bumps of the vaults, found once by Initialize
+    vault_x_bump: [u8; 1],
+    vault_y_bump: [u8; 1],
+    // bumps of the LP mint and of the config's own LP ATA, which holds wrapped positions and
+    // protocol-owned liquidity
+    mint_lp_bump: [u8; 1],
+    lp_vault_bump: [u8; 1],
+    // time-weighted price oracle: Q64.64 y per x and x per y summed over every second, the
+    // price the pool trades at since the last update, and when that was
+    price_x_cumulative: [u8; 16],
+    price_y_cumulative: [u8; 16],
+    last_price_x: [u8; 16],
+    last_price_y: [u8; 16],
+    last_price_timestamp: [u8; 8],
+    // decimals of mint_x and mint_y, read from the mints once by Initialize
+    decimals_x: u8,
+    decimals_y: u8,
+    // ring of the cumulatives as of each update, newest at `observation_index`, so a TWAP
+    // can start further back than the last update
+    observation_index: u8,
+    observations: [PriceObservation; PRICE_OBSERVATIONS],
+}
+
+// how many past updates the oracle keeps
+pub const PRICE_OBSERVATIONS: usize = 16;
+
+// the price cumulatives as of `timestamp`; a zero timestamp marks an unused slot
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PriceObservation {
+    timestamp: [u8; 8],
+    price_x_cumulative: [u8; 16],
+    price_y_cumulative: [u8; 16],
 }
 
 #[repr(u8)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum AmmState {
     Uninitialized = 0u8,
     Initialized = 1u8,
     Disabled = 2u8,
     WithdrawOnly = 3u8,
+    Paused = 4u8,
+}
+
+impl TryFrom<u8> for AmmState {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AmmState::Uninitialized),
+            1 => Ok(AmmState::Initialized),
+            2 => Ok(AmmState::Disabled),
+            3 => Ok(AmmState::WithdrawOnly),
+            4 => Ok(AmmState::Paused),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+impl From<AmmState> for u8 {
+    fn from(state: AmmState) -> Self {
+        state as u8
+    }
 }
 
 impl Config {
@@ -40,6 +118,12 @@ impl Config {
         }))
     }
 
+    /// Like `load`, without taking a borrow on the account data.
+    ///
+    /// # Safety
+    ///
+    /// The account data must not be mutably borrowed for as long as the returned reference
+    /// lives.
     #[inline(always)]
     pub unsafe fn load_unchecked(account_info: &AccountInfo) -> Result<&Self, ProgramError> {
         if account_info.data_len() != Self::LEN {
@@ -53,11 +137,22 @@ impl Config {
         ))
     }
 
+    /// Reinterprets `bytes` as a `Config` without checking it.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be at least `Config::LEN` long and hold a `Config` written by this program.
     #[inline(always)]
     pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> &Self {
         &*(bytes.as_ptr() as *const Config)
     }
 
+    /// Mutable counterpart of `from_bytes_unchecked`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `from_bytes_unchecked`: `bytes` must be at least `Config::LEN` long and hold a
+    /// `Config` written by this program.
     #[inline(always)]
     pub unsafe fn from_bytes_unchecked_mut(bytes: &mut [u8]) -> &mut Self {
         &mut *(bytes.as_mut_ptr() as *mut Config)
@@ -68,6 +163,10 @@ impl Config {
         self.state
     }
     #[inline(always)]
+    pub fn state_enum(&self) -> Result<AmmState, ProgramError> {
+        AmmState::try_from(self.state)
+    }
+    #[inline(always)]
     pub fn seed(&self) -> u64 {
         u64::from_le_bytes(self.seed)
     }
@@ -91,6 +190,152 @@ impl Config {
     pub fn config_bump(&self) -> [u8; 1] {
         self.config_bump
     }
+    #[inline(always)]
+    pub fn access_mode(&self) -> Result<AccessMode, ProgramError> {
+        AccessMode::try_from(self.access_mode)
+    }
+    #[inline(always)]
+    pub fn access_list_root(&self) -> &[u8; 32] {
+        &self.access_list_root
+    }
+    #[inline(always)]
+    pub fn short_volatility(&self) -> u64 {
+        u64::from_le_bytes(self.short_volatility)
+    }
+    #[inline(always)]
+    pub fn long_volatility(&self) -> u64 {
+        u64::from_le_bytes(self.long_volatility)
+    }
+    #[inline(always)]
+    pub fn min_fee_bps(&self) -> u16 {
+        u16::from_le_bytes(self.min_fee_bps)
+    }
+    #[inline(always)]
+    pub fn max_fee_bps(&self) -> u16 {
+        u16::from_le_bytes(self.max_fee_bps)
+    }
+    #[inline(always)]
+    pub fn last_update_slot(&self) -> u64 {
+        u64::from_le_bytes(self.last_update_slot)
+    }
+    // x-denominated swap volume for `day` (unix days), zero once that day has rolled over
+    #[inline(always)]
+    pub fn daily_volume(&self, day: u64) -> u64 {
+        match u64::from_le_bytes(self.volume_day).eq(&day) {
+            true => u64::from_le_bytes(self.daily_volume),
+            false => 0,
+        }
+    }
+    #[inline(always)]
+    pub fn protocol_fee_share_bps(&self) -> u16 {
+        u16::from_le_bytes(self.protocol_fee_share_bps)
+    }
+    #[inline(always)]
+    pub fn fees_collected_x(&self) -> u64 {
+        u64::from_le_bytes(self.fees_collected_x)
+    }
+    #[inline(always)]
+    pub fn fees_collected_y(&self) -> u64 {
+        u64::from_le_bytes(self.fees_collected_y)
+    }
+    // largest swap input as a fraction of the input reserve, 0 for no cap
+    #[inline(always)]
+    pub fn max_swap_bps(&self) -> u16 {
+        u16::from_le_bytes(self.max_swap_bps)
+    }
+    // fails when `amount_in` is more than the cap allows out of `reserve_in`
+    #[inline(always)]
+    pub fn check_swap_cap(&self, amount_in: u64, reserve_in: u64) -> Result<(), ProgramError> {
+        let max_swap_bps = self.max_swap_bps() as u128;
+        if max_swap_bps.ne(&0) && amount_in as u128 * 10_000 > reserve_in as u128 * max_swap_bps {
+            return Err(PinocchioError::SwapExceedsCap.into());
+        }
+        Ok(())
+    }
+    // user token balances below this may be swept into the vaults, 0 disables sweeping
+    #[inline(always)]
+    pub fn dust_threshold(&self) -> u64 {
+        u64::from_le_bytes(self.dust_threshold)
+    }
+    // whether LPPosition owners may hand their positions to someone else
+    #[inline(always)]
+    pub fn transferable_positions(&self) -> bool {
+        self.transferable_positions.ne(&0)
+    }
+    #[inline(always)]
+    pub fn lp_decimals(&self) -> u8 {
+        self.lp_decimals
+    }
+    #[inline(always)]
+    pub fn decimals_x(&self) -> u8 {
+        self.decimals_x
+    }
+    #[inline(always)]
+    pub fn decimals_y(&self) -> u8 {
+        self.decimals_y
+    }
+    // referrer's cut, in bps of the protocol's share of a swap fee
+    #[inline(always)]
+    pub fn referral_fee_bps(&self) -> u16 {
+        u16::from_le_bytes(self.referral_fee_bps)
+    }
+    #[inline(always)]
+    pub fn vault_x_bump(&self) -> [u8; 1] {
+        self.vault_x_bump
+    }
+    #[inline(always)]
+    pub fn vault_y_bump(&self) -> [u8; 1] {
+        self.vault_y_bump
+    }
+    #[inline(always)]
+    pub fn mint_lp_bump(&self) -> [u8; 1] {
+        self.mint_lp_bump
+    }
+    #[inline(always)]
+    pub fn lp_vault_bump(&self) -> [u8; 1] {
+        self.lp_vault_bump
+    }
+    // the treasury's part of a swap fee of `fee`
+    #[inline(always)]
+    pub fn protocol_fee(&self, fee: u64) -> u64 {
+        (fee as u128 * self.protocol_fee_share_bps() as u128 / 10_000) as u64
+    }
+    // the part of the treasury's share that goes to a referrer instead, LPs keep theirs in full
+    #[inline(always)]
+    pub fn referral_fee(&self, fee: u64) -> u64 {
+        (self.protocol_fee(fee) as u128 * self.referral_fee_bps() as u128 / 10_000) as u64
+    }
+    // protocol fees sit in the vaults until collected but belong to the treasury, not to LPs
+    #[inline(always)]
+    pub fn pool_reserves(&self, vault_x_amount: u64, vault_y_amount: u64) -> (u64, u64) {
+        (
+            vault_x_amount.saturating_sub(self.fees_collected_x()),
+            vault_y_amount.saturating_sub(self.fees_collected_y()),
+        )
+    }
+    // what the vaults should hold according to the pool's own transfers
+    #[inline(always)]
+    pub fn expected_vault_x_balance(&self) -> u64 {
+        u64::from_le_bytes(self.expected_vault_x_balance)
+    }
+    #[inline(always)]
+    pub fn expected_vault_y_balance(&self) -> u64 {
+        u64::from_le_bytes(self.expected_vault_y_balance)
+    }
+    // falls back to the static fee until the authority configures a volatility band
+    #[inline(always)]
+    pub fn effective_fee(&self) -> u16 {
+        let (min_fee, max_fee) = (self.min_fee_bps() as u128, self.max_fee_bps() as u128);
+        if max_fee.eq(&0) {
+            return self.fee();
+        }
+        let (short, long) = (self.short_volatility() as u128, self.long_volatility() as u128);
+        if long.eq(&0) {
+            return min_fee as u16;
+        }
+        let dynamic_fee = min_fee + (max_fee - min_fee) * short / long;
+        dynamic_fee.min(max_fee) as u16
+    }
 
     #[inline(always)]
     pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<Self>, ProgramError> {
@@ -106,10 +351,8 @@ impl Config {
     }
     #[inline(always)]
     pub fn set_state(&mut self, state: u8) -> Result<(), ProgramError> {
-        if state.ge(&(AmmState::WithdrawOnly as u8)) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        self.state = state as u8;
+        AmmState::try_from(state)?;
+        self.state = state;
         Ok(())
     }
     #[inline(always)]
@@ -145,6 +388,231 @@ impl Config {
         self.config_bump = config_bump;
         Ok(())
     }
+    #[inline(always)]
+    pub fn set_access_control(
+        &mut self,
+        access_mode: AccessMode,
+        access_list_root: [u8; 32],
+    ) -> Result<(), ProgramError> {
+        self.access_mode = access_mode as u8;
+        self.access_list_root = access_list_root;
+        Ok(())
+    }
+    #[inline(always)]
+    pub fn set_volatility_fee_bounds(
+        &mut self,
+        min_fee_bps: u16,
+        max_fee_bps: u16,
+    ) -> Result<(), ProgramError> {
+        if min_fee_bps.gt(&max_fee_bps) || max_fee_bps.ge(&10_000) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.min_fee_bps = min_fee_bps.to_le_bytes();
+        self.max_fee_bps = max_fee_bps.to_le_bytes();
+        self.short_volatility = [0u8; 8];
+        self.long_volatility = [0u8; 8];
+        Ok(())
+    }
+    // short window is an EMA with alpha 0.1, long window with alpha 0.01
+    #[inline(always)]
+    pub fn record_price_change(&mut self, price_change_bps: u64, slot: u64) {
+        let short = self.short_volatility() as u128;
+        let long = self.long_volatility() as u128;
+        let change = price_change_bps as u128;
+        let short = (short * 9 + change) / 10;
+        let long = (long * 99 + change) / 100;
+        self.short_volatility = (short.min(u64::MAX as u128) as u64).to_le_bytes();
+        self.long_volatility = (long.min(u64::MAX as u128) as u64).to_le_bytes();
+        self.last_update_slot = slot.to_le_bytes();
+    }
+    #[inline(always)]
+    pub fn set_protocol_fee_share(&mut self, share_bps: u16) -> Result<(), ProgramError> {
+        if share_bps.gt(&10_000) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.protocol_fee_share_bps = share_bps.to_le_bytes();
+        Ok(())
+    }
+    #[inline(always)]
+    pub fn set_max_swap_bps(&mut self, max_swap_bps: u16) -> Result<(), ProgramError> {
+        if max_swap_bps.gt(&10_000) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.max_swap_bps = max_swap_bps.to_le_bytes();
+        Ok(())
+    }
+    #[inline(always)]
+    pub fn set_dust_threshold(&mut self, dust_threshold: u64) {
+        self.dust_threshold = dust_threshold.to_le_bytes();
+    }
+    #[inline(always)]
+    pub fn set_transferable_positions(&mut self, transferable_positions: bool) {
+        self.transferable_positions = transferable_positions as u8;
+    }
+    #[inline(always)]
+    pub fn set_lp_decimals(&mut self, lp_decimals: u8) {
+        self.lp_decimals = lp_decimals;
+    }
+    #[inline(always)]
+    pub fn set_mint_decimals(&mut self, decimals_x: u8, decimals_y: u8) {
+        self.decimals_x = decimals_x;
+        self.decimals_y = decimals_y;
+    }
+    #[inline(always)]
+    pub fn set_vault_bumps(&mut self, vault_x_bump: [u8; 1], vault_y_bump: [u8; 1]) {
+        self.vault_x_bump = vault_x_bump;
+        self.vault_y_bump = vault_y_bump;
+    }
+    #[inline(always)]
+    pub fn set_lp_bumps(&mut self, mint_lp_bump: [u8; 1], lp_vault_bump: [u8; 1]) {
+        self.mint_lp_bump = mint_lp_bump;
+        self.lp_vault_bump = lp_vault_bump;
+    }
+    #[inline(always)]
+    pub fn set_referral_fee_bps(&mut self, referral_fee_bps: u16) -> Result<(), ProgramError> {
+        if referral_fee_bps.gt(&MAX_REFERRAL_FEE_BPS) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.referral_fee_bps = referral_fee_bps.to_le_bytes();
+        Ok(())
+    }
+    // `fee` is the swap fee charged on the input side, `is_x` when that side is x;
+    // `referral_fee` has already been paid out of the vault to a referrer
+    #[inline(always)]
+    pub fn accrue_protocol_fee(&mut self, is_x: bool, fee: u64, referral_fee: u64) {
+        let share = self.protocol_fee(fee).saturating_sub(referral_fee);
+        match is_x {
+            true => {
+                self.fees_collected_x = self.fees_collected_x().saturating_add(share).to_le_bytes()
+            }
+            false => {
+                self.fees_collected_y = self.fees_collected_y().saturating_add(share).to_le_bytes()
+            }
+        }
+    }
+    #[inline(always)]
+    pub fn release_fees_collected(&mut self, x: u64, y: u64) {
+        self.fees_collected_x = self.fees_collected_x().saturating_sub(x).to_le_bytes();
+        self.fees_collected_y = self.fees_collected_y().saturating_sub(y).to_le_bytes();
+    }
+    #[inline(always)]
+    pub fn reset_fees_collected(&mut self) {
+        self.fees_collected_x = [0u8; 8];
+        self.fees_collected_y = [0u8; 8];
+    }
+    #[inline(always)]
+    pub fn credit_expected_balances(&mut self, x: u64, y: u64) {
+        self.expected_vault_x_balance =
+            self.expected_vault_x_balance().saturating_add(x).to_le_bytes();
+        self.expected_vault_y_balance =
+            self.expected_vault_y_balance().saturating_add(y).to_le_bytes();
+    }
+    #[inline(always)]
+    pub fn debit_expected_balances(&mut self, x: u64, y: u64) {
+        self.expected_vault_x_balance =
+            self.expected_vault_x_balance().saturating_sub(x).to_le_bytes();
+        self.expected_vault_y_balance =
+            self.expected_vault_y_balance().saturating_sub(y).to_le_bytes();
+    }
+    #[inline(always)]
+    pub fn set_expected_balances(&mut self, x: u64, y: u64) {
+        self.expected_vault_x_balance = x.to_le_bytes();
+        self.expected_vault_y_balance = y.to_le_bytes();
+    }
+    #[inline(always)]
+    pub fn record_volume(&mut self, volume_x: u64, day: u64) {
+        let volume = self.daily_volume(day).saturating_add(volume_x);
+        self.daily_volume = volume.to_le_bytes();
+        self.volume_day = day.to_le_bytes();
+    }
+    // cumulative prices as of `now`, counting the current price up to it
+    #[inline(always)]
+    pub fn price_cumulatives(&self, now: i64) -> (u128, u128) {
+        let elapsed = now.saturating_sub(i64::from_le_bytes(self.last_price_timestamp)).max(0);
+        let accumulate = |cumulative: [u8; 16], price: [u8; 16]| {
+            // wraps by design, readers only ever take the difference of two readings
+            u128::from_le_bytes(cumulative)
+                .wrapping_add(u128::from_le_bytes(price).wrapping_mul(elapsed as u128))
+        };
+        (
+            accumulate(self.price_x_cumulative, self.last_price_x),
+            accumulate(self.price_y_cumulative, self.last_price_y),
+        )
+    }
+    // call after every change to the reserves: books the old price up to `now`, then starts
+    // the new one; an empty side prices at zero
+    #[inline(always)]
+    pub fn update_price_cumulatives(&mut self, reserve_x: u64, reserve_y: u64, now: i64) {
+        let (price_x_cumulative, price_y_cumulative) = self.price_cumulatives(now);
+        self.price_x_cumulative = price_x_cumulative.to_le_bytes();
+        self.price_y_cumulative = price_y_cumulative.to_le_bytes();
+        self.last_price_x = spot_price(reserve_x, reserve_y, 0, 0).unwrap_or(0).to_le_bytes();
+        self.last_price_y = spot_price(reserve_y, reserve_x, 0, 0).unwrap_or(0).to_le_bytes();
+        self.last_price_timestamp = now.to_le_bytes();
+        self.record_observation(now);
+    }
+    // several updates within the same second share one observation
+    fn record_observation(&mut self, now: i64) {
+        let mut index = self.observation_index as usize % PRICE_OBSERVATIONS;
+        if i64::from_le_bytes(self.observations[index].timestamp).ne(&now) {
+            index = (index + 1) % PRICE_OBSERVATIONS;
+        }
+        self.observations[index] = PriceObservation {
+            timestamp: now.to_le_bytes(),
+            price_x_cumulative: self.price_x_cumulative,
+            price_y_cumulative: self.price_y_cumulative,
+        };
+        self.observation_index = index as u8;
+    }
+    // cumulative prices as of `at`, which may lie before the last update as long as the
+    // observations still reach back to it
+    fn price_cumulatives_at(&self, at: i64) -> Result<(u128, u128), ProgramError> {
+        if at.ge(&i64::from_le_bytes(self.last_price_timestamp)) {
+            return Ok(self.price_cumulatives(at));
+        }
+        let mut newer: Option<&PriceObservation> = None;
+        for step in 0..PRICE_OBSERVATIONS {
+            let index =
+                (self.observation_index as usize + PRICE_OBSERVATIONS - step) % PRICE_OBSERVATIONS;
+            let older = &self.observations[index];
+            let timestamp = i64::from_le_bytes(older.timestamp);
+            if timestamp.eq(&0) {
+                break;
+            }
+            if timestamp.le(&at) {
+                let Some(newer) = newer else {
+                    return Ok(self.price_cumulatives(at));
+                };
+                // the price held still between the two updates, so the sum grew linearly
+                let span = (i64::from_le_bytes(newer.timestamp) - timestamp) as u128;
+                let elapsed = (at - timestamp) as u128;
+                let interpolate = |older: [u8; 16], newer: [u8; 16]| {
+                    let older = u128::from_le_bytes(older);
+                    let price = u128::from_le_bytes(newer).wrapping_sub(older) / span;
+                    older.wrapping_add(price.wrapping_mul(elapsed))
+                };
+                return Ok((
+                    interpolate(older.price_x_cumulative, newer.price_x_cumulative),
+                    interpolate(older.price_y_cumulative, newer.price_y_cumulative),
+                ));
+            }
+            newer = Some(older);
+        }
+        Err(PinocchioError::TwapWindowTooLong.into())
+    }
+    // Q64.64 time-weighted prices (y per x, x per y) over the `window` seconds up to `now`
+    pub fn twap(&self, now: i64, window: i64) -> Result<(u128, u128), ProgramError> {
+        if window.le(&0) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let start = now.checked_sub(window).ok_or(ProgramError::ArithmeticOverflow)?;
+        let (start_x, start_y) = self.price_cumulatives_at(start)?;
+        let (end_x, end_y) = self.price_cumulatives(now);
+        Ok((
+            end_x.wrapping_sub(start_x) / window as u128,
+            end_y.wrapping_sub(start_y) / window as u128,
+        ))
+    }
    
     #[inline(always)]
     pub fn set_inner_data(
@@ -176,3 +644,353 @@ impl Config {
         }
     }
 }
+
+/// The pool's cumulative Q64.64 prices (y per x, x per y) as of `now`. Two readings `t`
+/// seconds apart give the TWAP over that window as `(b - a) / t`, with wrapping subtraction.
+pub fn read_price_cumulatives(
+    config: &AccountInfo,
+    now: i64,
+) -> Result<(u128, u128), ProgramError> {
+    Ok(Config::load(config)?.price_cumulatives(now))
+}
+
+/// The pool's Q64.64 TWAPs (y per x, x per y) over the last `window` seconds. Fails with
+/// `TwapWindowTooLong` when the window starts before the oldest observation the pool keeps.
+pub fn read_twap(config: &AccountInfo, window: i64) -> Result<(u128, u128), ProgramError> {
+    let now = Clock::get()?.unix_timestamp;
+    Config::load(config)?.twap(now, window)
+}
+
+// most of the protocol's fee share a pool may hand to referrers
+pub const MAX_REFERRAL_FEE_BPS: u16 = 5_000;
+
+// fixed point scale of the LPPosition entry prices
+pub const PRICE_SCALE: u128 = 1_000_000_000;
+
+/// One position-style deposit, a PDA at `["position", pool, owner, deposit_index]`. Its LP
+/// tokens sit in the pool's own LP account, so `liquidity` counts towards the mint supply
+/// exactly like fungible LP does.
+#[repr(C)]
+pub struct LPPosition {
+    owner: Pubkey,
+    pool: Pubkey,
+    liquidity: [u8; 8],
+    // y per x and x per y at deposit, scaled by PRICE_SCALE
+    entry_price_x: [u8; 8],
+    entry_price_y: [u8; 8],
+    // swap fees compound into the reserves and so into `liquidity`; nothing is owed on top
+    // of that yet, these stay zero until the pool tracks fees per position
+    fee_owed_x: [u8; 8],
+    fee_owed_y: [u8; 8],
+    deposit_ts: [u8; 8],
+}
+
+impl LPPosition {
+    pub const LEN: usize = size_of::<LPPosition>();
+
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        Ok(Ref::map(account_info.try_borrow_data()?, |data| unsafe {
+            &*(data.as_ptr() as *const LPPosition)
+        }))
+    }
+
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        Ok(RefMut::map(account_info.try_borrow_mut_data()?, |data| unsafe {
+            &mut *(data.as_mut_ptr() as *mut LPPosition)
+        }))
+    }
+
+    #[inline(always)]
+    pub fn owner(&self) -> &Pubkey {
+        &self.owner
+    }
+    #[inline(always)]
+    pub fn pool(&self) -> &Pubkey {
+        &self.pool
+    }
+    #[inline(always)]
+    pub fn liquidity(&self) -> u64 {
+        u64::from_le_bytes(self.liquidity)
+    }
+    #[inline(always)]
+    pub fn entry_price_x(&self) -> u64 {
+        u64::from_le_bytes(self.entry_price_x)
+    }
+    #[inline(always)]
+    pub fn entry_price_y(&self) -> u64 {
+        u64::from_le_bytes(self.entry_price_y)
+    }
+    #[inline(always)]
+    pub fn fee_owed_x(&self) -> u64 {
+        u64::from_le_bytes(self.fee_owed_x)
+    }
+    #[inline(always)]
+    pub fn fee_owed_y(&self) -> u64 {
+        u64::from_le_bytes(self.fee_owed_y)
+    }
+    #[inline(always)]
+    pub fn deposit_ts(&self) -> i64 {
+        i64::from_le_bytes(self.deposit_ts)
+    }
+    #[inline(always)]
+    pub fn set_owner(&mut self, owner: Pubkey) {
+        self.owner = owner;
+    }
+    #[inline(always)]
+    pub fn set_inner(
+        &mut self,
+        owner: Pubkey,
+        pool: Pubkey,
+        liquidity: u64,
+        reserve_x: u64,
+        reserve_y: u64,
+        deposit_ts: i64,
+    ) {
+        let price = |num: u64, den: u64| match den {
+            0 => 0,
+            _ => (num as u128 * PRICE_SCALE / den as u128).min(u64::MAX as u128) as u64,
+        };
+        self.owner = owner;
+        self.pool = pool;
+        self.liquidity = liquidity.to_le_bytes();
+        self.entry_price_x = price(reserve_y, reserve_x).to_le_bytes();
+        self.entry_price_y = price(reserve_x, reserve_y).to_le_bytes();
+        self.fee_owed_x = [0u8; 8];
+        self.fee_owed_y = [0u8; 8];
+        self.deposit_ts = deposit_ts.to_le_bytes();
+    }
+}
+
+/// Protocol-owned liquidity of one pool, a PDA at `["pol", pool]`. The LP it counts
+/// (the `TreasuryLP` balance) sits in the pool's own LP account next to the `LPPosition` LP
+/// and only leaves it through a timelocked `WithdrawPOL`.
+#[repr(C)]
+pub struct POLConfig {
+    pool: Pubkey,
+    treasury: Pubkey,
+    // accrued protocol fees must reach this share of the reserves on either side
+    rebalance_threshold_bps: [u8; 2],
+    // no more rebalancing once the treasury holds this share of the LP supply
+    target_ratio_bps: [u8; 2],
+    lp_amount: [u8; 8],
+    // pending withdrawal, 0 when none is scheduled
+    withdraw_amount: [u8; 8],
+    withdraw_unlocks_at: [u8; 8],
+    bump: [u8; 1],
+}
+
+impl POLConfig {
+    pub const LEN: usize = size_of::<POLConfig>();
+
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        Ok(Ref::map(account_info.try_borrow_data()?, |data| unsafe {
+            &*(data.as_ptr() as *const POLConfig)
+        }))
+    }
+
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        Ok(RefMut::map(account_info.try_borrow_mut_data()?, |data| unsafe {
+            &mut *(data.as_mut_ptr() as *mut POLConfig)
+        }))
+    }
+
+    #[inline(always)]
+    pub fn pool(&self) -> &Pubkey {
+        &self.pool
+    }
+    #[inline(always)]
+    pub fn treasury(&self) -> &Pubkey {
+        &self.treasury
+    }
+    #[inline(always)]
+    pub fn rebalance_threshold_bps(&self) -> u16 {
+        u16::from_le_bytes(self.rebalance_threshold_bps)
+    }
+    #[inline(always)]
+    pub fn target_ratio_bps(&self) -> u16 {
+        u16::from_le_bytes(self.target_ratio_bps)
+    }
+    #[inline(always)]
+    pub fn lp_amount(&self) -> u64 {
+        u64::from_le_bytes(self.lp_amount)
+    }
+    #[inline(always)]
+    pub fn withdraw_amount(&self) -> u64 {
+        u64::from_le_bytes(self.withdraw_amount)
+    }
+    #[inline(always)]
+    pub fn withdraw_unlocks_at(&self) -> i64 {
+        i64::from_le_bytes(self.withdraw_unlocks_at)
+    }
+    #[inline(always)]
+    pub fn set_inner(&mut self, pool: Pubkey, bump: [u8; 1]) {
+        self.pool = pool;
+        self.bump = bump;
+    }
+    #[inline(always)]
+    pub fn set_params(
+        &mut self,
+        treasury: Pubkey,
+        rebalance_threshold_bps: u16,
+        target_ratio_bps: u16,
+    ) -> Result<(), ProgramError> {
+        if rebalance_threshold_bps.gt(&10_000) || target_ratio_bps.gt(&10_000) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.treasury = treasury;
+        self.rebalance_threshold_bps = rebalance_threshold_bps.to_le_bytes();
+        self.target_ratio_bps = target_ratio_bps.to_le_bytes();
+        Ok(())
+    }
+    #[inline(always)]
+    pub fn credit_lp(&mut self, lp: u64) {
+        self.lp_amount = self.lp_amount().saturating_add(lp).to_le_bytes();
+    }
+    #[inline(always)]
+    pub fn schedule_withdrawal(&mut self, amount: u64, unlocks_at: i64) {
+        self.withdraw_amount = amount.to_le_bytes();
+        self.withdraw_unlocks_at = unlocks_at.to_le_bytes();
+    }
+    // pays out the pending withdrawal from `lp_amount` and clears it
+    #[inline(always)]
+    pub fn settle_withdrawal(&mut self) {
+        self.lp_amount = self.lp_amount().saturating_sub(self.withdraw_amount()).to_le_bytes();
+        self.schedule_withdrawal(0, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: u128 = 1 << 64;
+
+    fn config(data: &mut [u8; Config::LEN]) -> &mut Config {
+        unsafe { Config::from_bytes_unchecked_mut(data) }
+    }
+
+    #[test]
+    fn swaps_within_the_cap_pass() {
+        let mut data = [0u8; Config::LEN];
+        let config = config(&mut data);
+
+        // no cap by default
+        assert_eq!(config.check_swap_cap(u64::MAX, 1), Ok(()));
+
+        config.set_max_swap_bps(500).unwrap();
+        assert_eq!(config.check_swap_cap(50_000, 1_000_000), Ok(()));
+        assert_eq!(
+            config.check_swap_cap(50_001, 1_000_000),
+            Err(PinocchioError::SwapExceedsCap.into())
+        );
+        // the product is taken in u128, so huge reserves can't wrap past the cap
+        assert_eq!(config.check_swap_cap(u64::MAX / 20, u64::MAX), Ok(()));
+        assert_eq!(
+            config.check_swap_cap(u64::MAX / 20 + 1, u64::MAX),
+            Err(PinocchioError::SwapExceedsCap.into())
+        );
+
+        // a full cap still lets a swap take no more than the whole reserve
+        config.set_max_swap_bps(10_000).unwrap();
+        assert_eq!(config.check_swap_cap(1_000, 1_000), Ok(()));
+        assert!(config.check_swap_cap(1_001, 1_000).is_err());
+        assert_eq!(
+            config.set_max_swap_bps(10_001),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn twap_spans_several_updates() {
+        let mut data = [0u8; Config::LEN];
+        let config = config(&mut data);
+        // y per x: 1 from 100, 2 from 200, 1/2 from 300
+        config.update_price_cumulatives(1_000, 1_000, 100);
+        config.update_price_cumulatives(1_000, 2_000, 200);
+        config.update_price_cumulatives(2_000, 1_000, 300);
+
+        // all within the current price
+        assert_eq!(config.twap(400, 100), Ok((ONE / 2, 2 * ONE)));
+        assert_eq!(
+            config.twap(400, 200),
+            Ok((250 * ONE / 200, 250 * ONE / 200))
+        );
+        // starts halfway between the first two updates
+        assert_eq!(
+            config.twap(400, 250),
+            Ok((300 * ONE / 250, 300 * ONE / 250))
+        );
+        // starts exactly at the oldest observation
+        assert_eq!(
+            config.twap(400, 300),
+            Ok((350 * ONE / 300, 350 * ONE / 300))
+        );
+        assert_eq!(
+            config.twap(400, 301),
+            Err(PinocchioError::TwapWindowTooLong.into())
+        );
+    }
+
+    #[test]
+    fn twap_refuses_an_empty_window() {
+        let mut data = [0u8; Config::LEN];
+        let config = config(&mut data);
+        config.update_price_cumulatives(1_000, 1_000, 100);
+
+        assert_eq!(config.twap(200, 0), Err(ProgramError::InvalidArgument));
+        assert_eq!(config.twap(200, -1), Err(ProgramError::InvalidArgument));
+        assert_eq!(config.twap(200, 100), Ok((ONE, ONE)));
+    }
+
+    #[test]
+    fn observations_keep_the_latest_updates() {
+        let mut data = [0u8; Config::LEN];
+        let config = config(&mut data);
+        for second in 1..=40 {
+            let reserve_y = match second % 2 {
+                0 => 1_000,
+                _ => 3_000,
+            };
+            config.update_price_cumulatives(1_000, reserve_y, second * 10);
+            // a second update within the same second doesn't take another slot
+            config.update_price_cumulatives(1_000, reserve_y, second * 10);
+        }
+
+        let oldest = (40 - PRICE_OBSERVATIONS as i64 + 1) * 10;
+        assert!(config.twap(400, 400 - oldest).is_ok());
+        assert_eq!(
+            config.twap(400, 400 - oldest + 1),
+            Err(PinocchioError::TwapWindowTooLong.into())
+        );
+        // prices alternate between 1 and 3 every 10 seconds
+        assert_eq!(config.twap(400, 20), Ok((2 * ONE, 2 * ONE / 3)));
+    }
+}
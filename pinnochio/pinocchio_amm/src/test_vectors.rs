@@ -0,0 +1,124 @@
+//! Canonical instruction data for client authors, built with the `test-vectors` feature and
+//! for the crate's tests, which parse each one back. Each vector is the full instruction data,
+//! discriminator first, for the field values noted beside it; dump `TEST_VECTORS` to a fixture
+//! and compare an encoder's output byte for byte.
+
+pub struct TestVector {
+    pub name: &'static str,
+    pub data: &'static [u8],
+}
+
+const fn write<const N: usize>(mut out: [u8; N], at: usize, bytes: &[u8]) -> [u8; N] {
+    let mut i = 0;
+    while i < bytes.len() {
+        out[at + i] = bytes[i];
+        i += 1;
+    }
+    out
+}
+
+const MINT_X: [u8; 32] = [1; 32];
+const MINT_Y: [u8; 32] = [2; 32];
+const AUTHORITY: [u8; 32] = [3; 32];
+
+// seed 42 | fee 30 | mint_x [1; 32] | mint_y [2; 32] | config_bump 254 | lp_bump 253
+const INITIALIZE_IMMUTABLE: [u8; 77] = {
+    let out = write([0u8; 77], 0, &[0]);
+    let out = write(out, 1, &42u64.to_le_bytes());
+    let out = write(out, 9, &30u16.to_le_bytes());
+    let out = write(out, 11, &MINT_X);
+    let out = write(out, 43, &MINT_Y);
+    write(out, 75, &[254, 253])
+};
+
+// as above, followed by authority [3; 32]
+const INITIALIZE_WITH_AUTHORITY: [u8; 109] = {
+    let out = write([0u8; 109], 0, &INITIALIZE_IMMUTABLE);
+    write(out, 77, &AUTHORITY)
+};
+
+// amount 1_000_000 | max_x 500_000 | max_y 2_000_000 | expiration 1_700_000_000 | no proof
+const DEPOSIT: [u8; 33] = {
+    let out = write([0u8; 33], 0, &[1]);
+    let out = write(out, 1, &1_000_000u64.to_le_bytes());
+    let out = write(out, 9, &500_000u64.to_le_bytes());
+    let out = write(out, 17, &2_000_000u64.to_le_bytes());
+    write(out, 25, &1_700_000_000i64.to_le_bytes())
+};
+
+pub const TEST_VECTORS: &[TestVector] = &[
+    TestVector {
+        name: "amm_initialize_immutable",
+        data: &INITIALIZE_IMMUTABLE,
+    },
+    TestVector {
+        name: "amm_initialize_with_authority",
+        data: &INITIALIZE_WITH_AUTHORITY,
+    },
+    TestVector {
+        name: "amm_deposit",
+        data: &DEPOSIT,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Deposit, DepositInstructionData, Initialize, InitializeInstructionData};
+
+    fn vector(name: &str) -> &'static [u8] {
+        TEST_VECTORS
+            .iter()
+            .find(|vector| vector.name.eq(name))
+            .unwrap()
+            .data
+    }
+
+    #[test]
+    fn initialize_immutable_parses() {
+        let (discriminator, data) = vector("amm_initialize_immutable").split_first().unwrap();
+        assert_eq!(discriminator, Initialize::DISCRIMINATOR);
+
+        // the struct is packed, its integer fields are copied out before comparing
+        let parsed = InitializeInstructionData::try_from(data).unwrap();
+        assert_eq!({ parsed.seed }, 42);
+        assert_eq!({ parsed.fee }, 30);
+        assert_eq!(parsed.mint_x, MINT_X);
+        assert_eq!(parsed.mint_y, MINT_Y);
+        assert_eq!(parsed.config_bump, [254]);
+        assert_eq!(parsed.lp_bump, [253]);
+        assert_eq!(parsed.authority, [0u8; 32]);
+        assert_eq!(parsed.lp_decimals, crate::DEFAULT_LP_DECIMALS);
+    }
+
+    #[test]
+    fn initialize_with_authority_parses() {
+        let (discriminator, data) = vector("amm_initialize_with_authority")
+            .split_first()
+            .unwrap();
+        assert_eq!(discriminator, Initialize::DISCRIMINATOR);
+
+        let parsed = InitializeInstructionData::try_from(data).unwrap();
+        assert_eq!({ parsed.seed }, 42);
+        assert_eq!({ parsed.fee }, 30);
+        assert_eq!(parsed.mint_x, MINT_X);
+        assert_eq!(parsed.mint_y, MINT_Y);
+        assert_eq!(parsed.config_bump, [254]);
+        assert_eq!(parsed.lp_bump, [253]);
+        assert_eq!(parsed.authority, AUTHORITY);
+        assert_eq!(parsed.lp_decimals, crate::DEFAULT_LP_DECIMALS);
+    }
+
+    #[test]
+    fn deposit_parses() {
+        let (discriminator, data) = vector("amm_deposit").split_first().unwrap();
+        assert_eq!(discriminator, Deposit::DISCRIMINATOR);
+
+        let parsed = DepositInstructionData::try_from(data).unwrap();
+        assert_eq!(parsed.amount, 1_000_000);
+        assert_eq!(parsed.max_x, 500_000);
+        assert_eq!(parsed.max_y, 2_000_000);
+        assert_eq!(parsed.expiration, 1_700_000_000);
+        assert!(parsed.access_proof.is_empty());
+    }
+}
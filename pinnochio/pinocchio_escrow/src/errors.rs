@@ -6,6 +6,19 @@ pub enum PinocchioError {
     InvalidOwner,
     InvalidAccountData,
     InvalidAddress,
+    MinReceiveNotMet,
+    InvalidOrderPosition,
+    OrdersDoNotCross,
+    OfferExpired,
+    InvalidExpiry,
+    InvalidCounteroffer,
+    Unauthorized,
+    BatchTooLarge,
+    OraclePriceDeviation,
+    StaleOraclePrice,
+    EscrowBalanceMismatch,
+    ConditionalListing,
+    OrderNotStale,
 }
 
 impl From<PinocchioError> for ProgramError {
@@ -0,0 +1,270 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{
+    close_program_account, delist_order, harvest_withheld_fees, parse_listing,
+    AssociatedTokenAccount, Counteroffer, CounterofferAccount, Escrow, MintAccount,
+    PinocchioError, ProgramAccount, SignerAccount, TokenAccount, TokenProgram,
+};
+
+/// Splits the escrow vault's `vault_amount` of mint_a into what the taker asked for and what
+/// goes back to the maker; a counteroffer asking for more than the vault holds is refused.
+pub fn counteroffer_payouts(vault_amount: u64, receive: u64) -> Result<(u64, u64), ProgramError> {
+    match vault_amount.checked_sub(receive) {
+        Some(rest) => Ok((receive, rest)),
+        None => Err(PinocchioError::InvalidCounteroffer.into()),
+    }
+}
+
+pub struct AcceptCounterofferAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub taker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub counteroffer: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub counteroffer_vault: &'a AccountInfo,
+    pub maker_ata_a: &'a AccountInfo,
+    pub maker_ata_b: &'a AccountInfo,
+    pub taker_ata_a: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub listing: Option<[&'a AccountInfo; 3]>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for AcceptCounterofferAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, taker, escrow, counteroffer, mint_a, mint_b, vault, counteroffer_vault, maker_ata_a, maker_ata_b, taker_ata_a, system_program, token_program, _, listing @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        let listing = parse_listing(listing)?;
+
+        SignerAccount::check(maker)?;
+        TokenProgram::check(token_program)?;
+        ProgramAccount::check(escrow)?;
+        CounterofferAccount::check(counteroffer)?;
+        MintAccount::check(mint_a)?;
+        MintAccount::check(mint_b)?;
+        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
+        AssociatedTokenAccount::check(counteroffer_vault, counteroffer, mint_b, token_program)?;
+
+        Ok(Self {
+            maker,
+            taker,
+            escrow,
+            counteroffer,
+            mint_a,
+            mint_b,
+            vault,
+            counteroffer_vault,
+            maker_ata_a,
+            maker_ata_b,
+            taker_ata_a,
+            system_program,
+            token_program,
+            listing,
+        })
+    }
+}
+
+pub struct AcceptCounteroffer<'a> {
+    pub accounts: AcceptCounterofferAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for AcceptCounteroffer<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = AcceptCounterofferAccounts::try_from(accounts)?;
+
+        for (ata, mint, owner) in [
+            (accounts.maker_ata_a, accounts.mint_a, accounts.maker),
+            (accounts.maker_ata_b, accounts.mint_b, accounts.maker),
+            (accounts.taker_ata_a, accounts.mint_a, accounts.taker),
+        ] {
+            AssociatedTokenAccount::init_if_needed(
+                ata,
+                mint,
+                accounts.maker,
+                owner,
+                accounts.system_program,
+                accounts.token_program,
+            )?;
+        }
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> AcceptCounteroffer<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &8;
+
+    /// Settles the escrow at the counteroffer's terms: the taker gets `receive` of mint_a out
+    /// of the escrow vault, the maker gets the rest of it back plus the whole counteroffer vault.
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = Escrow::load(&data)?;
+
+        let escrow_key = create_program_address(
+            &[
+                b"escrow",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let counteroffer_data = self.accounts.counteroffer.try_borrow_data()?;
+        let counteroffer = Counteroffer::load(&counteroffer_data)?;
+
+        let counteroffer_key = create_program_address(
+            &[
+                b"counteroffer",
+                self.accounts.escrow.key(),
+                self.accounts.taker.key(),
+                &counteroffer.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &counteroffer_key != self.accounts.counteroffer.key()
+            || counteroffer.escrow.ne(self.accounts.escrow.key())
+            || counteroffer.taker.ne(self.accounts.taker.key())
+        {
+            return Err(PinocchioError::InvalidCounteroffer.into());
+        }
+
+        delist_order(escrow, self.accounts.listing)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        if escrow.is_expired(now) {
+            return Err(PinocchioError::OfferExpired.into());
+        }
+        if !escrow.condition_met(now, None)? {
+            return Err(PinocchioError::Unauthorized.into());
+        }
+
+        let (to_taker, to_maker) = counteroffer_payouts(
+            TokenAccount::amount(self.accounts.vault)?,
+            counteroffer.receive,
+        )?;
+
+        let token_program = self.accounts.token_program.key();
+        let decimals_a = MintAccount::decimals(self.accounts.mint_a)?;
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"escrow"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let escrow_signer = Signer::from(&escrow_seeds);
+
+        for (to, amount) in [
+            (self.accounts.taker_ata_a, to_taker),
+            (self.accounts.maker_ata_a, to_maker),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            TransferChecked {
+                from: self.accounts.vault,
+                mint: self.accounts.mint_a,
+                to,
+                authority: self.accounts.escrow,
+                amount,
+                decimals: decimals_a,
+                token_program,
+            }
+            .invoke_signed(core::slice::from_ref(&escrow_signer))?;
+        }
+
+        harvest_withheld_fees(self.accounts.vault, self.accounts.mint_a)?;
+
+        CloseAccount {
+            account: self.accounts.vault,
+            destination: self.accounts.maker,
+            authority: self.accounts.escrow,
+            token_program,
+        }
+        .invoke_signed(&[escrow_signer])?;
+
+        let counteroffer_bump = counteroffer.bump;
+        let counteroffer_seeds = [
+            Seed::from(b"counteroffer"),
+            Seed::from(self.accounts.escrow.key().as_ref()),
+            Seed::from(self.accounts.taker.key().as_ref()),
+            Seed::from(&counteroffer_bump),
+        ];
+        let counteroffer_signer = Signer::from(&counteroffer_seeds);
+
+        TransferChecked {
+            from: self.accounts.counteroffer_vault,
+            mint: self.accounts.mint_b,
+            to: self.accounts.maker_ata_b,
+            authority: self.accounts.counteroffer,
+            amount: TokenAccount::amount(self.accounts.counteroffer_vault)?,
+            decimals: MintAccount::decimals(self.accounts.mint_b)?,
+            token_program,
+        }
+        .invoke_signed(core::slice::from_ref(&counteroffer_signer))?;
+
+        harvest_withheld_fees(self.accounts.counteroffer_vault, self.accounts.mint_b)?;
+
+        CloseAccount {
+            account: self.accounts.counteroffer_vault,
+            destination: self.accounts.taker,
+            authority: self.accounts.counteroffer,
+            token_program,
+        }
+        .invoke_signed(&[counteroffer_signer])?;
+
+        drop(data);
+        drop(counteroffer_data);
+        close_program_account(self.accounts.escrow, self.accounts.maker)?;
+        close_program_account(self.accounts.counteroffer, self.accounts.taker)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payouts_split_the_vault() {
+        assert_eq!(counteroffer_payouts(1_000, 400), Ok((400, 600)));
+    }
+
+    #[test]
+    fn payouts_give_the_whole_vault_to_a_taker_asking_for_all_of_it() {
+        assert_eq!(counteroffer_payouts(1_000, 1_000), Ok((1_000, 0)));
+    }
+
+    #[test]
+    fn payouts_refuse_more_than_the_vault_holds() {
+        assert_eq!(
+            counteroffer_payouts(1_000, 1_001),
+            Err(PinocchioError::InvalidCounteroffer.into())
+        );
+    }
+}
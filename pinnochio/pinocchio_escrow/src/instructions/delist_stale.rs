@@ -0,0 +1,209 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    optional_account, unlink_order, Escrow, OrderBookAccount, PinocchioError, ProgramAccount,
+};
+
+pub struct DelistStaleAccounts<'a> {
+    pub order_book: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub prev: Option<&'a AccountInfo>,
+    pub next: Option<&'a AccountInfo>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for DelistStaleAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [order_book, escrow, prev, next] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        OrderBookAccount::check(order_book)?;
+        ProgramAccount::check(escrow)?;
+
+        Ok(Self {
+            order_book,
+            escrow,
+            prev: optional_account(prev),
+            next: optional_account(next),
+        })
+    }
+}
+
+pub struct DelistStale<'a> {
+    pub accounts: DelistStaleAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for DelistStale<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = DelistStaleAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> DelistStale<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &10;
+
+    /// Takes an escrow MatchOrders could never settle off its order book, so it stops
+    /// blocking the orders behind it. Anyone may call it; the escrow and its vault stay
+    /// as they are, for the maker to refund or for a direct Take.
+    pub fn process(&mut self) -> ProgramResult {
+        delist_stale(
+            self.accounts.order_book,
+            self.accounts.escrow,
+            self.accounts.prev,
+            self.accounts.next,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+}
+
+pub fn delist_stale(
+    order_book: &AccountInfo,
+    escrow: &AccountInfo,
+    prev: Option<&AccountInfo>,
+    next: Option<&AccountInfo>,
+    now: i64,
+) -> ProgramResult {
+    let mut data = escrow.try_borrow_mut_data()?;
+    let escrow = Escrow::load_mut(data.as_mut())?;
+    if !escrow.stale(now) {
+        return Err(PinocchioError::OrderNotStale.into());
+    }
+
+    unlink_order(order_book, escrow, prev, next)?;
+
+    escrow.set_order_book(None);
+    escrow.set_prev_same_side(None);
+    escrow.set_next_same_side(None);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConditionType, OrderBook, RawAccount, Side};
+    use pinocchio::pubkey::Pubkey;
+
+    const BOOK: Pubkey = [9u8; 32];
+    const MINT_X: Pubkey = [1u8; 32];
+    const MINT_Y: Pubkey = [2u8; 32];
+
+    fn order_book(best_ask: &Pubkey) -> RawAccount {
+        let mut data = [0u8; OrderBook::LEN];
+        let book = OrderBook::load_mut(&mut data).unwrap();
+        book.set_inner(MINT_X, MINT_Y, [255]);
+        book.set_best(Side::Ask, Some(best_ask));
+        RawAccount::new(false, &crate::ID, &data).with_key(&BOOK)
+    }
+
+    // an ask listed on BOOK between `prev` and `next`
+    fn ask(key: &Pubkey, expiry: i64, prev: Option<&Pubkey>, next: Option<&Pubkey>) -> RawAccount {
+        let mut raw = RawAccount::new(false, &crate::ID, &[0u8; Escrow::LEN]).with_key(key);
+        {
+            let info = raw.info();
+            let mut data = info.try_borrow_mut_data().unwrap();
+            let escrow = Escrow::load_mut(data.as_mut()).unwrap();
+            escrow.set_inner(0, [5u8; 32], MINT_X, MINT_Y, 100, [255]);
+            escrow.set_expiry(expiry);
+            escrow.set_order_book(Some(&BOOK));
+            escrow.set_prev_same_side(prev);
+            escrow.set_next_same_side(next);
+        }
+        raw
+    }
+
+    fn escrow(raw: &mut RawAccount) -> (Option<Pubkey>, Option<Pubkey>, Option<Pubkey>) {
+        let info = raw.info();
+        let data = info.try_borrow_data().unwrap();
+        let escrow = Escrow::load(&data).unwrap();
+        (
+            escrow.order_book().copied(),
+            escrow.prev_same_side().copied(),
+            escrow.next_same_side().copied(),
+        )
+    }
+
+    #[test]
+    fn an_expired_head_is_delisted_by_anyone() {
+        let (a, b) = ([10u8; 32], [11u8; 32]);
+        let mut book = order_book(&a);
+        let mut head = ask(&a, 1_000, None, Some(&b));
+        let mut behind = ask(&b, 0, Some(&a), None);
+
+        assert_eq!(
+            delist_stale(
+                &book.info(),
+                &head.info(),
+                None,
+                Some(&behind.info()),
+                1_001
+            ),
+            Ok(())
+        );
+
+        // the next ask is now best, and the delisted one no longer points into the book
+        let info = book.info();
+        let data = info.try_borrow_data().unwrap();
+        assert_eq!(OrderBook::load(&data).unwrap().best(Side::Ask), Some(&b));
+        assert_eq!(escrow(&mut behind), (Some(BOOK), None, None));
+        assert_eq!(escrow(&mut head), (None, None, None));
+    }
+
+    #[test]
+    fn a_conditional_listing_is_delisted_before_it_expires() {
+        let a = [10u8; 32];
+        let mut book = order_book(&a);
+        let mut head = ask(&a, 0, None, None);
+        {
+            let info = head.info();
+            let mut data = info.try_borrow_mut_data().unwrap();
+            Escrow::load_mut(data.as_mut())
+                .unwrap()
+                .set_condition(ConditionType::TimeAfter, [0u8; 64]);
+        }
+
+        assert_eq!(
+            delist_stale(&book.info(), &head.info(), None, None, 0),
+            Ok(())
+        );
+        let info = book.info();
+        let data = info.try_borrow_data().unwrap();
+        assert_eq!(OrderBook::load(&data).unwrap().best(Side::Ask), None);
+    }
+
+    #[test]
+    fn a_live_listing_stays() {
+        let a = [10u8; 32];
+        let mut book = order_book(&a);
+        let mut head = ask(&a, 1_000, None, None);
+
+        assert_eq!(
+            delist_stale(&book.info(), &head.info(), None, None, 1_000),
+            Err(PinocchioError::OrderNotStale.into())
+        );
+        assert_eq!(escrow(&mut head), (Some(BOOK), None, None));
+    }
+
+    #[test]
+    fn delisting_needs_the_real_neighbours() {
+        let (a, b) = ([10u8; 32], [11u8; 32]);
+        let mut book = order_book(&a);
+        let mut head = ask(&a, 1_000, None, Some(&b));
+
+        assert_eq!(
+            delist_stale(&book.info(), &head.info(), None, None, 1_001),
+            Err(PinocchioError::InvalidOrderPosition.into())
+        );
+    }
+}
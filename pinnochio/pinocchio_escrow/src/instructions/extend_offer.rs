@@ -0,0 +1,130 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{check_offer_expiry, Escrow, PinocchioError, ProgramAccount, SignerAccount};
+
+pub struct ExtendOfferAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ExtendOfferAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(maker)?;
+        ProgramAccount::check(escrow)?;
+
+        Ok(Self { maker, escrow })
+    }
+}
+
+pub struct ExtendOfferInstructionData {
+    pub new_expiry: i64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for ExtendOfferInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<i64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let new_expiry = i64::from_le_bytes(data.try_into().unwrap());
+        check_offer_expiry(new_expiry)?;
+
+        Ok(Self { new_expiry })
+    }
+}
+
+pub struct ExtendOffer<'a> {
+    pub accounts: ExtendOfferAccounts<'a>,
+    pub instruction_data: ExtendOfferInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for ExtendOffer<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = ExtendOfferAccounts::try_from(accounts)?;
+        let instruction_data = ExtendOfferInstructionData::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> ExtendOffer<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &5;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = Escrow::load_mut(data.as_mut())?;
+
+        if escrow.maker.ne(self.accounts.maker.key()) {
+            return Err(PinocchioError::InvalidAddress.into());
+        }
+
+        escrow.set_expiry(self.instruction_data.new_expiry);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RawAccount;
+
+    const MAKER: [u8; 32] = [1u8; 32];
+
+    // the expiry is set directly, checking it against the clock is `check_offer_expiry`'s job
+    fn extend(signer: &[u8; 32], escrow: &mut RawAccount, new_expiry: i64) -> ProgramResult {
+        let mut maker = RawAccount::new(true, &[0u8; 32], &[]).with_key(signer);
+        let accounts = [maker.info(), escrow.info()];
+        ExtendOffer {
+            accounts: ExtendOfferAccounts::try_from(&accounts[..])?,
+            instruction_data: ExtendOfferInstructionData { new_expiry },
+        }
+        .process()
+    }
+
+    #[test]
+    fn maker_extends_the_offer() {
+        let mut escrow = RawAccount::escrow(|escrow| {
+            escrow.set_maker(MAKER);
+            escrow.set_expiry(1_000);
+        });
+
+        extend(&MAKER, &mut escrow, 5_000).unwrap();
+        let escrow = escrow.info();
+        let data = unsafe { escrow.borrow_data_unchecked() };
+        assert_eq!(Escrow::load(data).unwrap().expiry, 5_000);
+    }
+
+    #[test]
+    fn only_the_maker_can_extend() {
+        let mut escrow = RawAccount::escrow(|escrow| escrow.set_maker(MAKER));
+        assert_eq!(
+            extend(&[2u8; 32], &mut escrow, 5_000),
+            Err(PinocchioError::InvalidAddress.into())
+        );
+    }
+
+    #[test]
+    fn negative_expiries_are_refused() {
+        for new_expiry in [i64::MIN, -1] {
+            assert_eq!(
+                ExtendOfferInstructionData::try_from(&new_expiry.to_le_bytes()[..]).err(),
+                Some(PinocchioError::InvalidExpiry.into())
+            );
+        }
+        assert!(ExtendOfferInstructionData::try_from(&[0u8; 7][..]).is_err());
+    }
+}
@@ -1,17 +1,40 @@
 use pinocchio::{
     account_info::AccountInfo,
-    instruction::{Seed, Signer},
+    cpi::invoke,
+    instruction::{AccountMeta, Instruction, Seed, Signer},
     program_error::ProgramError,
-    pubkey::find_program_address,
-    sysvars::{rent::Rent, Sysvar},
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
     ProgramResult,
 };
-use pinocchio_associated_token_account::instructions::Create;
+use pinocchio_associated_token_account::instructions::{Create, CreateIdempotent};
 use pinocchio_system::instructions::CreateAccount;
 use pinocchio_token::instructions::{InitializeMint2};
 
+#[cfg(target_os = "solana")]
+use pinocchio::pubkey::find_program_address;
+
 use crate::PinocchioError;
 
+// host builds have no PDA syscall, nor the curve check behind the bump search. Tests only
+// compare addresses derived here with each other, so the hash at the first bump stands in
+#[cfg(not(target_os = "solana"))]
+pub(crate) fn find_program_address(
+    seeds: &[&[u8]],
+    program_id: &pinocchio::pubkey::Pubkey,
+) -> (pinocchio::pubkey::Pubkey, u8) {
+    let bump = u8::MAX;
+    let mut hasher = sha2_const_stable::Sha256::new();
+    for seed in seeds {
+        hasher = hasher.update(seed);
+    }
+    let address = hasher
+        .update(&[bump])
+        .update(program_id)
+        .update(pinocchio::pubkey::PDA_MARKER)
+        .finalize();
+    (address, bump)
+}
+
 pub trait AccountCheck {
     fn check(account: &AccountInfo) -> Result<(), ProgramError>;
 }
@@ -39,19 +62,56 @@ impl SystemAccount {
     }
 }
 
-pub struct MintAccount;
+pub struct TokenProgram;
 
-impl MintAccount {
+impl TokenProgram {
     pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
-        if !account.is_owned_by(&pinocchio_token::ID) {
-            return Err(PinocchioError::InvalidOwner.into());
+        if account.key().ne(&pinocchio_token::ID) && account.key().ne(&pinocchio_token_2022::ID) {
+            return Err(PinocchioError::InvalidAddress.into());
         }
 
-        if account.data_len() != pinocchio_token::state::Mint::LEN {
+        Ok(())
+    }
+}
+
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+// Token-2022 accounts with extensions are longer than the base layout and carry an account
+// type byte right after the size of a base token account, which tells mints and accounts apart
+fn check_token_layout(account: &AccountInfo, base_len: usize, account_type: u8) -> ProgramResult {
+    if account.is_owned_by(&pinocchio_token::ID) {
+        if account.data_len().ne(&base_len) {
             return Err(PinocchioError::InvalidAccountData.into());
         }
+        return Ok(());
+    }
 
-        Ok(())
+    if !account.is_owned_by(&pinocchio_token_2022::ID) {
+        return Err(PinocchioError::InvalidOwner.into());
+    }
+
+    let data = account.try_borrow_data()?;
+    let extended = data.len() > pinocchio_token::state::TokenAccount::LEN
+        && data[pinocchio_token::state::TokenAccount::LEN].eq(&account_type);
+    if data.len().ne(&base_len) && !extended {
+        return Err(PinocchioError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+pub struct MintAccount;
+
+impl MintAccount {
+    pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
+        check_token_layout(account, pinocchio_token::state::Mint::LEN, ACCOUNT_TYPE_MINT)
+    }
+
+    pub fn decimals(account: &AccountInfo) -> Result<u8, ProgramError> {
+        Self::check(account)?;
+        let data = account.try_borrow_data()?;
+        Ok(unsafe { pinocchio_token::state::Mint::from_bytes_unchecked(&data) }.decimals())
     }
 }
 
@@ -118,21 +178,74 @@ pub struct TokenAccount;
 
 impl TokenAccount {
     pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
-        if !account.is_owned_by(&pinocchio_token::ID) {
-            return Err(PinocchioError::InvalidOwner.into());
-        }
+        check_token_layout(
+            account,
+            pinocchio_token::state::TokenAccount::LEN,
+            ACCOUNT_TYPE_ACCOUNT,
+        )
+    }
 
-        if account
-            .data_len()
-            .ne(&pinocchio_token::state::TokenAccount::LEN)
-        {
-            return Err(PinocchioError::InvalidAccountData.into());
+    pub fn amount(account: &AccountInfo) -> Result<u64, ProgramError> {
+        Self::check(account)?;
+        let data = account.try_borrow_data()?;
+        Ok(unsafe { pinocchio_token::state::TokenAccount::from_bytes_unchecked(&data) }.amount())
+    }
+
+    // transfer fees Token-2022 withheld in the account out of the transfers it received, from
+    // its TransferFeeAmount extension; 0 for accounts without one
+    pub fn withheld_fees(account: &AccountInfo) -> Result<u64, ProgramError> {
+        Self::check(account)?;
+        let data = account.try_borrow_data()?;
+
+        // extensions follow the account type byte as (type u16, length u16, value) entries
+        let mut offset = pinocchio_token::state::TokenAccount::LEN + 1;
+        while offset + 4 <= data.len() {
+            let extension = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            let len = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            let value = data
+                .get(offset + 4..offset + 4 + len)
+                .ok_or(PinocchioError::InvalidAccountData)?;
+            match extension {
+                EXTENSION_UNINITIALIZED => break,
+                EXTENSION_TRANSFER_FEE_AMOUNT => {
+                    let withheld = value.get(..8).ok_or(PinocchioError::InvalidAccountData)?;
+                    return Ok(u64::from_le_bytes(withheld.try_into().unwrap()));
+                }
+                _ => offset += 4 + len,
+            }
         }
 
-        Ok(())
+        Ok(0)
     }
 }
 
+const EXTENSION_UNINITIALIZED: u16 = 0;
+const EXTENSION_TRANSFER_FEE_AMOUNT: u16 = 2;
+// TransferFeeExtension, HarvestWithheldTokensToMint
+const HARVEST_WITHHELD_TOKENS_TO_MINT: [u8; 2] = [26, 4];
+
+// Token-2022 refuses to close an account still holding withheld transfer fees, so every vault
+// is emptied of them before CloseAccount. Harvesting is permissionless and moves the fees to
+// the mint, where the fee authority collects them as usual; the mint of a fee-bearing vault
+// therefore has to be passed writable
+pub fn harvest_withheld_fees(vault: &AccountInfo, mint: &AccountInfo) -> ProgramResult {
+    if TokenAccount::withheld_fees(vault)?.eq(&0) {
+        return Ok(());
+    }
+
+    invoke(
+        &Instruction {
+            program_id: &pinocchio_token_2022::ID,
+            accounts: &[
+                AccountMeta::writable(mint.key()),
+                AccountMeta::writable(vault.key()),
+            ],
+            data: &HARVEST_WITHHELD_TOKENS_TO_MINT,
+        },
+        &[mint, vault],
+    )
+}
+
 pub struct AssociatedTokenAccount;
 
 impl AssociatedTokenAccount {
@@ -143,7 +256,17 @@ impl AssociatedTokenAccount {
         token_program: &AccountInfo,
     ) -> Result<(), ProgramError> {
         TokenAccount::check(account)?;
+        Self::check_address(account, authority, mint, token_program)
+    }
 
+    // address only, for a vault that is about to be created: it has to be the ATA of the
+    // escrow (or counteroffer) PDA, never an account the maker still controls
+    pub fn check_address(
+        account: &AccountInfo,
+        authority: &AccountInfo,
+        mint: &AccountInfo,
+        token_program: &AccountInfo,
+    ) -> Result<(), ProgramError> {
         if find_program_address(
             &[authority.key(), token_program.key(), mint.key()],
             &pinocchio_associated_token_account::ID,
@@ -205,11 +328,32 @@ impl AssociatedTokenAccount {
         system_program: &AccountInfo,
         token_program: &AccountInfo,
     ) -> ProgramResult {
-        match Self::check(account, payer, mint, token_program) {
+        match Self::check(account, owner, mint, token_program) {
             Ok(_) => Ok(()),
-            Err(_) => Self::init(account, mint, payer, owner, system_program, token_program),
+            Err(_) => Self::init_idempotent(account, mint, payer, owner, system_program, token_program),
         }
     }
+
+    // an earlier instruction in the same transaction may have created the ATA already,
+    // so let the ATA program treat an existing account as success
+    pub fn init_idempotent(
+        account: &AccountInfo,
+        mint: &AccountInfo,
+        payer: &AccountInfo,
+        owner: &AccountInfo,
+        system_program: &AccountInfo,
+        token_program: &AccountInfo,
+    ) -> ProgramResult {
+        CreateIdempotent {
+            funding_account: payer,
+            account,
+            wallet: owner,
+            mint,
+            system_program,
+            token_program,
+        }
+        .invoke()
+    }
 }
 
 pub struct ProgramAccount;
@@ -228,6 +372,84 @@ impl ProgramAccount {
     }
 }
 
+pub struct OrderBookAccount;
+
+impl OrderBookAccount {
+    pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
+        if !account.is_owned_by(&crate::ID) {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+
+        if account.data_len().ne(&crate::state::OrderBook::LEN) {
+            return Err(PinocchioError::InvalidAccountData.into());
+        }
+
+        Ok(())
+    }
+}
+
+pub struct CounterofferAccount;
+
+impl CounterofferAccount {
+    pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
+        if !account.is_owned_by(&crate::ID) {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+
+        if account.data_len().ne(&crate::state::Counteroffer::LEN) {
+            return Err(PinocchioError::InvalidAccountData.into());
+        }
+
+        Ok(())
+    }
+}
+
+// a new expiry must be a positive unix timestamp in (now, now + `Escrow::MAX_OFFER_DURATION`];
+// negative values are refused before the clock is read so i64::MIN can never be stored
+pub fn check_offer_expiry(expiry: i64) -> Result<(), ProgramError> {
+    if expiry.is_negative() {
+        return Err(PinocchioError::InvalidExpiry.into());
+    }
+
+    validate_expiration(Clock::get()?.unix_timestamp, expiry)
+}
+
+// offers are meant to sit on the book, so the horizon is the offer duration rather than the
+// day the AMM and vault allow for transaction deadlines
+pub const MAX_EXPIRATION_HORIZON_SECONDS: i64 = crate::Escrow::MAX_OFFER_DURATION;
+
+// `expiration` must be strictly after `now` and at most the horizon past it
+pub fn validate_expiration(now: i64, expiration: i64) -> Result<(), ProgramError> {
+    if expiration <= now || expiration > now.saturating_add(MAX_EXPIRATION_HORIZON_SECONDS) {
+        return Err(PinocchioError::InvalidExpiry.into());
+    }
+
+    Ok(())
+}
+
+// most escrows a single batch instruction may touch, each entry costs a few CPIs
+pub const MAX_BATCH: usize = 8;
+
+// every batch instruction checks its entry count with this before doing any work, so an
+// oversized batch fails up front instead of running out of compute part way through
+pub fn check_batch_len(len: usize) -> Result<(), ProgramError> {
+    if len.eq(&0) {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if len.gt(&MAX_BATCH) {
+        return Err(PinocchioError::BatchTooLarge.into());
+    }
+    Ok(())
+}
+
+// optional accounts are passed as the program id when absent
+pub fn optional_account(account: &AccountInfo) -> Option<&AccountInfo> {
+    match account.key().eq(&crate::ID) {
+        true => None,
+        false => Some(account),
+    }
+}
+
 pub trait ProgramAccountInit {
     fn init<'a, T: Sized>(
         payer: &AccountInfo,
@@ -261,19 +483,145 @@ impl ProgramAccount {
     }
 }
 
-pub trait AccountClose {
-    fn close(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult;
+// zeroes the data so nothing can be read back within the transaction, then hands every
+// lamport to `destination` and closes the account; kept in step with the flash loan's copy
+pub fn close_program_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    account.try_borrow_mut_data()?.fill(0);
+
+    let lamports = destination
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    *destination.try_borrow_mut_lamports()? = lamports;
+    *account.try_borrow_mut_lamports()? = 0;
+
+    account.close()
 }
 
-impl ProgramAccount {
-    pub fn close(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
-        {
-            let mut data = account.try_borrow_mut_data()?;
-            data[0] = 0xff;
+// escrow state on top of the shared test account
+#[cfg(test)]
+impl crate::RawAccount {
+    // an escrow account of the program, its state filled in by `set`
+    pub(crate) fn escrow(set: impl FnOnce(&mut crate::Escrow)) -> Self {
+        let mut raw = Self::new(false, &crate::ID, &[0u8; crate::Escrow::LEN]);
+        set(crate::Escrow::load_mut(&mut raw.bytes()[88..88 + crate::Escrow::LEN]).unwrap());
+        raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RawAccount;
+
+    #[test]
+    fn close_program_account_moves_every_lamport() {
+        let mut account = RawAccount::new(false, &crate::ID, &[7u8; 64]).with_lamports(1_500_000);
+        let mut destination = RawAccount::new(true, &[0u8; 32], &[]).with_lamports(2_000_000);
+        let (account, destination) = (account.info(), destination.info());
+
+        close_program_account(&account, &destination).unwrap();
+
+        assert_eq!(destination.lamports(), 3_500_000);
+        assert_eq!(account.lamports(), 0);
+        // the runtime only drops the data at the end of the instruction, until then it reads zero
+        let data = unsafe { account.borrow_data_unchecked() };
+        assert!(data.iter().all(|b| b.eq(&0)));
+    }
+
+    #[test]
+    fn close_program_account_refuses_to_overflow() {
+        let mut account = RawAccount::new(false, &crate::ID, &[7u8; 8]).with_lamports(2);
+        let mut destination = RawAccount::new(true, &[0u8; 32], &[]).with_lamports(u64::MAX - 1);
+        let (account, destination) = (account.info(), destination.info());
+
+        assert_eq!(
+            close_program_account(&account, &destination),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+        // nothing moved, the lamports still add up
+        assert_eq!(destination.lamports(), u64::MAX - 1);
+        assert_eq!(account.lamports(), 2);
+    }
+
+    #[test]
+    fn batch_len_boundaries() {
+        assert_eq!(check_batch_len(0), Err(ProgramError::NotEnoughAccountKeys));
+        assert_eq!(check_batch_len(1), Ok(()));
+        assert_eq!(check_batch_len(MAX_BATCH), Ok(()));
+        assert_eq!(
+            check_batch_len(MAX_BATCH + 1),
+            Err(PinocchioError::BatchTooLarge.into())
+        );
+    }
+
+    const TOKEN_ACCOUNT_LEN: usize = pinocchio_token::state::TokenAccount::LEN;
+
+    // a Token-2022 token account holding `amount`, followed by the given extension entries
+    fn token_2022_account(amount: u64, extensions: &[(u16, &[u8])]) -> ([u8; 320], usize) {
+        let mut data = [0u8; 320];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data[TOKEN_ACCOUNT_LEN] = ACCOUNT_TYPE_ACCOUNT;
+        let mut len = TOKEN_ACCOUNT_LEN + 1;
+        for (extension, value) in extensions {
+            data[len..len + 2].copy_from_slice(&extension.to_le_bytes());
+            data[len + 2..len + 4].copy_from_slice(&(value.len() as u16).to_le_bytes());
+            data[len + 4..len + 4 + value.len()].copy_from_slice(value);
+            len += 4 + value.len();
         }
+        (data, len)
+    }
 
-        *destination.try_borrow_mut_lamports()? += *account.try_borrow_lamports()?;
-        account.resize( 1)?;
-        account.close()
+    #[test]
+    fn fee_bearing_vaults_report_what_they_hold_and_withheld() {
+        // ImmutableOwner (7, empty) ahead of TransferFeeAmount, as the ATA program lays it out
+        let (data, len) = token_2022_account(
+            990,
+            &[
+                (7, &[]),
+                (EXTENSION_TRANSFER_FEE_AMOUNT, &10u64.to_le_bytes()),
+            ],
+        );
+        let mut vault = RawAccount::new(false, &pinocchio_token_2022::ID, &data[..len]);
+
+        // the balance is already net of the fee, the fee itself sits apart until harvested
+        assert_eq!(TokenAccount::amount(&vault.info()), Ok(990));
+        assert_eq!(TokenAccount::withheld_fees(&vault.info()), Ok(10));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn vaults_without_a_transfer_fee_have_nothing_withheld() {
+        let mut classic = RawAccount::new(false, &pinocchio_token::ID, &[0u8; TOKEN_ACCOUNT_LEN]);
+        assert_eq!(TokenAccount::withheld_fees(&classic.info()), Ok(0));
+
+        let mut base = RawAccount::new(false, &pinocchio_token_2022::ID, &[0u8; TOKEN_ACCOUNT_LEN]);
+        assert_eq!(TokenAccount::withheld_fees(&base.info()), Ok(0));
+
+        let (data, len) = token_2022_account(5, &[(7, &[])]);
+        let mut immutable_owner = RawAccount::new(false, &pinocchio_token_2022::ID, &data[..len]);
+        assert_eq!(TokenAccount::withheld_fees(&immutable_owner.info()), Ok(0));
+
+        // nothing withheld means there is nothing to harvest, so no CPI is made
+        assert_eq!(
+            harvest_withheld_fees(&immutable_owner.info(), &base.info()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn withheld_fees_refuse_a_truncated_extension() {
+        let (data, len) = token_2022_account(5, &[(EXTENSION_TRANSFER_FEE_AMOUNT, &[0u8; 8])]);
+        let mut vault = RawAccount::new(false, &pinocchio_token_2022::ID, &data[..len - 1]);
+        assert_eq!(
+            TokenAccount::withheld_fees(&vault.info()),
+            Err(PinocchioError::InvalidAccountData.into())
+        );
+
+        let (data, len) = token_2022_account(5, &[(EXTENSION_TRANSFER_FEE_AMOUNT, &[0u8; 4])]);
+        let mut vault = RawAccount::new(false, &pinocchio_token_2022::ID, &data[..len]);
+        assert_eq!(
+            TokenAccount::withheld_fees(&vault.info()),
+            Err(PinocchioError::InvalidAccountData.into())
+        );
+    }
+}
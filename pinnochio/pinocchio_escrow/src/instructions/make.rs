@@ -2,9 +2,13 @@ use pinocchio::{
     account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
     pubkey::find_program_address, ProgramResult,
 };
-use pinocchio_token::instructions::Transfer;
+use pinocchio_token_2022::instructions::TransferChecked;
 
-use crate::{AssociatedTokenAccount, Escrow, MintAccount, ProgramAccount, SignerAccount};
+use crate::{
+    check_offer_expiry, insert_order, optional_account, parse_listing, AssociatedTokenAccount,
+    ConditionType, Escrow, MintAccount, PinocchioError, ProgramAccount, SignerAccount,
+    TokenAccount, TokenProgram,
+};
 
 pub struct MakeAccounts<'a> {
     pub maker: &'a AccountInfo,
@@ -15,22 +19,28 @@ pub struct MakeAccounts<'a> {
     pub vault: &'a AccountInfo,
     pub system_program: &'a AccountInfo,
     pub token_program: &'a AccountInfo,
+    // order_book, prev, next when the offer should be listed
+    pub listing: Option<[&'a AccountInfo; 3]>,
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for MakeAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [maker, escrow, mint_a, mint_b, maker_ata_a, vault, system_program, token_program, _] =
+        let [maker, escrow, mint_a, mint_b, maker_ata_a, vault, system_program, token_program, _, listing @ ..] =
             accounts
         else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
 
+        let listing = parse_listing(listing)?;
+
         SignerAccount::check(maker)?;
+        TokenProgram::check(token_program)?;
         MintAccount::check(mint_a)?;
         MintAccount::check(mint_b)?;
         AssociatedTokenAccount::check(maker_ata_a, maker, mint_a, token_program)?;
+        AssociatedTokenAccount::check_address(vault, escrow, mint_a, token_program)?;
 
         Ok(Self {
             maker,
@@ -41,6 +51,7 @@ impl<'a> TryFrom<&'a [AccountInfo]> for MakeAccounts<'a> {
             vault,
             system_program,
             token_program,
+            listing,
         })
     }
 }
@@ -49,13 +60,42 @@ pub struct MakeInstructionData {
     pub seed: u64,
     pub receive: u64,
     pub amount: u64,
+    pub expiry: i64,
+    pub condition_type: ConditionType,
+    pub condition_data: [u8; 64],
 }
 
 impl<'a> TryFrom<&'a [u8]> for MakeInstructionData {
     type Error = ProgramError;
 
     fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
-        if data.len() != size_of::<u64>() * 3 {
+        // the trailing expiry is optional, offers without one never expire; a release
+        // condition (type u8 | data [u8; 64]) may follow the expiry
+        let expiry = match data.len() {
+            24 => 0,
+            32 | 97 => i64::from_le_bytes(data[24..32].try_into().unwrap()),
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+
+        let (condition_type, condition_data): (ConditionType, [u8; 64]) = match data.get(32..) {
+            Some([condition_type, condition_data @ ..]) => (
+                ConditionType::try_from(*condition_type)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?,
+                condition_data.try_into().unwrap(),
+            ),
+            _ => (ConditionType::None, [0u8; 64]),
+        };
+
+        // a price condition needs a feed to read and a tolerance of at most 100%
+        if condition_type == ConditionType::OraclePrice
+            && (condition_data[..32].eq(&[0u8; 32])
+                || u16::from_le_bytes(condition_data[32..34].try_into().unwrap()) > 10_000)
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        // a flag condition needs an account to read it from
+        if condition_type == ConditionType::AccountFlag && condition_data[..32].eq(&[0u8; 32]) {
             return Err(ProgramError::InvalidInstructionData);
         }
 
@@ -67,10 +107,17 @@ impl<'a> TryFrom<&'a [u8]> for MakeInstructionData {
             return Err(ProgramError::InvalidInstructionData);
         }
 
+        if expiry.ne(&0) {
+            check_offer_expiry(expiry)?;
+        }
+
         Ok(Self {
             seed,
             receive,
             amount,
+            expiry,
+            condition_type,
+            condition_data,
         })
     }
 }
@@ -145,15 +192,119 @@ impl<'a> Make<'a> {
             self.instruction_data.receive,
             [self.bump],
         );
+        escrow.set_expiry(self.instruction_data.expiry);
+        escrow.set_amount(self.instruction_data.amount);
+        escrow.set_condition(
+            self.instruction_data.condition_type,
+            self.instruction_data.condition_data,
+        );
+        if self.accounts.listing.is_some() && !escrow.listable() {
+            return Err(PinocchioError::ConditionalListing.into());
+        }
 
-        Transfer {
+        TransferChecked {
             from: self.accounts.maker_ata_a,
+            mint: self.accounts.mint_a,
             to: self.accounts.vault,
             authority: self.accounts.maker,
             amount: self.instruction_data.amount,
+            decimals: MintAccount::decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program.key(),
         }
         .invoke()?;
+        escrow.set_deposited(TokenAccount::amount(self.accounts.vault)?);
+
+        if let Some([order_book, prev, next]) = self.accounts.listing {
+            insert_order(
+                order_book,
+                self.accounts.escrow.key(),
+                escrow,
+                optional_account(prev),
+                optional_account(next),
+            )?;
+        }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate_expiration, RawAccount};
+
+    // seed | receive | amount | expiry
+    fn data(expiry: i64) -> [u8; 32] {
+        let mut data = [0u8; 32];
+        data[16..24].copy_from_slice(&100u64.to_le_bytes());
+        data[24..].copy_from_slice(&expiry.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn the_vault_must_be_the_escrows_ata() {
+        let (maker, escrow, mint_a) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        let ata = |authority: &[u8; 32]| {
+            crate::find_program_address(
+                &[authority, &pinocchio_token::ID, &mint_a],
+                &pinocchio_associated_token_account::ID,
+            )
+            .0
+        };
+        let mut accounts = [escrow, mint_a, pinocchio_token::ID]
+            .map(|key| RawAccount::new(false, &[0u8; 32], &[]).with_key(&key));
+        let [escrow, mint_a, token_program] = accounts.each_mut().map(|account| account.info());
+
+        let mut vault =
+            RawAccount::new(false, &pinocchio_token::ID, &[]).with_key(&ata(escrow.key()));
+        assert_eq!(
+            AssociatedTokenAccount::check_address(&vault.info(), &escrow, &mint_a, &token_program),
+            Ok(())
+        );
+
+        // the maker's own ATA would leave the escrowed tokens under the maker's control
+        let mut vault = RawAccount::new(false, &pinocchio_token::ID, &[]).with_key(&ata(&maker));
+        assert_eq!(
+            AssociatedTokenAccount::check_address(&vault.info(), &escrow, &mint_a, &token_program),
+            Err(PinocchioError::InvalidAddress.into())
+        );
+    }
+
+    #[test]
+    fn negative_expiries_are_refused_before_the_clock_is_read() {
+        for expiry in [i64::MIN, -1] {
+            assert_eq!(
+                MakeInstructionData::try_from(&data(expiry)[..]).err(),
+                Some(PinocchioError::InvalidExpiry.into())
+            );
+        }
+        // no expiry at all, trailing or zero, never expires
+        assert_eq!(
+            MakeInstructionData::try_from(&data(0)[..]).unwrap().expiry,
+            0
+        );
+        assert_eq!(
+            MakeInstructionData::try_from(&data(0)[..24])
+                .unwrap()
+                .expiry,
+            0
+        );
+    }
+
+    #[test]
+    fn expiries_must_fall_within_the_offer_duration() {
+        let now = 1_700_000_000;
+        assert_eq!(validate_expiration(now, now + 3_600), Ok(()));
+        assert_eq!(
+            validate_expiration(now, now + Escrow::MAX_OFFER_DURATION),
+            Ok(())
+        );
+
+        for expiry in [now, now + Escrow::MAX_OFFER_DURATION + 1, i64::MAX] {
+            assert_eq!(
+                validate_expiration(now, expiry),
+                Err(PinocchioError::InvalidExpiry.into())
+            );
+        }
+    }
+}
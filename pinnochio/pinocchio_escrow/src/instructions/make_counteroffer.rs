@@ -0,0 +1,221 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::find_program_address, ProgramResult,
+};
+use pinocchio_token_2022::instructions::TransferChecked;
+
+use crate::{
+    AssociatedTokenAccount, Counteroffer, Escrow, MintAccount, PinocchioError, ProgramAccount,
+    SignerAccount, TokenProgram,
+};
+
+pub struct MakeCounterofferAccounts<'a> {
+    pub taker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub counteroffer: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub taker_ata_b: &'a AccountInfo,
+    pub counteroffer_vault: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MakeCounterofferAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [taker, escrow, counteroffer, mint_b, taker_ata_b, counteroffer_vault, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(taker)?;
+        TokenProgram::check(token_program)?;
+        ProgramAccount::check(escrow)?;
+        MintAccount::check(mint_b)?;
+        AssociatedTokenAccount::check(taker_ata_b, taker, mint_b, token_program)?;
+        AssociatedTokenAccount::check_address(
+            counteroffer_vault,
+            counteroffer,
+            mint_b,
+            token_program,
+        )?;
+
+        Ok(Self {
+            taker,
+            escrow,
+            counteroffer,
+            mint_b,
+            taker_ata_b,
+            counteroffer_vault,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct MakeCounterofferInstructionData {
+    pub give: u64,
+    pub receive: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for MakeCounterofferInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let give = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let receive = u64::from_le_bytes(data[8..16].try_into().unwrap());
+
+        if give == 0 || receive == 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self { give, receive })
+    }
+}
+
+pub struct MakeCounteroffer<'a> {
+    pub accounts: MakeCounterofferAccounts<'a>,
+    pub instruction_data: MakeCounterofferInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for MakeCounteroffer<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = MakeCounterofferAccounts::try_from(accounts)?;
+        let instruction_data = MakeCounterofferInstructionData::try_from(data)?;
+
+        {
+            let escrow_data = accounts.escrow.try_borrow_data()?;
+            let escrow = Escrow::load(&escrow_data)?;
+            if escrow.mint_b.ne(accounts.mint_b.key()) {
+                return Err(PinocchioError::InvalidAccountData.into());
+            }
+        }
+
+        let (_, bump) = find_program_address(
+            &[b"counteroffer", accounts.escrow.key(), accounts.taker.key()],
+            &crate::ID,
+        );
+
+        let bump_binding = [bump];
+        let counteroffer_seeds = [
+            Seed::from(b"counteroffer"),
+            Seed::from(accounts.escrow.key().as_ref()),
+            Seed::from(accounts.taker.key().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+
+        ProgramAccount::init::<Counteroffer>(
+            accounts.taker,
+            accounts.counteroffer,
+            &counteroffer_seeds,
+            Counteroffer::LEN,
+        )?;
+
+        AssociatedTokenAccount::init(
+            accounts.counteroffer_vault,
+            accounts.mint_b,
+            accounts.taker,
+            accounts.counteroffer,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> MakeCounteroffer<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &7;
+
+    /// Proposes different terms for an escrow: the taker locks `give` of mint_b in a vault
+    /// owned by the counteroffer PDA and asks for `receive` of mint_a in return. The maker
+    /// settles it with `AcceptCounteroffer`, the taker can pull out with `WithdrawCounteroffer`.
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.counteroffer.try_borrow_mut_data()?;
+        let counteroffer = Counteroffer::load_mut(data.as_mut())?;
+
+        counteroffer.set_inner(
+            *self.accounts.escrow.key(),
+            *self.accounts.taker.key(),
+            self.instruction_data.give,
+            self.instruction_data.receive,
+            [self.bump],
+        );
+
+        TransferChecked {
+            from: self.accounts.taker_ata_b,
+            mint: self.accounts.mint_b,
+            to: self.accounts.counteroffer_vault,
+            authority: self.accounts.taker,
+            amount: self.instruction_data.give,
+            decimals: MintAccount::decimals(self.accounts.mint_b)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(give: u64, receive: u64) -> [u8; 16] {
+        let mut data = [0u8; 16];
+        data[..8].copy_from_slice(&give.to_le_bytes());
+        data[8..].copy_from_slice(&receive.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn parses_the_proposed_terms() {
+        let parsed = MakeCounterofferInstructionData::try_from(&data(500, 1_000)[..]).unwrap();
+        assert_eq!(parsed.give, 500);
+        assert_eq!(parsed.receive, 1_000);
+    }
+
+    #[test]
+    fn rejects_empty_terms() {
+        for (give, receive) in [(0, 1_000), (500, 0)] {
+            assert!(MakeCounterofferInstructionData::try_from(&data(give, receive)[..]).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_lengths() {
+        assert!(MakeCounterofferInstructionData::try_from(&data(500, 1_000)[..15]).is_err());
+        assert!(MakeCounterofferInstructionData::try_from(&[0u8; 17][..]).is_err());
+    }
+
+    #[test]
+    fn records_the_proposal() {
+        // account data is 8-byte aligned on chain, the backing buffer mirrors that
+        let mut backing = [0u64; Counteroffer::LEN.div_ceil(8)];
+        let bytes = unsafe {
+            core::slice::from_raw_parts_mut(backing.as_mut_ptr() as *mut u8, Counteroffer::LEN)
+        };
+
+        Counteroffer::load_mut(bytes)
+            .unwrap()
+            .set_inner([1u8; 32], [2u8; 32], 500, 1_000, [254]);
+
+        let counteroffer = Counteroffer::load(bytes).unwrap();
+        assert_eq!(counteroffer.escrow, [1u8; 32]);
+        assert_eq!(counteroffer.taker, [2u8; 32]);
+        assert_eq!(counteroffer.give, 500);
+        assert_eq!(counteroffer.receive, 1_000);
+        assert_eq!(counteroffer.bump, [254]);
+    }
+}
@@ -0,0 +1,256 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{
+    close_program_account, harvest_withheld_fees, optional_account, unlink_order,
+    AssociatedTokenAccount, Escrow, MintAccount, OrderBook, OrderBookAccount, PinocchioError,
+    ProgramAccount, Side, SignerAccount, TokenAccount, TokenProgram,
+};
+
+pub struct MatchOrdersAccounts<'a> {
+    pub cranker: &'a AccountInfo,
+    pub order_book: &'a AccountInfo,
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub ask: &'a AccountInfo,
+    pub ask_maker: &'a AccountInfo,
+    pub ask_vault: &'a AccountInfo,
+    pub ask_maker_ata_y: &'a AccountInfo,
+    pub ask_next: &'a AccountInfo,
+    pub bid: &'a AccountInfo,
+    pub bid_maker: &'a AccountInfo,
+    pub bid_vault: &'a AccountInfo,
+    pub bid_maker_ata_x: &'a AccountInfo,
+    pub bid_next: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MatchOrdersAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [cranker, order_book, mint_x, mint_y, ask, ask_maker, ask_vault, ask_maker_ata_y, ask_next, bid, bid_maker, bid_vault, bid_maker_ata_x, bid_next, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(cranker)?;
+        OrderBookAccount::check(order_book)?;
+        TokenProgram::check(token_program)?;
+        MintAccount::check(mint_x)?;
+        MintAccount::check(mint_y)?;
+        ProgramAccount::check(ask)?;
+        ProgramAccount::check(bid)?;
+        AssociatedTokenAccount::check(ask_vault, ask, mint_x, token_program)?;
+        AssociatedTokenAccount::check(bid_vault, bid, mint_y, token_program)?;
+
+        Ok(Self {
+            cranker,
+            order_book,
+            mint_x,
+            mint_y,
+            ask,
+            ask_maker,
+            ask_vault,
+            ask_maker_ata_y,
+            ask_next,
+            bid,
+            bid_maker,
+            bid_vault,
+            bid_maker_ata_x,
+            bid_next,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct MatchOrders<'a> {
+    pub accounts: MatchOrdersAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MatchOrders<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = MatchOrdersAccounts::try_from(accounts)?;
+
+        // the cranker fronts rent for any missing receiving account
+        AssociatedTokenAccount::init_if_needed(
+            accounts.ask_maker_ata_y,
+            accounts.mint_y,
+            accounts.cranker,
+            accounts.ask_maker,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        AssociatedTokenAccount::init_if_needed(
+            accounts.bid_maker_ata_x,
+            accounts.mint_x,
+            accounts.cranker,
+            accounts.bid_maker,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> MatchOrders<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &4;
+
+    /// Settles the best ask against the best bid. Escrows are all-or-nothing, so the
+    /// orders cross only when each vault covers what the other side asks for; each maker
+    /// then receives the other's whole vault.
+    pub fn process(&mut self) -> ProgramResult {
+        {
+            let book_data = self.accounts.order_book.try_borrow_data()?;
+            let book = OrderBook::load(&book_data)?;
+            if book.mint_x.ne(self.accounts.mint_x.key()) || book.mint_y.ne(self.accounts.mint_y.key())
+            {
+                return Err(PinocchioError::InvalidAccountData.into());
+            }
+            if book.best(Side::Ask).ne(&Some(self.accounts.ask.key()))
+                || book.best(Side::Bid).ne(&Some(self.accounts.bid.key()))
+            {
+                return Err(PinocchioError::InvalidOrderPosition.into());
+            }
+        }
+
+        let ask_data = self.accounts.ask.try_borrow_data()?;
+        let ask = Escrow::load(&ask_data)?;
+        check_escrow_address(self.accounts.ask, self.accounts.ask_maker, ask)?;
+
+        let bid_data = self.accounts.bid.try_borrow_data()?;
+        let bid = Escrow::load(&bid_data)?;
+        check_escrow_address(self.accounts.bid, self.accounts.bid_maker, bid)?;
+
+        let ask_amount = TokenAccount::amount(self.accounts.ask_vault)?;
+        let bid_amount = TokenAccount::amount(self.accounts.bid_vault)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        if ask.is_expired(now) || bid.is_expired(now) {
+            return Err(PinocchioError::OfferExpired.into());
+        }
+        if !ask.condition_met(now, None)? || !bid.condition_met(now, None)? {
+            return Err(PinocchioError::Unauthorized.into());
+        }
+
+        if bid_amount < ask.receive || ask_amount < bid.receive {
+            return Err(PinocchioError::OrdersDoNotCross.into());
+        }
+
+        unlink_order(
+            self.accounts.order_book,
+            ask,
+            None,
+            optional_account(self.accounts.ask_next),
+        )?;
+        unlink_order(
+            self.accounts.order_book,
+            bid,
+            None,
+            optional_account(self.accounts.bid_next),
+        )?;
+
+        settle(
+            self.accounts.ask,
+            self.accounts.ask_maker,
+            ask,
+            self.accounts.ask_vault,
+            self.accounts.mint_x,
+            self.accounts.bid_maker_ata_x,
+            self.accounts.token_program,
+            ask_amount,
+        )?;
+        settle(
+            self.accounts.bid,
+            self.accounts.bid_maker,
+            bid,
+            self.accounts.bid_vault,
+            self.accounts.mint_y,
+            self.accounts.ask_maker_ata_y,
+            self.accounts.token_program,
+            bid_amount,
+        )?;
+
+        drop(ask_data);
+        drop(bid_data);
+        close_program_account(self.accounts.ask, self.accounts.ask_maker)?;
+        close_program_account(self.accounts.bid, self.accounts.bid_maker)?;
+
+        Ok(())
+    }
+}
+
+fn check_escrow_address(account: &AccountInfo, maker: &AccountInfo, escrow: &Escrow) -> ProgramResult {
+    let escrow_key = create_program_address(
+        &[
+            b"escrow",
+            maker.key(),
+            &escrow.seed.to_le_bytes(),
+            &escrow.bump,
+        ],
+        &crate::ID,
+    )?;
+    if &escrow_key != account.key() {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    Ok(())
+}
+
+// empties `vault` into the counterparty and returns the vault rent to the maker
+#[allow(clippy::too_many_arguments)]
+fn settle(
+    account: &AccountInfo,
+    maker: &AccountInfo,
+    escrow: &Escrow,
+    vault: &AccountInfo,
+    mint: &AccountInfo,
+    to: &AccountInfo,
+    token_program: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    let seed_binding = escrow.seed.to_le_bytes();
+    let bump_binding = escrow.bump;
+    let escrow_seeds = [
+        Seed::from(b"escrow"),
+        Seed::from(maker.key().as_ref()),
+        Seed::from(&seed_binding),
+        Seed::from(&bump_binding),
+    ];
+    let signer = Signer::from(&escrow_seeds);
+
+    TransferChecked {
+        from: vault,
+        mint,
+        to,
+        authority: account,
+        amount,
+        decimals: MintAccount::decimals(mint)?,
+        token_program: token_program.key(),
+    }
+    .invoke_signed(core::slice::from_ref(&signer))?;
+
+    harvest_withheld_fees(vault, mint)?;
+
+    CloseAccount {
+        account: vault,
+        destination: maker,
+        authority: account,
+        token_program: token_program.key(),
+    }
+    .invoke_signed(&[signer])
+}
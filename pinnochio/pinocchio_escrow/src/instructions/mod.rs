@@ -8,4 +8,59 @@ pub mod take;
 pub use take::*;
 
 pub mod refund; 
-pub use refund::*; 
\ No newline at end of file
+pub use refund::*; 
+
+pub mod order_book;
+pub use order_book::*;
+
+pub mod match_orders;
+pub use match_orders::*;
+
+pub mod extend_offer;
+pub use extend_offer::*;
+
+pub mod update_offer_price;
+pub use update_offer_price::*;
+
+pub mod make_counteroffer;
+pub use make_counteroffer::*;
+
+pub mod accept_counteroffer;
+pub use accept_counteroffer::*;
+
+pub mod withdraw_counteroffer;
+pub use withdraw_counteroffer::*;
+
+pub mod delist_stale;
+pub use delist_stale::*;
+
+pub mod refund_batch;
+pub use refund_batch::*;
+
+// one per instruction, each dispatched on its own in lib.rs
+pub const DISCRIMINATORS: &[u8] = &[
+    *Make::DISCRIMINATOR,
+    *Take::DISCRIMINATOR,
+    *Refund::DISCRIMINATOR,
+    *InitOrderBook::DISCRIMINATOR,
+    *MatchOrders::DISCRIMINATOR,
+    *ExtendOffer::DISCRIMINATOR,
+    *UpdateOfferPrice::DISCRIMINATOR,
+    *MakeCounteroffer::DISCRIMINATOR,
+    *AcceptCounteroffer::DISCRIMINATOR,
+    *WithdrawCounteroffer::DISCRIMINATOR,
+    *DelistStale::DISCRIMINATOR,
+    *RefundBatch::DISCRIMINATOR,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discriminators_are_unique() {
+        for (i, discriminator) in DISCRIMINATORS.iter().enumerate() {
+            assert!(!DISCRIMINATORS[i + 1..].contains(discriminator));
+        }
+    }
+}
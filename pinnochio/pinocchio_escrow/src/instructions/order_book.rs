@@ -0,0 +1,224 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    ProgramResult,
+};
+
+use crate::{
+    optional_account, Escrow, MintAccount, OrderBook, OrderBookAccount, PinocchioError, ProgramAccount,
+    SignerAccount,
+};
+
+pub struct InitOrderBookAccounts<'a> {
+    pub payer: &'a AccountInfo,
+    pub order_book: &'a AccountInfo,
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for InitOrderBookAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, order_book, mint_x, mint_y, system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(payer)?;
+        MintAccount::check(mint_x)?;
+        MintAccount::check(mint_y)?;
+
+        // one book per pair, so the mints must come in canonical order
+        if mint_x.key().ge(mint_y.key()) {
+            return Err(PinocchioError::InvalidAccountData.into());
+        }
+
+        Ok(Self {
+            payer,
+            order_book,
+            mint_x,
+            mint_y,
+            system_program,
+        })
+    }
+}
+
+pub struct InitOrderBook<'a> {
+    pub accounts: InitOrderBookAccounts<'a>,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for InitOrderBook<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = InitOrderBookAccounts::try_from(accounts)?;
+
+        let (order_book, bump) = find_program_address(
+            &[
+                b"order_book",
+                accounts.mint_x.key(),
+                accounts.mint_y.key(),
+            ],
+            &crate::ID,
+        );
+        if order_book.ne(accounts.order_book.key()) {
+            return Err(PinocchioError::InvalidAddress.into());
+        }
+
+        let bump_binding = [bump];
+        let order_book_seeds = [
+            Seed::from(b"order_book"),
+            Seed::from(accounts.mint_x.key().as_ref()),
+            Seed::from(accounts.mint_y.key().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+
+        ProgramAccount::init::<OrderBook>(
+            accounts.payer,
+            accounts.order_book,
+            &order_book_seeds,
+            OrderBook::LEN,
+        )?;
+
+        Ok(Self { accounts, bump })
+    }
+}
+
+impl<'a> InitOrderBook<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &3;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.order_book.try_borrow_mut_data()?;
+        OrderBook::load_mut(data.as_mut())?.set_inner(
+            *self.accounts.mint_x.key(),
+            *self.accounts.mint_y.key(),
+            [self.bump],
+        );
+
+        Ok(())
+    }
+}
+
+/// Splits the optional `order_book, prev, next` accounts trailing Make, Take and Refund.
+pub fn parse_listing(accounts: &[AccountInfo]) -> Result<Option<[&AccountInfo; 3]>, ProgramError> {
+    match accounts {
+        [] => Ok(None),
+        [order_book, prev, next] => Ok(Some([order_book, prev, next])),
+        _ => Err(ProgramError::NotEnoughAccountKeys),
+    }
+}
+
+/// Links `escrow` into its side of `order_book` between `prev` and `next`.
+///
+/// The client finds the position off-chain; the program only checks that `prev` ranks at
+/// least as well as the new escrow, that `next` ranks strictly worse (so equal prices keep
+/// time priority) and that the two are adjacent in the list.
+pub fn insert_order(
+    order_book: &AccountInfo,
+    escrow_key: &Pubkey,
+    escrow: &mut Escrow,
+    prev: Option<&AccountInfo>,
+    next: Option<&AccountInfo>,
+) -> ProgramResult {
+    OrderBookAccount::check(order_book)?;
+    let mut book_data = order_book.try_borrow_mut_data()?;
+    let book = OrderBook::load_mut(book_data.as_mut())?;
+    let side = book.side(escrow)?;
+
+    let expected_next = match prev {
+        Some(prev) => {
+            ProgramAccount::check(prev)?;
+            let mut prev_data = prev.try_borrow_mut_data()?;
+            let prev_escrow = Escrow::load_mut(prev_data.as_mut())?;
+            if prev_escrow.order_book().ne(&Some(order_book.key()))
+                || prev_escrow.mint_a.ne(&escrow.mint_a)
+                || escrow.outranks(prev_escrow)
+            {
+                return Err(PinocchioError::InvalidOrderPosition.into());
+            }
+            let expected_next = prev_escrow.next_same_side().copied();
+            prev_escrow.set_next_same_side(Some(escrow_key));
+            expected_next
+        }
+        None => {
+            let expected_next = book.best(side).copied();
+            book.set_best(side, Some(escrow_key));
+            expected_next
+        }
+    };
+
+    if expected_next.as_ref().ne(&next.map(|next| next.key())) {
+        return Err(PinocchioError::InvalidOrderPosition.into());
+    }
+
+    if let Some(next) = next {
+        ProgramAccount::check(next)?;
+        let mut next_data = next.try_borrow_mut_data()?;
+        let next_escrow = Escrow::load_mut(next_data.as_mut())?;
+        if !escrow.outranks(next_escrow) {
+            return Err(PinocchioError::InvalidOrderPosition.into());
+        }
+        next_escrow.set_prev_same_side(Some(escrow_key));
+    }
+
+    escrow.set_order_book(Some(order_book.key()));
+    escrow.set_prev_same_side(prev.map(|prev| prev.key()));
+    escrow.set_next_same_side(next.map(|next| next.key()));
+
+    Ok(())
+}
+
+/// Unlinks `escrow` before it is closed if it was listed on an order book.
+pub fn delist_order(escrow: &Escrow, listing: Option<[&AccountInfo; 3]>) -> ProgramResult {
+    if escrow.order_book().is_none() {
+        return Ok(());
+    }
+    let Some([order_book, prev, next]) = listing else {
+        return Err(PinocchioError::InvalidOrderPosition.into());
+    };
+
+    unlink_order(
+        order_book,
+        escrow,
+        optional_account(prev),
+        optional_account(next),
+    )
+}
+
+/// Removes a listed `escrow` from `order_book`, re-pointing its neighbours (or the book's
+/// best price) past it. `prev` and `next` must be exactly the escrow's current links.
+pub fn unlink_order(
+    order_book: &AccountInfo,
+    escrow: &Escrow,
+    prev: Option<&AccountInfo>,
+    next: Option<&AccountInfo>,
+) -> ProgramResult {
+    if escrow.order_book().ne(&Some(order_book.key()))
+        || escrow.prev_same_side().ne(&prev.map(|prev| prev.key()))
+        || escrow.next_same_side().ne(&next.map(|next| next.key()))
+    {
+        return Err(PinocchioError::InvalidOrderPosition.into());
+    }
+
+    match prev {
+        Some(prev) => {
+            let mut prev_data = prev.try_borrow_mut_data()?;
+            Escrow::load_mut(prev_data.as_mut())?.set_next_same_side(escrow.next_same_side());
+        }
+        None => {
+            let mut book_data = order_book.try_borrow_mut_data()?;
+            let book = OrderBook::load_mut(book_data.as_mut())?;
+            let side = book.side(escrow)?;
+            book.set_best(side, escrow.next_same_side());
+        }
+    }
+
+    if let Some(next) = next {
+        let mut next_data = next.try_borrow_mut_data()?;
+        Escrow::load_mut(next_data.as_mut())?.set_prev_same_side(escrow.prev_same_side());
+    }
+
+    Ok(())
+}
@@ -2,15 +2,16 @@ use pinocchio::{
     account_info::AccountInfo,
     instruction::{Seed, Signer},
     program_error::ProgramError,
-    pubkey::create_program_address,
+    pubkey::{create_program_address, Pubkey},
     ProgramResult,
 };
-use pinocchio_token::{
-    instructions::{CloseAccount, Transfer},
-    state::TokenAccount,
-};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
 
-use crate::{AssociatedTokenAccount, Escrow, MintAccount, ProgramAccount, SignerAccount};
+use crate::{
+    close_program_account, delist_order, harvest_withheld_fees, parse_listing,
+    AssociatedTokenAccount, Escrow, MintAccount, PinocchioError, ProgramAccount, SignerAccount,
+    TokenAccount, TokenProgram,
+};
 
 pub struct RefundAccounts<'a> {
     pub maker: &'a AccountInfo,
@@ -20,19 +21,23 @@ pub struct RefundAccounts<'a> {
     pub maker_ata_a: &'a AccountInfo,
     pub system_program: &'a AccountInfo,
     pub token_program: &'a AccountInfo,
+    pub listing: Option<[&'a AccountInfo; 3]>,
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for RefundAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [maker, escrow, mint_a, vault, maker_ata_a, system_program, token_program, _] =
+        let [maker, escrow, mint_a, vault, maker_ata_a, system_program, token_program, _, listing @ ..] =
             accounts
         else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
 
+        let listing = parse_listing(listing)?;
+
         SignerAccount::check(maker)?;
+        TokenProgram::check(token_program)?;
         ProgramAccount::check(escrow)?;
         MintAccount::check(mint_a)?;
         AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
@@ -45,6 +50,7 @@ impl<'a> TryFrom<&'a [AccountInfo]> for RefundAccounts<'a> {
             maker_ata_a,
             system_program,
             token_program,
+            listing,
         })
     }
 }
@@ -66,6 +72,13 @@ impl<'a> TryFrom<&'a [AccountInfo]> for Refund<'a> {
             accounts.system_program,
             accounts.token_program,
         )?;
+        // whether it existed or was just created, the refund only ever goes to the maker's ATA
+        AssociatedTokenAccount::check(
+            accounts.maker_ata_a,
+            accounts.maker,
+            accounts.mint_a,
+            accounts.token_program,
+        )?;
 
         Ok(Self { accounts })
     }
@@ -74,9 +87,19 @@ impl<'a> TryFrom<&'a [AccountInfo]> for Refund<'a> {
 impl<'a> Refund<'a> {
     pub const DISCRIMINATOR: &'a u8 = &2;
 
+    // Only the recorded maker can refund. The vault is never partially filled, so the
+    // maker gets back whatever it holds and both the vault and the escrow are closed. An
+    // empty vault is closed without a transfer, the rent still goes back to the maker.
     pub fn process(&mut self) -> ProgramResult {
         let data = self.accounts.escrow.try_borrow_data()?;
         let escrow = Escrow::load(&data)?;
+
+        check_refund(
+            escrow,
+            self.accounts.maker.key(),
+            self.accounts.mint_a.key(),
+        )?;
+
         let escrow_key = create_program_address(
             &[
                 b"escrow",
@@ -101,26 +124,131 @@ impl<'a> Refund<'a> {
         ];
         let signer = Signer::from(&escrow_seeds);
 
-        let amount = TokenAccount::from_account_info(self.accounts.vault)?.amount();
-
-        Transfer {
-            from: self.accounts.vault,
-            to: self.accounts.maker_ata_a,
-            authority: self.accounts.escrow,
-            amount,
+        delist_order(escrow, self.accounts.listing)?;
+
+        let amount = TokenAccount::amount(self.accounts.vault)?;
+
+        if amount.ne(&0) {
+            TransferChecked {
+                from: self.accounts.vault,
+                mint: self.accounts.mint_a,
+                to: self.accounts.maker_ata_a,
+                authority: self.accounts.escrow,
+                amount,
+                decimals: MintAccount::decimals(self.accounts.mint_a)?,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke_signed(&[signer.clone()])?;
         }
-        .invoke_signed(&[signer.clone()])?;
+
+        harvest_withheld_fees(self.accounts.vault, self.accounts.mint_a)?;
 
         CloseAccount {
             account: self.accounts.vault,
             destination: self.accounts.maker,
             authority: self.accounts.escrow,
+            token_program: self.accounts.token_program.key(),
         }
         .invoke_signed(&[signer.clone()])?;
 
         drop(data);
-        ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+        close_program_account(self.accounts.escrow, self.accounts.maker)?;
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+// `maker` must be the one who made the offer, and `mint_a` the mint it escrowed. The PDA
+// check in `process` implies the maker, but a wrong maker should say so
+fn check_refund(escrow: &Escrow, maker: &Pubkey, mint_a: &Pubkey) -> ProgramResult {
+    if escrow.maker.ne(maker) {
+        return Err(PinocchioError::InvalidOwner.into());
+    }
+    if escrow.mint_a.ne(mint_a) {
+        return Err(PinocchioError::InvalidAccountData.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RawAccount;
+
+    const MAKER: Pubkey = [1u8; 32];
+    const MINT_A: Pubkey = [2u8; 32];
+
+    #[test]
+    fn maker_refunds_their_own_offer() {
+        let mut escrow = RawAccount::escrow(|escrow| {
+            escrow.set_maker(MAKER);
+            escrow.set_mint_a(MINT_A);
+        });
+        let escrow = escrow.info();
+        let escrow = Escrow::load(unsafe { escrow.borrow_data_unchecked() }).unwrap();
+
+        assert_eq!(check_refund(escrow, &MAKER, &MINT_A), Ok(()));
+        // the maker, but out of some other mint
+        assert_eq!(
+            check_refund(escrow, &MAKER, &[3u8; 32]),
+            Err(PinocchioError::InvalidAccountData.into())
+        );
+    }
+
+    #[test]
+    fn anyone_else_is_refused() {
+        let mut escrow = RawAccount::escrow(|escrow| {
+            escrow.set_maker(MAKER);
+            escrow.set_mint_a(MINT_A);
+        });
+        let mut other = RawAccount::new(true, &[0u8; 32], &[]).with_key(&[9u8; 32]);
+        let mut mint_a = RawAccount::new(false, &pinocchio_token::ID, &[]).with_key(&MINT_A);
+        let (escrow, other, mint_a) = (escrow.info(), other.info(), mint_a.info());
+
+        let mut refund = Refund {
+            accounts: RefundAccounts {
+                maker: &other,
+                escrow: &escrow,
+                mint_a: &mint_a,
+                vault: &other,
+                maker_ata_a: &other,
+                system_program: &other,
+                token_program: &other,
+                listing: None,
+            },
+        };
+        assert_eq!(refund.process(), Err(PinocchioError::InvalidOwner.into()));
+    }
+
+    #[test]
+    fn an_empty_vault_still_returns_the_rent() {
+        // Make failed after creating the vault: nothing to transfer back, only rent to return
+        let mut vault = RawAccount::new(
+            false,
+            &pinocchio_token::ID,
+            &[0u8; pinocchio_token::state::TokenAccount::LEN],
+        );
+        assert_eq!(TokenAccount::amount(&vault.info()), Ok(0));
+
+        let mut escrow =
+            RawAccount::escrow(|escrow| escrow.set_maker(MAKER)).with_lamports(2_500_000);
+        let mut maker = RawAccount::new(true, &[0u8; 32], &[])
+            .with_key(&MAKER)
+            .with_lamports(1_000);
+        let (escrow, maker) = (escrow.info(), maker.info());
+
+        close_program_account(&escrow, &maker).unwrap();
+        assert_eq!((escrow.lamports(), maker.lamports()), (0, 2_501_000));
+    }
+
+    #[test]
+    fn refund_needs_the_makers_signature() {
+        let mut raw: [RawAccount; 8] =
+            core::array::from_fn(|_| RawAccount::new(false, &[0u8; 32], &[]));
+        let accounts = raw.each_mut().map(|account| account.info());
+        assert_eq!(
+            RefundAccounts::try_from(&accounts[..]).err(),
+            Some(PinocchioError::NotSigner.into())
+        );
+    }
+}
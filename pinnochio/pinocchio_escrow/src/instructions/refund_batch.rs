@@ -0,0 +1,116 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{check_batch_len, Refund};
+
+// escrow, mint_a, vault and maker_ata_a of one refund
+pub const REFUND_BATCH_ENTRY_ACCOUNTS: usize = 4;
+
+// splits the per-escrow accounts into entries of `REFUND_BATCH_ENTRY_ACCOUNTS`, refusing a
+// partial entry and anything outside 1..=`MAX_BATCH` entries
+pub fn refund_batch_entries(
+    entries: &[AccountInfo],
+) -> Result<core::slice::ChunksExact<'_, AccountInfo>, ProgramError> {
+    if !entries.len().is_multiple_of(REFUND_BATCH_ENTRY_ACCOUNTS) {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    check_batch_len(entries.len() / REFUND_BATCH_ENTRY_ACCOUNTS)?;
+
+    Ok(entries.chunks_exact(REFUND_BATCH_ENTRY_ACCOUNTS))
+}
+
+pub struct RefundBatch<'a> {
+    pub maker: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub associated_token_program: &'a AccountInfo,
+    pub entries: core::slice::ChunksExact<'a, AccountInfo>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RefundBatch<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, system_program, token_program, associated_token_program, entries @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        Ok(Self {
+            maker,
+            system_program,
+            token_program,
+            associated_token_program,
+            entries: refund_batch_entries(entries)?,
+        })
+    }
+}
+
+impl<'a> RefundBatch<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &11;
+
+    /// Refunds several of the maker's unlisted escrows at once, each exactly as Refund
+    /// would; one failing entry fails the whole batch. Listed escrows need their book
+    /// neighbours and go through Refund one by one.
+    pub fn process(&mut self) -> ProgramResult {
+        for entry in self.entries.by_ref() {
+            let [escrow, mint_a, vault, maker_ata_a] = entry else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let accounts = [
+                *self.maker,
+                *escrow,
+                *mint_a,
+                *vault,
+                *maker_ata_a,
+                *self.system_program,
+                *self.token_program,
+                *self.associated_token_program,
+            ];
+            Refund::try_from(&accounts[..])?.process()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PinocchioError, RawAccount, MAX_BATCH};
+
+    fn entries(count: usize) -> usize {
+        let mut raw: [RawAccount; (MAX_BATCH + 1) * REFUND_BATCH_ENTRY_ACCOUNTS] =
+            core::array::from_fn(|_| RawAccount::new(false, &crate::ID, &[]));
+        let accounts = raw.each_mut().map(|raw| raw.info());
+        refund_batch_entries(&accounts[..count])
+            .map(|entries| entries.len())
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn batches_run_from_one_to_max_entries() {
+        assert_eq!(entries(REFUND_BATCH_ENTRY_ACCOUNTS), 1);
+        assert_eq!(entries(MAX_BATCH * REFUND_BATCH_ENTRY_ACCOUNTS), MAX_BATCH);
+    }
+
+    #[test]
+    fn batches_refuse_no_entries_too_many_or_a_partial_one() {
+        assert_eq!(
+            refund_batch_entries(&[]).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+
+        let mut raw: [RawAccount; (MAX_BATCH + 1) * REFUND_BATCH_ENTRY_ACCOUNTS] =
+            core::array::from_fn(|_| RawAccount::new(false, &crate::ID, &[]));
+        let accounts = raw.each_mut().map(|raw| raw.info());
+        assert_eq!(
+            refund_batch_entries(&accounts).err(),
+            Some(PinocchioError::BatchTooLarge.into())
+        );
+        assert_eq!(
+            refund_batch_entries(&accounts[..REFUND_BATCH_ENTRY_ACCOUNTS + 1]).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+}
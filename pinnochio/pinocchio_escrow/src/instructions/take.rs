@@ -3,14 +3,16 @@ use pinocchio::{
     instruction::{Seed, Signer},
     program_error::ProgramError,
     pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
     ProgramResult,
 };
-use pinocchio_token::{
-    instructions::{CloseAccount, Transfer},
-    state::TokenAccount,
-};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
 
-use crate::{AssociatedTokenAccount, Escrow, MintAccount, ProgramAccount, SignerAccount};
+use crate::{
+    close_program_account, delist_order, harvest_withheld_fees, parse_listing,
+    AssociatedTokenAccount, Escrow, MintAccount, PinocchioError, ProgramAccount, SignerAccount,
+    TokenAccount, TokenProgram,
+};
 
 pub struct TakeAccounts<'a> {
     pub taker: &'a AccountInfo,
@@ -24,19 +26,30 @@ pub struct TakeAccounts<'a> {
     pub maker_ata_b: &'a AccountInfo,
     pub system_program: &'a AccountInfo,
     pub token_program: &'a AccountInfo,
+    pub listing: Option<[&'a AccountInfo; 3]>,
+    pub condition: Option<&'a AccountInfo>,
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for TakeAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [taker, maker, escrow, mint_a, mint_b, vault, taker_ata_a, taker_ata_b, maker_ata_b, system_program, token_program, _] =
+        let [taker, maker, escrow, mint_a, mint_b, vault, taker_ata_a, taker_ata_b, maker_ata_b, system_program, token_program, _, rest @ ..] =
             accounts
         else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
 
+        // an escrow gated on an account (price feed or flag) takes it ahead of the optional
+        // listing accounts
+        let (condition, listing) = match rest {
+            [condition] | [condition, _, _, _] => (Some(condition), &rest[1..]),
+            _ => (None, rest),
+        };
+        let listing = parse_listing(listing)?;
+
         SignerAccount::check(taker)?;
+        TokenProgram::check(token_program)?;
         ProgramAccount::check(escrow)?;
         MintAccount::check(mint_a)?;
         MintAccount::check(mint_b)?;
@@ -55,19 +68,41 @@ impl<'a> TryFrom<&'a [AccountInfo]> for TakeAccounts<'a> {
             vault,
             system_program,
             token_program,
+            listing,
+            condition,
         })
     }
 }
 
+pub struct TakeInstructionData {
+    pub min_receive_a: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for TakeInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let min_receive_a = u64::from_le_bytes(data.try_into().unwrap());
+
+        Ok(Self { min_receive_a })
+    }
+}
+
 pub struct Take<'a> {
     pub accounts: TakeAccounts<'a>,
+    pub instruction_data: TakeInstructionData,
 }
 
-impl<'a> TryFrom<&'a [AccountInfo]> for Take<'a> {
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Take<'a> {
     type Error = ProgramError;
 
-    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
         let accounts = TakeAccounts::try_from(accounts)?;
+        let instruction_data = TakeInstructionData::try_from(data)?;
 
         AssociatedTokenAccount::init_if_needed(
             accounts.taker_ata_a,
@@ -87,7 +122,10 @@ impl<'a> TryFrom<&'a [AccountInfo]> for Take<'a> {
             accounts.token_program,
         )?;
 
-        Ok(Self { accounts })
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
     }
 }
 
@@ -98,6 +136,8 @@ impl<'a> Take<'a> {
         let data = self.accounts.escrow.try_borrow_data()?;
         let escrow = Escrow::load(&data)?;
 
+        escrow.check_mints(self.accounts.mint_a.key(), self.accounts.mint_b.key())?;
+
         let escrow_key = create_program_address(
             &[
                 b"escrow",
@@ -121,34 +161,270 @@ impl<'a> Take<'a> {
         ];
         let signer = Signer::from(&escrow_seeds);
 
-        let amount = TokenAccount::from_account_info(self.accounts.vault)?.amount();
+        delist_order(escrow, self.accounts.listing)?;
 
-        Transfer {
+        let now = Clock::get()?.unix_timestamp;
+        if escrow.is_expired(now) {
+            return Err(PinocchioError::OfferExpired.into());
+        }
+
+        check_condition(escrow, self.accounts.condition, now)?;
+
+        let amount = vault_amount(
+            escrow,
+            self.accounts.vault,
+            self.instruction_data.min_receive_a,
+        )?;
+
+        let token_program = self.accounts.token_program.key();
+        let received_before = TokenAccount::amount(self.accounts.taker_ata_a)?;
+
+        TransferChecked {
             from: self.accounts.vault,
+            mint: self.accounts.mint_a,
             to: self.accounts.taker_ata_a,
             authority: self.accounts.escrow,
             amount,
+            decimals: MintAccount::decimals(self.accounts.mint_a)?,
+            token_program,
         }
         .invoke_signed(&[signer.clone()])?;
 
+        // a transfer fee on mint_a is withheld again on the way out, so check what arrived
+        let received =
+            TokenAccount::amount(self.accounts.taker_ata_a)?.saturating_sub(received_before);
+        if received < self.instruction_data.min_receive_a {
+            return Err(PinocchioError::MinReceiveNotMet.into());
+        }
+
+        harvest_withheld_fees(self.accounts.vault, self.accounts.mint_a)?;
+
         CloseAccount {
             account: self.accounts.vault,
             destination: self.accounts.maker,
             authority: self.accounts.escrow,
+            token_program,
         }
         .invoke_signed(&[signer.clone()])?;
 
-        Transfer {
+        TransferChecked {
             from: self.accounts.taker_ata_b,
+            mint: self.accounts.mint_b,
             to: self.accounts.maker_ata_b,
             authority: self.accounts.taker,
             amount: escrow.receive,
+            decimals: MintAccount::decimals(self.accounts.mint_b)?,
+            token_program,
         }
         .invoke()?;
 
         drop(data);
-        ProgramAccount::close(self.accounts.escrow, self.accounts.taker)?;
+        close_program_account(self.accounts.escrow, self.accounts.taker)?;
 
         Ok(())
     }
+}
+
+// what the vault holds, all of which goes to the taker. It must still hold everything Make put
+// in, more only means someone donated, and at least what the taker expects, so nothing moves
+// out of a vault that came up short
+fn vault_amount(
+    escrow: &Escrow,
+    vault: &AccountInfo,
+    min_receive_a: u64,
+) -> Result<u64, ProgramError> {
+    let amount = TokenAccount::amount(vault)?;
+    if amount < escrow.deposited {
+        return Err(PinocchioError::EscrowBalanceMismatch.into());
+    }
+    if amount < min_receive_a {
+        return Err(PinocchioError::MinReceiveNotMet.into());
+    }
+    Ok(amount)
+}
+
+// the escrow's release condition has to hold at `now`, read from `condition` when it names an
+// account (price feed or flag)
+fn check_condition(escrow: &Escrow, condition: Option<&AccountInfo>, now: i64) -> ProgramResult {
+    let condition_data = match escrow.condition_account() {
+        Some(key) => {
+            let account = condition.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if account.key().ne(key) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            Some(account.try_borrow_data()?)
+        }
+        None => None,
+    };
+    if !escrow.condition_met(now, condition_data.as_deref())? {
+        return Err(PinocchioError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConditionType, PriceFeed, RawAccount};
+
+    const ORACLE: [u8; 32] = [5u8; 32];
+    const NOW: i64 = 1_700_000_000;
+
+    // 1_000 of mint_a for 2_000 of mint_b, a price of 2, with a 1% tolerance
+    fn priced_escrow() -> RawAccount {
+        let mut condition_data = [0u8; 64];
+        condition_data[..32].copy_from_slice(&ORACLE);
+        condition_data[32..34].copy_from_slice(&100u16.to_le_bytes());
+        RawAccount::escrow(|escrow| {
+            escrow.set_amount(1_000);
+            escrow.set_receive(2_000);
+            escrow.set_condition(ConditionType::OraclePrice, condition_data);
+        })
+    }
+
+    fn feed(price: u64, updated_at: i64) -> RawAccount {
+        let mut data = [0u8; PriceFeed::LEN];
+        data[..8].copy_from_slice(&price.to_le_bytes());
+        data[8..].copy_from_slice(&updated_at.to_le_bytes());
+        RawAccount::new(false, &[0u8; 32], &data).with_key(&ORACLE)
+    }
+
+    fn take_priced(feed: &mut RawAccount) -> ProgramResult {
+        let mut escrow = priced_escrow();
+        let escrow = escrow.info();
+        let escrow = Escrow::load(unsafe { escrow.borrow_data_unchecked() })?;
+        check_condition(escrow, Some(&feed.info()), NOW)
+    }
+
+    #[test]
+    fn prices_within_the_tolerance_settle() {
+        for price in [2_000_000_000, 2_020_000_000, 1_981_000_000] {
+            assert_eq!(take_priced(&mut feed(price, NOW - 10)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn prices_beyond_the_tolerance_are_refused() {
+        for price in [2_030_000_000, 1_970_000_000, 1] {
+            assert_eq!(
+                take_priced(&mut feed(price, NOW)),
+                Err(PinocchioError::OraclePriceDeviation.into())
+            );
+        }
+        assert_eq!(
+            take_priced(&mut feed(2_000_000_000, NOW - PriceFeed::MAX_AGE - 1)),
+            Err(PinocchioError::StaleOraclePrice.into())
+        );
+    }
+
+    fn vault(amount: u64) -> RawAccount {
+        let mut data = [0u8; pinocchio_token::state::TokenAccount::LEN];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        RawAccount::new(false, &pinocchio_token::ID, &data)
+    }
+
+    #[test]
+    fn an_ata_created_earlier_in_the_transaction_is_kept() {
+        let (taker, mint_a) = ([1u8; 32], [2u8; 32]);
+        let (ata, _) = crate::find_program_address(
+            &[&taker, &pinocchio_token::ID, &mint_a],
+            &pinocchio_associated_token_account::ID,
+        );
+        let mut accounts = [taker, mint_a, pinocchio_token::ID, pinocchio_system::ID]
+            .map(|key| RawAccount::new(true, &[0u8; 32], &[]).with_key(&key));
+        let [taker, mint_a, token_program, system_program] =
+            accounts.each_mut().map(|account| account.info());
+
+        // the taker's ATA as an earlier instruction left it; while its data is borrowed any CPI
+        // touching it would fail, so Ok means Take left it alone
+        let mut taker_ata_a = vault(500).with_key(&ata);
+        let taker_ata_a = taker_ata_a.info();
+        let _held = taker_ata_a.try_borrow_data().unwrap();
+        assert_eq!(
+            AssociatedTokenAccount::init_if_needed(
+                &taker_ata_a,
+                &mint_a,
+                &taker,
+                &taker,
+                &system_program,
+                &token_program,
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn vaults_short_of_the_deposit_are_refused() {
+        let mut escrow = RawAccount::escrow(|escrow| escrow.set_deposited(1_000));
+        let escrow = escrow.info();
+        let escrow = Escrow::load(unsafe { escrow.borrow_data_unchecked() }).unwrap();
+
+        assert_eq!(vault_amount(escrow, &vault(1_000).info(), 0), Ok(1_000));
+        // a donation on top goes to the taker with the rest
+        assert_eq!(vault_amount(escrow, &vault(1_250).info(), 1_200), Ok(1_250));
+        assert_eq!(
+            vault_amount(escrow, &vault(999).info(), 0),
+            Err(PinocchioError::EscrowBalanceMismatch.into())
+        );
+        assert_eq!(
+            vault_amount(escrow, &vault(1_000).info(), 1_001),
+            Err(PinocchioError::MinReceiveNotMet.into())
+        );
+    }
+
+    // released once byte 8 of the resolver's account reads 1
+    fn take_flagged(resolver: &[u8]) -> ProgramResult {
+        let mut condition_data = [0u8; 64];
+        condition_data[..32].copy_from_slice(&ORACLE);
+        condition_data[32..34].copy_from_slice(&8u16.to_le_bytes());
+        condition_data[34] = 1;
+        let mut escrow = RawAccount::escrow(|escrow| {
+            escrow.set_condition(ConditionType::AccountFlag, condition_data)
+        });
+        let mut resolver = RawAccount::new(false, &[0u8; 32], resolver).with_key(&ORACLE);
+
+        let escrow = escrow.info();
+        let escrow = Escrow::load(unsafe { escrow.borrow_data_unchecked() })?;
+        check_condition(escrow, Some(&resolver.info()), NOW)
+    }
+
+    #[test]
+    fn flagged_offers_release_once_resolved() {
+        let mut resolver = [0u8; 16];
+        assert_eq!(
+            take_flagged(&resolver),
+            Err(PinocchioError::Unauthorized.into())
+        );
+        // any value but the expected one is still unresolved
+        resolver[8] = 2;
+        assert_eq!(
+            take_flagged(&resolver),
+            Err(PinocchioError::Unauthorized.into())
+        );
+        // as is an account too short to hold the flag
+        assert_eq!(
+            take_flagged(&resolver[..8]),
+            Err(PinocchioError::Unauthorized.into())
+        );
+
+        resolver[8] = 1;
+        assert_eq!(take_flagged(&resolver), Ok(()));
+    }
+
+    #[test]
+    fn priced_offers_need_their_own_feed() {
+        let mut other = RawAccount::new(false, &[0u8; 32], &[0u8; PriceFeed::LEN]);
+        assert_eq!(
+            take_priced(&mut other),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        let mut escrow = priced_escrow();
+        let escrow = escrow.info();
+        let escrow = Escrow::load(unsafe { escrow.borrow_data_unchecked() }).unwrap();
+        assert_eq!(
+            check_condition(escrow, None, NOW),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
 }
\ No newline at end of file
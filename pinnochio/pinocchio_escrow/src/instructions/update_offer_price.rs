@@ -0,0 +1,130 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{Escrow, PinocchioError, ProgramAccount, SignerAccount};
+
+pub struct UpdateOfferPriceAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateOfferPriceAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(maker)?;
+        ProgramAccount::check(escrow)?;
+
+        Ok(Self { maker, escrow })
+    }
+}
+
+pub struct UpdateOfferPriceInstructionData {
+    pub new_receive_amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateOfferPriceInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let new_receive_amount = u64::from_le_bytes(data.try_into().unwrap());
+
+        Ok(Self { new_receive_amount })
+    }
+}
+
+pub struct UpdateOfferPrice<'a> {
+    pub accounts: UpdateOfferPriceAccounts<'a>,
+    pub instruction_data: UpdateOfferPriceInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateOfferPrice<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateOfferPriceAccounts::try_from(accounts)?;
+        let instruction_data = UpdateOfferPriceInstructionData::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateOfferPrice<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &6;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = Escrow::load_mut(data.as_mut())?;
+
+        if escrow.maker.ne(self.accounts.maker.key()) {
+            return Err(PinocchioError::InvalidAddress.into());
+        }
+
+        // repricing in place would break the book's ordering, listed offers must refund and relist
+        if escrow.order_book().is_some() {
+            return Err(PinocchioError::InvalidOrderPosition.into());
+        }
+
+        escrow.set_receive(self.instruction_data.new_receive_amount);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RawAccount;
+
+    const MAKER: [u8; 32] = [1u8; 32];
+
+    fn reprice(signer: &[u8; 32], escrow: &mut RawAccount, receive: u64) -> ProgramResult {
+        let mut maker = RawAccount::new(true, &[0u8; 32], &[]).with_key(signer);
+        let accounts = [maker.info(), escrow.info()];
+        UpdateOfferPrice::try_from((&receive.to_le_bytes()[..], &accounts[..]))?.process()
+    }
+
+    #[test]
+    fn maker_reprices_an_unlisted_offer() {
+        let mut escrow = RawAccount::escrow(|escrow| {
+            escrow.set_maker(MAKER);
+            escrow.set_receive(1_000);
+        });
+
+        reprice(&MAKER, &mut escrow, 1_500).unwrap();
+        let escrow = escrow.info();
+        let data = unsafe { escrow.borrow_data_unchecked() };
+        assert_eq!(Escrow::load(data).unwrap().receive, 1_500);
+    }
+
+    #[test]
+    fn only_the_maker_can_reprice() {
+        let mut escrow = RawAccount::escrow(|escrow| escrow.set_maker(MAKER));
+        assert_eq!(
+            reprice(&[2u8; 32], &mut escrow, 1_500),
+            Err(PinocchioError::InvalidAddress.into())
+        );
+    }
+
+    #[test]
+    fn listed_offers_cannot_be_repriced() {
+        let mut escrow = RawAccount::escrow(|escrow| {
+            escrow.set_maker(MAKER);
+            escrow.set_order_book(Some(&[9u8; 32]));
+        });
+        assert_eq!(
+            reprice(&MAKER, &mut escrow, 1_500),
+            Err(PinocchioError::InvalidOrderPosition.into())
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{
+    close_program_account, harvest_withheld_fees, AssociatedTokenAccount, Counteroffer,
+    CounterofferAccount, MintAccount, PinocchioError, SignerAccount, TokenAccount, TokenProgram,
+};
+
+pub struct WithdrawCounterofferAccounts<'a> {
+    pub taker: &'a AccountInfo,
+    pub counteroffer: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub counteroffer_vault: &'a AccountInfo,
+    pub taker_ata_b: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawCounterofferAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [taker, counteroffer, mint_b, counteroffer_vault, taker_ata_b, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(taker)?;
+        TokenProgram::check(token_program)?;
+        CounterofferAccount::check(counteroffer)?;
+        MintAccount::check(mint_b)?;
+        AssociatedTokenAccount::check(counteroffer_vault, counteroffer, mint_b, token_program)?;
+
+        Ok(Self {
+            taker,
+            counteroffer,
+            mint_b,
+            counteroffer_vault,
+            taker_ata_b,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct WithdrawCounteroffer<'a> {
+    pub accounts: WithdrawCounterofferAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawCounteroffer<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = WithdrawCounterofferAccounts::try_from(accounts)?;
+        AssociatedTokenAccount::init_if_needed(
+            accounts.taker_ata_b,
+            accounts.mint_b,
+            accounts.taker,
+            accounts.taker,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> WithdrawCounteroffer<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &9;
+
+    // the escrow may already be gone, so only the counteroffer itself is checked
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.counteroffer.try_borrow_data()?;
+        let counteroffer = Counteroffer::load(&data)?;
+
+        let counteroffer_key = create_program_address(
+            &[
+                b"counteroffer",
+                &counteroffer.escrow,
+                self.accounts.taker.key(),
+                &counteroffer.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &counteroffer_key != self.accounts.counteroffer.key()
+            || counteroffer.taker.ne(self.accounts.taker.key())
+        {
+            return Err(PinocchioError::InvalidCounteroffer.into());
+        }
+
+        let bump_binding = counteroffer.bump;
+        let counteroffer_seeds = [
+            Seed::from(b"counteroffer"),
+            Seed::from(counteroffer.escrow.as_ref()),
+            Seed::from(self.accounts.taker.key().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        let signer = Signer::from(&counteroffer_seeds);
+
+        TransferChecked {
+            from: self.accounts.counteroffer_vault,
+            mint: self.accounts.mint_b,
+            to: self.accounts.taker_ata_b,
+            authority: self.accounts.counteroffer,
+            amount: TokenAccount::amount(self.accounts.counteroffer_vault)?,
+            decimals: MintAccount::decimals(self.accounts.mint_b)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(core::slice::from_ref(&signer))?;
+
+        harvest_withheld_fees(self.accounts.counteroffer_vault, self.accounts.mint_b)?;
+
+        CloseAccount {
+            account: self.accounts.counteroffer_vault,
+            destination: self.accounts.taker,
+            authority: self.accounts.counteroffer,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&[signer])?;
+
+        drop(data);
+        close_program_account(self.accounts.counteroffer, self.accounts.taker)?;
+
+        Ok(())
+    }
+}
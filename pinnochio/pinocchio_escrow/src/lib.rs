@@ -2,12 +2,19 @@ use pinocchio::{
     ProgramResult, account_info::AccountInfo, entrypoint, program_error::ProgramError,
     pubkey::Pubkey,
 };
+use pinocchio_log::log;
 
 entrypoint!(process_instruction);
 
 pub mod instructions;
 pub use instructions::*;
 
+#[cfg(test)]
+#[path = "../../shared/raw_account.rs"]
+mod raw_account;
+#[cfg(test)]
+pub(crate) use raw_account::RawAccount;
+
 pub mod state;
 pub use state::*;
 
@@ -27,8 +34,31 @@ fn process_instruction(
 ) -> ProgramResult {
     match instruction_data.split_first() {
         Some((Make::DISCRIMINATOR, data)) => Make::try_from((data, accounts))?.process(),
-        Some((Take::DISCRIMINATOR, _)) => Take::try_from(accounts)?.process(),
+        Some((Take::DISCRIMINATOR, data)) => Take::try_from((data, accounts))?.process(),
         Some((Refund::DISCRIMINATOR, _)) => Refund::try_from(accounts)?.process(),
-        _ => Err(ProgramError::InvalidInstructionData),
+        Some((InitOrderBook::DISCRIMINATOR, _)) => InitOrderBook::try_from(accounts)?.process(),
+        Some((MatchOrders::DISCRIMINATOR, _)) => MatchOrders::try_from(accounts)?.process(),
+        Some((ExtendOffer::DISCRIMINATOR, data)) => {
+            ExtendOffer::try_from((data, accounts))?.process()
+        }
+        Some((UpdateOfferPrice::DISCRIMINATOR, data)) => {
+            UpdateOfferPrice::try_from((data, accounts))?.process()
+        }
+        Some((MakeCounteroffer::DISCRIMINATOR, data)) => {
+            MakeCounteroffer::try_from((data, accounts))?.process()
+        }
+        Some((AcceptCounteroffer::DISCRIMINATOR, _)) => {
+            AcceptCounteroffer::try_from(accounts)?.process()
+        }
+        Some((WithdrawCounteroffer::DISCRIMINATOR, _)) => {
+            WithdrawCounteroffer::try_from(accounts)?.process()
+        }
+        Some((DelistStale::DISCRIMINATOR, _)) => DelistStale::try_from(accounts)?.process(),
+        Some((RefundBatch::DISCRIMINATOR, _)) => RefundBatch::try_from(accounts)?.process(),
+        Some((discriminator, _)) => {
+            log!("Unknown instruction discriminator: {}", *discriminator);
+            Err(ProgramError::InvalidInstructionData)
+        }
+        None => Err(ProgramError::InvalidInstructionData),
     }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
 use core::mem::size_of;
 
+use crate::PinocchioError;
+
 #[repr(C)]
 pub struct Escrow {
     pub seed: u64,        
@@ -8,16 +10,42 @@ pub struct Escrow {
     pub mint_a: Pubkey,   
     pub mint_b: Pubkey,   
     pub receive: u64,     
-    pub bump: [u8;1]  
+    // gross amount the maker sent, the vault holds this minus any Token-2022 transfer fee
+    pub amount: u64,
+    // what the vault actually held once Make's transfer landed, net of any transfer fee;
+    // Take refuses to pay out of a vault holding less
+    pub deposited: u64,
+    // unix timestamp after which the offer can no longer be taken, 0 for no expiry;
+    // anything else is a positive time at most `MAX_OFFER_DURATION` past when it was set
+    pub expiry: i64,
+    pub bump: [u8;1],
+    // order book links, all-zero when the escrow is not listed
+    pub order_book: Pubkey,
+    pub prev_same_side: Pubkey,
+    pub next_same_side: Pubkey,
+    // `ConditionType` the taker has to satisfy, with its parameters in `condition_data`
+    pub condition_type: u8,
+    pub condition_data: [u8; 64],
 }
 
 impl Escrow {
+    // furthest into the future an offer may be set to expire
+    pub const MAX_OFFER_DURATION: i64 = 30 * 24 * 60 * 60;
+
     pub const LEN: usize = size_of::<u64>() 
     + size_of::<Pubkey>() 
     + size_of::<Pubkey>() 
     + size_of::<Pubkey>() 
     + size_of::<u64>()
-    + size_of::<[u8;1]>();
+    + size_of::<u64>()
+    + size_of::<u64>()
+    + size_of::<i64>()
+    + size_of::<[u8;1]>()
+    + size_of::<Pubkey>()
+    + size_of::<Pubkey>()
+    + size_of::<Pubkey>()
+    + size_of::<u8>()
+    + size_of::<[u8; 64]>();
 
     #[inline(always)]
     pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
@@ -60,11 +88,165 @@ impl Escrow {
         self.receive = receive;
     }
 
+    #[inline(always)]
+    pub fn set_amount(&mut self, amount: u64) {
+        self.amount = amount;
+    }
+
+    #[inline(always)]
+    pub fn set_deposited(&mut self, deposited: u64) {
+        self.deposited = deposited;
+    }
+
+    #[inline(always)]
+    pub fn set_expiry(&mut self, expiry: i64) {
+        self.expiry = expiry;
+    }
+
+    #[inline(always)]
+    pub fn is_expired(&self, now: i64) -> bool {
+        // a negative expiry can't be set through Make, treat one as long gone, never as open
+        self.expiry.ne(&0) && (self.expiry.is_negative() || now.gt(&self.expiry))
+    }
+
+    // the mints passed in must be the ones the offer was made for, or the vault and the
+    // payment could be checked against the wrong token
+    #[inline(always)]
+    pub fn check_mints(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<(), ProgramError> {
+        if self.mint_a.ne(mint_a) || self.mint_b.ne(mint_b) {
+            return Err(PinocchioError::InvalidAccountData.into());
+        }
+        Ok(())
+    }
+
+    // MatchOrders reads no condition accounts, so only unconditional offers go on a book
+    #[inline(always)]
+    pub fn listable(&self) -> bool {
+        self.condition_type.eq(&(ConditionType::None as u8))
+    }
+
+    // a listed escrow MatchOrders can never settle: it would sit at the head of its side and
+    // stop every match behind it, so anyone may delist it with DelistStale
+    #[inline(always)]
+    pub fn stale(&self, now: i64) -> bool {
+        self.is_expired(now) || !self.listable()
+    }
+
+    #[inline(always)]
+    pub fn set_condition(&mut self, condition_type: ConditionType, condition_data: [u8; 64]) {
+        self.condition_type = condition_type as u8;
+        self.condition_data = condition_data;
+    }
+
+    // `condition` is the data of the account named by `condition_account`, only Take passes
+    // one in; a condition that reads an account is never met without it
+    #[inline(always)]
+    pub fn condition_met(&self, now: i64, condition: Option<&[u8]>) -> Result<bool, ProgramError> {
+        let timestamp = i64::from_le_bytes(self.condition_data[..8].try_into().unwrap());
+        Ok(match ConditionType::try_from(self.condition_type)? {
+            ConditionType::None => true,
+            ConditionType::TimeAfter => now >= timestamp,
+            ConditionType::TimeBefore => now < timestamp,
+            ConditionType::OraclePrice => match condition {
+                Some(data) => {
+                    self.check_price(PriceFeed::load(data)?, now)?;
+                    true
+                }
+                None => false,
+            },
+            ConditionType::AccountFlag => match condition {
+                Some(data) => {
+                    let offset =
+                        u16::from_le_bytes(self.condition_data[32..34].try_into().unwrap());
+                    // an account too short to hold the flag has not flipped it either
+                    data.get(offset as usize).eq(&Some(&self.condition_data[34]))
+                }
+                None => false,
+            },
+        })
+    }
+
+    // the account a condition reads, the first 32 bytes of `condition_data` for those that
+    // read one
+    #[inline(always)]
+    pub fn condition_account(&self) -> Option<&Pubkey> {
+        match ConditionType::try_from(self.condition_type) {
+            Ok(ConditionType::OraclePrice | ConditionType::AccountFlag) => {
+                Some(self.condition_data[..32].try_into().unwrap())
+            }
+            _ => None,
+        }
+    }
+
+    // the price the maker asks, receive over amount, has to sit within `max_deviation_bps` of
+    // a fresh feed price
+    fn check_price(&self, price: &PriceFeed, now: i64) -> Result<(), ProgramError> {
+        if now.saturating_sub(price.updated_at) > PriceFeed::MAX_AGE {
+            return Err(PinocchioError::StaleOraclePrice.into());
+        }
+        let max_deviation_bps =
+            u16::from_le_bytes(self.condition_data[32..34].try_into().unwrap()) as u128;
+
+        // both sides in mint_b base units scaled by PRICE_SCALE
+        let asked = self.receive as u128 * PriceFeed::PRICE_SCALE as u128;
+        let fair = (self.amount as u128)
+            .checked_mul(price.price as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let deviation = asked.abs_diff(fair);
+
+        let within = deviation
+            .checked_mul(10_000)
+            .zip(fair.checked_mul(max_deviation_bps))
+            .map(|(deviation, tolerance)| deviation <= tolerance)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if !within {
+            return Err(PinocchioError::OraclePriceDeviation.into());
+        }
+        Ok(())
+    }
+
     #[inline(always)]
     pub fn set_bump(&mut self, bump: [u8;1]) {
         self.bump = bump;
     }
 
+    #[inline(always)]
+    pub fn order_book(&self) -> Option<&Pubkey> {
+        as_link(&self.order_book)
+    }
+
+    #[inline(always)]
+    pub fn prev_same_side(&self) -> Option<&Pubkey> {
+        as_link(&self.prev_same_side)
+    }
+
+    #[inline(always)]
+    pub fn next_same_side(&self) -> Option<&Pubkey> {
+        as_link(&self.next_same_side)
+    }
+
+    #[inline(always)]
+    pub fn set_order_book(&mut self, order_book: Option<&Pubkey>) {
+        self.order_book = from_link(order_book);
+    }
+
+    #[inline(always)]
+    pub fn set_prev_same_side(&mut self, prev: Option<&Pubkey>) {
+        self.prev_same_side = from_link(prev);
+    }
+
+    #[inline(always)]
+    pub fn set_next_same_side(&mut self, next: Option<&Pubkey>) {
+        self.next_same_side = from_link(next);
+    }
+
+    // both sides rank by how little they ask per unit offered: for an ask (sells x) that is
+    // the y price, for a bid (sells y) it is the inverse of the x price it is willing to pay
+    #[inline(always)]
+    pub fn outranks(&self, other: &Escrow) -> bool {
+        (self.receive as u128 * other.amount as u128) < (other.receive as u128 * self.amount as u128)
+    }
+
     #[inline(always)]
     pub fn set_inner(&mut self, seed: u64, maker: Pubkey, mint_a: Pubkey, mint_b: Pubkey, receive: u64, bump: [u8;1]) {
         self.seed = seed;
@@ -74,4 +256,260 @@ impl Escrow {
         self.receive = receive;
         self.bump = bump;
     }
-}
\ No newline at end of file
+}
+
+// condition_data holds an i64 unix timestamp in its first 8 bytes for both time conditions,
+// oracle (Pubkey) | max_deviation_bps (u16) for the price condition and
+// condition account (Pubkey) | offset (u16) | expected (u8) for the account flag
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConditionType {
+    None = 0u8,
+    // can only be taken from the timestamp on
+    TimeAfter = 1u8,
+    // can only be taken until the timestamp
+    TimeBefore = 2u8,
+    // can only be taken while the oracle price is fresh and close to the escrow's price
+    OraclePrice = 3u8,
+    // can only be taken once the byte at `offset` of the condition account equals `expected`,
+    // e.g. a resolver marking an event settled; whoever writes that account is trusted
+    AccountFlag = 4u8,
+}
+
+impl TryFrom<u8> for ConditionType {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::TimeAfter),
+            2 => Ok(Self::TimeBefore),
+            3 => Ok(Self::OraclePrice),
+            4 => Ok(Self::AccountFlag),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+// Minimal price account an `OraclePrice` escrow reads. Whoever publishes into it is trusted by
+// the maker naming its key, the escrow program never writes one.
+#[repr(C)]
+pub struct PriceFeed {
+    // mint_b base units per mint_a base unit, scaled by `PRICE_SCALE`
+    pub price: u64,
+    // unix timestamp of the last update
+    pub updated_at: i64,
+}
+
+impl PriceFeed {
+    pub const PRICE_SCALE: u64 = 1_000_000_000;
+
+    // oldest price Take will settle against, in seconds
+    pub const MAX_AGE: i64 = 60;
+
+    pub const LEN: usize = size_of::<u64>()
+    + size_of::<i64>();
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != PriceFeed::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) })
+    }
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Side {
+    // sells mint_x for mint_y
+    Ask = 0u8,
+    // sells mint_y for mint_x
+    Bid = 1u8,
+}
+
+#[repr(C)]
+pub struct OrderBook {
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub best_bid: Pubkey,
+    pub best_ask: Pubkey,
+    pub bump: [u8;1]
+}
+
+impl OrderBook {
+    pub const LEN: usize = size_of::<Pubkey>()
+    + size_of::<Pubkey>()
+    + size_of::<Pubkey>()
+    + size_of::<Pubkey>()
+    + size_of::<[u8;1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != OrderBook::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) })
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != OrderBook::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) })
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, mint_x: Pubkey, mint_y: Pubkey, bump: [u8;1]) {
+        self.mint_x = mint_x;
+        self.mint_y = mint_y;
+        self.best_bid = [0u8; 32];
+        self.best_ask = [0u8; 32];
+        self.bump = bump;
+    }
+
+    #[inline(always)]
+    pub fn side(&self, escrow: &Escrow) -> Result<Side, ProgramError> {
+        if escrow.mint_a.eq(&self.mint_x) && escrow.mint_b.eq(&self.mint_y) {
+            Ok(Side::Ask)
+        } else if escrow.mint_a.eq(&self.mint_y) && escrow.mint_b.eq(&self.mint_x) {
+            Ok(Side::Bid)
+        } else {
+            Err(ProgramError::InvalidAccountData)
+        }
+    }
+
+    #[inline(always)]
+    pub fn best(&self, side: Side) -> Option<&Pubkey> {
+        match side {
+            Side::Ask => as_link(&self.best_ask),
+            Side::Bid => as_link(&self.best_bid),
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_best(&mut self, side: Side, escrow: Option<&Pubkey>) {
+        match side {
+            Side::Ask => self.best_ask = from_link(escrow),
+            Side::Bid => self.best_bid = from_link(escrow),
+        }
+    }
+}
+
+#[repr(C)]
+pub struct Counteroffer {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    // mint_b the taker put up, gross of any transfer fee
+    pub give: u64,
+    // mint_a the taker asks for out of the escrow vault
+    pub receive: u64,
+    pub bump: [u8;1],
+}
+
+impl Counteroffer {
+    pub const LEN: usize = size_of::<Pubkey>()
+    + size_of::<Pubkey>()
+    + size_of::<u64>()
+    + size_of::<u64>()
+    + size_of::<[u8;1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != Counteroffer::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) })
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != Counteroffer::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) })
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, escrow: Pubkey, taker: Pubkey, give: u64, receive: u64, bump: [u8;1]) {
+        self.escrow = escrow;
+        self.taker = taker;
+        self.give = give;
+        self.receive = receive;
+        self.bump = bump;
+    }
+}
+
+#[inline(always)]
+fn as_link(key: &Pubkey) -> Option<&Pubkey> {
+    match key.eq(&[0u8; 32]) {
+        true => None,
+        false => Some(key),
+    }
+}
+
+#[inline(always)]
+fn from_link(key: Option<&Pubkey>) -> Pubkey {
+    key.copied().unwrap_or([0u8; 32])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every field is plain bytes or integers, all zero is a valid (unset) escrow
+    fn escrow() -> Escrow {
+        unsafe { core::mem::zeroed() }
+    }
+
+    #[test]
+    fn check_mints_wants_both_offer_mints() {
+        let mut escrow = escrow();
+        escrow.set_mint_a([1u8; 32]);
+        escrow.set_mint_b([2u8; 32]);
+
+        assert_eq!(escrow.check_mints(&[1u8; 32], &[2u8; 32]), Ok(()));
+        // either mint wrong, or the two swapped
+        for (mint_a, mint_b) in [
+            ([3u8; 32], [2u8; 32]),
+            ([1u8; 32], [3u8; 32]),
+            ([2u8; 32], [1u8; 32]),
+        ] {
+            assert_eq!(
+                escrow.check_mints(&mint_a, &mint_b),
+                Err(PinocchioError::InvalidAccountData.into())
+            );
+        }
+    }
+
+    #[test]
+    fn only_unconditional_live_offers_belong_on_a_book() {
+        let mut escrow = escrow();
+        escrow.set_expiry(1_000);
+        assert!(escrow.listable());
+        assert!(!escrow.stale(1_000));
+        assert!(escrow.stale(1_001));
+
+        // MatchOrders reads no condition account, so a conditional offer could never match
+        escrow.set_expiry(0);
+        escrow.set_condition(ConditionType::OraclePrice, [0u8; 64]);
+        assert!(!escrow.listable());
+        assert!(escrow.stale(0));
+    }
+
+    #[test]
+    fn offers_are_open_until_their_expiry() {
+        let mut escrow = escrow();
+        assert!(!escrow.is_expired(i64::MAX));
+
+        escrow.set_expiry(1_700_000_000);
+        assert!(!escrow.is_expired(1_700_000_000));
+        assert!(escrow.is_expired(1_700_000_001));
+
+        // Make refuses it, but an i64::MIN expiry already stored can't wrap into an open offer
+        escrow.set_expiry(i64::MIN);
+        assert!(escrow.is_expired(0));
+        assert!(escrow.is_expired(i64::MIN));
+    }
+}
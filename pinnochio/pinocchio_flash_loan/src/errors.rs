@@ -0,0 +1,42 @@
+use pinocchio::program_error::ProgramError;
+
+/// Program errors, surfaced as `ProgramError::Custom(code)`. The codes are part of the
+/// program's interface: new variants only ever go at the end, none is renumbered or reused.
+#[derive(Clone, PartialEq)]
+pub enum PinocchioError {
+    /// The protocol balance plus the loan fee does not fit in a u64, so the amount Repay
+    /// must restore cannot be recorded.
+    BalanceOverflow = 0,
+    /// Liquidity can't be provided in a transaction that also takes a loan, or borrowed
+    /// tokens could be booked as the borrower's own deposit.
+    LoanInProgress = 1,
+    /// A requested loan amount is zero or below its mint's minimum in the borrow cap account.
+    LoanTooSmall = 2,
+    /// A protocol token account holds less than its balance before the loan plus the fee.
+    RepaymentShortfall = 3,
+    /// Loan instruction data carries no amounts.
+    EmptyLoan = 4,
+    /// The same protocol token account appears in more than one loan pair.
+    DuplicateTokenAccount = 5,
+    /// A loan entry asks for more than the protocol's borrow cap for its mint.
+    BorrowCapExceeded = 6,
+    /// The transaction's last instruction isn't a Repay of this loan by its borrower.
+    LoanNotRepaid = 7,
+    /// Repay's protocol token accounts aren't the ones recorded in the loan, in order.
+    LoanMismatch = 8,
+    /// A loan entry asks for more than its protocol token account holds.
+    InsufficientLiquidity = 9,
+    /// The protocol's fee split authority paused it, no loans until it is unpaused.
+    ProtocolPaused = 10,
+    /// EmergencyDrain only empties a protocol that has been paused first.
+    ProtocolNotPaused = 11,
+    /// Loan, Repay and ProvideLiquidity only run as instructions of the transaction itself,
+    /// never through a CPI.
+    NotTopLevel = 12,
+}
+
+impl From<PinocchioError> for ProgramError {
+    fn from(e: PinocchioError) -> Self {
+        Self::Custom(e as u32)
+    }
+}
@@ -0,0 +1,157 @@
+//! Events emitted with `sol_log_data`, one data entry per event: a one-byte tag, then the
+//! fields in declaration order, keys as raw bytes and integers little-endian. Same scheme as
+//! the AMM's events, tags are per program.
+
+use pinocchio::{log::sol_log_data, program_error::ProgramError, pubkey::Pubkey};
+
+// one per loan entry, so a multi-token loan emits one event per protocol token account
+pub struct LoanEvent {
+  pub protocol: Pubkey,
+  pub borrower: Pubkey,
+  pub protocol_token_account: Pubkey,
+  pub amount: u64,
+  pub fee: u64,
+}
+
+impl LoanEvent {
+  pub const TAG: u8 = 0;
+  pub const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8;
+
+  pub fn encode(&self) -> [u8; Self::LEN] {
+    let mut data = [0u8; Self::LEN];
+    data[0] = Self::TAG;
+    data[1..33].copy_from_slice(&self.protocol);
+    data[33..65].copy_from_slice(&self.borrower);
+    data[65..97].copy_from_slice(&self.protocol_token_account);
+    data[97..105].copy_from_slice(&self.amount.to_le_bytes());
+    data[105..113].copy_from_slice(&self.fee.to_le_bytes());
+    data
+  }
+
+  pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
+    if data.len().ne(&Self::LEN) || data[0].ne(&Self::TAG) {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(Self {
+      protocol: data[1..33].try_into().unwrap(),
+      borrower: data[33..65].try_into().unwrap(),
+      protocol_token_account: data[65..97].try_into().unwrap(),
+      amount: u64::from_le_bytes(data[97..105].try_into().unwrap()),
+      fee: u64::from_le_bytes(data[105..113].try_into().unwrap()),
+    })
+  }
+
+  pub fn emit(&self) {
+    sol_log_data(&[&self.encode()]);
+  }
+}
+
+// one per EmergencyDrain, the whole balance of one protocol token account
+pub struct DrainEvent {
+  pub protocol: Pubkey,
+  pub protocol_token_account: Pubkey,
+  pub destination: Pubkey,
+  pub amount: u64,
+}
+
+impl DrainEvent {
+  pub const TAG: u8 = 1;
+  pub const LEN: usize = 1 + 32 + 32 + 32 + 8;
+
+  pub fn encode(&self) -> [u8; Self::LEN] {
+    let mut data = [0u8; Self::LEN];
+    data[0] = Self::TAG;
+    data[1..33].copy_from_slice(&self.protocol);
+    data[33..65].copy_from_slice(&self.protocol_token_account);
+    data[65..97].copy_from_slice(&self.destination);
+    data[97..105].copy_from_slice(&self.amount.to_le_bytes());
+    data
+  }
+
+  pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
+    if data.len().ne(&Self::LEN) || data[0].ne(&Self::TAG) {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(Self {
+      protocol: data[1..33].try_into().unwrap(),
+      protocol_token_account: data[33..65].try_into().unwrap(),
+      destination: data[65..97].try_into().unwrap(),
+      amount: u64::from_le_bytes(data[97..105].try_into().unwrap()),
+    })
+  }
+
+  pub fn emit(&self) {
+    sol_log_data(&[&self.encode()]);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn loan_event_round_trips() {
+    let event = LoanEvent {
+      protocol: [1u8; 32],
+      borrower: [2u8; 32],
+      protocol_token_account: [3u8; 32],
+      amount: u64::MAX,
+      fee: 5,
+    };
+    let data = event.encode();
+    assert_eq!(data[0], LoanEvent::TAG);
+
+    let decoded = LoanEvent::decode(&data).unwrap();
+    assert_eq!(
+      (
+        decoded.protocol,
+        decoded.borrower,
+        decoded.protocol_token_account
+      ),
+      (event.protocol, event.borrower, event.protocol_token_account)
+    );
+    assert_eq!((decoded.amount, decoded.fee), (u64::MAX, 5));
+  }
+
+  #[test]
+  fn drain_event_round_trips() {
+    let event = DrainEvent {
+      protocol: [1u8; 32],
+      protocol_token_account: [3u8; 32],
+      destination: [4u8; 32],
+      amount: 1_000,
+    };
+    let decoded = DrainEvent::decode(&event.encode()).unwrap();
+    assert_eq!(
+      (
+        decoded.protocol,
+        decoded.protocol_token_account,
+        decoded.destination
+      ),
+      (
+        event.protocol,
+        event.protocol_token_account,
+        event.destination
+      )
+    );
+    assert_eq!(decoded.amount, 1_000);
+  }
+
+  #[test]
+  fn decode_refuses_other_events_and_lengths() {
+    let loan = LoanEvent {
+      protocol: [1u8; 32],
+      borrower: [2u8; 32],
+      protocol_token_account: [3u8; 32],
+      amount: 1,
+      fee: 0,
+    }
+    .encode();
+    assert!(DrainEvent::decode(&loan).is_err());
+    assert!(LoanEvent::decode(&loan[..LoanEvent::LEN - 1]).is_err());
+
+    let mut retagged = loan;
+    retagged[0] = DrainEvent::TAG;
+    assert!(LoanEvent::decode(&retagged).is_err());
+  }
+}
@@ -0,0 +1,212 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program_error::ProgramError,
+  pubkey::find_program_address,
+  ProgramResult,
+};
+
+use crate::{
+  check_protocol_vault, check_same_mint, get_token_amount, transfer_checked, AccountInfoExt,
+  DrainEvent, FeeSplit, PinocchioError, SignerAccount, ID,
+};
+
+// a drain only empties a paused protocol, and only into a token account of the treasury its
+// fee split registered
+pub fn check_drain(fee_split: &FeeSplit, destination: &AccountInfo) -> ProgramResult {
+  if !fee_split.is_paused() {
+    return Err(PinocchioError::ProtocolNotPaused.into());
+  }
+  // token account owner lives at bytes 32..64
+  if destination.try_borrow_data()?.get(32..64).ne(&Some(&fee_split.treasury[..])) {
+    return Err(ProgramError::InvalidAccountData);
+  }
+  Ok(())
+}
+
+pub struct EmergencyDrainAccounts<'a> {
+  pub authority: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub fee_split: &'a AccountInfo,
+  pub protocol_token_account: &'a AccountInfo,
+  pub mint: &'a AccountInfo,
+  pub destination: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for EmergencyDrainAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [authority, protocol, fee_split, protocol_token_account, mint, destination, _token_program] =
+      accounts
+    else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(authority)?;
+
+    fee_split.require_owner(&ID)?;
+
+    Ok(Self {
+      authority,
+      protocol,
+      fee_split,
+      protocol_token_account,
+      mint,
+      destination,
+    })
+  }
+}
+
+pub struct EmergencyDrainInstructionData {
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+}
+
+impl<'a> TryFrom<&'a [u8]> for EmergencyDrainInstructionData {
+  type Error = ProgramError;
+
+  fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+    if data.len() != size_of::<u16>() + size_of::<u8>() {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(Self {
+      protocol_fee: [data[0], data[1]],
+      protocol_bump: [data[2]],
+    })
+  }
+}
+
+pub struct EmergencyDrain<'a> {
+  pub accounts: EmergencyDrainAccounts<'a>,
+  pub instruction_data: EmergencyDrainInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for EmergencyDrain<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = EmergencyDrainAccounts::try_from(accounts)?;
+    let instruction_data = EmergencyDrainInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> EmergencyDrain<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &9;
+
+  /// Moves the whole balance of one protocol token account to `destination`, a token account
+  /// of the same mint held by the fee split's treasury, and logs a `DrainEvent`. Like
+  /// SetFreeLoans it is gated on the authority of the protocol's fee split, and the protocol
+  /// has to be paused with SetPaused first. Liquidity positions are left as they are, so
+  /// providers can be made whole from the treasury once the protocol is fixed.
+  pub fn process(&mut self) -> ProgramResult {
+    check_protocol_vault(
+      self.accounts.protocol,
+      self.accounts.protocol_token_account,
+      &self.instruction_data.protocol_fee,
+      &self.instruction_data.protocol_bump,
+    )?;
+
+    let (fee_split_key, _) =
+      find_program_address(&[b"fee_split", self.accounts.protocol.key()], &ID);
+    if fee_split_key.ne(self.accounts.fee_split.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+    check_same_mint(self.accounts.protocol_token_account, self.accounts.destination)?;
+    {
+      let data = self.accounts.fee_split.try_borrow_data()?;
+      let fee_split = FeeSplit::load(&data)?;
+      if fee_split.authority.ne(self.accounts.authority.key()) {
+        return Err(ProgramError::IncorrectAuthority);
+      }
+      check_drain(fee_split, self.accounts.destination)?;
+    }
+
+    let amount = get_token_amount(self.accounts.protocol_token_account)?;
+
+    let seeds = [
+      Seed::from(b"protocol"),
+      Seed::from(&self.instruction_data.protocol_fee),
+      Seed::from(&self.instruction_data.protocol_bump),
+    ];
+
+    transfer_checked(
+      self.accounts.protocol_token_account,
+      self.accounts.destination,
+      self.accounts.mint,
+      self.accounts.protocol,
+      amount,
+      &[Signer::from(&seeds)],
+    )?;
+
+    DrainEvent {
+      protocol: *self.accounts.protocol.key(),
+      protocol_token_account: *self.accounts.protocol_token_account.key(),
+      destination: *self.accounts.destination.key(),
+      amount,
+    }
+    .emit();
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::RawAccount;
+  use pinocchio_token::state::TokenAccount;
+
+  const TREASURY: [u8; 32] = [3u8; 32];
+
+  fn fee_split(paused: bool) -> [u8; FeeSplit::LEN] {
+    let mut data = [0u8; FeeSplit::LEN];
+    let fee_split = FeeSplit::load_mut(&mut data).unwrap();
+    fee_split.paused = paused as u8;
+    fee_split.treasury = TREASURY;
+    data
+  }
+
+  fn token_account(owner: &[u8; 32]) -> RawAccount {
+    let mut data = [0u8; TokenAccount::LEN];
+    data[32..64].copy_from_slice(owner);
+    RawAccount::new(false, &pinocchio_token::ID, &data)
+  }
+
+  #[test]
+  fn drains_a_paused_protocol_into_its_treasury() {
+    let data = fee_split(true);
+    let fee_split = FeeSplit::load(&data).unwrap();
+    assert_eq!(check_drain(fee_split, &token_account(&TREASURY).info()), Ok(()));
+  }
+
+  #[test]
+  fn refuses_a_running_protocol() {
+    let data = fee_split(false);
+    let fee_split = FeeSplit::load(&data).unwrap();
+    assert_eq!(
+      check_drain(fee_split, &token_account(&TREASURY).info()),
+      Err(PinocchioError::ProtocolNotPaused.into())
+    );
+  }
+
+  #[test]
+  fn refuses_any_destination_but_the_treasury() {
+    let data = fee_split(true);
+    let fee_split = FeeSplit::load(&data).unwrap();
+    assert_eq!(
+      check_drain(fee_split, &token_account(&[4u8; 32]).info()),
+      Err(ProgramError::InvalidAccountData)
+    );
+    assert_eq!(
+      check_drain(fee_split, &RawAccount::new(false, &pinocchio_token::ID, &[0u8; 40]).info()),
+      Err(ProgramError::InvalidAccountData)
+    );
+  }
+}
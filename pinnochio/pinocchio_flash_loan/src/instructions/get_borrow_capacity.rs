@@ -0,0 +1,137 @@
+use pinocchio::{
+    account_info::AccountInfo, log::sol_log_data, program_error::ProgramError,
+    pubkey::find_program_address, ProgramResult,
+};
+
+use crate::{get_token_amount, AccountInfoExt, BorrowCap, ID, MAX_LOAN_TOKENS};
+
+pub struct GetBorrowCapacityAccounts<'a> {
+    pub protocol: &'a AccountInfo,
+    pub token_accounts: &'a [AccountInfo],
+    // one ["borrow_cap", protocol, mint] account per token account, in the same order
+    pub borrow_caps: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetBorrowCapacityAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [protocol, token_accounts @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        if token_accounts.is_empty() || !token_accounts.len().is_multiple_of(2) {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let (token_accounts, borrow_caps) = token_accounts.split_at(token_accounts.len() / 2);
+        if token_accounts.len().gt(&MAX_LOAN_TOKENS) {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        Ok(Self {
+            protocol,
+            token_accounts,
+            borrow_caps,
+        })
+    }
+}
+
+pub struct GetBorrowCapacityInstructionData<'a> {
+    pub token_mints: &'a [[u8; 32]],
+}
+
+impl<'a> TryFrom<&'a [u8]> for GetBorrowCapacityInstructionData<'a> {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.is_empty() || !data.len().is_multiple_of(size_of::<[u8; 32]>()) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let token_mints: &[[u8; 32]] = unsafe {
+            core::slice::from_raw_parts(
+                data.as_ptr() as *const [u8; 32],
+                data.len() / size_of::<[u8; 32]>(),
+            )
+        };
+
+        Ok(Self { token_mints })
+    }
+}
+
+pub struct GetBorrowCapacity<'a> {
+    pub accounts: GetBorrowCapacityAccounts<'a>,
+    pub instruction_data: GetBorrowCapacityInstructionData<'a>,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for GetBorrowCapacity<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = GetBorrowCapacityAccounts::try_from(accounts)?;
+        let instruction_data = GetBorrowCapacityInstructionData::try_from(data)?;
+
+        if instruction_data.token_mints.len() != accounts.token_accounts.len() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> GetBorrowCapacity<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &3;
+
+    // meant for simulateTransaction, emits one `mint | max_borrow: u64` entry per token account:
+    // its balance, limited by the mint's borrow cap when the protocol has set one
+    pub fn process(&mut self) -> ProgramResult {
+        let mut capacities = [[0u8; 40]; MAX_LOAN_TOKENS];
+
+        for (((token_account, borrow_cap), mint), capacity) in self
+            .accounts
+            .token_accounts
+            .iter()
+            .zip(self.accounts.borrow_caps)
+            .zip(self.instruction_data.token_mints)
+            .zip(capacities.iter_mut())
+        {
+            let balance = get_token_amount(token_account)?;
+
+            let data = token_account.try_borrow_data()?;
+            // token account mint lives at bytes 0..32, owner at 32..64
+            if data[0..32].ne(mint) || data[32..64].ne(self.accounts.protocol.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let (borrow_cap_key, _) = find_program_address(
+                &[b"borrow_cap", self.accounts.protocol.key(), mint],
+                &ID,
+            );
+            if borrow_cap_key.ne(borrow_cap.key()) {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            // until SetBorrowCap creates it the mint is uncapped
+            let max_borrow = match borrow_cap.data_len() {
+                0 => balance,
+                _ => {
+                    borrow_cap.require_owner(&ID)?;
+                    BorrowCap::load(&borrow_cap.try_borrow_data()?)?.capacity(balance)
+                }
+            };
+
+            capacity[..32].copy_from_slice(mint);
+            capacity[32..].copy_from_slice(&max_borrow.to_le_bytes());
+        }
+
+        let mut fields = [&[][..]; MAX_LOAN_TOKENS];
+        for (field, capacity) in fields.iter_mut().zip(capacities.iter()) {
+            *field = &capacity[..];
+        }
+        sol_log_data(&fields[..self.accounts.token_accounts.len()]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::find_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_log::log;
+
+use crate::{get_token_amount, AccountInfoExt, Metrics, ID};
+
+// `part` as basis points of `whole`, zero when there is no whole to measure against
+pub fn ratio_bps(part: u64, whole: u64) -> u64 {
+    if whole.eq(&0) {
+        return 0;
+    }
+    (part as u128 * 10_000 / whole as u128).min(u64::MAX as u128) as u64
+}
+
+pub struct GetProtocolHealthAccounts<'a> {
+    pub protocol: &'a AccountInfo,
+    // the program's ["metrics"] account
+    pub metrics: &'a AccountInfo,
+    // any token account the protocol's operators keep as its insurance fund
+    pub insurance_fund: &'a AccountInfo,
+    pub token_accounts: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetProtocolHealthAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [protocol, metrics, insurance_fund, token_accounts @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        if token_accounts.is_empty() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        metrics.require_owner(&ID)?;
+
+        Ok(Self {
+            protocol,
+            metrics,
+            insurance_fund,
+            token_accounts,
+        })
+    }
+}
+
+pub struct GetProtocolHealth<'a> {
+    pub accounts: GetProtocolHealthAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetProtocolHealth<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = GetProtocolHealthAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> GetProtocolHealth<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &2;
+
+    // meant for simulateTransaction, results are only logged. Loans are repaid within their
+    // own transaction, so utilization is the day's borrowed volume against the liquidity
+    // rather than anything outstanding; the daily figures come from the metrics account
+    pub fn process(&mut self) -> ProgramResult {
+        let (metrics_key, _) = find_program_address(&[b"metrics"], &ID);
+        if metrics_key.ne(self.accounts.metrics.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let mut total_liquidity: u64 = 0;
+
+        for token_account in self.accounts.token_accounts {
+            let balance = get_token_amount(token_account)?;
+
+            let data = token_account.try_borrow_data()?;
+            // token account owner lives at bytes 32..64
+            if data[32..64].ne(self.accounts.protocol.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            total_liquidity = total_liquidity
+                .checked_add(balance)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+
+        let insurance_fund = get_token_amount(self.accounts.insurance_fund)?;
+
+        let data = self.accounts.metrics.try_borrow_data()?;
+        let (loans_24h, borrowed_24h, fees_24h) = Metrics::load(&data)?.today(Clock::get()?.slot);
+
+        log!("total_liquidity: {}", total_liquidity);
+        log!("utilization_rate_bps: {}", ratio_bps(borrowed_24h, total_liquidity));
+        log!("insurance_fund_ratio_bps: {}", ratio_bps(insurance_fund, total_liquidity));
+        log!("total_loans_24h: {}", loans_24h);
+        log!("total_fees_24h: {}", fees_24h);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratio_bps_measures_against_the_liquidity() {
+        assert_eq!(ratio_bps(250, 1_000), 2_500);
+        assert_eq!(ratio_bps(1_000, 1_000), 10_000);
+        // a day's volume can turn the liquidity over more than once
+        assert_eq!(ratio_bps(3_000, 1_000), 30_000);
+        assert_eq!(ratio_bps(1, 3), 3_333);
+        assert_eq!(ratio_bps(500, 0), 0);
+        assert_eq!(ratio_bps(u64::MAX, 1), u64::MAX);
+    }
+}
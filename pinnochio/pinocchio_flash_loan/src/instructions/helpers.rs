@@ -1,9 +1,468 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::Signer,
+  program_error::ProgramError,
+  pubkey::{find_program_address, Pubkey},
+  ProgramResult,
+};
+use pinocchio_token::state::{Mint, TokenAccount};
+use pinocchio_token_2022::instructions::TransferChecked;
+
+use crate::{AccountInfoExt, PinocchioError, ID};
+
+// BPFLoaderUpgradeab1e11111111111111111111111
+pub const BPF_LOADER_UPGRADEABLE_ID: Pubkey = [
+  0x02, 0xa8, 0xf6, 0x91, 0x4e, 0x88, 0xa1, 0xb0,
+  0xe2, 0x10, 0x15, 0x3e, 0xf7, 0x63, 0xae, 0x2b,
+  0x00, 0xc2, 0xb9, 0x3d, 0x16, 0xc1, 0x24, 0xd2,
+  0xc0, 0x53, 0x7a, 0x10, 0x04, 0x80, 0x00, 0x00,
+];
+
+// stack height of an instruction the transaction invokes directly, every CPI runs above it
+pub const TRANSACTION_LEVEL_STACK_HEIGHT: u64 = 1;
+
+// Loan, Repay and ProvideLiquidity check each other through the instructions sysvar, which only
+// lists top-level instructions. Run from a CPI, a loan and a deposit of the borrowed tokens
+// could hide from those checks inside another program's instruction, so all three refuse it
+pub fn check_top_level(stack_height: u64) -> ProgramResult {
+  if stack_height.ne(&TRANSACTION_LEVEL_STACK_HEIGHT) {
+    return Err(PinocchioError::NotTopLevel.into());
+  }
+  Ok(())
+}
+
+pub fn stack_height() -> u64 {
+  #[cfg(target_os = "solana")]
+  unsafe {
+    pinocchio::syscalls::sol_get_stack_height()
+  }
+  #[cfg(not(target_os = "solana"))]
+  TRANSACTION_LEVEL_STACK_HEIGHT
+}
+
+// upper bound on entries per loan, shared by Loan and Repay so both sides agree
+pub const MAX_LOAN_TOKENS: usize = 16;
+pub const MAX_REPAY_TOKENS: usize = MAX_LOAN_TOKENS;
+// protocol token account, receiver and mint
+pub const LOAN_ENTRY_ACCOUNTS: usize = 3;
+
 #[repr(C, packed)]
 pub struct LoanData {
   pub protocol_token_account: [u8; 32],
   pub balance: u64,
+  // fee part of `balance`, split among the fee recipients on repay
+  pub fee: u64,
+  // principal handed to the borrower, so a shortfall can be reported against what was lent
+  pub amount: u64,
+}
+
+// Repay reads the entries back at fixed offsets, so the packed layout must not drift
+const _: () = assert!(size_of::<LoanData>() == 32 + 8 + 8 + 8);
+
+// Token-2022 keeps the classic layout and appends its extensions after an account type byte,
+// 2 for a token account. A multisig is the one other account of that program this long
+const ACCOUNT_TYPE_TOKEN_ACCOUNT: u8 = 2;
+const MULTISIG_LEN: usize = 355;
+
+// the program that owns `account`, which must really be a token account of it: the classic
+// layout to the byte, or for Token-2022 that layout with or without extensions after it.
+// Anything else could put any bytes it likes where a balance or mint is read from
+pub fn token_program_of(account: &AccountInfo) -> Result<&'static Pubkey, ProgramError> {
+  if account.is_owned_by(&pinocchio_token::ID) && account.data_len().eq(&TokenAccount::LEN) {
+    return Ok(&pinocchio_token::ID);
+  }
+  if account.is_owned_by(&pinocchio_token_2022::ID) && account.data_len().ne(&MULTISIG_LEN) {
+    let data = account.try_borrow_data()?;
+    match data.get(TokenAccount::LEN) {
+      None if data.len().eq(&TokenAccount::LEN) => return Ok(&pinocchio_token_2022::ID),
+      Some(&ACCOUNT_TYPE_TOKEN_ACCOUNT) => return Ok(&pinocchio_token_2022::ID),
+      _ => {}
+    }
+  }
+  Err(ProgramError::InvalidAccountData)
+}
+
+pub fn get_token_amount(account: &AccountInfo) -> Result<u64, ProgramError> {
+  token_program_of(account)?;
+  // amount sits after mint and owner in the base layout both token programs share
+  account.read_u64_at(64)
 }
 
-pub fn get_token_amount(data: &[u8]) -> u64 {
-  unsafe { *(data.as_ptr().add(64) as *const u64) }
-}
\ No newline at end of file
+// `receiver` has to be a token account of the same mint, and so the same token program, as
+// `token_account`, so a loan is never paid out into an account that can't hold it
+pub fn check_same_mint(token_account: &AccountInfo, receiver: &AccountInfo) -> ProgramResult {
+  let token_program = token_program_of(token_account)?;
+  if token_program_of(receiver).ne(&Ok(token_program)) {
+    return Err(ProgramError::InvalidAccountOwner);
+  }
+  // the mint is the first field of a token account
+  if token_account.try_borrow_data()?[..32] != receiver.try_borrow_data()?[..32] {
+    return Err(ProgramError::InvalidAccountData);
+  }
+  Ok(())
+}
+
+// moves `amount` from `from` to `to` through the token program that owns them, with
+// TransferChecked: Token-2022 refuses the plain Transfer for mints with a transfer fee, and
+// both programs take it. `mint` must be the mint of `from`
+pub fn transfer_checked(
+  from: &AccountInfo,
+  to: &AccountInfo,
+  mint: &AccountInfo,
+  authority: &AccountInfo,
+  amount: u64,
+  signers: &[Signer],
+) -> ProgramResult {
+  let token_program = token_program_of(from)?;
+  mint.require_owner(token_program)?;
+  if from.try_borrow_data()?[..32].ne(mint.key()) {
+    return Err(ProgramError::InvalidAccountData);
+  }
+
+  TransferChecked {
+    from,
+    mint,
+    to,
+    authority,
+    amount,
+    decimals: mint_decimals(mint)?,
+    token_program,
+  }
+  .invoke_signed(signers)
+}
+
+// decimals of a mint of either token program, read from the base layout they share
+pub fn mint_decimals(mint: &AccountInfo) -> Result<u8, ProgramError> {
+  let data = mint.try_borrow_data()?;
+  if data.len().lt(&Mint::LEN) {
+    return Err(ProgramError::InvalidAccountData);
+  }
+  // mint authority (4 + 32) and supply (8) come first
+  Ok(data[44])
+}
+
+// the upgrade authority recorded in the program's ProgramData account: a u32 tag of 3, the
+// slot of the last deploy, then an optional authority. None once the program is immutable
+pub fn upgrade_authority(program_data: &[u8]) -> Option<&Pubkey> {
+  match program_data.get(..45) {
+    Some([3, 0, 0, 0, _, _, _, _, _, _, _, _, 1, authority @ ..]) => authority.try_into().ok(),
+    _ => None,
+  }
+}
+
+// `authority` has to be the upgrade authority of this program, read from its ProgramData account
+pub fn check_upgrade_authority(program_data: &AccountInfo, authority: &Pubkey) -> ProgramResult {
+  let (program_data_key, _) = find_program_address(&[&ID], &BPF_LOADER_UPGRADEABLE_ID);
+  if program_data_key.ne(program_data.key()) {
+    return Err(ProgramError::InvalidSeeds);
+  }
+  program_data.require_owner(&BPF_LOADER_UPGRADEABLE_ID)?;
+  if upgrade_authority(&program_data.try_borrow_data()?).ne(&Some(authority)) {
+    return Err(ProgramError::IncorrectAuthority);
+  }
+  Ok(())
+}
+
+// every protocol token account, the first of each loan entry's accounts, may be lent from only
+// once per loan: a second entry would snapshot a balance the first transfer already reduced,
+// and Repay would accept the loan back short
+pub fn check_distinct_protocol_accounts(token_accounts: &[AccountInfo]) -> ProgramResult {
+  let protocol_token_accounts = token_accounts.iter().step_by(LOAN_ENTRY_ACCOUNTS);
+  for (i, a) in protocol_token_accounts.clone().enumerate() {
+    if protocol_token_accounts.clone().skip(i + 1).any(|b| a.key().eq(b.key())) {
+      return Err(PinocchioError::DuplicateTokenAccount.into());
+    }
+  }
+  Ok(())
+}
+
+// zeroes the data so nothing can be read back within the transaction, then hands every
+// lamport to `destination` and closes the account; kept in step with the escrow's copy
+pub fn close_program_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+  account.try_borrow_mut_data()?.fill(0);
+
+  let lamports = destination
+    .lamports()
+    .checked_add(account.lamports())
+    .ok_or(ProgramError::ArithmeticOverflow)?;
+  *destination.try_borrow_mut_lamports()? = lamports;
+  *account.try_borrow_mut_lamports()? = 0;
+
+  account.close()
+}
+
+/// Declares `$name` as the `[Seed; N]` array for a PDA signer.
+///
+/// Each seed expression is first bound to its own local, so owned temporaries such as
+/// `seed.to_le_bytes()` live as long as the array instead of being dropped at the end of the
+/// `let`. Any seed that is `AsRef<[u8]>` works: byte strings, keys, bump arrays.
+///
+/// ```
+/// use pinocchio::instruction::Signer;
+/// use pinocchio_flash_loan::signer_seeds;
+///
+/// let (fee, bump) = (500u16, [254u8]);
+/// signer_seeds!(seeds = [b"protocol", fee.to_le_bytes(), bump]);
+/// let signer = [Signer::from(&seeds)];
+/// # assert_eq!(seeds.len(), 3);
+/// # assert_eq!(signer.len(), 1);
+/// ```
+#[macro_export]
+macro_rules! signer_seeds {
+  ($name:ident = [$($seed:expr),+ $(,)?]) => {
+    $crate::signer_seeds!(@bind $name [] $($seed,)+);
+  };
+  // every level introduces a fresh hygienic `seed`, so the bindings never shadow each other
+  (@bind $name:ident [$($bound:ident)*] $seed:expr, $($rest:expr,)*) => {
+    let seed = $seed;
+    $crate::signer_seeds!(@bind $name [$($bound)* seed] $($rest,)*);
+  };
+  (@bind $name:ident [$($bound:ident)*]) => {
+    let $name = [$(::pinocchio::instruction::Seed::from(AsRef::<[u8]>::as_ref(&$bound))),*];
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::RawAccount;
+
+  const OWNER: Pubkey = [7u8; 32];
+
+  #[test]
+  fn close_program_account_moves_every_lamport() {
+    let mut account = RawAccount::new(false, &ID, &[7u8; 64]).with_lamports(1_500_000);
+    let mut destination = RawAccount::new(true, &OWNER, &[]).with_lamports(2_000_000);
+    let (account, destination) = (account.info(), destination.info());
+
+    close_program_account(&account, &destination).unwrap();
+
+    assert_eq!(destination.lamports(), 3_500_000);
+    assert_eq!(account.lamports(), 0);
+    // the runtime only drops the data at the end of the instruction, until then it reads zero
+    assert!(unsafe { account.borrow_data_unchecked() }.iter().all(|b| b.eq(&0)));
+  }
+
+  #[test]
+  fn close_program_account_refuses_to_overflow() {
+    let mut account = RawAccount::new(false, &ID, &[7u8; 8]).with_lamports(2);
+    let mut destination = RawAccount::new(true, &OWNER, &[]).with_lamports(u64::MAX - 1);
+    let (account, destination) = (account.info(), destination.info());
+
+    assert_eq!(
+      close_program_account(&account, &destination),
+      Err(ProgramError::ArithmeticOverflow)
+    );
+    // nothing moved, the lamports still add up
+    assert_eq!(destination.lamports(), u64::MAX - 1);
+    assert_eq!(account.lamports(), 2);
+  }
+
+  #[test]
+  fn require_signer() {
+    assert_eq!(
+      RawAccount::new(true, &OWNER, &[]).info().require_signer(),
+      Ok(())
+    );
+    assert_eq!(
+      RawAccount::new(false, &OWNER, &[]).info().require_signer(),
+      Err(ProgramError::MissingRequiredSignature)
+    );
+  }
+
+  #[test]
+  fn require_owner() {
+    let mut raw = RawAccount::new(false, &OWNER, &[]);
+    assert_eq!(raw.info().require_owner(&OWNER), Ok(()));
+    assert_eq!(
+      raw.info().require_owner(&[8u8; 32]),
+      Err(ProgramError::InvalidAccountOwner)
+    );
+  }
+
+  #[test]
+  fn require_len() {
+    let mut raw = RawAccount::new(false, &OWNER, &[0u8; 16]);
+    assert_eq!(raw.info().require_len(16), Ok(()));
+    assert_eq!(
+      raw.info().require_len(15),
+      Err(ProgramError::InvalidAccountData)
+    );
+    assert_eq!(
+      raw.info().require_len(17),
+      Err(ProgramError::InvalidAccountData)
+    );
+  }
+
+  #[test]
+  fn read_u64_at() {
+    let mut data = [0u8; 16];
+    data[0..8].copy_from_slice(&1u64.to_le_bytes());
+    data[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+    let mut raw = RawAccount::new(false, &OWNER, &data);
+    let info = raw.info();
+
+    assert_eq!(info.read_u64_at(0), Ok(1));
+    assert_eq!(info.read_u64_at(8), Ok(u64::MAX));
+    assert_eq!(info.read_u64_at(9), Err(ProgramError::InvalidAccountData));
+    assert_eq!(info.read_u64_at(16), Err(ProgramError::InvalidAccountData));
+    assert_eq!(
+      info.read_u64_at(usize::MAX - 3),
+      Err(ProgramError::InvalidAccountData)
+    );
+  }
+
+  #[test]
+  fn token_program_of_parses_both_token_programs() {
+    let classic = pinocchio_token::ID;
+    let token_2022 = pinocchio_token_2022::ID;
+    let mut data = [0u8; TokenAccount::LEN + 12];
+    data[64..72].copy_from_slice(&500u64.to_le_bytes());
+    data[TokenAccount::LEN] = ACCOUNT_TYPE_TOKEN_ACCOUNT;
+
+    for (owner, len, program) in [
+      (classic, TokenAccount::LEN, classic),
+      (token_2022, TokenAccount::LEN, token_2022),
+      // a Token-2022 account with extensions after the account type byte
+      (token_2022, TokenAccount::LEN + 12, token_2022),
+    ] {
+      let mut raw = RawAccount::new(false, &owner, &data[..len]);
+      assert_eq!(token_program_of(&raw.info()), Ok(&program));
+      assert_eq!(get_token_amount(&raw.info()), Ok(500));
+    }
+
+    // the classic program has no extensions, and an account needs a token program owner
+    for (owner, len) in [
+      (classic, TokenAccount::LEN + 12),
+      (classic, TokenAccount::LEN - 1),
+      (token_2022, TokenAccount::LEN - 1),
+      (OWNER, TokenAccount::LEN),
+    ] {
+      assert_eq!(
+        get_token_amount(&RawAccount::new(false, &owner, &data[..len]).info()),
+        Err(ProgramError::InvalidAccountData)
+      );
+    }
+
+    // a Token-2022 mint padded out to the same length, and a multisig
+    let mut mint = data;
+    mint[TokenAccount::LEN] = 1;
+    let multisig = [ACCOUNT_TYPE_TOKEN_ACCOUNT; MULTISIG_LEN];
+    for data in [&mint[..], &multisig[..]] {
+      assert_eq!(
+        token_program_of(&RawAccount::new(false, &token_2022, data).info()),
+        Err(ProgramError::InvalidAccountData)
+      );
+    }
+  }
+
+  #[test]
+  fn each_protocol_token_account_backs_one_entry() {
+    let (vault_a, vault_b, receiver, mint) = ([1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]);
+    let entries = |first: &Pubkey, second: &Pubkey| {
+      // protocol token account, receiver and mint per entry; one receiver and mint for both
+      [first, &receiver, &mint, second, &receiver, &mint]
+        .map(|key| RawAccount::new(false, &pinocchio_token::ID, &[]).with_key(key))
+    };
+
+    let mut raw = entries(&vault_a, &vault_b);
+    let accounts = raw.each_mut().map(|account| account.info());
+    assert_eq!(check_distinct_protocol_accounts(&accounts), Ok(()));
+
+    let mut raw = entries(&vault_a, &vault_a);
+    let accounts = raw.each_mut().map(|account| account.info());
+    assert_eq!(
+      check_distinct_protocol_accounts(&accounts),
+      Err(PinocchioError::DuplicateTokenAccount.into())
+    );
+  }
+
+  #[test]
+  fn spoofed_token_accounts_report_no_balance() {
+    // the exact layout of a funded token account, planted in accounts no token program owns
+    let mut data = [0u8; TokenAccount::LEN];
+    data[64..72].copy_from_slice(&u64::MAX.to_le_bytes());
+
+    for owner in [ID, OWNER, pinocchio_system::ID] {
+      let mut spoofed = RawAccount::new(false, &owner, &data);
+      assert_eq!(
+        get_token_amount(&spoofed.info()),
+        Err(ProgramError::InvalidAccountData)
+      );
+      // nor can one stand in for the receiver of a real token account
+      let mut real = RawAccount::new(false, &pinocchio_token::ID, &data);
+      assert!(check_same_mint(&real.info(), &spoofed.info()).is_err());
+      assert!(check_same_mint(&spoofed.info(), &real.info()).is_err());
+    }
+  }
+
+  #[test]
+  fn check_same_mint_stays_within_one_token_program() {
+    let mut data = [0u8; TokenAccount::LEN];
+    data[..32].copy_from_slice(&[5u8; 32]);
+    let mut classic = RawAccount::new(false, &pinocchio_token::ID, &data);
+    let mut token_2022 = RawAccount::new(false, &pinocchio_token_2022::ID, &data);
+    let mut receiver = RawAccount::new(false, &pinocchio_token_2022::ID, &data);
+    data[..32].copy_from_slice(&[6u8; 32]);
+    let mut other_mint = RawAccount::new(false, &pinocchio_token_2022::ID, &data);
+
+    assert_eq!(check_same_mint(&token_2022.info(), &receiver.info()), Ok(()));
+    assert_eq!(
+      check_same_mint(&classic.info(), &receiver.info()),
+      Err(ProgramError::InvalidAccountOwner)
+    );
+    assert_eq!(
+      check_same_mint(&token_2022.info(), &other_mint.info()),
+      Err(ProgramError::InvalidAccountData)
+    );
+  }
+
+  #[test]
+  fn mint_decimals_reads_the_base_layout() {
+    let mut data = [0u8; Mint::LEN + 20];
+    data[44] = 9;
+    assert_eq!(
+      mint_decimals(&RawAccount::new(false, &pinocchio_token_2022::ID, &data).info()),
+      Ok(9)
+    );
+    assert_eq!(
+      mint_decimals(&RawAccount::new(false, &pinocchio_token::ID, &data[..Mint::LEN]).info()),
+      Ok(9)
+    );
+    assert_eq!(
+      mint_decimals(&RawAccount::new(false, &pinocchio_token::ID, &data[..Mint::LEN - 1]).info()),
+      Err(ProgramError::InvalidAccountData)
+    );
+  }
+
+  #[test]
+  fn check_top_level_refuses_cpis() {
+    assert_eq!(check_top_level(TRANSACTION_LEVEL_STACK_HEIGHT), Ok(()));
+    for height in [0, 2, 5] {
+      assert_eq!(
+        check_top_level(height),
+        Err(PinocchioError::NotTopLevel.into())
+      );
+    }
+  }
+
+  #[test]
+  fn upgrade_authority_reads_program_data() {
+    let authority = [9u8; 32];
+    let mut data = [0u8; 45];
+    data[0..4].copy_from_slice(&3u32.to_le_bytes());
+    data[4..12].copy_from_slice(&77u64.to_le_bytes());
+    data[12] = 1;
+    data[13..45].copy_from_slice(&authority);
+    assert_eq!(upgrade_authority(&data), Some(&authority));
+
+    // an immutable program has no authority left, and only ProgramData has one at all
+    let mut immutable = data;
+    immutable[12] = 0;
+    assert_eq!(upgrade_authority(&immutable), None);
+    let mut program = data;
+    program[0] = 2;
+    assert_eq!(upgrade_authority(&program), None);
+    assert_eq!(upgrade_authority(&data[..44]), None);
+  }
+}
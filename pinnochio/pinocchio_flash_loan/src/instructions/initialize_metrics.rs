@@ -0,0 +1,214 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program_error::ProgramError,
+  pubkey::find_program_address,
+  sysvars::{rent::Rent, Sysvar},
+  ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::{SignerAccount, ID};
+
+// ~400ms slots
+pub const SLOTS_PER_DAY: u64 = 216_000;
+
+// running totals across every protocol of the program, at ["metrics"]. Loans only update it
+// when it is passed along, so the numbers cover the loans that opted in, not every loan.
+// total_borrowed adds raw amounts of whatever mints were lent
+#[repr(C)]
+pub struct Metrics {
+  pub loans_issued: [u8; 8],
+  pub total_borrowed: [u8; 8],
+  pub total_fees: [u8; 8],
+  // `slot / SLOTS_PER_DAY` of the last recorded loan; the daily counters below only count
+  // loans of that day and start over with the first loan of the next
+  pub day: [u8; 8],
+  pub loans_today: [u8; 8],
+  pub borrowed_today: [u8; 8],
+  pub fees_today: [u8; 8],
+}
+
+impl Metrics {
+  pub const LEN: usize = size_of::<Metrics>();
+
+  pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &*(data.as_ptr() as *const Self) })
+  }
+
+  pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+  }
+
+  pub fn loans_issued(&self) -> u64 {
+    u64::from_le_bytes(self.loans_issued)
+  }
+
+  pub fn total_borrowed(&self) -> u64 {
+    u64::from_le_bytes(self.total_borrowed)
+  }
+
+  pub fn total_fees(&self) -> u64 {
+    u64::from_le_bytes(self.total_fees)
+  }
+
+  // loans, amount borrowed and fees of the day `slot` falls in, zero once that day has
+  // passed without a loan
+  pub fn today(&self, slot: u64) -> (u64, u64, u64) {
+    if u64::from_le_bytes(self.day).ne(&(slot / SLOTS_PER_DAY)) {
+      return (0, 0, 0);
+    }
+    (
+      u64::from_le_bytes(self.loans_today),
+      u64::from_le_bytes(self.borrowed_today),
+      u64::from_le_bytes(self.fees_today),
+    )
+  }
+
+  // counters saturate rather than overflow, a full counter must never fail a loan
+  pub fn record_loan(&mut self, borrowed: u64, fees: u64, slot: u64) {
+    self.loans_issued = self.loans_issued().saturating_add(1).to_le_bytes();
+    self.total_borrowed = self.total_borrowed().saturating_add(borrowed).to_le_bytes();
+    self.total_fees = self.total_fees().saturating_add(fees).to_le_bytes();
+
+    let (loans, borrowed_today, fees_today) = self.today(slot);
+    self.day = (slot / SLOTS_PER_DAY).to_le_bytes();
+    self.loans_today = loans.saturating_add(1).to_le_bytes();
+    self.borrowed_today = borrowed_today.saturating_add(borrowed).to_le_bytes();
+    self.fees_today = fees_today.saturating_add(fees).to_le_bytes();
+  }
+}
+
+pub struct InitializeMetricsAccounts<'a> {
+  pub payer: &'a AccountInfo,
+  pub metrics: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for InitializeMetricsAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [payer, metrics, _system_program] = accounts else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(payer)?;
+
+    Ok(Self { payer, metrics })
+  }
+}
+
+pub struct InitializeMetrics<'a> {
+  pub accounts: InitializeMetricsAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for InitializeMetrics<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let accounts = InitializeMetricsAccounts::try_from(accounts)?;
+
+    Ok(Self { accounts })
+  }
+}
+
+impl<'a> InitializeMetrics<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &11;
+
+  /// Creates the program's metrics account with every counter at zero. Anyone may pay for
+  /// it: it holds nothing but counters, and only once.
+  pub fn process(&mut self) -> ProgramResult {
+    let (metrics_key, bump) = find_program_address(&[b"metrics"], &ID);
+    if metrics_key.ne(self.accounts.metrics.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+    if self.accounts.metrics.data_len().ne(&0) {
+      return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let bump = [bump];
+    let seeds = [Seed::from(b"metrics"), Seed::from(&bump)];
+
+    CreateAccount {
+      from: self.accounts.payer,
+      to: self.accounts.metrics,
+      lamports: Rent::get()?.minimum_balance(Metrics::LEN),
+      space: Metrics::LEN as u64,
+      owner: &ID,
+    }
+    .invoke_signed(&[Signer::from(&seeds)])?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn record_loan_accumulates() {
+    let mut data = [0u8; Metrics::LEN];
+    let metrics = Metrics::load_mut(&mut data).unwrap();
+
+    metrics.record_loan(1_000, 5, 0);
+    metrics.record_loan(250, 0, 1);
+    metrics.record_loan(40, 2, 2);
+
+    assert_eq!(metrics.loans_issued(), 3);
+    assert_eq!(metrics.total_borrowed(), 1_290);
+    assert_eq!(metrics.total_fees(), 7);
+  }
+
+  #[test]
+  fn record_loan_saturates() {
+    let mut data = [0u8; Metrics::LEN];
+    let metrics = Metrics::load_mut(&mut data).unwrap();
+
+    metrics.record_loan(u64::MAX - 1, u64::MAX, 0);
+    metrics.record_loan(10, 1, 0);
+
+    assert_eq!(metrics.loans_issued(), 2);
+    assert_eq!(metrics.total_borrowed(), u64::MAX);
+    assert_eq!(metrics.total_fees(), u64::MAX);
+
+    assert_eq!(metrics.today(0), (2, u64::MAX, u64::MAX));
+
+    metrics.loans_issued = u64::MAX.to_le_bytes();
+    metrics.record_loan(0, 0, 0);
+    assert_eq!(metrics.loans_issued(), u64::MAX);
+  }
+
+  #[test]
+  fn daily_counters_roll_over() {
+    let mut data = [0u8; Metrics::LEN];
+    let metrics = Metrics::load_mut(&mut data).unwrap();
+
+    metrics.record_loan(1_000, 5, SLOTS_PER_DAY * 3);
+    metrics.record_loan(500, 2, SLOTS_PER_DAY * 4 - 1);
+    assert_eq!(metrics.today(SLOTS_PER_DAY * 3 + 10), (2, 1_500, 7));
+
+    // the next day reads as empty before any loan, and its first loan starts over
+    assert_eq!(metrics.today(SLOTS_PER_DAY * 4), (0, 0, 0));
+    metrics.record_loan(80, 1, SLOTS_PER_DAY * 4);
+    assert_eq!(metrics.today(SLOTS_PER_DAY * 4), (1, 80, 1));
+    assert_eq!(metrics.loans_issued(), 3);
+    assert_eq!(metrics.total_fees(), 8);
+  }
+
+  #[test]
+  fn load_checks_the_length() {
+    assert!(Metrics::load(&[0u8; Metrics::LEN - 1]).is_err());
+    assert!(Metrics::load(&[0u8; Metrics::LEN + 1]).is_err());
+    assert_eq!(
+      Metrics::load(&[0u8; Metrics::LEN]).unwrap().loans_issued(),
+      0
+    );
+  }
+}
@@ -0,0 +1,181 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{AccountMeta, Instruction, Seed, Signer},
+  program::{get_return_data, invoke},
+  program_error::ProgramError,
+  pubkey::{create_program_address, find_program_address},
+  sysvars::{rent::Rent, Sysvar},
+  ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::state::TokenAccount;
+use pinocchio_token_2022::instructions::InitializeAccount3;
+
+use crate::{SignerAccount, ID};
+
+// size of a token account for `mint`. Token-2022 accounts carry whatever extensions their mint
+// requires, so that program is asked with GetAccountDataSize
+fn token_account_size(mint: &AccountInfo) -> Result<usize, ProgramError> {
+  if mint.is_owned_by(&pinocchio_token::ID) {
+    return Ok(TokenAccount::LEN);
+  }
+
+  invoke(
+    &Instruction {
+      program_id: &pinocchio_token_2022::ID,
+      accounts: &[AccountMeta::readonly(mint.key())],
+      // GetAccountDataSize, asking for no extensions beyond the mint's own
+      data: &[21],
+    },
+    &[mint],
+  )?;
+
+  let return_data = get_return_data().ok_or(ProgramError::InvalidAccountData)?;
+  if return_data.program_id().ne(&pinocchio_token_2022::ID) {
+    return Err(ProgramError::InvalidAccountData);
+  }
+  let size: [u8; 8] = return_data
+    .as_slice()
+    .try_into()
+    .map_err(|_| ProgramError::InvalidAccountData)?;
+  Ok(u64::from_le_bytes(size) as usize)
+}
+
+pub struct InitializeProtocolAccounts<'a> {
+  pub payer: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub protocol_token_account: &'a AccountInfo,
+  pub mint: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for InitializeProtocolAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [payer, protocol, protocol_token_account, mint, _token_program, _system_program] =
+      accounts
+    else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(payer)?;
+
+    if !mint.is_owned_by(&pinocchio_token::ID) && !mint.is_owned_by(&pinocchio_token_2022::ID) {
+      return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    Ok(Self {
+      payer,
+      protocol,
+      protocol_token_account,
+      mint,
+    })
+  }
+}
+
+pub struct InitializeProtocolInstructionData {
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+}
+
+impl<'a> TryFrom<&'a [u8]> for InitializeProtocolInstructionData {
+  type Error = ProgramError;
+
+  fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+    if data.len() != size_of::<u16>() + size_of::<u8>() {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(Self {
+      protocol_fee: [data[0], data[1]],
+      protocol_bump: [data[2]],
+    })
+  }
+}
+
+pub struct InitializeProtocol<'a> {
+  pub accounts: InitializeProtocolAccounts<'a>,
+  pub instruction_data: InitializeProtocolInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for InitializeProtocol<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = InitializeProtocolAccounts::try_from(accounts)?;
+    let instruction_data = InitializeProtocolInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> InitializeProtocol<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &8;
+
+  /// Creates the protocol's token account for `mint` at ["vault", protocol, mint], owned by
+  /// the protocol PDA, so providers can fund it through ProvideLiquidity and Loan can lend
+  /// from it. The mint may belong to either token program, the vault belongs to the same one.
+  /// Anyone may pay for it; it only ever belongs to the protocol.
+  pub fn process(&mut self) -> ProgramResult {
+    let protocol = create_program_address(
+      &[
+        b"protocol",
+        &self.instruction_data.protocol_fee,
+        &self.instruction_data.protocol_bump,
+      ],
+      &ID,
+    )?;
+    if protocol.ne(self.accounts.protocol.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (vault_key, bump) = find_program_address(
+      &[
+        b"vault",
+        self.accounts.protocol.key(),
+        self.accounts.mint.key(),
+      ],
+      &ID,
+    );
+    if vault_key.ne(self.accounts.protocol_token_account.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+    if self.accounts.protocol_token_account.data_len().ne(&0) {
+      return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let bump = [bump];
+    let seeds = [
+      Seed::from(b"vault"),
+      Seed::from(self.accounts.protocol.key()),
+      Seed::from(self.accounts.mint.key()),
+      Seed::from(&bump),
+    ];
+
+    let token_program = match self.accounts.mint.is_owned_by(&pinocchio_token::ID) {
+      true => &pinocchio_token::ID,
+      false => &pinocchio_token_2022::ID,
+    };
+    let space = token_account_size(self.accounts.mint)?;
+
+    CreateAccount {
+      from: self.accounts.payer,
+      to: self.accounts.protocol_token_account,
+      lamports: Rent::get()?.minimum_balance(space),
+      space: space as u64,
+      owner: token_program,
+    }
+    .invoke_signed(&[Signer::from(&seeds)])?;
+
+    InitializeAccount3 {
+      account: self.accounts.protocol_token_account,
+      mint: self.accounts.mint,
+      owner: self.accounts.protocol.key(),
+      token_program,
+    }
+    .invoke()
+  }
+}
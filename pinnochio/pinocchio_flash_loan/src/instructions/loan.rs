@@ -1,32 +1,71 @@
-use pinocchio::{account_info::AccountInfo, instruction::{Seed, Signer}, msg, program_error::ProgramError, sysvars::{instructions::{Instructions, INSTRUCTIONS_ID}, rent::Rent, Sysvar}, ProgramResult};
+use pinocchio::{account_info::AccountInfo, instruction::Signer, msg, program_error::ProgramError, pubkey::{find_program_address, Pubkey}, sysvars::{clock::Clock, instructions::{Instructions, INSTRUCTIONS_ID}, rent::Rent, Sysvar}, ProgramResult};
 use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::instructions::Transfer;
 
-use crate::{get_token_amount, LoanData, Repay, ID};
+use crate::{apply_bps, check_distinct_protocol_accounts, check_not_paused, check_top_level, stack_height, check_same_mint, get_token_amount, transfer_checked, LOAN_ENTRY_ACCOUNTS, signer_seeds, AccountInfoExt, BorrowCap, FreeLoans, LoanData, LoanEvent, Metrics, PinocchioError, Repay, SignerAccount, ID, MAX_LOAN_TOKENS};
 
 pub struct LoanAccounts<'a> {
     pub borrower: &'a AccountInfo,
     pub protocol: &'a AccountInfo,
     pub loan: &'a AccountInfo,
     pub instruction_sysvar: &'a AccountInfo,
+    // ["fee_split", protocol], empty if the protocol has none; a paused protocol lends nothing
+    pub fee_split: &'a AccountInfo,
+    // protocol token account, receiver and mint for every entry
     pub token_accounts: &'a [AccountInfo],
+    // one ["borrow_cap", protocol, mint] account per entry, in entry order
+    pub borrow_caps: &'a [AccountInfo],
+    pub free_loans: Option<&'a AccountInfo>,
+    pub metrics: Option<&'a AccountInfo>,
 }
  
 impl<'a> TryFrom<&'a [AccountInfo]> for LoanAccounts<'a> {
     type Error = ProgramError;
  
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [borrower, protocol, loan, instruction_sysvar, _token_program, _system_program, token_accounts @ ..] = accounts else {
+        let [borrower, protocol, loan, instruction_sysvar, _token_program, _system_program, fee_split, token_accounts @ ..] = accounts else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
+
+        // the protocol's free loan counter, when it runs a promotion, and then the program's
+        // metrics account, when the caller opts in, come ahead of the entries, and every entry
+        // has its borrow cap account after all of them. A lone optional account is the metrics
+        // one only if it sits at the metrics address
+        let per_entry = LOAN_ENTRY_ACCOUNTS + 1;
+        let (free_loans, metrics, token_accounts) = match token_accounts.len() % per_entry {
+            0 => (None, None, token_accounts),
+            1 => {
+                let (metrics_key, _) = find_program_address(&[b"metrics"], &ID);
+                match token_accounts[0].key().eq(&metrics_key) {
+                    true => (None, Some(&token_accounts[0]), &token_accounts[1..]),
+                    false => (Some(&token_accounts[0]), None, &token_accounts[1..]),
+                }
+            }
+            2 => {
+                let (metrics_key, _) = find_program_address(&[b"metrics"], &ID);
+                if token_accounts[1].key().ne(&metrics_key) {
+                    return Err(ProgramError::InvalidSeeds);
+                }
+                (Some(&token_accounts[0]), Some(&token_accounts[1]), &token_accounts[2..])
+            }
+            _ => return Err(ProgramError::NotEnoughAccountKeys),
+        };
+        let (token_accounts, borrow_caps) = token_accounts.split_at(token_accounts.len() / per_entry * LOAN_ENTRY_ACCOUNTS);
+ 
+        SignerAccount::check(borrower)?;
  
         if instruction_sysvar.key() != &INSTRUCTIONS_ID {
             return Err(ProgramError::UnsupportedSysvar);
         }
  
-        if (token_accounts.len() % 2).ne(&0) || token_accounts.len().eq(&0) {
+        if token_accounts.len().eq(&0) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+ 
+        if (token_accounts.len() / LOAN_ENTRY_ACCOUNTS).gt(&MAX_LOAN_TOKENS) {
             return Err(ProgramError::InvalidAccountData);
         }
+
+        check_distinct_protocol_accounts(token_accounts)?;
  
         if loan.try_borrow_data()?.len().ne(&0) {
             return Err(ProgramError::InvalidAccountData);
@@ -37,7 +76,11 @@ impl<'a> TryFrom<&'a [AccountInfo]> for LoanAccounts<'a> {
             protocol,
             loan,
             instruction_sysvar,
+            fee_split,
             token_accounts,
+            borrow_caps,
+            free_loans,
+            metrics,
         })
     }
 }
@@ -45,7 +88,9 @@ impl<'a> TryFrom<&'a [AccountInfo]> for LoanAccounts<'a> {
 pub struct LoanInstructionData<'a> {
     pub bump: [u8; 1],
     pub fee: u16,
-    pub amounts: &'a [u64],
+    // little-endian u64 amounts, one per loan entry; kept as bytes since instruction
+    // data has no alignment to borrow a &[u64] from
+    pub amounts: &'a [u8],
 }
  
 impl<'a> TryFrom<&'a [u8]> for LoanInstructionData<'a> {
@@ -60,14 +105,33 @@ impl<'a> TryFrom<&'a [u8]> for LoanInstructionData<'a> {
             return Err(ProgramError::InvalidInstructionData);
         }
  
-        let amounts: &[u64] = unsafe {
-            core::slice::from_raw_parts(
-                data.as_ptr() as *const u64,
-                data.len() / size_of::<u64>()
-            )
-        };
+        let instruction_data = Self { bump: [*bump], fee: u16::from_le_bytes(fee.try_into().map_err(|_| ProgramError::InvalidInstructionData)?), amounts: data };
+
+        // a loan account with no entries would be created zero-sized and prove nothing on repay
+        if instruction_data.amount_count().eq(&0) {
+            return Err(PinocchioError::EmptyLoan.into());
+        }
+
+        // per-mint minimums need the borrow cap accounts and are checked in process
+        if instruction_data.amounts().any(|amount| amount.eq(&0)) {
+            return Err(PinocchioError::LoanTooSmall.into());
+        }
  
-        Ok(Self { bump: [*bump], fee: u16::from_le_bytes(fee.try_into().map_err(|_| ProgramError::InvalidInstructionData)?), amounts })
+        Ok(instruction_data)
+    }
+}
+
+impl<'a> LoanInstructionData<'a> {
+    pub fn amount_count(&self) -> usize {
+        self.amounts.len() / size_of::<u64>()
+    }
+
+    // the amounts start right after the bump and fee, at an odd offset, so each one is read by
+    // value instead of through a reference that would be misaligned
+    pub fn amounts(&self) -> impl Iterator<Item = u64> + 'a {
+        self.amounts
+            .chunks_exact(size_of::<u64>())
+            .map(|amount| u64::from_le_bytes(amount.try_into().unwrap()))
     }
 }
 pub struct Loan<'a> {
@@ -82,7 +146,7 @@ impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Loan<'a> {
         let accounts = LoanAccounts::try_from(accounts)?;
         let instruction_data = LoanInstructionData::try_from(data)?;
  
-        if instruction_data.amounts.len() != accounts.token_accounts.len() / 2 {
+        if instruction_data.amount_count() != accounts.token_accounts.len() / LOAN_ENTRY_ACCOUNTS {
             return Err(ProgramError::InvalidInstructionData);
         }
  
@@ -97,16 +161,45 @@ impl<'a> Loan<'a> {
     pub const DISCRIMINATOR: &'a u8 = &0;
  
     pub fn process(&mut self) -> ProgramResult {
-        let fee = self.instruction_data.fee.to_le_bytes();
- 
-        let signer_seeds = [
-            Seed::from("protocol".as_bytes()),
-            Seed::from(&fee),
-            Seed::from(&self.instruction_data.bump),
-        ];
+        check_top_level(stack_height())?;
+
+        // try_from already matched these; checked again here since the loop below and the
+        // size of the loan account both rely on it
+        if self.instruction_data.amount_count() != self.accounts.token_accounts.len() / LOAN_ENTRY_ACCOUNTS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        signer_seeds!(signer_seeds = [
+            b"protocol",
+            self.instruction_data.fee.to_le_bytes(),
+            self.instruction_data.bump,
+        ]);
         let signer_seeds = [Signer::from(&signer_seeds)];
+
+        let (fee_split_key, _) = find_program_address(&[b"fee_split", self.accounts.protocol.key()], &ID);
+        if fee_split_key.ne(self.accounts.fee_split.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        check_not_paused(self.accounts.fee_split)?;
+
+        let free = self.take_free_loan()?;
+
+        // every cap, minimum and balance is checked before the first transfer goes out. The
+        // protocol token accounts are distinct, so each entry can only draw on its own account's
+        // balance
+        let mut fee_floors = [0u64; MAX_LOAN_TOKENS];
+        for (((amount, entry), borrow_cap), fee_floor) in self
+            .instruction_data
+            .amounts()
+            .zip(self.accounts.token_accounts.chunks_exact(LOAN_ENTRY_ACCOUNTS))
+            .zip(self.accounts.borrow_caps)
+            .zip(fee_floors.iter_mut())
+        {
+            *fee_floor = self.check_borrow_cap(borrow_cap, &entry[0], amount)?;
+            check_liquidity(&entry[0], amount)?;
+        }
  
-        let size = size_of::<LoanData>() * self.instruction_data.amounts.len();
+        let size = size_of::<LoanData>() * self.instruction_data.amount_count();
         let lamports = Rent::get()?.minimum_balance(size);
  
         CreateAccount {
@@ -118,53 +211,238 @@ impl<'a> Loan<'a> {
         }.invoke()?;
  
         let mut loan_data = self.accounts.loan.try_borrow_mut_data()?;
+        debug_assert!(loan_data.len() == size);
         let loan_entries = unsafe {
             core::slice::from_raw_parts_mut(
                 loan_data.as_mut_ptr() as *mut LoanData,
-                self.instruction_data.amounts.len()
+                self.instruction_data.amount_count()
             )
         };
 
-        for (i, amount) in self.instruction_data.amounts.iter().enumerate() {
-            let protocol_token_account = &self.accounts.token_accounts[i * 2];
-            let borrower_token_account = &self.accounts.token_accounts[i * 2 + 1];
+        let mut total_borrowed: u64 = 0;
+        let mut total_fees: u64 = 0;
+
+        // walking entries, amounts and entry accounts together leaves nothing to index out of
+        // bounds, whatever the lengths
+        for (((entry, amount), accounts), fee_floor) in loan_entries
+            .iter_mut()
+            .zip(self.instruction_data.amounts())
+            .zip(self.accounts.token_accounts.chunks_exact(LOAN_ENTRY_ACCOUNTS))
+            .zip(fee_floors)
+        {
+            // the receiver can be any token account of the loan's mint, e.g. a strategy vault;
+            // the obligation stays with the borrower, Repay only looks at the protocol side
+            let [protocol_token_account, receiver, mint] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
         
-            let balance = get_token_amount(&protocol_token_account.try_borrow_data()?);
-            let balance_with_fee = balance.checked_add(
-                amount.checked_mul(self.instruction_data.fee as u64)
-                    .and_then(|x| x.checked_div(10_000))
-                    .ok_or(ProgramError::InvalidInstructionData)?
-            ).ok_or(ProgramError::InvalidInstructionData)?;
+            let balance = get_token_amount(protocol_token_account)?;
+            check_same_mint(protocol_token_account, receiver)?;
+            let fee = match free {
+                true => 0,
+                false => apply_bps(amount, self.instruction_data.fee)?.max(fee_floor),
+            };
+            let balance_with_fee = balance.checked_add(fee).ok_or(PinocchioError::BalanceOverflow)?;
         
-            loan_entries[i] = LoanData {
+            *entry = LoanData {
                 protocol_token_account: *protocol_token_account.key(),
                 balance: balance_with_fee,
+                fee,
+                amount,
             };
         
-            Transfer {
-                from: protocol_token_account,
-                to: borrower_token_account,
-                authority: self.accounts.protocol,
-                amount: *amount,
-            }.invoke_signed(&signer_seeds)?;
+            transfer_checked(protocol_token_account, receiver, mint, self.accounts.protocol, amount, &signer_seeds)?;
+
+            LoanEvent {
+                protocol: *self.accounts.protocol.key(),
+                borrower: *self.accounts.borrower.key(),
+                protocol_token_account: *protocol_token_account.key(),
+                amount,
+                fee,
+            }.emit();
+
+            total_borrowed = total_borrowed.saturating_add(amount);
+            total_fees = total_fees.saturating_add(fee);
         }
 
+        self.record_metrics(total_borrowed, total_fees)?;
+
         let instruction_sysvar = unsafe { Instructions::new_unchecked(self.accounts.instruction_sysvar.try_borrow_data()?) };
         let num_instructions = instruction_sysvar.num_instructions();
-        let instruction = instruction_sysvar.load_instruction_at(num_instructions as usize - 1)?;
+        let repay_index = num_instructions as usize - 1;
+
+        // the repay must run after this loan, a repay at or before our own index proves nothing
+        if repay_index <= instruction_sysvar.load_current_index() as usize {
+            return Err(PinocchioError::LoanNotRepaid.into());
+        }
+
+        let instruction = instruction_sysvar.load_instruction_at(repay_index)?;
         
         if instruction.get_program_id() != &crate::ID {
-            return Err(ProgramError::InvalidInstructionData);
+            return Err(PinocchioError::LoanNotRepaid.into());
         }
         
         if unsafe { *(instruction.get_instruction_data().as_ptr()) } != *Repay::DISCRIMINATOR {
-            return Err(ProgramError::InvalidInstructionData);
+            return Err(PinocchioError::LoanNotRepaid.into());
         }
         
         if unsafe { instruction.get_account_meta_at_unchecked(1).key } != *self.accounts.loan.key() {
-            return Err(ProgramError::InvalidInstructionData);
+            return Err(PinocchioError::LoanNotRepaid.into());
+        }
+
+        // Repay closes the loan to its borrower, so the repay has to be signed by this loan's
+        // borrower and not by whoever else is in the transaction
+        if unsafe { instruction.get_account_meta_at_unchecked(0).key } != *self.accounts.borrower.key() {
+            return Err(PinocchioError::LoanNotRepaid.into());
+        }
+
+        Ok(())
+    }
+
+    // `borrow_cap` must be the protocol's cap account for the mint of `protocol_token_account`;
+    // until SetBorrowCap creates it the mint is uncapped, with no minimum and no fee floor.
+    // Returns the mint's fee floor
+    fn check_borrow_cap(
+        &self,
+        borrow_cap: &AccountInfo,
+        protocol_token_account: &AccountInfo,
+        amount: u64,
+    ) -> Result<u64, ProgramError> {
+        // the mint is the first field of a token account of either program
+        let mint: Pubkey = protocol_token_account
+            .try_borrow_data()?
+            .get(..32)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .try_into()
+            .unwrap();
+        let (borrow_cap_key, _) = find_program_address(
+            &[b"borrow_cap", self.accounts.protocol.key(), &mint],
+            &ID,
+        );
+        if borrow_cap_key.ne(borrow_cap.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if borrow_cap.data_len().eq(&0) {
+            return Ok(0);
         }
+        borrow_cap.require_owner(&ID)?;
+        let data = borrow_cap.try_borrow_data()?;
+        let borrow_cap = BorrowCap::load(&data)?;
+        if amount.gt(&borrow_cap.max_amount()) {
+            return Err(PinocchioError::BorrowCapExceeded.into());
+        }
+        if amount.lt(&borrow_cap.min_amount()) {
+            return Err(PinocchioError::LoanTooSmall.into());
+        }
+        Ok(borrow_cap.min_fee())
+    }
+
+    // adds this loan to the program's metrics when the caller passed the account; try_from
+    // already matched its address
+    fn record_metrics(&self, borrowed: u64, fees: u64) -> ProgramResult {
+        let Some(metrics) = self.accounts.metrics else {
+            return Ok(());
+        };
 
+        metrics.require_owner(&ID)?;
+        let mut data = metrics.try_borrow_mut_data()?;
+        Metrics::load_mut(&mut data)?.record_loan(borrowed, fees, Clock::get()?.slot);
         Ok(())
     }
-}
\ No newline at end of file
+
+    // uses up one of the protocol's free loans if any are left; the whole loan, every entry,
+    // then goes without a fee
+    fn take_free_loan(&self) -> Result<bool, ProgramError> {
+        let Some(free_loans) = self.accounts.free_loans else {
+            return Ok(false);
+        };
+
+        free_loans.require_owner(&ID)?;
+        let (free_loans_key, _) =
+            find_program_address(&[b"free_loans", self.accounts.protocol.key()], &ID);
+        if free_loans_key.ne(free_loans.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let mut data = free_loans.try_borrow_mut_data()?;
+        let free_loans = FreeLoans::load_mut(&mut data)?;
+        match free_loans.remaining() {
+            0 => Ok(false),
+            remaining => {
+                free_loans.set_remaining(remaining - 1);
+                Ok(true)
+            }
+        }
+    }
+}
+
+// `amount` can be lent out of `protocol_token_account` as it stands, before any transfer
+fn check_liquidity(protocol_token_account: &AccountInfo, amount: u64) -> ProgramResult {
+    if amount.gt(&get_token_amount(protocol_token_account)?) {
+        return Err(PinocchioError::InsufficientLiquidity.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pinocchio_token::state::TokenAccount;
+
+    use super::*;
+    use crate::RawAccount;
+
+    // bump | fee | amounts
+    fn data(amounts: &[u64]) -> ([u8; 3 + 8 * MAX_LOAN_TOKENS], usize) {
+        let mut data = [0u8; 3 + 8 * MAX_LOAN_TOKENS];
+        data[0] = 254;
+        data[1..3].copy_from_slice(&5u16.to_le_bytes());
+        for (i, amount) in amounts.iter().enumerate() {
+            data[3 + i * 8..11 + i * 8].copy_from_slice(&amount.to_le_bytes());
+        }
+        (data, 3 + amounts.len() * 8)
+    }
+
+    #[test]
+    fn loans_beyond_the_protocol_balance_are_refused() {
+        let mut data = [0u8; TokenAccount::LEN];
+        data[64..72].copy_from_slice(&1_000u64.to_le_bytes());
+        let mut vault = RawAccount::new(false, &pinocchio_token::ID, &data);
+        let vault = vault.info();
+
+        assert_eq!(check_liquidity(&vault, 1_000), Ok(()));
+        assert_eq!(
+            check_liquidity(&vault, 1_001),
+            Err(PinocchioError::InsufficientLiquidity.into())
+        );
+    }
+
+    #[test]
+    fn instruction_data_needs_at_least_one_amount() {
+        let (data, len) = data(&[]);
+        assert_eq!(
+            LoanInstructionData::try_from(&data[..len]).err(),
+            Some(PinocchioError::EmptyLoan.into())
+        );
+    }
+
+    #[test]
+    fn instruction_data_reads_every_amount() {
+        let (data, len) = data(&[1_000, u64::MAX]);
+        let parsed = LoanInstructionData::try_from(&data[..len]).unwrap();
+        assert_eq!(parsed.bump, [254]);
+        assert_eq!(parsed.fee, 5);
+        assert_eq!(parsed.amount_count(), 2);
+        assert!(parsed.amounts().eq([1_000, u64::MAX]));
+
+        // a zero amount, a partial amount, or no fee at all
+        let (data, len) = self::data(&[1_000, 0]);
+        assert_eq!(
+            LoanInstructionData::try_from(&data[..len]).err(),
+            Some(PinocchioError::LoanTooSmall.into())
+        );
+        let (data, len) = self::data(&[1_000]);
+        assert!(LoanInstructionData::try_from(&data[..len - 1]).is_err());
+        assert!(LoanInstructionData::try_from(&data[..2]).is_err());
+    }
+}
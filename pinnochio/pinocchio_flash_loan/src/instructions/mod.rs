@@ -5,4 +5,70 @@ pub mod loan;
 pub use loan::*; 
 
 pub mod repay;
-pub use repay::*; 
\ No newline at end of file
+pub use repay::*; 
+
+pub mod get_protocol_health;
+pub use get_protocol_health::*;
+
+pub mod get_borrow_capacity;
+pub use get_borrow_capacity::*;
+
+pub mod set_fee_split;
+pub use set_fee_split::*;
+
+pub mod provide_liquidity;
+pub use provide_liquidity::*;
+
+pub mod withdraw_liquidity;
+pub use withdraw_liquidity::*;
+
+pub mod set_free_loans;
+pub use set_free_loans::*;
+
+pub mod initialize_protocol;
+pub use initialize_protocol::*;
+
+pub mod emergency_drain;
+pub use emergency_drain::*;
+
+pub mod set_borrow_cap;
+pub use set_borrow_cap::*;
+
+pub mod initialize_metrics;
+pub use initialize_metrics::*;
+
+pub mod set_paused;
+pub use set_paused::*;
+
+pub mod recover_stray_tokens;
+pub use recover_stray_tokens::*;
+
+// one per instruction, each dispatched on its own in lib.rs
+pub const DISCRIMINATORS: &[u8] = &[
+    *Loan::DISCRIMINATOR,
+    *Repay::DISCRIMINATOR,
+    *GetProtocolHealth::DISCRIMINATOR,
+    *GetBorrowCapacity::DISCRIMINATOR,
+    *SetFeeSplit::DISCRIMINATOR,
+    *ProvideLiquidity::DISCRIMINATOR,
+    *WithdrawLiquidity::DISCRIMINATOR,
+    *SetFreeLoans::DISCRIMINATOR,
+    *InitializeProtocol::DISCRIMINATOR,
+    *EmergencyDrain::DISCRIMINATOR,
+    *SetBorrowCap::DISCRIMINATOR,
+    *InitializeMetrics::DISCRIMINATOR,
+    *SetPaused::DISCRIMINATOR,
+    *RecoverStrayTokens::DISCRIMINATOR,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discriminators_are_unique() {
+        for (i, discriminator) in DISCRIMINATORS.iter().enumerate() {
+            assert!(!DISCRIMINATORS[i + 1..].contains(discriminator));
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program_error::ProgramError,
+  pubkey::{create_program_address, find_program_address, Pubkey},
+  sysvars::{
+    instructions::{Instructions, INSTRUCTIONS_ID},
+    rent::Rent,
+    Sysvar,
+  },
+  ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::{
+  check_top_level, get_token_amount, stack_height, token_program_of, transfer_checked, Loan,
+  PinocchioError, SignerAccount, ID,
+};
+
+// what one provider put into one protocol token account, at ["liquidity", vault, provider]
+#[repr(C)]
+pub struct LiquidityPosition {
+  pub provider: Pubkey,
+  pub protocol_token_account: Pubkey,
+  pub amount: [u8; 8],
+}
+
+impl LiquidityPosition {
+  pub const LEN: usize = size_of::<LiquidityPosition>();
+
+  pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &*(data.as_ptr() as *const Self) })
+  }
+
+  pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+  }
+
+  pub fn amount(&self) -> u64 {
+    u64::from_le_bytes(self.amount)
+  }
+}
+
+// the protocol PDA for `protocol_fee` and `protocol_bump`, which must also own the vault. The
+// vault has to sit at ["vault", protocol, mint], where InitializeProtocol creates it, so any
+// other token account the protocol owns holds nothing a position accounts for
+pub(crate) fn check_protocol_vault(
+  protocol: &AccountInfo,
+  protocol_token_account: &AccountInfo,
+  protocol_fee: &[u8; 2],
+  protocol_bump: &[u8; 1],
+) -> ProgramResult {
+  let key = create_program_address(&[b"protocol", protocol_fee, protocol_bump], &ID)?;
+  if key.ne(protocol.key()) {
+    return Err(ProgramError::InvalidSeeds);
+  }
+  token_program_of(protocol_token_account)?;
+  let data = protocol_token_account.try_borrow_data()?;
+  // token account mint lives at bytes 0..32, owner at 32..64
+  if data[32..64].ne(protocol.key()) {
+    return Err(ProgramError::InvalidAccountData);
+  }
+  let (vault, _) = find_program_address(&[b"vault", protocol.key(), &data[..32]], &ID);
+  if vault.ne(protocol_token_account.key()) {
+    return Err(ProgramError::InvalidSeeds);
+  }
+  Ok(())
+}
+
+pub struct ProvideLiquidityAccounts<'a> {
+  pub provider: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub provider_token_account: &'a AccountInfo,
+  pub protocol_token_account: &'a AccountInfo,
+  pub mint: &'a AccountInfo,
+  pub position: &'a AccountInfo,
+  pub instruction_sysvar: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ProvideLiquidityAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [provider, protocol, provider_token_account, protocol_token_account, mint, position, instruction_sysvar, _token_program, _system_program] =
+      accounts
+    else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(provider)?;
+
+    if instruction_sysvar.key().ne(&INSTRUCTIONS_ID) {
+      return Err(ProgramError::UnsupportedSysvar);
+    }
+
+    Ok(Self {
+      provider,
+      protocol,
+      provider_token_account,
+      protocol_token_account,
+      mint,
+      position,
+      instruction_sysvar,
+    })
+  }
+}
+
+pub struct LiquidityInstructionData {
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+  pub amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for LiquidityInstructionData {
+  type Error = ProgramError;
+
+  fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+    if data.len().ne(&(size_of::<u16>() + size_of::<u8>() + size_of::<u64>())) {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let amount = u64::from_le_bytes(data[3..11].try_into().unwrap());
+    if amount.eq(&0) {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(Self {
+      protocol_fee: [data[0], data[1]],
+      protocol_bump: [data[2]],
+      amount,
+    })
+  }
+}
+
+pub struct ProvideLiquidity<'a> {
+  pub accounts: ProvideLiquidityAccounts<'a>,
+  pub instruction_data: LiquidityInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for ProvideLiquidity<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = ProvideLiquidityAccounts::try_from(accounts)?;
+    let instruction_data = LiquidityInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> ProvideLiquidity<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &5;
+
+  /// Moves `amount` from the provider into a protocol token account and credits what arrived
+  /// to the provider's `LiquidityPosition`, creating the position on first use. A Token-2022
+  /// transfer fee is withheld on the way in, so the credit can be less than `amount`.
+  pub fn process(&mut self) -> ProgramResult {
+    check_top_level(stack_height())?;
+
+    check_protocol_vault(
+      self.accounts.protocol,
+      self.accounts.protocol_token_account,
+      &self.instruction_data.protocol_fee,
+      &self.instruction_data.protocol_bump,
+    )?;
+
+    // borrowed tokens deposited back would pass Repay's balance check and be credited as
+    // the borrower's own liquidity, so no deposits while a loan is open in this transaction.
+    // Loan and this instruction both run top-level only, so the sysvar lists every loan
+    let instruction_sysvar =
+      unsafe { Instructions::new_unchecked(self.accounts.instruction_sysvar.try_borrow_data()?) };
+    for i in 0..instruction_sysvar.num_instructions() as usize {
+      let instruction = instruction_sysvar.load_instruction_at(i)?;
+      if instruction.get_program_id().eq(&ID)
+        && instruction.get_instruction_data().first().eq(&Some(Loan::DISCRIMINATOR))
+      {
+        return Err(PinocchioError::LoanInProgress.into());
+      }
+    }
+
+    let (position_key, bump) = find_program_address(
+      &[
+        b"liquidity",
+        self.accounts.protocol_token_account.key(),
+        self.accounts.provider.key(),
+      ],
+      &ID,
+    );
+    if position_key.ne(self.accounts.position.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    if self.accounts.position.data_len().eq(&0) {
+      let bump = [bump];
+      let seeds = [
+        Seed::from(b"liquidity"),
+        Seed::from(self.accounts.protocol_token_account.key()),
+        Seed::from(self.accounts.provider.key()),
+        Seed::from(&bump),
+      ];
+
+      CreateAccount {
+        from: self.accounts.provider,
+        to: self.accounts.position,
+        lamports: Rent::get()?.minimum_balance(LiquidityPosition::LEN),
+        space: LiquidityPosition::LEN as u64,
+        owner: &ID,
+      }
+      .invoke_signed(&[Signer::from(&seeds)])?;
+
+      let mut data = self.accounts.position.try_borrow_mut_data()?;
+      let position = LiquidityPosition::load_mut(&mut data)?;
+      position.provider = *self.accounts.provider.key();
+      position.protocol_token_account = *self.accounts.protocol_token_account.key();
+    } else if !self.accounts.position.is_owned_by(&ID) {
+      return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let balance = get_token_amount(self.accounts.protocol_token_account)?;
+    transfer_checked(
+      self.accounts.provider_token_account,
+      self.accounts.protocol_token_account,
+      self.accounts.mint,
+      self.accounts.provider,
+      self.instruction_data.amount,
+      &[],
+    )?;
+    let received = get_token_amount(self.accounts.protocol_token_account)?
+      .checked_sub(balance)
+      .ok_or(ProgramError::InvalidAccountData)?;
+
+    let mut data = self.accounts.position.try_borrow_mut_data()?;
+    let position = LiquidityPosition::load_mut(&mut data)?;
+    position.amount = position
+      .amount()
+      .checked_add(received)
+      .ok_or(PinocchioError::BalanceOverflow)?
+      .to_le_bytes();
+
+    Ok(())
+  }
+}
@@ -0,0 +1,212 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program_error::ProgramError,
+  pubkey::{create_program_address, find_program_address, Pubkey},
+  ProgramResult,
+};
+use pinocchio_log::log;
+
+use crate::{check_same_mint, get_token_amount, transfer_checked, FeeSplit, SignerAccount, ID};
+
+// the balance of `stray`, a token account the protocol owns that isn't `vault`, its
+// ["vault", protocol, mint] account for the same mint. Liquidity is only ever recorded against
+// that vault, so whatever any other account of the protocol holds was sent there by mistake
+pub fn stray_amount(
+  stray: &AccountInfo,
+  protocol: &Pubkey,
+  vault: &Pubkey,
+) -> Result<u64, ProgramError> {
+  let amount = get_token_amount(stray)?;
+  // token account owner lives at bytes 32..64
+  if stray.try_borrow_data()?[32..64].ne(protocol) {
+    return Err(ProgramError::InvalidAccountData);
+  }
+  if stray.key().eq(vault) {
+    return Err(ProgramError::InvalidArgument);
+  }
+  if amount.eq(&0) {
+    return Err(ProgramError::InsufficientFunds);
+  }
+  Ok(amount)
+}
+
+pub struct RecoverStrayTokensAccounts<'a> {
+  pub authority: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub fee_split: &'a AccountInfo,
+  pub stray: &'a AccountInfo,
+  pub mint: &'a AccountInfo,
+  pub destination: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RecoverStrayTokensAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [authority, protocol, fee_split, stray, mint, destination, _token_program] = accounts
+    else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(authority)?;
+
+    if !fee_split.is_owned_by(&ID) {
+      return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    Ok(Self {
+      authority,
+      protocol,
+      fee_split,
+      stray,
+      mint,
+      destination,
+    })
+  }
+}
+
+pub struct RecoverStrayTokensInstructionData {
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+}
+
+impl<'a> TryFrom<&'a [u8]> for RecoverStrayTokensInstructionData {
+  type Error = ProgramError;
+
+  fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+    if data.len() != size_of::<u16>() + size_of::<u8>() {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(Self {
+      protocol_fee: [data[0], data[1]],
+      protocol_bump: [data[2]],
+    })
+  }
+}
+
+pub struct RecoverStrayTokens<'a> {
+  pub accounts: RecoverStrayTokensAccounts<'a>,
+  pub instruction_data: RecoverStrayTokensInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for RecoverStrayTokens<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = RecoverStrayTokensAccounts::try_from(accounts)?;
+    let instruction_data = RecoverStrayTokensInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> RecoverStrayTokens<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &13;
+
+  /// Admin recovery for tokens sent to a protocol PDA by mistake, e.g. to its ATA: moves the
+  /// whole balance of a token account the protocol owns to `destination`, any token account
+  /// of the same mint. The protocol's vault for that mint is always refused. Gated on the fee
+  /// split authority like SetFreeLoans.
+  pub fn process(&mut self) -> ProgramResult {
+    let protocol = create_program_address(
+      &[
+        b"protocol",
+        &self.instruction_data.protocol_fee,
+        &self.instruction_data.protocol_bump,
+      ],
+      &ID,
+    )?;
+    if protocol.ne(self.accounts.protocol.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (fee_split_key, _) = find_program_address(&[b"fee_split", &protocol], &ID);
+    if fee_split_key.ne(self.accounts.fee_split.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+    {
+      let data = self.accounts.fee_split.try_borrow_data()?;
+      if FeeSplit::load(&data)?.authority.ne(self.accounts.authority.key()) {
+        return Err(ProgramError::IncorrectAuthority);
+      }
+    }
+
+    let (vault, _) = find_program_address(&[b"vault", &protocol, self.accounts.mint.key()], &ID);
+    let amount = stray_amount(self.accounts.stray, &protocol, &vault)?;
+    check_same_mint(self.accounts.stray, self.accounts.destination)?;
+
+    let seeds = [
+      Seed::from(b"protocol"),
+      Seed::from(&self.instruction_data.protocol_fee),
+      Seed::from(&self.instruction_data.protocol_bump),
+    ];
+
+    // also checks that `mint` is the stray account's mint, so `vault` above was its vault
+    transfer_checked(
+      self.accounts.stray,
+      self.accounts.destination,
+      self.accounts.mint,
+      self.accounts.protocol,
+      amount,
+      &[Signer::from(&seeds)],
+    )?;
+
+    log!("RecoverStrayTokens: moved {} tokens", amount);
+    pinocchio::pubkey::log(self.accounts.stray.key());
+    pinocchio::pubkey::log(self.accounts.destination.key());
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::RawAccount;
+  use pinocchio_token::state::TokenAccount;
+
+  const PROTOCOL: Pubkey = [1u8; 32];
+  const VAULT: Pubkey = [2u8; 32];
+  const STRAY: Pubkey = [3u8; 32];
+
+  fn token_account(key: &Pubkey, owner: &Pubkey, amount: u64) -> RawAccount {
+    let mut data = [0u8; TokenAccount::LEN];
+    data[32..64].copy_from_slice(owner);
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    RawAccount::new(false, &pinocchio_token::ID, &data).with_key(key)
+  }
+
+  #[test]
+  fn recovers_tokens_the_protocol_holds_outside_its_vault() {
+    let mut stray = token_account(&STRAY, &PROTOCOL, 42);
+    assert_eq!(stray_amount(&stray.info(), &PROTOCOL, &VAULT), Ok(42));
+  }
+
+  #[test]
+  fn refuses_the_protocol_vault() {
+    let mut vault = token_account(&VAULT, &PROTOCOL, 42);
+    assert_eq!(
+      stray_amount(&vault.info(), &PROTOCOL, &VAULT),
+      Err(ProgramError::InvalidArgument)
+    );
+  }
+
+  #[test]
+  fn refuses_accounts_the_protocol_does_not_own_or_empty_ones() {
+    let mut foreign = token_account(&STRAY, &[9u8; 32], 42);
+    assert_eq!(
+      stray_amount(&foreign.info(), &PROTOCOL, &VAULT),
+      Err(ProgramError::InvalidAccountData)
+    );
+    let mut empty = token_account(&STRAY, &PROTOCOL, 0);
+    assert_eq!(
+      stray_amount(&empty.info(), &PROTOCOL, &VAULT),
+      Err(ProgramError::InsufficientFunds)
+    );
+  }
+}
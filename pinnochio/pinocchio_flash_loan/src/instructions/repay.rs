@@ -1,8 +1,20 @@
 use core::mem::size_of;
 
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program::set_return_data,
+  program_error::ProgramError,
+  pubkey::find_program_address,
+  ProgramResult,
+};
+use pinocchio_log::log;
 
-use crate::helpers::{get_token_amount, LoanData};
+use crate::helpers::{
+  check_same_mint, check_top_level, close_program_account, get_token_amount, stack_height,
+  transfer_checked, LoanData, MAX_REPAY_TOKENS,
+};
+use crate::{AccountInfoExt, FeeSplit, PinocchioError, SignerAccount, ID};
 
 pub struct Repay<'a> {
   pub accounts: RepayAccounts<'a>,
@@ -22,64 +34,308 @@ impl<'a> Repay<'a> {
   pub const DISCRIMINATOR: &'a u8 = &1;
 
   pub fn process(&mut self) -> ProgramResult {
+    check_top_level(stack_height())?;
+
     let loan_data = self.accounts.loan.try_borrow_data()?;
     let loan_num = loan_data.len() / size_of::<LoanData>();
 
     if loan_num.ne(&self.accounts.token_accounts.len()) {
-      return Err(ProgramError::InvalidAccountData);
+      return Err(PinocchioError::LoanMismatch.into());
     }
 
+    let mut total_repaid: u128 = 0;
+
     for i in 0..loan_num {
       let protocol_token_account = &self.accounts.token_accounts[i];
 
+      debug_assert!((i + 1) * size_of::<LoanData>() <= loan_data.len());
       if unsafe { *(loan_data.as_ptr().add(i * size_of::<LoanData>()) as *const [u8; 32]) }
         != *protocol_token_account.key()
       {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(PinocchioError::LoanMismatch.into());
       }
 
-      let balance = get_token_amount(&protocol_token_account.try_borrow_data()?);
+      let balance = get_token_amount(protocol_token_account)?;
       let loan_balance = unsafe {
-        *(loan_data
+        (loan_data
           .as_ptr()
           .add(i * size_of::<LoanData>() + size_of::<[u8; 32]>()) as *const u64)
+          .read_unaligned()
       };
 
-      if balance < loan_balance {
-        return Err(ProgramError::InvalidAccountData);
-      }
-    }
+      let amount = unsafe {
+        (loan_data
+          .as_ptr()
+          .add(i * size_of::<LoanData>() + size_of::<[u8; 32]>() + 2 * size_of::<u64>())
+          as *const u64)
+          .read_unaligned()
+      };
 
-    unsafe {
-      *self.accounts.borrower.borrow_mut_lamports_unchecked() +=
-        *self.accounts.loan.borrow_lamports_unchecked();
-      *self.accounts.loan.borrow_mut_lamports_unchecked() = 0;
+      // principal plus fee on top of the pre-loan balance, to the unit. Nothing else can credit
+      // the account between Loan and Repay: Loan pins Repay as the last instruction and
+      // ProvideLiquidity refuses to run alongside a loan, so any inflow is the borrower's own.
+      check_repaid(i, balance, loan_balance, amount)?;
 
-      self.accounts.loan.close_unchecked();
+      total_repaid += loan_balance as u128;
     }
 
-    Ok(())
+    distribute_fees(&loan_data, self.accounts.token_accounts, &self.accounts.fee_accounts)?;
+
+    // entries: u8 | total_repaid: u128, the sum of the settled balances across all entries
+    let mut return_data = [0u8; size_of::<u8>() + size_of::<u128>()];
+    return_data[0] = loan_num as u8;
+    return_data[1..].copy_from_slice(&total_repaid.to_le_bytes());
+    set_return_data(&return_data);
+
+    drop(loan_data);
+    close_program_account(self.accounts.loan, self.accounts.borrower)
+  }
+}
+
+// fails unless loan entry `entry` is back at `loan_balance`, logging the exact shortfall against
+// the `amount` borrowed for integrators
+fn check_repaid(entry: usize, balance: u64, loan_balance: u64, amount: u64) -> ProgramResult {
+  if balance < loan_balance {
+    log!(
+      "Repay: entry {} short by {} on {} borrowed",
+      entry,
+      loan_balance - balance,
+      amount
+    );
+    return Err(PinocchioError::RepaymentShortfall.into());
   }
+  Ok(())
 }
 
 pub struct RepayAccounts<'a> {
   pub borrower: &'a AccountInfo,
   pub loan: &'a AccountInfo,
   pub token_accounts: &'a [AccountInfo],
+  pub fee_accounts: RepayFeeAccounts<'a>,
+}
+
+// always passed after the token accounts, so a borrower can't skip the split by leaving it out
+pub struct RepayFeeAccounts<'a> {
+  pub protocol: &'a AccountInfo,
+  // ["fee_split", protocol], empty while the protocol has no split
+  pub fee_split: &'a AccountInfo,
+  // the mint of every loan entry, in entry order
+  pub mints: &'a [AccountInfo],
+  // one token account per recipient for every loan entry, entry-major
+  pub recipient_accounts: &'a [AccountInfo],
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for RepayAccounts<'a> {
   type Error = ProgramError;
 
   fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-    let [borrower, loan, token_accounts @ ..] = accounts else {
+    let [borrower, loan, remaining @ ..] = accounts else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(borrower)?;
+
+    // a loan already repaid in this transaction is closed and empty, and with no entries the
+    // checks below would pass it trivially
+    loan.require_owner(&ID)?;
+    if loan.data_len().eq(&0) || loan.data_len() % size_of::<LoanData>() != 0 {
+      return Err(ProgramError::UninitializedAccount);
+    }
+
+    let loan_num = loan.data_len() / size_of::<LoanData>();
+    if loan_num.gt(&MAX_REPAY_TOKENS) {
+      return Err(ProgramError::InvalidAccountData);
+    }
+
+    let (token_accounts, fee_accounts) = remaining
+      .split_at_checked(loan_num)
+      .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let [protocol, fee_split, _token_program, fee_accounts @ ..] = fee_accounts else {
       return Err(ProgramError::NotEnoughAccountKeys);
     };
+    let (mints, recipient_accounts) = fee_accounts
+      .split_at_checked(loan_num)
+      .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let fee_accounts = RepayFeeAccounts {
+      protocol,
+      fee_split,
+      mints,
+      recipient_accounts,
+    };
 
     Ok(Self {
       borrower,
       loan,
       token_accounts,
+      fee_accounts,
     })
   }
-}
\ No newline at end of file
+}
+// pays every recipient its weighted share of each entry's fee out of the protocol token account,
+// when the protocol the loan was taken from has a fee split
+fn distribute_fees(
+  loan_data: &[u8],
+  token_accounts: &[AccountInfo],
+  fee_accounts: &RepayFeeAccounts,
+) -> ProgramResult {
+  // the split is looked up from the protocol, so that has to be the one the loan drew on
+  for protocol_token_account in token_accounts {
+    // token account owner lives at bytes 32..64
+    if protocol_token_account.try_borrow_data()?[32..64].ne(fee_accounts.protocol.key()) {
+      return Err(PinocchioError::LoanMismatch.into());
+    }
+  }
+
+  let (fee_split_key, _) = find_program_address(&[b"fee_split", fee_accounts.protocol.key()], &ID);
+  if fee_split_key.ne(fee_accounts.fee_split.key()) {
+    return Err(ProgramError::InvalidSeeds);
+  }
+  if fee_accounts.fee_split.data_len().eq(&0) {
+    if !fee_accounts.recipient_accounts.is_empty() {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    return Ok(());
+  }
+  fee_accounts.fee_split.require_owner(&ID)?;
+
+  let fee_split_data = fee_accounts.fee_split.try_borrow_data()?;
+  let fee_split = FeeSplit::load(&fee_split_data)?;
+  let recipients = fee_split.recipients();
+
+  if fee_accounts
+    .recipient_accounts
+    .len()
+    .ne(&(token_accounts.len() * recipients.len()))
+  {
+    return Err(ProgramError::NotEnoughAccountKeys);
+  }
+
+  let signer_seeds = [
+    Seed::from(b"protocol"),
+    Seed::from(&fee_split.protocol_fee),
+    Seed::from(&fee_split.protocol_bump),
+  ];
+  let signer = [Signer::from(&signer_seeds)];
+
+  for (i, ((protocol_token_account, mint), recipient_accounts)) in token_accounts
+    .iter()
+    .zip(fee_accounts.mints)
+    .zip(fee_accounts.recipient_accounts.chunks_exact(recipients.len()))
+    .enumerate()
+  {
+    debug_assert!((i + 1) * size_of::<LoanData>() <= loan_data.len());
+    let fee = unsafe {
+      (loan_data
+        .as_ptr()
+        .add(i * size_of::<LoanData>() + size_of::<[u8; 32]>() + size_of::<u64>()) as *const u64)
+        .read_unaligned()
+    };
+
+    for (r, (recipient, recipient_account)) in
+      recipients.iter().zip(recipient_accounts).enumerate()
+    {
+      let share = fee_split.share(r, fee);
+      if share.eq(&0) {
+        continue;
+      }
+
+      check_same_mint(protocol_token_account, recipient_account)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+      // token account owner lives at bytes 32..64
+      if recipient_account.try_borrow_data()?[32..64].ne(recipient) {
+        return Err(ProgramError::InvalidAccountData);
+      }
+
+      transfer_checked(
+        protocol_token_account,
+        recipient_account,
+        mint,
+        fee_accounts.protocol,
+        share,
+        &signer,
+      )?;
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::RawAccount;
+
+  // borrower, a one-entry loan, its token account, then protocol, fee split, token program
+  // and the entry's mint
+  fn accounts(loan: RawAccount) -> [RawAccount; 7] {
+    let mut loan = Some(loan);
+    core::array::from_fn(|i| match i {
+      0 => RawAccount::new(true, &[0u8; 32], &[]).with_lamports(1_000_000),
+      1 => loan.take().unwrap(),
+      _ => RawAccount::new(false, &ID, &[]),
+    })
+  }
+
+  #[test]
+  fn a_repaid_loan_cannot_be_repaid_again() {
+    let loan = RawAccount::new(false, &ID, &[0u8; size_of::<LoanData>()]).with_lamports(500_000);
+    let mut raw = accounts(loan);
+    let infos = raw.each_mut().map(|account| account.info());
+    assert!(RepayAccounts::try_from(&infos[..]).is_ok());
+
+    // the first Repay closes the loan: the runtime zeroes its owner, lamports and length.
+    // Host builds have no syscall to do that, so the closed account is built as it ends up
+    let mut raw = accounts(RawAccount::new(false, &[0u8; 32], &[]));
+    let infos = raw.each_mut().map(|account| account.info());
+    assert_eq!(
+      RepayAccounts::try_from(&infos[..]).err(),
+      Some(ProgramError::InvalidAccountOwner)
+    );
+  }
+
+  #[test]
+  fn repay_needs_a_live_loan_of_the_program() {
+    let cases = [
+      // the program's, but with no entries left
+      (
+        RawAccount::new(false, &ID, &[]),
+        ProgramError::UninitializedAccount,
+      ),
+      // not a whole number of entries
+      (
+        RawAccount::new(false, &ID, &[0u8; size_of::<LoanData>() + 1]),
+        ProgramError::UninitializedAccount,
+      ),
+      // entry-shaped data in an account anyone could have written
+      (
+        RawAccount::new(false, &[9u8; 32], &[0u8; size_of::<LoanData>()]),
+        ProgramError::InvalidAccountOwner,
+      ),
+    ];
+    for (loan, error) in cases {
+      let mut raw = accounts(loan);
+      let infos = raw.each_mut().map(|account| account.info());
+      assert_eq!(RepayAccounts::try_from(&infos[..]).err(), Some(error));
+    }
+  }
+
+  #[test]
+  fn exact_repayment_settles_the_entry() {
+    // 1_000 lent out of 10_000 at a 5 fee: the account must be back at 10_005
+    assert_eq!(check_repaid(0, 10_005, 10_005, 1_000), Ok(()));
+    // anything on top is the borrower's loss, not an error
+    assert_eq!(check_repaid(0, 10_006, 10_005, 1_000), Ok(()));
+  }
+
+  #[test]
+  fn short_repayment_is_refused() {
+    // the principal without the fee, one unit short, and nothing back at all
+    for balance in [10_000, 10_004, 9_000] {
+      assert_eq!(
+        check_repaid(1, balance, 10_005, 1_000),
+        Err(PinocchioError::RepaymentShortfall.into())
+      );
+    }
+  }
+}
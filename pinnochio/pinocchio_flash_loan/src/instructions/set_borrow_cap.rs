@@ -0,0 +1,255 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program_error::ProgramError,
+  pubkey::{create_program_address, find_program_address, Pubkey},
+  sysvars::{rent::Rent, Sysvar},
+  ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::{AccountInfoExt, FeeSplit, SignerAccount, ID};
+
+// most and least a single loan entry of one mint may borrow from a protocol, and the least fee
+// it pays, at ["borrow_cap", protocol, mint]. Amounts are in the mint's own raw units
+#[repr(C)]
+pub struct BorrowCap {
+  pub protocol: Pubkey,
+  pub mint: Pubkey,
+  pub max_amount: [u8; 8],
+  pub min_amount: [u8; 8],
+  // charged instead of the bps fee when that comes out lower, free loans aside
+  pub min_fee: [u8; 8],
+}
+
+impl BorrowCap {
+  pub const LEN: usize = size_of::<BorrowCap>();
+
+  pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &*(data.as_ptr() as *const Self) })
+  }
+
+  pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+  }
+
+  pub fn max_amount(&self) -> u64 {
+    u64::from_le_bytes(self.max_amount)
+  }
+
+  pub fn min_amount(&self) -> u64 {
+    u64::from_le_bytes(self.min_amount)
+  }
+
+  pub fn min_fee(&self) -> u64 {
+    u64::from_le_bytes(self.min_fee)
+  }
+
+  // most one loan entry can borrow from an account holding `balance`: nothing below the
+  // minimum, and never more than the cap
+  pub fn capacity(&self, balance: u64) -> u64 {
+    if balance.lt(&self.min_amount()) {
+      return 0;
+    }
+    balance.min(self.max_amount())
+  }
+}
+
+pub struct SetBorrowCapAccounts<'a> {
+  pub authority: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub fee_split: &'a AccountInfo,
+  pub mint: &'a AccountInfo,
+  pub borrow_cap: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SetBorrowCapAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [authority, protocol, fee_split, mint, borrow_cap, _system_program] = accounts else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(authority)?;
+
+    fee_split.require_owner(&ID)?;
+    if !mint.is_owned_by(&pinocchio_token::ID) && !mint.is_owned_by(&pinocchio_token_2022::ID) {
+      return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    Ok(Self {
+      authority,
+      protocol,
+      fee_split,
+      mint,
+      borrow_cap,
+    })
+  }
+}
+
+pub struct SetBorrowCapInstructionData {
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+  pub max_amount: u64,
+  pub min_amount: u64,
+  pub min_fee: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetBorrowCapInstructionData {
+  type Error = ProgramError;
+
+  fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+    if data.len() != size_of::<u16>() + size_of::<u8>() + size_of::<u64>() * 3 {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let max_amount = u64::from_le_bytes(data[3..11].try_into().unwrap());
+    let min_amount = u64::from_le_bytes(data[11..19].try_into().unwrap());
+    // a minimum above the cap would refuse every loan of the mint
+    if min_amount > max_amount {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(Self {
+      protocol_fee: [data[0], data[1]],
+      protocol_bump: [data[2]],
+      max_amount,
+      min_amount,
+      min_fee: u64::from_le_bytes(data[19..27].try_into().unwrap()),
+    })
+  }
+}
+
+pub struct SetBorrowCap<'a> {
+  pub accounts: SetBorrowCapAccounts<'a>,
+  pub instruction_data: SetBorrowCapInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for SetBorrowCap<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = SetBorrowCapAccounts::try_from(accounts)?;
+    let instruction_data = SetBorrowCapInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> SetBorrowCap<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &10;
+
+  /// Sets the most and least one loan entry may borrow of `mint` from a protocol, and its fee
+  /// floor, creating the cap on first use. Gated on the fee split authority like SetFreeLoans.
+  /// Every loan passes the cap account of each of its mints, so the limits always apply.
+  pub fn process(&mut self) -> ProgramResult {
+    let protocol = create_program_address(
+      &[
+        b"protocol",
+        &self.instruction_data.protocol_fee,
+        &self.instruction_data.protocol_bump,
+      ],
+      &ID,
+    )?;
+    if protocol.ne(self.accounts.protocol.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (fee_split_key, _) = find_program_address(&[b"fee_split", &protocol], &ID);
+    if fee_split_key.ne(self.accounts.fee_split.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+    {
+      let data = self.accounts.fee_split.try_borrow_data()?;
+      if FeeSplit::load(&data)?.authority.ne(self.accounts.authority.key()) {
+        return Err(ProgramError::IncorrectAuthority);
+      }
+    }
+
+    let mint = self.accounts.mint.key();
+    let (borrow_cap_key, bump) = find_program_address(&[b"borrow_cap", &protocol, mint], &ID);
+    if borrow_cap_key.ne(self.accounts.borrow_cap.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    if self.accounts.borrow_cap.data_len().eq(&0) {
+      let bump = [bump];
+      let seeds = [
+        Seed::from(b"borrow_cap"),
+        Seed::from(&protocol),
+        Seed::from(mint),
+        Seed::from(&bump),
+      ];
+
+      CreateAccount {
+        from: self.accounts.authority,
+        to: self.accounts.borrow_cap,
+        lamports: Rent::get()?.minimum_balance(BorrowCap::LEN),
+        space: BorrowCap::LEN as u64,
+        owner: &ID,
+      }
+      .invoke_signed(&[Signer::from(&seeds)])?;
+    } else {
+      self.accounts.borrow_cap.require_owner(&ID)?;
+    }
+
+    let mut data = self.accounts.borrow_cap.try_borrow_mut_data()?;
+    let borrow_cap = BorrowCap::load_mut(&mut data)?;
+
+    borrow_cap.protocol = protocol;
+    borrow_cap.mint = *mint;
+    borrow_cap.max_amount = self.instruction_data.max_amount.to_le_bytes();
+    borrow_cap.min_amount = self.instruction_data.min_amount.to_le_bytes();
+    borrow_cap.min_fee = self.instruction_data.min_fee.to_le_bytes();
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn borrow_cap(max_amount: u64, min_amount: u64) -> [u8; BorrowCap::LEN] {
+    let mut data = [0u8; BorrowCap::LEN];
+    let borrow_cap = BorrowCap::load_mut(&mut data).unwrap();
+    borrow_cap.max_amount = max_amount.to_le_bytes();
+    borrow_cap.min_amount = min_amount.to_le_bytes();
+    data
+  }
+
+  #[test]
+  fn capacity_is_the_balance_up_to_the_cap() {
+    let data = borrow_cap(1_000, 10);
+    let borrow_cap = BorrowCap::load(&data).unwrap();
+
+    assert_eq!(borrow_cap.capacity(500), 500);
+    assert_eq!(borrow_cap.capacity(1_000), 1_000);
+    assert_eq!(borrow_cap.capacity(u64::MAX), 1_000);
+    // below the minimum no entry can be taken at all
+    assert_eq!(borrow_cap.capacity(10), 10);
+    assert_eq!(borrow_cap.capacity(9), 0);
+  }
+
+  #[test]
+  fn instruction_data_refuses_a_minimum_above_the_cap() {
+    let mut data = [0u8; 27];
+    data[3..11].copy_from_slice(&100u64.to_le_bytes());
+    data[11..19].copy_from_slice(&100u64.to_le_bytes());
+    assert!(SetBorrowCapInstructionData::try_from(&data[..]).is_ok());
+
+    data[11..19].copy_from_slice(&101u64.to_le_bytes());
+    assert!(SetBorrowCapInstructionData::try_from(&data[..]).is_err());
+    assert!(SetBorrowCapInstructionData::try_from(&data[..26]).is_err());
+  }
+}
@@ -0,0 +1,305 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program_error::ProgramError,
+  pubkey::{create_program_address, find_program_address, Pubkey},
+  sysvars::{rent::Rent, Sysvar},
+  ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::{check_upgrade_authority, SignerAccount, ID};
+
+pub const MAX_FEE_RECIPIENTS: usize = 4;
+// recipient weights must add up to exactly this
+pub const FEE_SPLIT_TOTAL_WEIGHT: u16 = 10_000;
+
+#[repr(C)]
+pub struct FeeSplit {
+  pub authority: Pubkey,
+  // seeds of the protocol PDA whose loan fees are split
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+  pub recipient_count: u8,
+  pub recipients: [Pubkey; MAX_FEE_RECIPIENTS],
+  pub weights: [[u8; 2]; MAX_FEE_RECIPIENTS],
+  // set by SetPaused; Loan refuses the protocol and EmergencyDrain needs it
+  pub paused: u8,
+  // wallet whose token accounts are the only place EmergencyDrain sends liquidity to
+  pub treasury: Pubkey,
+}
+
+impl FeeSplit {
+  pub const LEN: usize = size_of::<FeeSplit>();
+
+  pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &*(data.as_ptr() as *const Self) })
+  }
+
+  pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+  }
+
+  pub fn recipients(&self) -> &[Pubkey] {
+    &self.recipients[..self.recipient_count as usize]
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused.ne(&0)
+  }
+
+  pub fn weight(&self, index: usize) -> u16 {
+    u16::from_le_bytes(self.weights[index])
+  }
+
+  // `fee * weight / FEE_SPLIT_TOTAL_WEIGHT`, rounding dust stays with the protocol
+  pub fn share(&self, index: usize, fee: u64) -> u64 {
+    (fee as u128 * self.weight(index) as u128 / FEE_SPLIT_TOTAL_WEIGHT as u128) as u64
+  }
+}
+
+pub struct SetFeeSplitAccounts<'a> {
+  pub authority: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub fee_split: &'a AccountInfo,
+  // the program's ProgramData account, read when the split is first created
+  pub program_data: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SetFeeSplitAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [authority, protocol, fee_split, program_data, _system_program] = accounts else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(authority)?;
+
+    Ok(Self {
+      authority,
+      protocol,
+      fee_split,
+      program_data,
+    })
+  }
+}
+
+pub struct SetFeeSplitInstructionData<'a> {
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+  pub treasury: Pubkey,
+  // recipient: Pubkey | weight: u16, per recipient
+  pub recipients: &'a [u8],
+}
+
+const RECIPIENT_LEN: usize = size_of::<Pubkey>() + size_of::<u16>();
+
+impl<'a> TryFrom<&'a [u8]> for SetFeeSplitInstructionData<'a> {
+  type Error = ProgramError;
+
+  fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+    let (fields, recipients) = data
+      .split_at_checked(size_of::<u16>() + size_of::<u8>() + size_of::<Pubkey>())
+      .ok_or(ProgramError::InvalidInstructionData)?;
+
+    if recipients.is_empty()
+      || recipients.len() % RECIPIENT_LEN != 0
+      || recipients.len() / RECIPIENT_LEN > MAX_FEE_RECIPIENTS
+    {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut total_weight: u16 = 0;
+    for recipient in recipients.chunks_exact(RECIPIENT_LEN) {
+      let weight = u16::from_le_bytes(recipient[32..34].try_into().unwrap());
+      if weight.eq(&0) {
+        return Err(ProgramError::InvalidInstructionData);
+      }
+      total_weight = total_weight
+        .checked_add(weight)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    }
+    if total_weight.ne(&FEE_SPLIT_TOTAL_WEIGHT) {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(Self {
+      protocol_fee: [fields[0], fields[1]],
+      protocol_bump: [fields[2]],
+      treasury: fields[3..].try_into().unwrap(),
+      recipients,
+    })
+  }
+}
+
+pub struct SetFeeSplit<'a> {
+  pub accounts: SetFeeSplitAccounts<'a>,
+  pub instruction_data: SetFeeSplitInstructionData<'a>,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for SetFeeSplit<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = SetFeeSplitAccounts::try_from(accounts)?;
+    let instruction_data = SetFeeSplitInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> SetFeeSplit<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &4;
+
+  /// Creates or replaces the fee split of a protocol PDA. The split's authority also gates
+  /// SetFreeLoans, SetBorrowCap and EmergencyDrain, so only the program's upgrade authority
+  /// may create it; from then on only the split's authority can replace it.
+  pub fn process(&mut self) -> ProgramResult {
+    let protocol = create_program_address(
+      &[
+        b"protocol",
+        &self.instruction_data.protocol_fee,
+        &self.instruction_data.protocol_bump,
+      ],
+      &ID,
+    )?;
+    if protocol.ne(self.accounts.protocol.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (fee_split_key, bump) = find_program_address(&[b"fee_split", &protocol], &ID);
+    if fee_split_key.ne(self.accounts.fee_split.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    if self.accounts.fee_split.data_len().eq(&0) {
+      check_upgrade_authority(self.accounts.program_data, self.accounts.authority.key())?;
+
+      let bump = [bump];
+      let seeds = [
+        Seed::from(b"fee_split"),
+        Seed::from(&protocol),
+        Seed::from(&bump),
+      ];
+
+      CreateAccount {
+        from: self.accounts.authority,
+        to: self.accounts.fee_split,
+        lamports: Rent::get()?.minimum_balance(FeeSplit::LEN),
+        space: FeeSplit::LEN as u64,
+        owner: &ID,
+      }
+      .invoke_signed(&[Signer::from(&seeds)])?;
+    } else {
+      if !self.accounts.fee_split.is_owned_by(&ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+      }
+      let data = self.accounts.fee_split.try_borrow_data()?;
+      if FeeSplit::load(&data)?.authority.ne(self.accounts.authority.key()) {
+        return Err(ProgramError::IncorrectAuthority);
+      }
+    }
+
+    let mut data = self.accounts.fee_split.try_borrow_mut_data()?;
+    let fee_split = FeeSplit::load_mut(&mut data)?;
+
+    fee_split.authority = *self.accounts.authority.key();
+    fee_split.protocol_fee = self.instruction_data.protocol_fee;
+    fee_split.protocol_bump = self.instruction_data.protocol_bump;
+    fee_split.treasury = self.instruction_data.treasury;
+    fee_split.recipient_count = (self.instruction_data.recipients.len() / RECIPIENT_LEN) as u8;
+    fee_split.recipients = [[0u8; 32]; MAX_FEE_RECIPIENTS];
+    fee_split.weights = [[0u8; 2]; MAX_FEE_RECIPIENTS];
+    for (i, recipient) in self
+      .instruction_data
+      .recipients
+      .chunks_exact(RECIPIENT_LEN)
+      .enumerate()
+    {
+      fee_split.recipients[i] = recipient[..32].try_into().unwrap();
+      fee_split.weights[i] = [recipient[32], recipient[33]];
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TREASURY: Pubkey = [1u8; 32];
+  const INSURANCE: Pubkey = [2u8; 32];
+
+  fn fee_split(recipients: &[(Pubkey, u16)]) -> [u8; FeeSplit::LEN] {
+    let mut data = [0u8; FeeSplit::LEN];
+    let fee_split = FeeSplit::load_mut(&mut data).unwrap();
+    fee_split.recipient_count = recipients.len() as u8;
+    for (i, (recipient, weight)) in recipients.iter().enumerate() {
+      fee_split.recipients[i] = *recipient;
+      fee_split.weights[i] = weight.to_le_bytes();
+    }
+    data
+  }
+
+  fn instruction_data(recipients: &[(Pubkey, u16)]) -> ([u8; 35 + 4 * RECIPIENT_LEN], usize) {
+    let mut data = [0u8; 35 + 4 * RECIPIENT_LEN];
+    for (i, (recipient, weight)) in recipients.iter().enumerate() {
+      let at = 35 + i * RECIPIENT_LEN;
+      data[at..at + 32].copy_from_slice(recipient);
+      data[at + 32..at + 34].copy_from_slice(&weight.to_le_bytes());
+    }
+    (data, 35 + recipients.len() * RECIPIENT_LEN)
+  }
+
+  #[test]
+  fn two_way_split_pays_each_recipient_its_weight() {
+    let data = fee_split(&[(TREASURY, 7_000), (INSURANCE, 3_000)]);
+    let fee_split = FeeSplit::load(&data).unwrap();
+
+    assert_eq!(fee_split.recipients(), &[TREASURY, INSURANCE]);
+    assert_eq!(fee_split.share(0, 1_000), 700);
+    assert_eq!(fee_split.share(1, 1_000), 300);
+
+    // shares round down, the odd lamport stays with the protocol
+    assert_eq!(fee_split.share(0, 999), 699);
+    assert_eq!(fee_split.share(1, 999), 299);
+
+    // the widening to u128 keeps the largest fee exact
+    assert_eq!(
+      fee_split.share(0, u64::MAX),
+      (u64::MAX as u128 * 7 / 10) as u64
+    );
+  }
+
+  #[test]
+  fn weights_must_add_up_to_the_total() {
+    let (data, len) = instruction_data(&[(TREASURY, 7_000), (INSURANCE, 3_000)]);
+    let parsed = SetFeeSplitInstructionData::try_from(&data[..len]).unwrap();
+    assert_eq!(parsed.recipients.len(), 2 * RECIPIENT_LEN);
+
+    for recipients in [
+      &[(TREASURY, 7_000), (INSURANCE, 2_999)][..],
+      &[(TREASURY, 7_000), (INSURANCE, 3_001)],
+      &[(TREASURY, 10_000), (INSURANCE, 0)],
+      &[],
+    ] {
+      let (data, len) = instruction_data(recipients);
+      assert!(SetFeeSplitInstructionData::try_from(&data[..len]).is_err());
+    }
+
+    // a half-written recipient
+    let (data, len) = instruction_data(&[(TREASURY, 10_000)]);
+    assert!(SetFeeSplitInstructionData::try_from(&data[..len - 1]).is_err());
+  }
+}
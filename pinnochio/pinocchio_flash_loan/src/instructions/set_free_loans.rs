@@ -0,0 +1,179 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program_error::ProgramError,
+  pubkey::{create_program_address, find_program_address, Pubkey},
+  sysvars::{rent::Rent, Sysvar},
+  ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::{FeeSplit, SignerAccount, ID};
+
+// promotional fee-free loans left for a protocol, at ["free_loans", protocol]
+#[repr(C)]
+pub struct FreeLoans {
+  pub protocol: Pubkey,
+  pub remaining: [u8; 8],
+}
+
+impl FreeLoans {
+  pub const LEN: usize = size_of::<FreeLoans>();
+
+  pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &*(data.as_ptr() as *const Self) })
+  }
+
+  pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+    if data.len() != Self::LEN {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+  }
+
+  pub fn remaining(&self) -> u64 {
+    u64::from_le_bytes(self.remaining)
+  }
+
+  pub fn set_remaining(&mut self, remaining: u64) {
+    self.remaining = remaining.to_le_bytes();
+  }
+}
+
+pub struct SetFreeLoansAccounts<'a> {
+  pub authority: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub fee_split: &'a AccountInfo,
+  pub free_loans: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SetFreeLoansAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [authority, protocol, fee_split, free_loans, _system_program] = accounts else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(authority)?;
+
+    Ok(Self {
+      authority,
+      protocol,
+      fee_split,
+      free_loans,
+    })
+  }
+}
+
+pub struct SetFreeLoansInstructionData {
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+  pub free_loans: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetFreeLoansInstructionData {
+  type Error = ProgramError;
+
+  fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+    if data.len() != size_of::<u16>() + size_of::<u8>() + size_of::<u64>() {
+      return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(Self {
+      protocol_fee: [data[0], data[1]],
+      protocol_bump: [data[2]],
+      free_loans: u64::from_le_bytes(data[3..11].try_into().unwrap()),
+    })
+  }
+}
+
+pub struct SetFreeLoans<'a> {
+  pub accounts: SetFreeLoansAccounts<'a>,
+  pub instruction_data: SetFreeLoansInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for SetFreeLoans<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = SetFreeLoansAccounts::try_from(accounts)?;
+    let instruction_data = SetFreeLoansInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> SetFreeLoans<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &7;
+
+  /// Sets how many of a protocol's next loans are charged no fee, creating the counter on
+  /// first use. The protocol PDA can't sign, so the authority of its fee split stands in for
+  /// it; a protocol needs a fee split before it can run a promotion.
+  pub fn process(&mut self) -> ProgramResult {
+    let protocol = create_program_address(
+      &[
+        b"protocol",
+        &self.instruction_data.protocol_fee,
+        &self.instruction_data.protocol_bump,
+      ],
+      &ID,
+    )?;
+    if protocol.ne(self.accounts.protocol.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (fee_split_key, _) = find_program_address(&[b"fee_split", &protocol], &ID);
+    if fee_split_key.ne(self.accounts.fee_split.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+    if !self.accounts.fee_split.is_owned_by(&ID) {
+      return Err(ProgramError::InvalidAccountOwner);
+    }
+    {
+      let data = self.accounts.fee_split.try_borrow_data()?;
+      if FeeSplit::load(&data)?.authority.ne(self.accounts.authority.key()) {
+        return Err(ProgramError::IncorrectAuthority);
+      }
+    }
+
+    let (free_loans_key, bump) = find_program_address(&[b"free_loans", &protocol], &ID);
+    if free_loans_key.ne(self.accounts.free_loans.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    if self.accounts.free_loans.data_len().eq(&0) {
+      let bump = [bump];
+      let seeds = [
+        Seed::from(b"free_loans"),
+        Seed::from(&protocol),
+        Seed::from(&bump),
+      ];
+
+      CreateAccount {
+        from: self.accounts.authority,
+        to: self.accounts.free_loans,
+        lamports: Rent::get()?.minimum_balance(FreeLoans::LEN),
+        space: FreeLoans::LEN as u64,
+        owner: &ID,
+      }
+      .invoke_signed(&[Signer::from(&seeds)])?;
+    } else if !self.accounts.free_loans.is_owned_by(&ID) {
+      return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut data = self.accounts.free_loans.try_borrow_mut_data()?;
+    let free_loans = FreeLoans::load_mut(&mut data)?;
+
+    free_loans.protocol = protocol;
+    free_loans.set_remaining(self.instruction_data.free_loans);
+
+    Ok(())
+  }
+}
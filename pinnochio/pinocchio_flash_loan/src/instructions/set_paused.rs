@@ -0,0 +1,177 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  program_error::ProgramError,
+  pubkey::{create_program_address, find_program_address},
+  ProgramResult,
+};
+
+use crate::{AccountInfoExt, FeeSplit, PinocchioError, SignerAccount, ID};
+
+// `fee_split` is the protocol's ["fee_split", protocol] account, already matched by the caller.
+// A protocol without a split has no one who could pause it, so it is never paused
+pub fn check_not_paused(fee_split: &AccountInfo) -> ProgramResult {
+  if fee_split.data_len().eq(&0) {
+    return Ok(());
+  }
+  fee_split.require_owner(&ID)?;
+  let data = fee_split.try_borrow_data()?;
+  if FeeSplit::load(&data)?.is_paused() {
+    return Err(PinocchioError::ProtocolPaused.into());
+  }
+  Ok(())
+}
+
+pub struct SetPausedAccounts<'a> {
+  pub authority: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub fee_split: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SetPausedAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [authority, protocol, fee_split] = accounts else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(authority)?;
+
+    fee_split.require_owner(&ID)?;
+
+    Ok(Self {
+      authority,
+      protocol,
+      fee_split,
+    })
+  }
+}
+
+pub struct SetPausedInstructionData {
+  pub protocol_fee: [u8; 2],
+  pub protocol_bump: [u8; 1],
+  pub paused: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetPausedInstructionData {
+  type Error = ProgramError;
+
+  fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+    let [fee_0, fee_1, bump, paused] = data else {
+      return Err(ProgramError::InvalidInstructionData);
+    };
+
+    let paused = match paused {
+      0 => false,
+      1 => true,
+      _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    Ok(Self {
+      protocol_fee: [*fee_0, *fee_1],
+      protocol_bump: [*bump],
+      paused,
+    })
+  }
+}
+
+pub struct SetPaused<'a> {
+  pub accounts: SetPausedAccounts<'a>,
+  pub instruction_data: SetPausedInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for SetPaused<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = SetPausedAccounts::try_from(accounts)?;
+    let instruction_data = SetPausedInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> SetPaused<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &12;
+
+  /// Pauses or unpauses a protocol. A paused protocol lends nothing, and only a paused one can
+  /// be emptied with EmergencyDrain. Gated on the fee split authority like SetFreeLoans.
+  pub fn process(&mut self) -> ProgramResult {
+    let protocol = create_program_address(
+      &[
+        b"protocol",
+        &self.instruction_data.protocol_fee,
+        &self.instruction_data.protocol_bump,
+      ],
+      &ID,
+    )?;
+    if protocol.ne(self.accounts.protocol.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (fee_split_key, _) = find_program_address(&[b"fee_split", &protocol], &ID);
+    if fee_split_key.ne(self.accounts.fee_split.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut data = self.accounts.fee_split.try_borrow_mut_data()?;
+    let fee_split = FeeSplit::load_mut(&mut data)?;
+    if fee_split.authority.ne(self.accounts.authority.key()) {
+      return Err(ProgramError::IncorrectAuthority);
+    }
+
+    fee_split.paused = self.instruction_data.paused as u8;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::RawAccount;
+
+  fn fee_split(paused: bool) -> [u8; FeeSplit::LEN] {
+    let mut data = [0u8; FeeSplit::LEN];
+    FeeSplit::load_mut(&mut data).unwrap().paused = paused as u8;
+    data
+  }
+
+  #[test]
+  fn paused_protocols_refuse_loans() {
+    assert_eq!(
+      check_not_paused(&RawAccount::new(false, &ID, &fee_split(false)).info()),
+      Ok(())
+    );
+    assert_eq!(
+      check_not_paused(&RawAccount::new(false, &ID, &fee_split(true)).info()),
+      Err(PinocchioError::ProtocolPaused.into())
+    );
+    // no split yet, nobody could have paused it
+    assert_eq!(
+      check_not_paused(&RawAccount::new(false, &[0u8; 32], &[]).info()),
+      Ok(())
+    );
+    // a look-alike the program doesn't own can't unpause anything
+    assert_eq!(
+      check_not_paused(&RawAccount::new(false, &[1u8; 32], &fee_split(false)).info()),
+      Err(ProgramError::InvalidAccountOwner)
+    );
+  }
+
+  #[test]
+  fn instruction_data_takes_a_strict_flag() {
+    let data = SetPausedInstructionData::try_from(&[0xf4, 0x01, 254, 1][..]).unwrap();
+    assert_eq!(data.protocol_fee, 500u16.to_le_bytes());
+    assert_eq!(data.protocol_bump, [254]);
+    assert!(data.paused);
+    assert!(!SetPausedInstructionData::try_from(&[0, 0, 0, 0][..]).unwrap().paused);
+
+    for data in [&[0, 0, 0, 2][..], &[0, 0, 0][..], &[0, 0, 0, 1, 0][..]] {
+      assert!(SetPausedInstructionData::try_from(data).is_err());
+    }
+  }
+}
@@ -0,0 +1,135 @@
+use pinocchio::{
+  account_info::AccountInfo,
+  instruction::{Seed, Signer},
+  program_error::ProgramError,
+  pubkey::find_program_address,
+  ProgramResult,
+};
+
+use crate::{
+  check_protocol_vault, close_program_account, transfer_checked, AccountInfoExt,
+  LiquidityInstructionData, LiquidityPosition, SignerAccount, ID,
+};
+
+pub struct WithdrawLiquidityAccounts<'a> {
+  pub provider: &'a AccountInfo,
+  pub protocol: &'a AccountInfo,
+  pub protocol_token_account: &'a AccountInfo,
+  pub provider_token_account: &'a AccountInfo,
+  pub mint: &'a AccountInfo,
+  pub position: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawLiquidityAccounts<'a> {
+  type Error = ProgramError;
+
+  fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    let [provider, protocol, protocol_token_account, provider_token_account, mint, position, _token_program] =
+      accounts
+    else {
+      return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SignerAccount::check(provider)?;
+
+    position.require_owner(&ID)?;
+
+    Ok(Self {
+      provider,
+      protocol,
+      protocol_token_account,
+      provider_token_account,
+      mint,
+      position,
+    })
+  }
+}
+
+pub struct WithdrawLiquidity<'a> {
+  pub accounts: WithdrawLiquidityAccounts<'a>,
+  pub instruction_data: LiquidityInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for WithdrawLiquidity<'a> {
+  type Error = ProgramError;
+
+  fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+    let accounts = WithdrawLiquidityAccounts::try_from(accounts)?;
+    let instruction_data = LiquidityInstructionData::try_from(data)?;
+
+    Ok(Self {
+      accounts,
+      instruction_data,
+    })
+  }
+}
+
+impl<'a> WithdrawLiquidity<'a> {
+  pub const DISCRIMINATOR: &'a u8 = &6;
+
+  /// Pays `amount` of the provider's recorded liquidity back out of the protocol token
+  /// account and closes the position once nothing is left in it. Tokens that are lent out
+  /// in the same transaction simply aren't there to withdraw, and Repay still has to see
+  /// the full balance come back.
+  pub fn process(&mut self) -> ProgramResult {
+    check_protocol_vault(
+      self.accounts.protocol,
+      self.accounts.protocol_token_account,
+      &self.instruction_data.protocol_fee,
+      &self.instruction_data.protocol_bump,
+    )?;
+
+    let (position_key, _) = find_program_address(
+      &[
+        b"liquidity",
+        self.accounts.protocol_token_account.key(),
+        self.accounts.provider.key(),
+      ],
+      &ID,
+    );
+    if position_key.ne(self.accounts.position.key()) {
+      return Err(ProgramError::InvalidSeeds);
+    }
+
+    let remaining = {
+      let mut data = self.accounts.position.try_borrow_mut_data()?;
+      let position = LiquidityPosition::load_mut(&mut data)?;
+
+      if position.provider.ne(self.accounts.provider.key())
+        || position
+          .protocol_token_account
+          .ne(self.accounts.protocol_token_account.key())
+      {
+        return Err(ProgramError::InvalidAccountData);
+      }
+
+      let remaining = position
+        .amount()
+        .checked_sub(self.instruction_data.amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+      position.amount = remaining.to_le_bytes();
+      remaining
+    };
+
+    let seeds = [
+      Seed::from(b"protocol"),
+      Seed::from(&self.instruction_data.protocol_fee),
+      Seed::from(&self.instruction_data.protocol_bump),
+    ];
+
+    transfer_checked(
+      self.accounts.protocol_token_account,
+      self.accounts.provider_token_account,
+      self.accounts.mint,
+      self.accounts.protocol,
+      self.instruction_data.amount,
+      &[Signer::from(&seeds)],
+    )?;
+
+    if remaining.eq(&0) {
+      close_program_account(self.accounts.position, self.accounts.provider)?;
+    }
+
+    Ok(())
+  }
+}
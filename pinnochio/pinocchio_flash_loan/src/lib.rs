@@ -1,9 +1,33 @@
 use pinocchio::{account_info::AccountInfo, entrypoint, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+use pinocchio_log::log;
 entrypoint!(process_instruction);
 
 pub mod instructions;
 pub use instructions::*;
 
+#[path = "../../shared/accounts.rs"]
+pub mod accounts;
+pub use accounts::*;
+
+#[cfg(test)]
+#[path = "../../shared/raw_account.rs"]
+mod raw_account;
+#[cfg(test)]
+pub(crate) use raw_account::RawAccount;
+
+pub mod errors;
+pub use errors::*;
+
+pub mod math;
+pub use math::*;
+
+pub mod events;
+pub use events::*;
+
+// also compiled for tests, which check every vector against its parser
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod test_vectors;
+
 // 22222222222222222222222222222222222222222222
 pub const ID: Pubkey = [
     0x0f, 0x1e, 0x6b, 0x14, 0x21, 0xc0, 0x4a, 0x07, 
@@ -20,6 +44,46 @@ fn process_instruction(
     match instruction_data.split_first() {
         Some((Loan::DISCRIMINATOR, data)) => Loan::try_from((data, accounts))?.process(),
         Some((Repay::DISCRIMINATOR, _)) => Repay::try_from(accounts)?.process(),
-        _ => Err(ProgramError::InvalidInstructionData)
+        Some((GetProtocolHealth::DISCRIMINATOR, _)) => {
+            GetProtocolHealth::try_from(accounts)?.process()
+        }
+        Some((GetBorrowCapacity::DISCRIMINATOR, data)) => {
+            GetBorrowCapacity::try_from((data, accounts))?.process()
+        }
+        Some((SetFeeSplit::DISCRIMINATOR, data)) => {
+            SetFeeSplit::try_from((data, accounts))?.process()
+        }
+        Some((ProvideLiquidity::DISCRIMINATOR, data)) => {
+            ProvideLiquidity::try_from((data, accounts))?.process()
+        }
+        Some((WithdrawLiquidity::DISCRIMINATOR, data)) => {
+            WithdrawLiquidity::try_from((data, accounts))?.process()
+        }
+        Some((SetFreeLoans::DISCRIMINATOR, data)) => {
+            SetFreeLoans::try_from((data, accounts))?.process()
+        }
+        Some((InitializeProtocol::DISCRIMINATOR, data)) => {
+            InitializeProtocol::try_from((data, accounts))?.process()
+        }
+        Some((EmergencyDrain::DISCRIMINATOR, data)) => {
+            EmergencyDrain::try_from((data, accounts))?.process()
+        }
+        Some((SetBorrowCap::DISCRIMINATOR, data)) => {
+            SetBorrowCap::try_from((data, accounts))?.process()
+        }
+        Some((InitializeMetrics::DISCRIMINATOR, _)) => {
+            InitializeMetrics::try_from(accounts)?.process()
+        }
+        Some((SetPaused::DISCRIMINATOR, data)) => {
+            SetPaused::try_from((data, accounts))?.process()
+        }
+        Some((RecoverStrayTokens::DISCRIMINATOR, data)) => {
+            RecoverStrayTokens::try_from((data, accounts))?.process()
+        }
+        Some((discriminator, _)) => {
+            log!("Unknown instruction discriminator: {}", *discriminator);
+            Err(ProgramError::InvalidInstructionData)
+        }
+        None => Err(ProgramError::InvalidInstructionData),
     }
 }
\ No newline at end of file
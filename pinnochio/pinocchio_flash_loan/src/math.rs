@@ -0,0 +1,56 @@
+use pinocchio::program_error::ProgramError;
+
+// basis points in a whole
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// `amount * bps / 10_000`, rounded down. The product is taken in u128 so only a result that
+/// doesn't fit a u64 (possible once `bps` exceeds 10_000) fails, with `ArithmeticOverflow`.
+#[inline(always)]
+pub fn apply_bps(amount: u64, bps: u16) -> Result<u64, ProgramError> {
+  let scaled = amount as u128 * bps as u128 / BPS_DENOMINATOR as u128;
+  u64::try_from(scaled).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn zero_amount_or_zero_bps_is_zero() {
+    assert_eq!(apply_bps(0, 500), Ok(0));
+    assert_eq!(apply_bps(1_000_000, 0), Ok(0));
+  }
+
+  #[test]
+  fn full_bps_returns_the_amount() {
+    assert_eq!(apply_bps(123_456, 10_000), Ok(123_456));
+    assert_eq!(apply_bps(u64::MAX, 10_000), Ok(u64::MAX));
+  }
+
+  #[test]
+  fn rounds_down() {
+    // 9_999 * 5 / 10_000 = 4.9995
+    assert_eq!(apply_bps(9_999, 5), Ok(4));
+    assert_eq!(apply_bps(1, 9_999), Ok(0));
+    assert_eq!(apply_bps(10_000, 1), Ok(1));
+  }
+
+  #[test]
+  fn max_amount_times_max_bps_does_not_overflow_the_product() {
+    assert_eq!(apply_bps(u64::MAX, 5_000), Ok(u64::MAX / 2));
+  }
+
+  #[test]
+  fn result_past_u64_overflows() {
+    assert_eq!(
+      apply_bps(u64::MAX, 10_001),
+      Err(ProgramError::ArithmeticOverflow)
+    );
+    assert_eq!(
+      apply_bps(u64::MAX, u16::MAX),
+      Err(ProgramError::ArithmeticOverflow)
+    );
+    // u16::MAX bps multiplies by ~6.55, which still fits for a small enough amount
+    assert_eq!(apply_bps(10_000, u16::MAX), Ok(u16::MAX as u64));
+  }
+}
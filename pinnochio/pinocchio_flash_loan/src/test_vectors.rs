@@ -0,0 +1,56 @@
+//! Canonical instruction data for client authors, built with the `test-vectors` feature and
+//! for the crate's tests, which parse each one back. Each vector is the full instruction data,
+//! discriminator first, for the field values noted beside it; dump `TEST_VECTORS` to a fixture
+//! and compare an encoder's output byte for byte.
+
+pub struct TestVector {
+  pub name: &'static str,
+  pub data: &'static [u8],
+}
+
+const fn write<const N: usize>(mut out: [u8; N], at: usize, bytes: &[u8]) -> [u8; N] {
+  let mut i = 0;
+  while i < bytes.len() {
+    out[at + i] = bytes[i];
+    i += 1;
+  }
+  out
+}
+
+// bump 255 | fee 500 | amounts [1_000_000, 2_500]; one amount per loan entry
+const LOAN: [u8; 20] = {
+  let out = write([0u8; 20], 0, &[0, 255]);
+  let out = write(out, 2, &500u16.to_le_bytes());
+  let out = write(out, 4, &1_000_000u64.to_le_bytes());
+  write(out, 12, &2_500u64.to_le_bytes())
+};
+
+pub const TEST_VECTORS: &[TestVector] = &[TestVector {
+  name: "flash_loan_loan",
+  data: &LOAN,
+}];
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Loan, LoanInstructionData};
+
+  #[test]
+  fn loan_parses() {
+    let vector = TEST_VECTORS
+      .iter()
+      .find(|vector| vector.name.eq("flash_loan_loan"))
+      .unwrap();
+    let (discriminator, data) = vector.data.split_first().unwrap();
+    assert_eq!(discriminator, Loan::DISCRIMINATOR);
+
+    let parsed = LoanInstructionData::try_from(data).unwrap();
+    assert_eq!(parsed.bump, [255]);
+    assert_eq!(parsed.fee, 500);
+    assert_eq!(parsed.amount_count(), 2);
+    let mut amounts = parsed.amounts();
+    assert_eq!(amounts.next(), Some(1_000_000));
+    assert_eq!(amounts.next(), Some(2_500));
+    assert_eq!(amounts.next(), None);
+  }
+}
@@ -0,0 +1,285 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref},
+    instruction::Seed,
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{
+        clock::Clock,
+        instructions::{Instructions, IntrospectedInstruction},
+        Sysvar,
+    },
+    ProgramResult,
+};
+use pinocchio_secp256r1_instruction::{Secp256r1Instruction, Secp256r1Pubkey};
+
+use crate::{
+    bytes::{read_i64, read_pubkey},
+    instructions::{create_program_account, validate_expiration, vault_seeds, SignerAccount},
+};
+
+// domain tag prefixed to the signed message so no other vault signature can be replayed here
+pub const ADD_WITHDRAW_DESTINATION_DOMAIN: &[u8; 15] = b"add_destination";
+
+pub const MAX_WITHDRAW_DESTINATIONS: usize = 8;
+
+// how long a destination added to an existing allow-list waits before funds may go to it, so
+// a stolen passkey can't add its own address and withdraw straight away
+pub const WITHDRAW_DESTINATION_DELAY_SECONDS: i64 = 24 * 60 * 60;
+
+#[repr(C)]
+pub struct WithdrawDestination {
+    pub destination: Pubkey,
+    pub active_at: [u8; 8],
+}
+
+impl WithdrawDestination {
+    pub fn active_at(&self) -> i64 {
+        i64::from_le_bytes(self.active_at)
+    }
+}
+
+// the only accounts Withdraw, CloseVault and RecoverFunds may pay out to, stored at ["allowlist", vault];
+// vaults without one can pay out anywhere
+#[repr(C)]
+pub struct WithdrawAllowlist {
+    pub count: u8,
+    pub destinations: [WithdrawDestination; MAX_WITHDRAW_DESTINATIONS],
+}
+
+impl WithdrawAllowlist {
+    pub const LEN: usize = size_of::<WithdrawAllowlist>();
+
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*(data.as_ptr() as *const Self) })
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+    }
+
+    pub fn destinations(&self) -> &[WithdrawDestination] {
+        &self.destinations[..(self.count as usize).min(MAX_WITHDRAW_DESTINATIONS)]
+    }
+
+    // `destination` is on the list and its delay has run out by `now`
+    pub fn allows(&self, destination: &Pubkey, now: i64) -> bool {
+        self.destinations()
+            .iter()
+            .any(|entry| entry.destination.eq(destination) && entry.active_at() <= now)
+    }
+}
+
+// `allowlist` must be the vault's allow-list account; once it exists `destination` has to be
+// on it and past its delay
+pub fn check_withdraw_destination(
+    allowlist: &AccountInfo,
+    vault: &AccountInfo,
+    destination: &AccountInfo,
+    now: i64,
+) -> ProgramResult {
+    let (allowlist_key, _) = find_program_address(&[b"allowlist", vault.key()], &crate::ID);
+    if allowlist_key.ne(allowlist.key()) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if allowlist.data_len().eq(&0) {
+        return Ok(());
+    }
+    if !allowlist.is_owned_by(&crate::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let data = allowlist.try_borrow_data()?;
+    if !WithdrawAllowlist::load(&data)?.allows(destination.key(), now) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+//structs
+pub struct AddWithdrawDestinationAccounts<'a> {
+    pub payer: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub allowlist: &'a AccountInfo,
+    pub instructions: &'a AccountInfo,
+}
+
+pub struct AddWithdrawDestination<'a> {
+    pub accounts: AddWithdrawDestinationAccounts<'a>,
+}
+
+//validation
+impl<'a> TryFrom<&'a [AccountInfo]> for AddWithdrawDestinationAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, vault, allowlist, instructions, _system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(payer)?;
+
+        if !vault.is_owned_by(&pinocchio_system::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        };
+
+        Ok(Self {
+            payer,
+            vault,
+            allowlist,
+            instructions,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for AddWithdrawDestination<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = AddWithdrawDestinationAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+//add withdraw destination ix
+impl<'a> AddWithdrawDestination<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &5;
+
+    // the first destination creates the allow-list and can be paid right away, since until
+    // then the vault could pay anyone; later ones only after WITHDRAW_DESTINATION_DELAY_SECONDS
+    pub fn process(&self) -> ProgramResult {
+        let instructions: Instructions<Ref<[u8]>> =
+            Instructions::try_from(self.accounts.instructions)?;
+
+        let ix: IntrospectedInstruction = instructions.get_instruction_relative(1)?;
+
+        let secp256r1_ix = Secp256r1Instruction::try_from(&ix)?;
+
+        if secp256r1_ix.num_signatures() != 1 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let signer: Secp256r1Pubkey = *secp256r1_ix.get_signer(0)?;
+
+        // message layout: domain (15) | vault (32) | destination (32) | expiry (8)
+        let message = secp256r1_ix
+            .get_message_data(0)?
+            .strip_prefix(ADD_WITHDRAW_DESTINATION_DOMAIN)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        if message.len().ne(&(size_of::<Pubkey>() * 2 + size_of::<i64>())) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let (vault, message) = message.split_at(size_of::<Pubkey>());
+        let (destination, expiry) = message.split_at(size_of::<Pubkey>());
+
+        let current = Clock::get()?.unix_timestamp;
+
+        validate_expiration(current, read_i64(expiry, 0)?)?;
+
+        let (vault_key, _) = find_program_address(&vault_seeds(&signer), &crate::ID);
+        if vault_key.ne(self.accounts.vault.key()) || vault.ne(&vault_key) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let (allowlist_key, bump) = find_program_address(&[b"allowlist", &vault_key], &crate::ID);
+        if allowlist_key.ne(self.accounts.allowlist.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let active_at = match self.accounts.allowlist.data_len() {
+            0 => {
+                let bump_binding = [bump];
+                let allowlist_seeds = [
+                    Seed::from(b"allowlist"),
+                    Seed::from(&vault_key),
+                    Seed::from(&bump_binding),
+                ];
+
+                create_program_account(
+                    self.accounts.payer,
+                    self.accounts.allowlist,
+                    WithdrawAllowlist::LEN,
+                    &allowlist_seeds,
+                )?;
+
+                current
+            }
+            _ => {
+                if !self.accounts.allowlist.is_owned_by(&crate::ID) {
+                    return Err(ProgramError::InvalidAccountOwner);
+                }
+                current.saturating_add(WITHDRAW_DESTINATION_DELAY_SECONDS)
+            }
+        };
+
+        let mut data = self.accounts.allowlist.try_borrow_mut_data()?;
+        let allowlist = WithdrawAllowlist::load_mut(&mut data)?;
+
+        // adding a destination twice would only push its activation further out
+        if allowlist
+            .destinations()
+            .iter()
+            .any(|entry| entry.destination.eq(destination))
+        {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let count = allowlist.count as usize;
+        if count >= MAX_WITHDRAW_DESTINATIONS {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        allowlist.destinations[count] = WithdrawDestination {
+            destination: read_pubkey(destination, 0)?,
+            active_at: active_at.to_le_bytes(),
+        };
+        allowlist.count += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_only_listed_destinations_past_their_delay() {
+        let mut data = [0u8; WithdrawAllowlist::LEN];
+        let allowlist = WithdrawAllowlist::load_mut(&mut data).unwrap();
+        allowlist.destinations[0] = WithdrawDestination {
+            destination: [1u8; 32],
+            active_at: 100i64.to_le_bytes(),
+        };
+        allowlist.destinations[1] = WithdrawDestination {
+            destination: [2u8; 32],
+            active_at: (100 + WITHDRAW_DESTINATION_DELAY_SECONDS).to_le_bytes(),
+        };
+        allowlist.count = 2;
+
+        assert!(allowlist.allows(&[1u8; 32], 100));
+        assert!(!allowlist.allows(&[1u8; 32], 99));
+        // e.g. a recovery authority added a moment ago
+        assert!(!allowlist.allows(&[2u8; 32], 101));
+        assert!(allowlist.allows(&[2u8; 32], 100 + WITHDRAW_DESTINATION_DELAY_SECONDS));
+        assert!(!allowlist.allows(&[3u8; 32], i64::MAX));
+    }
+
+    #[test]
+    fn ignores_slots_past_the_count() {
+        let mut data = [0u8; WithdrawAllowlist::LEN];
+        let allowlist = WithdrawAllowlist::load_mut(&mut data).unwrap();
+        allowlist.destinations[0].destination = [1u8; 32];
+
+        assert!(!allowlist.allows(&[1u8; 32], 0));
+    }
+}
@@ -0,0 +1,136 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref},
+    program_error::ProgramError,
+    pubkey::find_program_address,
+    sysvars::{
+        clock::Clock,
+        instructions::{Instructions, IntrospectedInstruction},
+        Sysvar,
+    },
+    ProgramResult,
+};
+use pinocchio_secp256r1_instruction::{Secp256r1Instruction, Secp256r1Pubkey};
+
+use crate::{
+    bytes::{read_i64, read_u64},
+    instructions::{consume_nonce, validate_expiration, vault_seeds, SignerAccount},
+};
+
+// domain tag prefixed to the signed message so no other vault signature can be replayed here
+pub const CANCEL_RECOVERY_DOMAIN: &[u8; 15] = b"cancel_recovery";
+
+//structs
+pub struct CancelRecoveryAccounts<'a> {
+    pub payer: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub recovery: &'a AccountInfo,
+    pub instructions: &'a AccountInfo,
+    pub nonce: &'a AccountInfo,
+}
+
+pub struct CancelRecovery<'a> {
+    pub accounts: CancelRecoveryAccounts<'a>,
+}
+
+//validation
+impl<'a> TryFrom<&'a [AccountInfo]> for CancelRecoveryAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, vault, recovery, instructions, _system_program, nonce] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(payer)?;
+
+        if !vault.is_owned_by(&pinocchio_system::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        };
+
+        if !recovery.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Self {
+            payer,
+            vault,
+            recovery,
+            instructions,
+            nonce,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CancelRecovery<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = CancelRecoveryAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+//cancel recovery ix
+impl<'a> CancelRecovery<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &6;
+
+    // removes the vault's recovery authority, signed by the passkey; the rent of the recovery
+    // account goes back into the vault
+    pub fn process(&self) -> ProgramResult {
+        let instructions: Instructions<Ref<[u8]>> =
+            Instructions::try_from(self.accounts.instructions)?;
+
+        let ix: IntrospectedInstruction = instructions.get_instruction_relative(1)?;
+
+        let secp256r1_ix = Secp256r1Instruction::try_from(&ix)?;
+
+        if secp256r1_ix.num_signatures() != 1 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let signer: Secp256r1Pubkey = *secp256r1_ix.get_signer(0)?;
+
+        // message layout: domain (15) | nonce (8) | expiry (8)
+        let message = secp256r1_ix
+            .get_message_data(0)?
+            .strip_prefix(CANCEL_RECOVERY_DOMAIN)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        if message.len().ne(&(size_of::<u64>() + size_of::<i64>())) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let (nonce, expiry) = message.split_at(size_of::<u64>());
+
+        validate_expiration(Clock::get()?.unix_timestamp, read_i64(expiry, 0)?)?;
+
+        let (vault_key, _) = find_program_address(&vault_seeds(&signer), &crate::ID);
+        if vault_key.ne(self.accounts.vault.key()) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let (recovery_key, _) = find_program_address(&[b"recovery", &vault_key], &crate::ID);
+        if recovery_key.ne(self.accounts.recovery.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        consume_nonce(
+            self.accounts.payer,
+            self.accounts.vault,
+            self.accounts.nonce,
+            read_u64(nonce, 0)?,
+        )?;
+
+        let lamports = self
+            .accounts
+            .vault
+            .lamports()
+            .checked_add(self.accounts.recovery.lamports())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        *self.accounts.vault.try_borrow_mut_lamports()? = lamports;
+        *self.accounts.recovery.try_borrow_mut_lamports()? = 0;
+
+        self.accounts.recovery.close()
+    }
+}
@@ -0,0 +1,174 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref},
+    instruction::Signer,
+    program_error::ProgramError,
+    sysvars::{
+        clock::Clock,
+        instructions::{Instructions, IntrospectedInstruction},
+        Sysvar,
+    },
+    ProgramResult,
+};
+use pinocchio_secp256r1_instruction::{Secp256r1Instruction, Secp256r1Pubkey};
+use pinocchio_system::instructions::Transfer;
+
+use crate::{
+    instructions::{
+        check_vault_signer, check_withdraw_destination, consume_nonce, rent_floor,
+        validate_expiration, vault_signer_seeds, SignerAccount,
+    },
+    message::{CloseVaultMessage, CLOSE_VAULT_DOMAIN},
+};
+
+//structs
+pub struct CloseVaultAccounts<'a> {
+    pub payer: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub destination: &'a AccountInfo,
+    pub instructions: &'a AccountInfo,
+    pub allowlist: &'a AccountInfo,
+    pub nonce: &'a AccountInfo,
+}
+
+pub struct CloseVaultInstructionData {
+    pub bump: [u8; 1],
+    pub sweep: bool,
+}
+
+pub struct CloseVault<'a> {
+    pub accounts: CloseVaultAccounts<'a>,
+    pub instruction_datas: CloseVaultInstructionData,
+}
+
+//validation
+impl<'a> TryFrom<&'a [AccountInfo]> for CloseVaultAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, vault, destination, instructions, _system_program, allowlist, nonce] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        // pays for the vault's nonce account the first time one of its messages is used
+        SignerAccount::check(payer)?;
+
+        if !vault.is_owned_by(&pinocchio_system::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        };
+
+        if vault.lamports().eq(&0) {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        Ok(Self {
+            payer,
+            vault,
+            destination,
+            instructions,
+            allowlist,
+            nonce,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for CloseVaultInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let [bump, sweep] = data else {
+            return Err(ProgramError::InvalidInstructionData);
+        };
+
+        let sweep = match sweep {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+
+        Ok(Self {
+            bump: [*bump],
+            sweep,
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for CloseVault<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = CloseVaultAccounts::try_from(accounts)?;
+        let instruction_datas = CloseVaultInstructionData::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction_datas,
+        })
+    }
+}
+
+//close ix
+impl<'a> CloseVault<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &2;
+
+    pub fn process(&self) -> ProgramResult {
+        // only rent is left unless the owner explicitly asked to sweep everything
+        if !self.instruction_datas.sweep
+            && self.accounts.vault.lamports() > rent_floor(self.accounts.vault)?
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let instructions: Instructions<Ref<[u8]>> =
+            Instructions::try_from(self.accounts.instructions)?;
+
+        let ix: IntrospectedInstruction = instructions.get_instruction_relative(1)?;
+
+        let secp256r1_ix = Secp256r1Instruction::try_from(&ix)?;
+
+        if secp256r1_ix.num_signatures() != 1 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let signer: Secp256r1Pubkey = *secp256r1_ix.get_signer(0)?;
+
+        let message = CloseVaultMessage::from_bytes(secp256r1_ix.get_message_data(0)?)?;
+
+        if message.domain.ne(&CLOSE_VAULT_DOMAIN)
+            || message.vault.ne(self.accounts.vault.key())
+            || message.destination.ne(self.accounts.destination.key())
+            || message.sweep.ne(&self.instruction_datas.sweep)
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let current = Clock::get()?.unix_timestamp;
+
+        validate_expiration(current, message.expiry)?;
+        check_vault_signer(self.accounts.vault, &signer, &self.instruction_datas.bump)?;
+        consume_nonce(
+            self.accounts.payer,
+            self.accounts.vault,
+            self.accounts.nonce,
+            message.nonce,
+        )?;
+        check_withdraw_destination(
+            self.accounts.allowlist,
+            self.accounts.vault,
+            self.accounts.destination,
+            current,
+        )?;
+
+        let vault_seeds = vault_signer_seeds(&signer, &self.instruction_datas.bump);
+
+        let vault_signer = Signer::from(&vault_seeds);
+
+        // draining every lamport lets the runtime reclaim the system-owned PDA
+        Transfer {
+            from: self.accounts.vault,
+            to: self.accounts.destination,
+            lamports: self.accounts.vault.lamports(),
+        }
+        .invoke_signed(&[vault_signer])
+    }
+}
@@ -1,11 +1,54 @@
-use pinocchio::{ProgramResult, account_info::AccountInfo, program_error::ProgramError, pubkey::find_program_address};
+use pinocchio::{
+    ProgramResult,
+    account_info::AccountInfo,
+    instruction::Seed,
+    program_error::ProgramError,
+    pubkey::{Pubkey, find_program_address},
+};
 use pinocchio_secp256r1_instruction::Secp256r1Pubkey;
 use pinocchio_system::instructions::Transfer;
 
+use crate::{
+    bytes::{read_bytes, read_u64},
+    instructions::{create_program_account, vault_seeds, SignerAccount},
+};
+
+// running total one payer has deposited into one vault, stored at ["deposit", vault, payer];
+// only written when the depositor passes it, withdrawals don't read or reduce it
+#[repr(C)]
+pub struct DepositRecord {
+    pub depositor: Pubkey,
+    pub vault: Pubkey,
+    pub amount: [u8; 8],
+}
+
+impl DepositRecord {
+    pub const LEN: usize = size_of::<DepositRecord>();
+
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*(data.as_ptr() as *const Self) })
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+    }
+
+    pub fn amount(&self) -> u64 {
+        u64::from_le_bytes(self.amount)
+    }
+}
+
 //structs
 pub struct DepositAccounts<'a> {
     pub payer: &'a AccountInfo,
     pub vault: &'a AccountInfo,
+    pub record: Option<&'a AccountInfo>,
 }
 
 #[repr(C, packed)]
@@ -14,6 +57,9 @@ pub struct DepositInstructionData {
     pub amount: u64,
 }
 
+// instruction data is accepted only at exactly this size, without any padding
+const _: () = assert!(size_of::<DepositInstructionData>() == 33 + 8);
+
 pub struct Deposit<'a> {
     pub accounts: DepositAccounts<'a>,
     pub instruction_data: DepositInstructionData,
@@ -24,20 +70,24 @@ impl<'a> TryFrom<&'a [AccountInfo]> for DepositAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [payer, vault, _] = accounts else {
-            return Err(ProgramError::NotEnoughAccountKeys);
+        // a trailing DepositRecord account opts into attribution
+        let (payer, vault, system_program, record) = match accounts {
+            [payer, vault, system_program] => (payer, vault, system_program, None),
+            [payer, vault, system_program, record] => {
+                (payer, vault, system_program, Some(record))
+            }
+            _ => return Err(ProgramError::NotEnoughAccountKeys),
         };
-        if !payer.is_signer() {
-            return Err(ProgramError::InvalidAccountOwner);
+        SignerAccount::check(payer)?;
+        // the transfer CPI would fail anyway, but without saying which account was wrong
+        if system_program.key().ne(&pinocchio_system::ID) {
+            return Err(ProgramError::IncorrectProgramId);
         }
         if !vault.is_owned_by(&pinocchio_system::ID) {
             return Err(ProgramError::InvalidAccountOwner);
         }
-        if vault.lamports().ne(&0) {
-            return Err(ProgramError::InvalidAccountData);
-        }
 
-        Ok(Self { payer, vault })
+        Ok(Self { payer, vault, record })
     }
 }
 
@@ -48,10 +98,9 @@ impl<'a> TryFrom<&'a [u8]> for DepositInstructionData {
         if data.len() != size_of::<Self>() {
             return Err(ProgramError::InvalidInstructionData);
         }
-        let (pubkey_bytes, amount_bytes) = data.split_at(size_of::<Secp256r1Pubkey>());
         Ok(Self {
-            pubkey: pubkey_bytes.try_into().unwrap(),
-            amount: u64::from_le_bytes(amount_bytes.try_into().unwrap()),
+            pubkey: read_bytes(data, 0)?,
+            amount: read_u64(data, size_of::<Secp256r1Pubkey>())?,
         })
     }
 }
@@ -72,14 +121,8 @@ impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Deposit<'a> {
 impl<'a> Deposit<'a> {
     pub const DISCRIMINATOR: &'a u8 = &0;
     pub fn process(&mut self) -> ProgramResult {
-        let (vault_key, _) = find_program_address(
-            &[
-                b"vault",
-                &self.instruction_data.pubkey[..1],
-                &self.instruction_data.pubkey[1..33]
-            ],
-            &crate::ID
-        );
+        let (vault_key, _) =
+            find_program_address(&vault_seeds(&self.instruction_data.pubkey), &crate::ID);
         if vault_key.ne(self.accounts.vault.key()) {
             return Err(ProgramError::InvalidAccountOwner);
         }
@@ -88,6 +131,55 @@ impl<'a> Deposit<'a> {
             to: self.accounts.vault,
             lamports: self.instruction_data.amount,
         }
-        .invoke()
+        .invoke()?;
+
+        match self.accounts.record {
+            Some(record) => self.record_deposit(record),
+            None => Ok(()),
+        }
+    }
+
+    fn record_deposit(&self, record: &AccountInfo) -> ProgramResult {
+        let (record_key, bump) = find_program_address(
+            &[b"deposit", self.accounts.vault.key(), self.accounts.payer.key()],
+            &crate::ID,
+        );
+        if record_key.ne(record.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if record.data_len().eq(&0) {
+            let bump_binding = [bump];
+            let record_seeds = [
+                Seed::from(b"deposit"),
+                Seed::from(self.accounts.vault.key()),
+                Seed::from(self.accounts.payer.key()),
+                Seed::from(&bump_binding),
+            ];
+
+            create_program_account(
+                self.accounts.payer,
+                record,
+                DepositRecord::LEN,
+                &record_seeds,
+            )?;
+
+            let mut data = record.try_borrow_mut_data()?;
+            let entry = DepositRecord::load_mut(&mut data)?;
+            entry.depositor = *self.accounts.payer.key();
+            entry.vault = *self.accounts.vault.key();
+        } else if !record.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let mut data = record.try_borrow_mut_data()?;
+        let entry = DepositRecord::load_mut(&mut data)?;
+        entry.amount = entry
+            .amount()
+            .checked_add(self.instruction_data.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .to_le_bytes();
+
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,225 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::{create_program_address, find_program_address},
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_secp256r1_instruction::Secp256r1Pubkey;
+use pinocchio_system::instructions::{Allocate, Assign, CreateAccount, Transfer};
+
+// furthest ahead a signed message may expire; a signature good for longer than this is one
+// nobody should be holding on to
+pub const MAX_EXPIRATION_HORIZON_SECONDS: i64 = 24 * 60 * 60;
+
+// `expiration` must not have passed yet and must be within the horizon of `now`
+pub fn validate_expiration(now: i64, expiration: i64) -> ProgramResult {
+    if now > expiration || expiration > now.saturating_add(MAX_EXPIRATION_HORIZON_SECONDS) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+// minimum lamports the account must keep to stay rent exempt at its current size
+#[inline(always)]
+pub fn rent_floor(account: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(rent_floor_at(&Rent::get()?, account.data_len()))
+}
+
+// `rent_floor` of `data_len` bytes under a given rent, which host tests can build without the
+// sysvar
+#[inline(always)]
+pub fn rent_floor_at(rent: &Rent, data_len: usize) -> u64 {
+    rent.minimum_balance(data_len)
+}
+
+// seeds of the vault PDA of `pubkey`, bump aside. A seed is at most 32 bytes, so the 33 byte
+// compressed key goes in split after its first byte; every derivation of a vault goes through
+// here so the split can't differ between instructions
+pub fn vault_seeds(pubkey: &Secp256r1Pubkey) -> [&[u8]; 3] {
+    [b"vault", &pubkey[..1], &pubkey[1..]]
+}
+
+// `vault_seeds` with the bump, for signing as the vault
+pub fn vault_signer_seeds<'a>(pubkey: &'a Secp256r1Pubkey, bump: &'a [u8; 1]) -> [Seed<'a>; 4] {
+    let [prefix, head, tail] = vault_seeds(pubkey);
+    [Seed::from(prefix), Seed::from(head), Seed::from(tail), Seed::from(bump)]
+}
+
+// `vault` must be the PDA of the key that signed the precompile instruction, at `bump`; checked
+// up front so a mismatch reads as bad seeds rather than a failed transfer signature
+pub fn check_vault_signer(
+    vault: &AccountInfo,
+    signer: &Secp256r1Pubkey,
+    bump: &[u8; 1],
+) -> ProgramResult {
+    let [prefix, head, tail] = vault_seeds(signer);
+    let vault_key = create_program_address(&[prefix, head, tail, bump], &crate::ID)?;
+    if vault_key.ne(vault.key()) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+// creates `account`, a PDA of this program signed for by `seeds`, with `space` bytes paid for by
+// `payer`. Every PDA here sits at an address anyone can work out, and CreateAccount refuses one
+// that already holds lamports, so an address someone pre-funded is topped up to rent exemption,
+// allocated and assigned instead
+pub fn create_program_account(
+    payer: &AccountInfo,
+    account: &AccountInfo,
+    space: usize,
+    seeds: &[Seed],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(space);
+    let signer = [Signer::from(seeds)];
+
+    if account.lamports().eq(&0) {
+        return CreateAccount {
+            from: payer,
+            to: account,
+            lamports,
+            space: space as u64,
+            owner: &crate::ID,
+        }
+        .invoke_signed(&signer);
+    }
+
+    let top_up = lamports.saturating_sub(account.lamports());
+    if top_up.gt(&0) {
+        Transfer {
+            from: payer,
+            to: account,
+            lamports: top_up,
+        }
+        .invoke()?;
+    }
+    Allocate {
+        account,
+        space: space as u64,
+    }
+    .invoke_signed(&signer)?;
+    Assign {
+        account,
+        owner: &crate::ID,
+    }
+    .invoke_signed(&signer)
+}
+
+// per-vault counter at ["nonce", vault]. A message that could otherwise be replayed until it
+// expires signs the counter's current value, and using the message moves the counter on
+#[repr(C)]
+pub struct VaultNonce {
+    pub nonce: [u8; 8],
+}
+
+impl VaultNonce {
+    pub const LEN: usize = size_of::<VaultNonce>();
+
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*(data.as_ptr() as *const Self) })
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+    }
+
+    pub fn nonce(&self) -> u64 {
+        u64::from_le_bytes(self.nonce)
+    }
+
+    // `signed` must be the current value; the counter then moves past it
+    pub fn consume(&mut self, signed: u64) -> ProgramResult {
+        if self.nonce().ne(&signed) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        self.nonce = signed
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .to_le_bytes();
+        Ok(())
+    }
+}
+
+// uses up `signed` on the nonce account of `vault`, creating the account at 0 on first use with
+// `payer` paying its rent. The account is never closed, so a used value stays used even after
+// the vault itself is emptied and funded again
+pub fn consume_nonce(
+    payer: &AccountInfo,
+    vault: &AccountInfo,
+    nonce_account: &AccountInfo,
+    signed: u64,
+) -> ProgramResult {
+    let (nonce_key, bump) = find_program_address(&[b"nonce", vault.key()], &crate::ID);
+    if nonce_key.ne(nonce_account.key()) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if nonce_account.data_len().eq(&0) {
+        let bump = [bump];
+        let nonce_seeds = [
+            Seed::from(b"nonce"),
+            Seed::from(vault.key()),
+            Seed::from(&bump),
+        ];
+        create_program_account(payer, nonce_account, VaultNonce::LEN, &nonce_seeds)?;
+    } else if !nonce_account.is_owned_by(&crate::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut data = nonce_account.try_borrow_mut_data()?;
+    VaultNonce::load_mut(&mut data)?.consume(signed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // mainnet's rent: 3480 lamports per byte-year, exempt at two years, half burned. u64
+    // words keep it aligned for `Rent::from_bytes`
+    const RENT: [u64; 3] = [3_480, 2f64.to_bits(), 50];
+
+    #[test]
+    fn rent_floor_of_empty_and_sized_accounts() {
+        let bytes = unsafe { core::slice::from_raw_parts(RENT.as_ptr() as *const u8, Rent::LEN) };
+        let rent = Rent::from_bytes(bytes).unwrap();
+
+        // vaults are data-less system accounts, which still pay for the 128 byte overhead
+        assert_eq!(rent_floor_at(rent, 0), 890_880);
+        assert_eq!(
+            rent_floor_at(rent, VaultNonce::LEN),
+            (128 + VaultNonce::LEN as u64) * 6_960
+        );
+    }
+
+    #[test]
+    fn nonce_is_used_once_and_in_order() {
+        let mut data = [0u8; VaultNonce::LEN];
+        let nonce = VaultNonce::load_mut(&mut data).unwrap();
+
+        assert_eq!(nonce.consume(1), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(nonce.consume(0), Ok(()));
+        // the same message again
+        assert_eq!(nonce.consume(0), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(nonce.consume(1), Ok(()));
+        assert_eq!(nonce.nonce(), 2);
+    }
+
+    #[test]
+    fn nonce_does_not_wrap() {
+        let mut data = u64::MAX.to_le_bytes();
+        let nonce = VaultNonce::load_mut(&mut data).unwrap();
+
+        assert_eq!(
+            nonce.consume(u64::MAX),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+    }
+}
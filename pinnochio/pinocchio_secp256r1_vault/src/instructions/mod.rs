@@ -2,4 +2,49 @@ pub mod deposit;
 pub use deposit::*; 
 
 pub mod withdraw;
-pub use withdraw::*; 
\ No newline at end of file
+pub use withdraw::*; 
+
+pub mod close;
+pub use close::*; 
+
+pub mod set_recovery;
+pub use set_recovery::*;
+
+pub mod cancel_recovery;
+pub use cancel_recovery::*;
+
+pub mod recover_funds;
+pub use recover_funds::*;
+
+pub mod add_withdraw_destination;
+pub use add_withdraw_destination::*;
+
+pub mod helpers;
+pub use helpers::*;
+
+#[path = "../../../shared/accounts.rs"]
+pub mod accounts;
+pub use accounts::*;
+
+// one per instruction, each dispatched on its own in lib.rs
+pub const DISCRIMINATORS: &[u8] = &[
+    *Deposit::DISCRIMINATOR,
+    *Withdraw::DISCRIMINATOR,
+    *CloseVault::DISCRIMINATOR,
+    *SetRecovery::DISCRIMINATOR,
+    *RecoverFunds::DISCRIMINATOR,
+    *AddWithdrawDestination::DISCRIMINATOR,
+    *CancelRecovery::DISCRIMINATOR,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discriminators_are_unique() {
+        for (i, discriminator) in DISCRIMINATORS.iter().enumerate() {
+            assert!(!DISCRIMINATORS[i + 1..].contains(discriminator));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Signer,
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_system::instructions::Transfer;
+
+use crate::instructions::{
+    check_withdraw_destination, vault_signer_seeds, Recovery, SignerAccount,
+};
+
+//structs
+pub struct RecoverFundsAccounts<'a> {
+    pub recovery_authority: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub recovery: &'a AccountInfo,
+    pub allowlist: &'a AccountInfo,
+}
+
+pub struct RecoverFunds<'a> {
+    pub accounts: RecoverFundsAccounts<'a>,
+}
+
+//validation
+impl<'a> TryFrom<&'a [AccountInfo]> for RecoverFundsAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [recovery_authority, vault, recovery, _system_program, allowlist] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(recovery_authority)?;
+
+        if !vault.is_owned_by(&pinocchio_system::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        };
+
+        if vault.lamports().eq(&0) {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        if !recovery.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Self {
+            recovery_authority,
+            vault,
+            recovery,
+            allowlist,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RecoverFunds<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = RecoverFundsAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+//recover funds ix
+impl<'a> RecoverFunds<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &4;
+
+    // no passkey signature here: this is the way out once every passkey is lost, so all it
+    // takes is the recovery authority and an expired timelock. The funds go to the authority,
+    // which like any other payout has to be on the vault's allow-list once it has one
+    pub fn process(&self) -> ProgramResult {
+        let data = self.accounts.recovery.try_borrow_data()?;
+        let recovery = Recovery::load(&data)?;
+
+        let recovery_key = create_program_address(
+            &[b"recovery", self.accounts.vault.key(), &recovery.bump],
+            &crate::ID,
+        )?;
+        if recovery_key.ne(self.accounts.recovery.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if recovery
+            .recovery_authority
+            .ne(self.accounts.recovery_authority.key())
+        {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+
+        let current = Clock::get()?.unix_timestamp;
+        if current < recovery.recovery_available_at() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        check_withdraw_destination(
+            self.accounts.allowlist,
+            self.accounts.vault,
+            self.accounts.recovery_authority,
+            current,
+        )?;
+
+        let vault_seeds = vault_signer_seeds(&recovery.pubkey, &recovery.vault_bump);
+
+        let vault_signer = Signer::from(&vault_seeds);
+
+        // the whole balance goes, nobody is left who could use a rent-exempt vault
+        Transfer {
+            from: self.accounts.vault,
+            to: self.accounts.recovery_authority,
+            lamports: self.accounts.vault.lamports(),
+        }
+        .invoke_signed(&[vault_signer])
+    }
+}
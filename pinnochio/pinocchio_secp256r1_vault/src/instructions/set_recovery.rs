@@ -0,0 +1,242 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref},
+    instruction::Seed,
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{
+        clock::Clock,
+        instructions::{Instructions, IntrospectedInstruction},
+        Sysvar,
+    },
+    ProgramResult,
+};
+use pinocchio_secp256r1_instruction::{Secp256r1Instruction, Secp256r1Pubkey};
+
+use crate::{
+    bytes::{read_bytes, read_i64, read_pubkey, read_u64},
+    instructions::{
+        consume_nonce, create_program_account, validate_expiration, vault_seeds, SignerAccount,
+    },
+};
+
+// domain tag prefixed to the signed message so no other vault signature can be replayed here
+pub const SET_RECOVERY_DOMAIN: &[u8; 12] = b"set_recovery";
+
+// shortest wait between setting a recovery authority and it being able to take the funds, so
+// the passkey owner has a day to notice one they didn't set and cancel it
+pub const MIN_RECOVERY_DELAY_SECONDS: i64 = 24 * 60 * 60;
+
+// `available_at` has to leave at least MIN_RECOVERY_DELAY_SECONDS from `now`
+pub fn validate_recovery_delay(now: i64, available_at: i64) -> ProgramResult {
+    if available_at < now.saturating_add(MIN_RECOVERY_DELAY_SECONDS) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// recovery settings of one vault, stored at ["recovery", vault]; vaults without one
+// have no recovery authority
+#[repr(C)]
+pub struct Recovery {
+    pub pubkey: Secp256r1Pubkey,
+    pub vault_bump: [u8; 1],
+    pub recovery_authority: Pubkey,
+    pub recovery_available_at: [u8; 8],
+    pub bump: [u8; 1],
+}
+
+impl Recovery {
+    pub const LEN: usize = size_of::<Recovery>();
+
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*(data.as_ptr() as *const Self) })
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+    }
+
+    pub fn recovery_available_at(&self) -> i64 {
+        i64::from_le_bytes(self.recovery_available_at)
+    }
+}
+
+//structs
+pub struct SetRecoveryAccounts<'a> {
+    pub payer: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub recovery: &'a AccountInfo,
+    pub instructions: &'a AccountInfo,
+    pub nonce: &'a AccountInfo,
+}
+
+pub struct SetRecovery<'a> {
+    pub accounts: SetRecoveryAccounts<'a>,
+}
+
+//validation
+impl<'a> TryFrom<&'a [AccountInfo]> for SetRecoveryAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, vault, recovery, instructions, _system_program, nonce] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(payer)?;
+
+        if !vault.is_owned_by(&pinocchio_system::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        };
+
+        Ok(Self {
+            payer,
+            vault,
+            recovery,
+            instructions,
+            nonce,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SetRecovery<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = SetRecoveryAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+//set recovery ix
+impl<'a> SetRecovery<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &3;
+
+    // sets the vault's recovery authority, or rotates an existing one; either way the new
+    // authority waits out a fresh delay of at least MIN_RECOVERY_DELAY_SECONDS
+    pub fn process(&self) -> ProgramResult {
+        let instructions: Instructions<Ref<[u8]>> =
+            Instructions::try_from(self.accounts.instructions)?;
+
+        let ix: IntrospectedInstruction = instructions.get_instruction_relative(1)?;
+
+        let secp256r1_ix = Secp256r1Instruction::try_from(&ix)?;
+
+        if secp256r1_ix.num_signatures() != 1 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let signer: Secp256r1Pubkey = *secp256r1_ix.get_signer(0)?;
+
+        // message layout: domain (12) | recovery_authority (32) | recovery_available_at (8) |
+        // nonce (8) | expiry (8); the nonce keeps an old rotation from being replayed over a
+        // newer one
+        let message = secp256r1_ix
+            .get_message_data(0)?
+            .strip_prefix(SET_RECOVERY_DOMAIN)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        if message.len().ne(&(size_of::<Pubkey>() + size_of::<i64>() * 3)) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let (recovery_authority, message) = message.split_at(size_of::<Pubkey>());
+        let (recovery_available_at, message) = message.split_at(size_of::<i64>());
+        let (nonce, expiry) = message.split_at(size_of::<u64>());
+
+        let current = Clock::get()?.unix_timestamp;
+
+        validate_expiration(current, read_i64(expiry, 0)?)?;
+        validate_recovery_delay(current, read_i64(recovery_available_at, 0)?)?;
+
+        let (vault_key, vault_bump) = find_program_address(&vault_seeds(&signer), &crate::ID);
+        if vault_key.ne(self.accounts.vault.key()) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let (recovery_key, bump) = find_program_address(&[b"recovery", &vault_key], &crate::ID);
+        if recovery_key.ne(self.accounts.recovery.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        consume_nonce(
+            self.accounts.payer,
+            self.accounts.vault,
+            self.accounts.nonce,
+            read_u64(nonce, 0)?,
+        )?;
+
+        let bump_binding = [bump];
+        if self.accounts.recovery.data_len().eq(&0) {
+            let recovery_seeds = [
+                Seed::from(b"recovery"),
+                Seed::from(&vault_key),
+                Seed::from(&bump_binding),
+            ];
+
+            create_program_account(
+                self.accounts.payer,
+                self.accounts.recovery,
+                Recovery::LEN,
+                &recovery_seeds,
+            )?;
+        } else if !self.accounts.recovery.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let mut data = self.accounts.recovery.try_borrow_mut_data()?;
+        let recovery = Recovery::load_mut(&mut data)?;
+
+        recovery.pubkey = signer;
+        recovery.vault_bump = [vault_bump];
+        recovery.recovery_authority = read_pubkey(recovery_authority, 0)?;
+        recovery.recovery_available_at = read_bytes(recovery_available_at, 0)?;
+        recovery.bump = bump_binding;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_delay_needs_a_full_day() {
+        let now = 1_700_000_000;
+
+        assert_eq!(
+            validate_recovery_delay(now, now + MIN_RECOVERY_DELAY_SECONDS),
+            Ok(())
+        );
+        assert_eq!(
+            validate_recovery_delay(now, now + MIN_RECOVERY_DELAY_SECONDS - 1),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            validate_recovery_delay(now, now + 1),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            validate_recovery_delay(now, now),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn recovery_delay_near_the_end_of_time() {
+        // now + delay saturates instead of wrapping into the past
+        assert_eq!(
+            validate_recovery_delay(i64::MAX - 1, i64::MAX - 1),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(validate_recovery_delay(i64::MAX - 1, i64::MAX), Ok(()));
+    }
+}
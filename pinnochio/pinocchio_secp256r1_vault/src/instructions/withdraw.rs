@@ -1,6 +1,6 @@
 use pinocchio::{
     account_info::{AccountInfo, Ref},
-    instruction::{Seed, Signer},
+    instruction::Signer,
     program_error::ProgramError,
     sysvars::{
         clock::Clock,
@@ -12,11 +12,20 @@ use pinocchio::{
 use pinocchio_secp256r1_instruction::{Secp256r1Instruction, Secp256r1Pubkey};
 use pinocchio_system::instructions::Transfer;
 
+use crate::{
+    instructions::{
+        check_vault_signer, check_withdraw_destination, rent_floor, validate_expiration,
+        vault_signer_seeds, SignerAccount,
+    },
+    message::{WithdrawMessage, WITHDRAW_DOMAIN},
+};
+
 //structs
 pub struct WithdrawAccounts<'a> {
     pub owner: &'a AccountInfo,
     pub vault: &'a AccountInfo,
     pub instructions: &'a AccountInfo,
+    pub allowlist: &'a AccountInfo,
 }
 
 pub struct WithdrawInstructionData {
@@ -33,13 +42,11 @@ impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [owner, vault, instructions, _system_program] = accounts else {
+        let [owner, vault, instructions, _system_program, allowlist] = accounts else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
 
-        if !owner.is_signer() {
-            return Err(ProgramError::InvalidAccountOwner);
-        };
+        SignerAccount::check(owner)?;
 
         if !vault.is_owned_by(&pinocchio_system::ID) {
             return Err(ProgramError::InvalidAccountOwner);
@@ -53,6 +60,7 @@ impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawAccounts<'a> {
             owner,
             vault,
             instructions,
+            allowlist,
         })
     }
 }
@@ -96,49 +104,62 @@ impl<'a> Withdraw<'a> {
         let instructions: Instructions<Ref<[u8]>> =
             Instructions::try_from(self.accounts.instructions)?;
 
-        let ix: IntrospectedInstruction = instructions.get_instruction_relative(1)?;
-
-        let secp256r1_ix = Secp256r1Instruction::try_from(&ix)?;
+        // a missing precompile instruction, or one that isn't secp256r1, means the withdraw was
+        // never authorized; report it as such rather than as whatever the parser hit first
+        let ix: IntrospectedInstruction = instructions
+            .get_instruction_relative(1)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
 
-        let signer: Secp256r1Pubkey = *secp256r1_ix.get_signer(0)?;
+        let secp256r1_ix = Secp256r1Instruction::try_from(&ix)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
 
         if secp256r1_ix.num_signatures() != 1 {
             return Err(ProgramError::InvalidInstructionData);
         }
 
-        let (payer, expiry) = secp256r1_ix
-            .get_message_data(0)?
-            .split_at_checked(32)
-            .ok_or(ProgramError::InvalidInstructionData)?;
+        let signer: Secp256r1Pubkey = *secp256r1_ix.get_signer(0)?;
+
+        let message = WithdrawMessage::from_bytes(secp256r1_ix.get_message_data(0)?)?;
 
-        if self.accounts.owner.key().ne(&payer) {
+        if message.domain.ne(&WITHDRAW_DOMAIN)
+            || message.vault.ne(self.accounts.vault.key())
+            || message.destination.ne(self.accounts.owner.key())
+        {
             return Err(ProgramError::InvalidInstructionData);
         }
 
         let current = Clock::get()?.unix_timestamp;
-
-        let expiry = i64::from_le_bytes(
-            expiry
-                .try_into()
-                .map_err(|_| ProgramError::InvalidInstructionData)?,
-        );
-
-        if current > expiry {
+        validate_expiration(current, message.expiry)?;
+        check_vault_signer(self.accounts.vault, &signer, &self.instruction_datas.bump)?;
+        check_withdraw_destination(
+            self.accounts.allowlist,
+            self.accounts.vault,
+            self.accounts.owner,
+            current,
+        )?;
+
+        let vault_seeds = vault_signer_seeds(&signer, &self.instruction_datas.bump);
+
+        // keep the rent floor behind, CloseVault reclaims it once the vault is retired
+        let lamports = self
+            .accounts
+            .vault
+            .lamports()
+            .saturating_sub(rent_floor(self.accounts.vault)?);
+        if lamports.eq(&0) {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        // the signature pins the exact payout, so replaying it once the vault has been
+        // emptied or topped up again fails instead of withdrawing a second time
+        if lamports.ne(&message.amount) {
             return Err(ProgramError::InvalidInstructionData);
         }
 
-        let vault_seeds = [
-            Seed::from(b"vault"),
-            Seed::from(signer[..1].as_ref()),
-            Seed::from(signer[1..].as_ref()),
-            Seed::from(&self.instruction_datas.bump),
-        ];
-
         let vault_signer = Signer::from(&vault_seeds);
          Transfer {
             from: self.accounts.vault,
             to: self.accounts.owner,
-            lamports: self.accounts.vault.lamports(),
+            lamports,
         }
         .invoke_signed(&[vault_signer])
 
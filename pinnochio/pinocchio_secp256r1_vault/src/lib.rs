@@ -3,13 +3,28 @@
 use pinocchio::{
     ProgramResult, account_info::AccountInfo, entrypoint, nostd_panic_handler, program_error::ProgramError, pubkey::Pubkey
 };
+use pinocchio_log::log;
 
 entrypoint!(process_instruction);
 nostd_panic_handler!();
 
 pub mod instructions;
 
-use crate::instructions::{deposit::Deposit, withdraw::Withdraw};
+pub mod message;
+
+// shared with the AMM
+#[path = "../../shared/bytes.rs"]
+pub mod bytes;
+
+// also compiled for tests, which check every vector against its parser
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod test_vectors;
+
+use crate::instructions::{
+    add_withdraw_destination::AddWithdrawDestination, cancel_recovery::CancelRecovery,
+    close::CloseVault, deposit::Deposit, recover_funds::RecoverFunds, set_recovery::SetRecovery,
+    withdraw::Withdraw,
+};
 
 pub const ID: Pubkey = [
     0x0f, 0x1e, 0x6b, 0x14, 0x21, 0xc0, 0x4a, 0x07, 0x04, 0x31, 0x26, 0x5c, 0x19, 0xc5, 0xbb, 0xee,
@@ -24,6 +39,17 @@ fn process_instruction(
     match instruction_data.split_first() {
         Some((Deposit::DISCRIMINATOR, data)) => Deposit::try_from((data, accounts))?.process(),
         Some((Withdraw::DISCRIMINATOR, data)) => Withdraw::try_from((data, accounts))?.process(),
-        _ => Err(ProgramError::InvalidInstructionData),
+        Some((CloseVault::DISCRIMINATOR, data)) => CloseVault::try_from((data, accounts))?.process(),
+        Some((SetRecovery::DISCRIMINATOR, _)) => SetRecovery::try_from(accounts)?.process(),
+        Some((RecoverFunds::DISCRIMINATOR, _)) => RecoverFunds::try_from(accounts)?.process(),
+        Some((AddWithdrawDestination::DISCRIMINATOR, _)) => {
+            AddWithdrawDestination::try_from(accounts)?.process()
+        }
+        Some((CancelRecovery::DISCRIMINATOR, _)) => CancelRecovery::try_from(accounts)?.process(),
+        Some((discriminator, _)) => {
+            log!("Unknown instruction discriminator: {}", *discriminator);
+            Err(ProgramError::InvalidInstructionData)
+        }
+        None => Err(ProgramError::InvalidInstructionData),
     }
 }
\ No newline at end of file
@@ -0,0 +1,183 @@
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::bytes::{read_bytes, read_i64, read_pubkey, read_u64};
+
+// tags the signed bytes as a withdraw from this program, so a signature over some other
+// message of the same length can't be replayed as one
+pub const WITHDRAW_DOMAIN: [u8; 8] = *b"bsvwdrw1";
+
+/// What the secp256r1 key signs to authorize `Withdraw`. Clients build it with `to_bytes`,
+/// the program reads it back with `from_bytes`; the layout is
+/// `domain [u8; 8] | vault | amount u64 | destination | expiry i64`, little-endian. It needs
+/// no nonce: a replay is bounded by `amount`, which must equal the whole withdrawable balance,
+/// and by `expiry`.
+pub struct WithdrawMessage {
+    pub domain: [u8; 8],
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub expiry: i64,
+}
+
+impl WithdrawMessage {
+    pub const LEN: usize = 8 + 32 + 8 + 32 + 8;
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            domain: read_bytes(data, 0)?,
+            vault: read_pubkey(data, 8)?,
+            amount: read_u64(data, 40)?,
+            destination: read_pubkey(data, 48)?,
+            expiry: read_i64(data, 80)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut data = [0u8; Self::LEN];
+        data[0..8].copy_from_slice(&self.domain);
+        data[8..40].copy_from_slice(&self.vault);
+        data[40..48].copy_from_slice(&self.amount.to_le_bytes());
+        data[48..80].copy_from_slice(&self.destination);
+        data[80..88].copy_from_slice(&self.expiry.to_le_bytes());
+        data
+    }
+}
+
+// tags the signed bytes as a close of a vault, so a withdraw signature can't be replayed as one
+pub const CLOSE_VAULT_DOMAIN: [u8; 11] = *b"close_vault";
+
+/// What the secp256r1 key signs to authorize `CloseVault`; the layout is
+/// `domain [u8; 11] | vault | destination | sweep u8 | nonce u64 | expiry i64`, little-endian.
+/// `sweep` is signed so a relayer can't turn a close of an emptied vault into a drain, and
+/// `nonce` must match the vault's stored counter, so the message works once and only on the
+/// vault it names, even if that vault is funded again before it expires.
+pub struct CloseVaultMessage {
+    pub domain: [u8; 11],
+    pub vault: Pubkey,
+    pub destination: Pubkey,
+    pub sweep: bool,
+    pub nonce: u64,
+    pub expiry: i64,
+}
+
+impl CloseVaultMessage {
+    pub const LEN: usize = 11 + 32 + 32 + 1 + 8 + 8;
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            domain: read_bytes(data, 0)?,
+            vault: read_pubkey(data, 11)?,
+            destination: read_pubkey(data, 43)?,
+            sweep: match data[75] {
+                0 => false,
+                1 => true,
+                _ => return Err(ProgramError::InvalidInstructionData),
+            },
+            nonce: read_u64(data, 76)?,
+            expiry: read_i64(data, 84)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut data = [0u8; Self::LEN];
+        data[0..11].copy_from_slice(&self.domain);
+        data[11..43].copy_from_slice(&self.vault);
+        data[43..75].copy_from_slice(&self.destination);
+        data[75] = self.sweep as u8;
+        data[76..84].copy_from_slice(&self.nonce.to_le_bytes());
+        data[84..92].copy_from_slice(&self.expiry.to_le_bytes());
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> WithdrawMessage {
+        WithdrawMessage {
+            domain: WITHDRAW_DOMAIN,
+            vault: [1u8; 32],
+            amount: 5_000_000,
+            destination: [2u8; 32],
+            expiry: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let bytes = message().to_bytes();
+        let parsed = WithdrawMessage::from_bytes(&bytes).unwrap();
+
+        assert_eq!(parsed.domain, WITHDRAW_DOMAIN);
+        assert_eq!(parsed.vault, [1u8; 32]);
+        assert_eq!(parsed.amount, 5_000_000);
+        assert_eq!(parsed.destination, [2u8; 32]);
+        assert_eq!(parsed.expiry, 1_700_000_000);
+        assert_eq!(parsed.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn layout_is_fixed() {
+        let bytes = message().to_bytes();
+
+        assert_eq!(&bytes[0..8], b"bsvwdrw1");
+        assert_eq!(&bytes[40..48], &5_000_000u64.to_le_bytes());
+        assert_eq!(&bytes[80..88], &1_700_000_000i64.to_le_bytes());
+    }
+
+    #[test]
+    fn rejects_truncated_and_extended_messages() {
+        let bytes = message().to_bytes();
+
+        assert!(WithdrawMessage::from_bytes(&[]).is_err());
+        assert!(WithdrawMessage::from_bytes(&bytes[..WithdrawMessage::LEN - 1]).is_err());
+
+        let mut extended = [0u8; WithdrawMessage::LEN + 1];
+        extended[..WithdrawMessage::LEN].copy_from_slice(&bytes);
+        assert!(WithdrawMessage::from_bytes(&extended).is_err());
+    }
+
+    fn close_message() -> CloseVaultMessage {
+        CloseVaultMessage {
+            domain: CLOSE_VAULT_DOMAIN,
+            vault: [1u8; 32],
+            destination: [2u8; 32],
+            sweep: true,
+            nonce: 7,
+            expiry: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn close_message_round_trips() {
+        let bytes = close_message().to_bytes();
+        let parsed = CloseVaultMessage::from_bytes(&bytes).unwrap();
+
+        assert_eq!(parsed.domain, CLOSE_VAULT_DOMAIN);
+        assert_eq!(parsed.vault, [1u8; 32]);
+        assert_eq!(parsed.destination, [2u8; 32]);
+        assert!(parsed.sweep);
+        assert_eq!(parsed.nonce, 7);
+        assert_eq!(parsed.expiry, 1_700_000_000);
+        assert_eq!(parsed.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn close_message_rejects_bad_sweep_and_lengths() {
+        let mut bytes = close_message().to_bytes();
+
+        assert!(CloseVaultMessage::from_bytes(&bytes[..CloseVaultMessage::LEN - 1]).is_err());
+
+        bytes[75] = 2;
+        assert!(CloseVaultMessage::from_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,54 @@
+//! Canonical instruction data for client authors, built with the `test-vectors` feature and
+//! for the crate's tests, which parse each one back. Each vector is the full instruction data,
+//! discriminator first, for the field values noted beside it; dump `TEST_VECTORS` to a fixture
+//! and compare an encoder's output byte for byte.
+
+pub struct TestVector {
+    pub name: &'static str,
+    pub data: &'static [u8],
+}
+
+const fn write<const N: usize>(mut out: [u8; N], at: usize, bytes: &[u8]) -> [u8; N] {
+    let mut i = 0;
+    while i < bytes.len() {
+        out[at + i] = bytes[i];
+        i += 1;
+    }
+    out
+}
+
+// compressed secp256r1 pubkey 0x02 followed by [0x11; 32]
+const PUBKEY: [u8; 33] = write([0x11; 33], 0, &[0x02]);
+
+// pubkey as above | amount 1_000_000_000
+const DEPOSIT: [u8; 42] = {
+    let out = write([0u8; 42], 0, &[0]);
+    let out = write(out, 1, &PUBKEY);
+    write(out, 34, &1_000_000_000u64.to_le_bytes())
+};
+
+pub const TEST_VECTORS: &[TestVector] = &[TestVector {
+    name: "secp256r1_vault_deposit",
+    data: &DEPOSIT,
+}];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::deposit::{Deposit, DepositInstructionData};
+
+    #[test]
+    fn deposit_parses() {
+        let vector = TEST_VECTORS
+            .iter()
+            .find(|vector| vector.name.eq("secp256r1_vault_deposit"))
+            .unwrap();
+        let (discriminator, data) = vector.data.split_first().unwrap();
+        assert_eq!(discriminator, Deposit::DISCRIMINATOR);
+
+        // DepositInstructionData is packed, amount is copied out before comparing
+        let parsed = DepositInstructionData::try_from(data).unwrap();
+        assert_eq!(parsed.pubkey, PUBKEY);
+        assert_eq!({ parsed.amount }, 1_000_000_000);
+    }
+}
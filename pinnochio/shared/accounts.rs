@@ -0,0 +1,60 @@
+//! Signer, owner and length checks shared by the programs, compiled into each with `#[path]`
+//! against its own pinocchio.
+
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+
+/// The account checks every instruction repeats, as methods on `AccountInfo`.
+pub trait AccountInfoExt {
+    /// `MissingRequiredSignature` unless the account signed.
+    fn require_signer(&self) -> ProgramResult;
+    /// `InvalidAccountOwner` unless `owner` owns the account.
+    fn require_owner(&self, owner: &Pubkey) -> ProgramResult;
+    /// `InvalidAccountData` unless the account holds exactly `len` bytes.
+    fn require_len(&self, len: usize) -> ProgramResult;
+    /// The little-endian u64 at `offset`, `InvalidAccountData` if it runs past the data.
+    fn read_u64_at(&self, offset: usize) -> Result<u64, ProgramError>;
+}
+
+impl AccountInfoExt for AccountInfo {
+    fn require_signer(&self) -> ProgramResult {
+        if !self.is_signer() {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(())
+    }
+
+    fn require_owner(&self, owner: &Pubkey) -> ProgramResult {
+        if !self.is_owned_by(owner) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        Ok(())
+    }
+
+    fn require_len(&self, len: usize) -> ProgramResult {
+        if self.data_len().ne(&len) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    fn read_u64_at(&self, offset: usize) -> Result<u64, ProgramError> {
+        let end = offset
+            .checked_add(size_of::<u64>())
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let data = self.try_borrow_data()?;
+        let bytes = data
+            .get(offset..end)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+pub struct SignerAccount;
+
+impl SignerAccount {
+    pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
+        account.require_signer()
+    }
+}
@@ -0,0 +1,103 @@
+//! Bounds-checked little-endian reads out of instruction data and other raw byte buffers, so
+//! a short buffer fails with `InvalidInstructionData` instead of panicking on a slice.
+//!
+//! Not a crate of its own: the AMM (pinocchio 0.9) and the secp256r1 vault (pinocchio 0.8) each
+//! compile it in with `#[path]`, against their own `ProgramError`.
+
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+
+#[inline(always)]
+pub fn read_bytes<const N: usize>(buf: &[u8], offset: usize) -> Result<[u8; N], ProgramError> {
+    let end = offset.checked_add(N).ok_or(ProgramError::InvalidInstructionData)?;
+    buf.get(offset..end)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+#[inline(always)]
+pub fn read_u16(buf: &[u8], offset: usize) -> Result<u16, ProgramError> {
+    read_bytes(buf, offset).map(u16::from_le_bytes)
+}
+
+#[inline(always)]
+pub fn read_u64(buf: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    read_bytes(buf, offset).map(u64::from_le_bytes)
+}
+
+#[inline(always)]
+pub fn read_i64(buf: &[u8], offset: usize) -> Result<i64, ProgramError> {
+    read_bytes(buf, offset).map(i64::from_le_bytes)
+}
+
+#[inline(always)]
+pub fn read_pubkey(buf: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    read_bytes(buf, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0x00, 0x01, .. 0x27
+    fn buf() -> [u8; 40] {
+        core::array::from_fn(|i| i as u8)
+    }
+
+    #[test]
+    fn reads_little_endian_in_bounds() {
+        let buf = buf();
+        assert_eq!(read_u16(&buf, 0), Ok(0x0100));
+        assert_eq!(read_u16(&buf, 38), Ok(0x2726));
+        assert_eq!(read_u64(&buf, 1), Ok(0x0807_0605_0403_0201));
+        assert_eq!(read_u64(&buf, 32), Ok(0x2726_2524_2322_2120));
+        assert_eq!(read_i64(&[0xff; 8], 0), Ok(-1));
+        assert_eq!(read_i64(&buf, 8), Ok(0x0f0e_0d0c_0b0a_0908));
+        assert_eq!(
+            read_pubkey(&buf, 8),
+            Ok(core::array::from_fn(|i| i as u8 + 8))
+        );
+    }
+
+    #[test]
+    fn rejects_reads_past_the_end() {
+        let buf = buf();
+        assert_eq!(
+            read_u16(&buf, 39),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_u64(&buf, 33),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_i64(&buf, 40),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_pubkey(&buf, 9),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(read_u64(&[], 0), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn rejects_offsets_that_overflow() {
+        let buf = buf();
+        assert_eq!(
+            read_u16(&buf, usize::MAX),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_u64(&buf, usize::MAX - 7),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_i64(&buf, usize::MAX - 3),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_pubkey(&buf, usize::MAX - 31),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+}
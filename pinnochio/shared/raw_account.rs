@@ -0,0 +1,47 @@
+//! Test fixture shared by the programs' unit tests, compiled in with `#[path]` under
+//! `#[cfg(test)]`.
+
+use pinocchio::{account_info::AccountInfo, pubkey::Pubkey};
+
+// an account laid out the way the runtime hands it over: the 88-byte header pinocchio reads
+// through `AccountInfo` (borrow state, flags, key, owner, lamports, data length), then the
+// data. u64 words keep the header aligned
+pub(crate) struct RawAccount([u64; 64]);
+
+// not every program's tests use every builder
+#[allow(dead_code)]
+impl RawAccount {
+    pub(crate) fn new(is_signer: bool, owner: &Pubkey, data: &[u8]) -> Self {
+        let mut raw = Self([0u64; 64]);
+        let bytes = raw.bytes();
+        // every borrow bit set, i.e. nothing borrowed yet
+        bytes[0] = 0xff;
+        bytes[1] = is_signer as u8;
+        bytes[40..72].copy_from_slice(owner);
+        bytes[80..88].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes[88..88 + data.len()].copy_from_slice(data);
+        raw
+    }
+
+    pub(crate) fn with_key(mut self, key: &Pubkey) -> Self {
+        self.bytes()[8..40].copy_from_slice(key);
+        self
+    }
+
+    pub(crate) fn with_lamports(mut self, lamports: u64) -> Self {
+        self.bytes()[72..80].copy_from_slice(&lamports.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn info(&mut self) -> AccountInfo {
+        // `AccountInfo` is a single `repr(C)` pointer to that header
+        unsafe { core::mem::transmute::<*mut u64, AccountInfo>(self.0.as_mut_ptr()) }
+    }
+
+    // the header and data as bytes, the data starting at 88
+    pub(crate) fn bytes(&mut self) -> &mut [u8] {
+        unsafe {
+            core::slice::from_raw_parts_mut(self.0.as_mut_ptr() as *mut u8, size_of::<Self>())
+        }
+    }
+}